@@ -0,0 +1,1368 @@
+//! grustyvman-cli — headless front end to the grustyvman backend.
+//!
+//! Usage:
+//!   grustyvman-cli [--uri URI] [--json] <command> <subcommand> [args]
+//!
+//! Commands:
+//!   host info
+//!   vm list
+//!   vm start|shutdown|force-stop|reboot|pause|resume <vm>
+//!   vm delete <vm> [--storage]
+//!   vm save <vm> [--file PATH]
+//!   vm restore <file>
+//!   vm discard-save <vm>
+//!   vm rename <vm> <new-name>
+//!   vm grow-disk <vm> <target> <add-gib> [--grow-fs]
+//!   vm disk-chain <vm> <target>
+//!   vm consolidate <vm> <target> [--commit [--force]]
+//!   vm migrate <vm> <dest-uri> [--offline] [--copy-storage] [--p2p]
+//!             [--transient] [--undefine-source] [--bandwidth MIBPS] [--compressed]
+//!   vm clone <vm> <new-name> [--linked] [--copy-nvram]
+//!   vm create <name> [--vcpus N] [--memory MIB] [--disk GIB] [--format qcow2|raw]
+//!             [--firmware bios|efi] [--os SHORT-ID]
+//!             [--iso PATH | --import DISK | --cloud-image BASE]
+//!             [--network NAME | --bridge DEV] [--nic-model MODEL]
+//!             [--disk-bus virtio|sata|scsi|ide] [--video MODEL]
+//!             [--tpm crb|tis|none] [--pool NAME]
+//!             [--hostname NAME] [--user NAME] [--ssh-key FILE]
+//!             [--packages PKG,PKG] [--runcmd CMD]
+//!             [--admin-password PW [--admin-user NAME] [--product-key KEY]
+//!              [--computer-name NAME] [--locale TAG] [--timezone ZONE]]
+//!             [--virtio-win ISO]
+//!   template list
+//!   template mark <vm> [--description TEXT]
+//!   template unmark <template>
+//!   template instantiate <template> [--name PATTERN] [--count N]
+//!             [--user NAME] [--ssh-key FILE] [--packages PKG,PKG] [--runcmd CMD]
+//!   snapshot list <vm>
+//!   snapshot create <vm> <name> [--description TEXT] [--external | --disk-only]
+//!             [--memory-file PATH] [--exclude DEV,DEV] [--overlay DEV=FILE,...]
+//!             [--quiesce] [--no-atomic]
+//!   snapshot revert|delete <vm> <name>
+//!   snapshot diff <vm> <name>
+//!   pool list
+//!   pool start|stop|refresh|delete <pool>
+//!   pool autostart <pool> on|off
+//!   pool create <name> [--type dir] [--target PATH] [--source-device DEV]
+//!               [--source-host HOST] [--source-dir DIR] [--source-name NAME]
+//!               [--source-format FMT]
+//!   volume list <pool>
+//!   volume create <pool> <name> <size-gib> [--format qcow2|raw]
+//!   volume delete <pool> <name>
+//!   volume upload <pool> <file> [--name NAME] [--convert]
+//!   volume download <pool> <name> <file>
+//!   volume info <pool> <name>
+//!   volume resize <pool> <name> <size-gib> [--allocate] [--shrink]
+//!   volume wipe <pool> <name> [--algorithm zero|random|dod|gutmann|...]
+//!   volume clone <pool> <name> <new-name> [--to-pool POOL]
+//!   network list
+//!   network start|stop|delete <network>
+//!   network autostart <network> on|off
+//!   network create <name> [--forward nat|route|isolated|bridge|open]
+//!                  [--bridge NAME] [--ip ADDR] [--netmask MASK]
+//!                  [--dhcp-start ADDR --dhcp-end ADDR]
+//!
+//! `vm create` picks hardware for the guest OS given with --os (a libosinfo
+//! short id such as win11) or detected from the --iso volume label; explicit
+//! options override those defaults. With --cloud-image the new disk is a
+//! qcow2 overlay of BASE, provisioned by cloud-init from the --hostname,
+//! --user, --ssh-key (an authorized_keys file), --packages and --runcmd
+//! options, each of which can be repeated; every --user gets the keys of
+//! every --ssh-key. With --admin-password a Windows install from --iso runs
+//! unattended, loading drivers from the --virtio-win ISO when given.
+//!
+//! `vm clone` gives the clone fresh UEFI variables and TPM state. With
+//! --copy-nvram it starts from the VM's UEFI variables instead, keeping its
+//! boot entries and enrolled keys; libvirt copies them on the clone's first
+//! start.
+//!
+//! `vm save` stops the VM with its memory saved, in libvirt's managed save
+//! image (the next `vm start` resumes it) or in the --file PATH on the VM's
+//! host, which `vm restore` starts it from. `vm discard-save` drops the
+//! managed save image, so the next start boots afresh.
+//!
+//! `vm grow-disk` grows a disk (such as vda) by <add-gib>, live while the VM
+//! runs. With --grow-fs the guest agent then grows the partition and the
+//! filesystems on it (ext4, xfs and btrfs on Linux, NTFS on Windows).
+//!
+//! `vm disk-chain` lists the images of a disk, from the one the VM writes
+//! to down to its base. `vm consolidate` flattens that chain while the VM
+//! runs: by default it pulls the backing data into the top image, which
+//! then stands alone; with --commit it merges the overlays down into the
+//! base image, which the VM writes to from then on, and deletes them.
+//! A commit is refused when other VMs, such as linked clones, use the base
+//! image, and when external snapshots refer to the overlays unless --force
+//! is given.
+//!
+//! `vm migrate` moves the VM live to the host at <dest-uri>, defining it
+//! there unless --transient is given, after checking that host's CPU can run
+//! the guest. --offline moves only the definition, --copy-storage copies
+//! the disks for hosts without shared storage, and with --p2p the source
+//! libvirtd connects to <dest-uri> itself instead of this client.
+//!
+//! `snapshot create` makes an internal snapshot unless --external (new
+//! overlay files for the disks, plus the memory of a running VM in
+//! --memory-file, by default next to its first disk) or --disk-only (overlays
+//! only) is given. --exclude leaves disks out, --overlay names the overlay of
+//! a disk, and --quiesce has the guest agent flush the guest's filesystems
+//! first. `snapshot list` shows each snapshot below the one it was taken on.
+//! `snapshot delete` commits the overlays of an external snapshot into the
+//! images below them, which needs the VM running, and removes its files.
+//! `snapshot diff` lists what changed in the VM's configuration since the
+//! snapshot was taken.
+//!
+//! `volume upload` keeps the image's format; with --convert, VMDK, VDI, VHD
+//! and VHDX images are converted to qcow2 with the local qemu-img first.
+//!
+//! `volume resize` refuses to shrink a volume unless --shrink is given, and
+//! refuses volumes of running VMs. `volume wipe` overwrites with zeroes
+//! unless another --algorithm is named.
+//!
+//! `template instantiate` makes --count (default 1) linked clones named
+//! after --name, where {n} is a free number and {template} the template's
+//! name (default "{template}-{n}"). Any of the cloud-init options gives
+//! each instance a seed with its name as hostname.
+//!
+//! <vm>, <pool> and <network> accept either a name or a UUID. The URI
+//! defaults to $LIBVIRT_DEFAULT_URI, then qemu:///system. With --json every
+//! command prints a single JSON document on stdout, including errors.
+//! Options other than the cloud-init ones, --exclude and --overlay may only
+//! be given once.
+
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::process::ExitCode;
+
+use serde_json::{json, Value};
+
+use grustyvman_core as backend;
+use grustyvman_core::cloudinit::{CloudInitConfig, CloudInitUser};
+use grustyvman_core::domain_xml::NewVmParams;
+use grustyvman_core::jobs::Job;
+use grustyvman_core::osinfo::{self, OsDatabase, OsDefaults};
+use grustyvman_core::template::InstantiateParams;
+use grustyvman_core::unattend::{self, WindowsUnattendConfig};
+use grustyvman_core::types::{
+    ChainMerge, CloneParams, CreateSnapshotParams, DiskBus, DiskFormat, DiskSnapshotMode,
+    FirmwareType, ForwardMode, GuestResize, HostInfo, InstallSource, MigrateParams, MigrationMode,
+    NetworkCreateParams, NetworkModel, NetworkSourceType, NewVmNetworkConfig, NvramClone,
+    PoolCreateParams, PoolInfo, SnapshotDisk, SnapshotInfo, SnapshotKind, TemplateInfo,
+    TpmModel, VideoModel, VirtNetworkInfo, VmInfo, VolumeInfo, WipeAlgorithm,
+};
+use grustyvman_core::error::AppError;
+
+const DEFAULT_URI: &str = "qemu:///system";
+
+/// Flags that never take a value.
+const SWITCHES: &[&str] = &[
+    "json", "linked", "copy-nvram", "storage", "convert", "allocate", "shrink",
+    "grow-fs", "offline", "copy-storage", "p2p", "transient", "undefine-source", "compressed",
+    "external", "disk-only", "quiesce", "no-atomic", "commit", "force", "help",
+];
+
+/// Options that may be given more than once; each time adds a value.
+const REPEATABLE: &[&str] = &["user", "ssh-key", "packages", "runcmd", "exclude", "overlay"];
+
+// ---------------------------------------------------------------------------
+// Argument parsing
+// ---------------------------------------------------------------------------
+
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, Vec<String>>,
+    switches: Vec<String>,
+}
+
+impl Args {
+    fn parse(raw: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut args = Args {
+            positional: Vec::new(),
+            options: HashMap::new(),
+            switches: Vec::new(),
+        };
+        let mut raw = raw.peekable();
+        while let Some(arg) = raw.next() {
+            if let Some(name) = arg.strip_prefix("--") {
+                if SWITCHES.contains(&name) {
+                    args.switches.push(name.to_string());
+                } else {
+                    let value = raw
+                        .next()
+                        .ok_or_else(|| format!("--{name} needs a value"))?;
+                    let values = args.options.entry(name.to_string()).or_default();
+                    if !values.is_empty() && !REPEATABLE.contains(&name) {
+                        return Err(format!("--{name} can only be given once"));
+                    }
+                    values.push(value);
+                }
+            } else {
+                args.positional.push(arg);
+            }
+        }
+        Ok(args)
+    }
+
+    fn switch(&self, name: &str) -> bool {
+        self.switches.iter().any(|s| s == name)
+    }
+
+    fn opt(&self, name: &str) -> Option<&str> {
+        self.options.get(name)?.first().map(|s| s.as_str())
+    }
+
+    /// Every value given for option `name`, in order.
+    fn opt_all(&self, name: &str) -> &[String] {
+        self.options.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Comma-separated values of option `name`, from every time it is
+    /// given; empty if it is not.
+    fn opt_list(&self, name: &str) -> Vec<String> {
+        self.opt_all(name)
+            .iter()
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn opt_parse<T: std::str::FromStr>(&self, name: &str, default: T) -> Result<T, String> {
+        match self.opt(name) {
+            Some(v) => v.parse().map_err(|_| format!("invalid value for --{name}: {v}")),
+            None => Ok(default),
+        }
+    }
+
+    /// Positional argument `idx` (after command and subcommand).
+    fn pos(&self, idx: usize, what: &str) -> Result<&str, String> {
+        self.positional
+            .get(idx + 2)
+            .map(|s| s.as_str())
+            .ok_or_else(|| format!("missing <{what}>"))
+    }
+}
+
+enum CliError {
+    Usage(String),
+    Backend(AppError),
+}
+
+impl From<AppError> for CliError {
+    fn from(err: AppError) -> Self {
+        CliError::Backend(err)
+    }
+}
+
+impl From<String> for CliError {
+    fn from(msg: String) -> Self {
+        CliError::Usage(msg)
+    }
+}
+
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::Usage(msg) => f.write_str(msg),
+            CliError::Backend(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+/// What a command produced: a JSON document plus its human-readable form.
+struct Output {
+    json: Value,
+    text: String,
+}
+
+impl Output {
+    fn done(message: impl Into<String>) -> Self {
+        let text = message.into();
+        Output { json: json!({ "ok": true, "message": text }), text }
+    }
+}
+
+/// Run `f` as a job, showing its progress on stderr unless that is not a
+/// terminal or the output is JSON.
+fn run_job<T>(
+    title: &str,
+    args: &Args,
+    f: impl FnOnce(&Job) -> Result<T, AppError>,
+) -> Result<T, AppError> {
+    let job = if args.switch("json") || !std::io::stderr().is_terminal() {
+        Job::detached(title)
+    } else {
+        Job::watched(title, |info| {
+            if info.is_running() {
+                let percent = info
+                    .fraction()
+                    .map(|f| format!(" {:.0}%", f * 100.0))
+                    .unwrap_or_default();
+                eprint!("\r\x1b[K{}: {}{percent}", info.title, info.detail);
+            } else {
+                eprint!("\r\x1b[K");
+            }
+        })
+    };
+    let result = f(&job);
+    job.finish(&result);
+    result
+}
+
+// ---------------------------------------------------------------------------
+
+fn main() -> ExitCode {
+    let raw: Vec<String> = std::env::args().skip(1).collect();
+    let args = match Args::parse(raw.iter().cloned()) {
+        Ok(args) => args,
+        Err(msg) => {
+            if raw.iter().any(|a| a == "--json") {
+                println!("{}", json!({ "ok": false, "error": msg }));
+            } else {
+                eprintln!("grustyvman-cli: {msg}");
+            }
+            return ExitCode::from(2);
+        }
+    };
+
+    if args.switch("help") || args.positional.is_empty() {
+        print_usage();
+        return ExitCode::SUCCESS;
+    }
+
+    let uri = args
+        .opt("uri")
+        .map(str::to_string)
+        .or_else(|| std::env::var("LIBVIRT_DEFAULT_URI").ok())
+        .unwrap_or_else(|| DEFAULT_URI.to_string());
+    let json_mode = args.switch("json");
+
+    match run(&uri, &args) {
+        Ok(out) => {
+            if json_mode {
+                println!("{}", serde_json::to_string_pretty(&out.json).unwrap_or_default());
+            } else if !out.text.is_empty() {
+                println!("{}", out.text);
+            }
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            let code = match err {
+                CliError::Usage(_) => 2,
+                CliError::Backend(_) => 1,
+            };
+            if json_mode {
+                println!("{}", json!({ "ok": false, "error": err.to_string() }));
+            } else {
+                eprintln!("grustyvman-cli: {err}");
+            }
+            ExitCode::from(code)
+        }
+    }
+}
+
+fn print_usage() {
+    println!("Usage: grustyvman-cli [--uri URI] [--json] <command> <subcommand> [args]");
+    println!();
+    println!("Commands: host, vm, template, snapshot, pool, volume, network");
+    println!("Run `grustyvman-cli <command>` without a subcommand to list its subcommands.");
+}
+
+fn run(uri: &str, args: &Args) -> Result<Output, CliError> {
+    let command = args.positional[0].as_str();
+    let sub = args.positional.get(1).map(|s| s.as_str()).unwrap_or("");
+
+    match command {
+        "host" => host_command(uri, sub),
+        "vm" => vm_command(uri, sub, args),
+        "template" => template_command(uri, sub, args),
+        "snapshot" => snapshot_command(uri, sub, args),
+        "pool" => pool_command(uri, sub, args),
+        "volume" => volume_command(uri, sub, args),
+        "network" => network_command(uri, sub, args),
+        other => Err(CliError::Usage(format!("unknown command: {other}"))),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// host
+// ---------------------------------------------------------------------------
+
+fn host_command(uri: &str, sub: &str) -> Result<Output, CliError> {
+    match sub {
+        "info" => {
+            let info = backend::connection::get_host_info(uri)?;
+            Ok(Output {
+                text: format!(
+                    "{}  {}  libvirt {}  hypervisor {}\n{} ({} MHz), {} sockets x {} cores x {} threads, {} MiB",
+                    info.hostname,
+                    info.uri,
+                    info.libvirt_version,
+                    info.hypervisor_version,
+                    info.cpu_model,
+                    info.cpu_mhz,
+                    info.cpu_sockets,
+                    info.cpu_cores,
+                    info.cpu_threads,
+                    info.memory_kib / 1024,
+                ),
+                json: host_json(&info),
+            })
+        }
+        _ => Err(CliError::Usage("host subcommands: info".to_string())),
+    }
+}
+
+fn host_json(info: &HostInfo) -> Value {
+    json!({
+        "hostname": info.hostname,
+        "uri": info.uri,
+        "libvirt_version": info.libvirt_version,
+        "hypervisor_version": info.hypervisor_version,
+        "cpu_model": info.cpu_model,
+        "cpu_mhz": info.cpu_mhz,
+        "cpu_sockets": info.cpu_sockets,
+        "cpu_cores": info.cpu_cores,
+        "cpu_threads": info.cpu_threads,
+        "cpu_nodes": info.cpu_nodes,
+        "memory_kib": info.memory_kib,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// vm
+// ---------------------------------------------------------------------------
+
+fn vm_command(uri: &str, sub: &str, args: &Args) -> Result<Output, CliError> {
+    match sub {
+        "list" => {
+            let vms = backend::connection::list_all_vms(uri)?;
+            let text = vms
+                .iter()
+                .map(|vm| {
+                    let state = if vm.is_template { "Template" } else { vm.state.label() };
+                    format!("{:<36}  {:<10}  {}", vm.uuid, state, vm.name)
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            Ok(Output {
+                json: Value::Array(vms.iter().map(vm_json).collect()),
+                text,
+            })
+        }
+        "start" | "shutdown" | "force-stop" | "reboot" | "pause" | "resume" => {
+            let vm = find_vm(uri, args.pos(0, "vm")?)?;
+            match sub {
+                "start" => backend::domain::start_vm(uri, &vm.uuid)?,
+                "shutdown" => backend::domain::shutdown_vm(uri, &vm.uuid)?,
+                "force-stop" => backend::domain::force_stop_vm(uri, &vm.uuid)?,
+                "reboot" => backend::domain::reboot_vm(uri, &vm.uuid)?,
+                "pause" => backend::domain::pause_vm(uri, &vm.uuid)?,
+                _ => backend::domain::resume_vm(uri, &vm.uuid)?,
+            }
+            Ok(Output::done(format!("{sub}: {}", vm.name)))
+        }
+        "delete" => {
+            let vm = find_vm(uri, args.pos(0, "vm")?)?;
+            if args.switch("storage") {
+                let paths = backend::domain::get_vm_disk_paths(uri, &vm.uuid)?;
+                backend::domain::delete_vm_with_storage(uri, &vm.uuid, paths)?;
+            } else {
+                backend::domain::delete_vm(uri, &vm.uuid)?;
+            }
+            Ok(Output::done(format!("Deleted {}", vm.name)))
+        }
+        "grow-disk" => {
+            let vm = find_vm(uri, args.pos(0, "vm")?)?;
+            let target = args.pos(1, "target")?;
+            let add_gib: f64 = args
+                .pos(2, "add-gib")?
+                .parse()
+                .map_err(|_| "invalid <add-gib>".to_string())?;
+            let grow_by = (add_gib * 1024.0 * 1024.0 * 1024.0) as u64;
+            let outcome = backend::domain::grow_disk(
+                uri,
+                &vm.uuid,
+                target,
+                grow_by,
+                args.switch("grow-fs"),
+            )?;
+            let guest = match outcome {
+                GuestResize::NotRequested => String::new(),
+                GuestResize::NoAgent => "; no guest agent answered".to_string(),
+                GuestResize::Grown(mountpoints) if mountpoints.is_empty() => {
+                    "; no mounted filesystems on it".to_string()
+                }
+                GuestResize::Grown(mountpoints) => format!("; grew {}", mountpoints.join(", ")),
+                GuestResize::Failed(msg) => format!("; filesystems not grown: {msg}"),
+            };
+            Ok(Output::done(format!("Grew {}/{target} by {add_gib} GiB{guest}", vm.name)))
+        }
+        "disk-chain" => {
+            let vm = find_vm(uri, args.pos(0, "vm")?)?;
+            let chain = backend::blockjob::backing_chain(uri, &vm.uuid, args.pos(1, "target")?)?;
+            Ok(Output {
+                text: chain.join("\n"),
+                json: json!(chain),
+            })
+        }
+        "consolidate" => {
+            let vm = find_vm(uri, args.pos(0, "vm")?)?;
+            let target = args.pos(1, "target")?;
+            let merge = if args.switch("commit") { ChainMerge::Commit } else { ChainMerge::Pull };
+            let force = args.switch("force");
+            run_job(&format!("Consolidating {}/{target}", vm.name), args, |job| {
+                backend::blockjob::consolidate_disk_with_progress(
+                    uri, &vm.uuid, target, merge, force, job,
+                )
+            })?;
+            Ok(Output::done(format!("Consolidated {}/{target}", vm.name)))
+        }
+        "migrate" => {
+            let vm = find_vm(uri, args.pos(0, "vm")?)?;
+            let dest_uri = args.pos(1, "dest-uri")?;
+            let params = MigrateParams {
+                mode: if args.switch("offline") {
+                    MigrationMode::Offline
+                } else {
+                    MigrationMode::Live
+                },
+                copy_storage: args.switch("copy-storage"),
+                peer_to_peer: args.switch("p2p"),
+                persistent: !args.switch("transient"),
+                undefine_source: args.switch("undefine-source"),
+                bandwidth_mib: args
+                    .opt("bandwidth")
+                    .map(|_| args.opt_parse("bandwidth", 0))
+                    .transpose()?,
+                compressed: args.switch("compressed"),
+                ..MigrateParams::new(dest_uri)
+            };
+            run_job(&format!("Migrating {}", vm.name), args, |job| {
+                backend::migration::migrate_vm_with_progress(uri, &vm.uuid, &params, job)
+            })?;
+            Ok(Output::done(format!("Migrated {} to {dest_uri}", vm.name)))
+        }
+        "save" => {
+            let vm = find_vm(uri, args.pos(0, "vm")?)?;
+            match args.opt("file") {
+                Some(path) => {
+                    run_job(&format!("Saving {}", vm.name), args, |job| {
+                        backend::domain::save_vm_to_file_with_progress(uri, &vm.uuid, path, job)
+                    })?;
+                    Ok(Output::done(format!("Saved {} to {path}", vm.name)))
+                }
+                None => {
+                    run_job(&format!("Saving {}", vm.name), args, |job| {
+                        backend::domain::managed_save_vm_with_progress(uri, &vm.uuid, job)
+                    })?;
+                    Ok(Output::done(format!("Saved {}", vm.name)))
+                }
+            }
+        }
+        "restore" => {
+            let path = args.pos(0, "file")?;
+            backend::domain::restore_vm_from_file(uri, path)?;
+            Ok(Output::done(format!("Restored {path}")))
+        }
+        "discard-save" => {
+            let vm = find_vm(uri, args.pos(0, "vm")?)?;
+            backend::domain::discard_saved_state(uri, &vm.uuid)?;
+            Ok(Output::done(format!("Discarded the saved state of {}", vm.name)))
+        }
+        "rename" => {
+            let vm = find_vm(uri, args.pos(0, "vm")?)?;
+            let new_name = args.pos(1, "new-name")?;
+            backend::domain::rename_domain(uri, &vm.uuid, new_name)?;
+            Ok(Output::done(format!("Renamed {} to {new_name}", vm.name)))
+        }
+        "clone" => {
+            let vm = find_vm(uri, args.pos(0, "vm")?)?;
+            let new_name = args.pos(1, "new-name")?;
+            let nvram = if args.switch("copy-nvram") {
+                NvramClone::Copy
+            } else {
+                NvramClone::Reset
+            };
+            let params = CloneParams {
+                nvram,
+                ..CloneParams::new(new_name, !args.switch("linked"))
+            };
+            run_job(&format!("Cloning {}", vm.name), args, |job| {
+                backend::domain::clone_domain_with_progress(uri, &vm.uuid, &params, job)
+            })?;
+            Ok(Output::done(format!("Cloned {} to {new_name}", vm.name)))
+        }
+        "create" => {
+            let params = new_vm_params(uri, args)?;
+            backend::domain_xml::create_vm(uri, &params)?;
+            Ok(Output::done(format!("Created {}", params.name)))
+        }
+        _ => Err(CliError::Usage(
+            "vm subcommands: list, start, shutdown, force-stop, reboot, pause, resume, delete, \
+             save, restore, discard-save, rename, grow-disk, disk-chain, consolidate, migrate, \
+             clone, create"
+                .to_string(),
+        )),
+    }
+}
+
+fn new_vm_params(uri: &str, args: &Args) -> Result<NewVmParams, CliError> {
+    let name = args.pos(0, "name")?.to_string();
+
+    let install = match (args.opt("iso"), args.opt("import"), args.opt("cloud-image")) {
+        (Some(iso), None, None) => InstallSource::Iso(iso.to_string()),
+        (None, Some(disk), None) => InstallSource::ImportDisk(disk.to_string()),
+        (None, None, Some(base)) => InstallSource::CloudImage(base.to_string()),
+        (None, None, None) => InstallSource::None,
+        _ => {
+            return Err(CliError::Usage(
+                "use only one of --iso, --import and --cloud-image".to_string(),
+            ))
+        }
+    };
+    let cloud_init = match install {
+        InstallSource::CloudImage(_) => Some(cloud_init_config(&name, args)?),
+        _ => None,
+    };
+    let virtio_win_iso = args.opt("virtio-win").map(str::to_string);
+
+    // Hardware defaults follow the guest OS: --os, else whatever the ISO
+    // label says. Detection is best effort.
+    let os = match (args.opt("os"), install.iso_path()) {
+        (Some(id), _) => Some(
+            OsDatabase::system()
+                .find(id)
+                .cloned()
+                .ok_or_else(|| format!("unknown OS: {id}"))?,
+        ),
+        (None, Some(iso)) => osinfo::detect_iso(uri, iso).ok().flatten(),
+        (None, None) => None,
+    };
+    let defaults = os
+        .as_ref()
+        .map(|os| os.defaults.clone())
+        .unwrap_or_else(OsDefaults::virtio);
+
+    let disk_format = match args.opt("format") {
+        Some(f) => DiskFormat::ALL
+            .iter()
+            .copied()
+            .find(|d| d.as_str() == f)
+            .ok_or_else(|| format!("unknown disk format: {f}"))?,
+        None => match install {
+            InstallSource::ImportDisk(ref path) if !path.ends_with(".qcow2") => DiskFormat::Raw,
+            _ => DiskFormat::Qcow2,
+        },
+    };
+
+    let firmware = match args.opt("firmware") {
+        Some(f @ ("bios" | "efi")) => FirmwareType::from_name(f),
+        Some(f) => return Err(CliError::Usage(format!("unknown firmware: {f}"))),
+        None => defaults.firmware,
+    };
+
+    let model = match args.opt("nic-model") {
+        Some(m) => NetworkModel::ALL
+            .iter()
+            .copied()
+            .find(|n| n.as_str() == m)
+            .ok_or_else(|| format!("unknown NIC model: {m}"))?,
+        None => defaults.nic_model,
+    };
+
+    let disk_bus = match args.opt("disk-bus") {
+        Some(b) => DiskBus::ALL
+            .iter()
+            .copied()
+            .find(|d| d.as_str() == b)
+            .ok_or_else(|| format!("unknown disk bus: {b}"))?,
+        None => defaults.disk_bus,
+    };
+
+    let video = match args.opt("video") {
+        Some(v) => VideoModel::ALL
+            .iter()
+            .copied()
+            .find(|m| m.as_str() == v)
+            .ok_or_else(|| format!("unknown video model: {v}"))?,
+        None => defaults.video,
+    };
+
+    let network = match (args.opt("network"), args.opt("bridge")) {
+        (Some(_), Some(_)) => {
+            return Err(CliError::Usage("use either --network or --bridge".to_string()))
+        }
+        (_, Some(dev)) => NewVmNetworkConfig {
+            source_type: NetworkSourceType::Bridge,
+            source_value: dev.to_string(),
+            model,
+        },
+        (net, None) => NewVmNetworkConfig {
+            source_type: NetworkSourceType::VirtualNetwork,
+            source_value: net.unwrap_or("default").to_string(),
+            model,
+        },
+    };
+
+    let tpm_model = match args.opt("tpm") {
+        Some("crb") => Some(TpmModel::Crb),
+        Some("tis") => Some(TpmModel::Tis),
+        Some("none") => None,
+        Some(t) => return Err(CliError::Usage(format!("unknown TPM model: {t}"))),
+        None => defaults.tpm,
+    };
+
+    let unattend = match (args.opt("admin-password"), install.iso_path()) {
+        (Some(password), Some(_)) => Some(WindowsUnattendConfig {
+            locale: args.opt("locale").unwrap_or("en-US").to_string(),
+            timezone: args.opt("timezone").unwrap_or("UTC").to_string(),
+            product_key: args.opt("product-key").map(str::to_string),
+            computer_name: args.opt("computer-name").unwrap_or_default().to_string(),
+            admin_user: args.opt("admin-user").unwrap_or("Admin").to_string(),
+            admin_password: password.to_string(),
+            // Drivers only load if the ISO is attached
+            virtio_driver_dir: virtio_win_iso
+                .as_ref()
+                .and(os.as_ref())
+                .and_then(|os| unattend::virtio_driver_dir(&os.short_id))
+                .map(str::to_string),
+        }),
+        (Some(_), None) => {
+            return Err(CliError::Usage("--admin-password needs an --iso installer".to_string()))
+        }
+        (None, _) => None,
+    };
+
+    Ok(NewVmParams {
+        name,
+        vcpus: args.opt_parse("vcpus", defaults.vcpus)?,
+        memory_mib: args.opt_parse("memory", defaults.memory_mib)?,
+        disk_size_gib: args.opt_parse("disk", defaults.disk_size_gib)?,
+        disk_format,
+        install,
+        firmware,
+        network,
+        tpm_model,
+        storage_pool: args.opt("pool").map(str::to_string),
+        disk_bus,
+        video,
+        hyperv: defaults.hyperv,
+        os_id: os.map(|os| os.id),
+        cloud_init,
+        unattend,
+        virtio_win_iso,
+    })
+}
+
+fn cloud_init_config(vm_name: &str, args: &Args) -> Result<CloudInitConfig, CliError> {
+    let mut ssh_authorized_keys = Vec::new();
+    for file in args.opt_all("ssh-key") {
+        let keys = std::fs::read_to_string(file).map_err(|e| format!("cannot read {file}: {e}"))?;
+        ssh_authorized_keys.extend(
+            keys.lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                .map(str::to_string),
+        );
+    }
+    // With --user the keys go to those accounts, else to the image's default user
+    let users: Vec<CloudInitUser> = args
+        .opt_all("user")
+        .iter()
+        .map(|user| CloudInitUser {
+            name: user.clone(),
+            ssh_authorized_keys: ssh_authorized_keys.clone(),
+            sudo: true,
+        })
+        .collect();
+    if !users.is_empty() {
+        ssh_authorized_keys.clear();
+    }
+
+    Ok(CloudInitConfig {
+        hostname: args.opt("hostname").unwrap_or(vm_name).to_string(),
+        users,
+        ssh_authorized_keys,
+        packages: args.opt_list("packages"),
+        runcmd: args.opt_all("runcmd").to_vec(),
+    })
+}
+
+fn find_vm(uri: &str, key: &str) -> Result<VmInfo, CliError> {
+    backend::connection::list_all_vms(uri)?
+        .into_iter()
+        .find(|vm| vm.uuid == key || vm.name == key)
+        .ok_or_else(|| CliError::Usage(format!("no such VM: {key}")))
+}
+
+fn vm_json(vm: &VmInfo) -> Value {
+    json!({
+        "name": vm.name,
+        "uuid": vm.uuid,
+        "state": vm.state.as_str(),
+        "vcpus": vm.vcpus,
+        "memory_kib": vm.memory_kib,
+        "id": vm.id,
+        "template": vm.is_template,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// template
+// ---------------------------------------------------------------------------
+
+fn template_command(uri: &str, sub: &str, args: &Args) -> Result<Output, CliError> {
+    match sub {
+        "list" => {
+            let templates: Vec<(VmInfo, TemplateInfo)> = backend::connection::list_all_vms(uri)?
+                .into_iter()
+                .filter(|vm| vm.is_template)
+                .map(|vm| {
+                    let info = backend::template::get_template(uri, &vm.uuid)?.unwrap_or_default();
+                    Ok((vm, info))
+                })
+                .collect::<Result<_, AppError>>()?;
+            let text = templates
+                .iter()
+                .map(|(vm, info)| format!("{:<36}  {:<24}  {}", vm.uuid, vm.name, info.description))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let json = templates
+                .iter()
+                .map(|(vm, info)| {
+                    json!({
+                        "name": vm.name,
+                        "uuid": vm.uuid,
+                        "description": info.description,
+                        "created": info.created,
+                    })
+                })
+                .collect();
+            Ok(Output { json: Value::Array(json), text })
+        }
+        "mark" => {
+            let vm = find_vm(uri, args.pos(0, "vm")?)?;
+            let description = args.opt("description").unwrap_or_default();
+            backend::template::mark_template(uri, &vm.uuid, description)?;
+            Ok(Output::done(format!("{} is now a template", vm.name)))
+        }
+        "unmark" => {
+            let vm = find_vm(uri, args.pos(0, "template")?)?;
+            backend::template::unmark_template(uri, &vm.uuid)?;
+            Ok(Output::done(format!("{} is now an ordinary VM", vm.name)))
+        }
+        "instantiate" => {
+            let vm = find_vm(uri, args.pos(0, "template")?)?;
+            let wants_cloud_init = ["user", "ssh-key", "packages", "runcmd"]
+                .iter()
+                .any(|name| args.opt(name).is_some());
+            let params = InstantiateParams {
+                name_pattern: args.opt("name").unwrap_or("{template}-{n}").to_string(),
+                count: args.opt_parse("count", 1)?,
+                // The hostname is filled in per instance
+                cloud_init: if wants_cloud_init {
+                    Some(cloud_init_config("", args)?)
+                } else {
+                    None
+                },
+            };
+            let names = backend::template::instantiate_template(uri, &vm.uuid, &params)?;
+            Ok(Output {
+                text: format!("Created {}", names.join(", ")),
+                json: json!({ "ok": true, "created": names }),
+            })
+        }
+        _ => Err(CliError::Usage(
+            "template subcommands: list, mark, unmark, instantiate".to_string(),
+        )),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// snapshot
+// ---------------------------------------------------------------------------
+
+fn snapshot_command(uri: &str, sub: &str, args: &Args) -> Result<Output, CliError> {
+    match sub {
+        "list" => {
+            let vm = find_vm(uri, args.pos(0, "vm")?)?;
+            let snaps = backend::snapshot::list_snapshots(uri, &vm.uuid)?;
+            let text = backend::snapshot::snapshot_tree(&snaps)
+                .into_iter()
+                .map(|(depth, s)| {
+                    let marker = if s.is_current { "*" } else { " " };
+                    let name = format!("{}{}", "  ".repeat(depth), s.name);
+                    let kind = if s.is_external() { "external" } else { "internal" };
+                    format!(
+                        "{marker} {name:<24}  {:<10}  {kind:<8}  {}",
+                        s.state.label(),
+                        s.description
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            Ok(Output {
+                json: Value::Array(snaps.iter().map(snapshot_json).collect()),
+                text,
+            })
+        }
+        "create" => {
+            let vm = find_vm(uri, args.pos(0, "vm")?)?;
+            let kind = match (args.switch("external"), args.switch("disk-only")) {
+                (true, true) => {
+                    return Err(CliError::Usage(
+                        "--external and --disk-only exclude each other".to_string(),
+                    ))
+                }
+                (true, false) => SnapshotKind::External,
+                (false, true) => SnapshotKind::DiskOnly,
+                (false, false) => SnapshotKind::Internal,
+            };
+            let mut disks: Vec<SnapshotDisk> = args.opt_list("exclude")
+                .into_iter()
+                .map(|dev| SnapshotDisk {
+                    target_dev: dev,
+                    mode: DiskSnapshotMode::Excluded,
+                    file: None,
+                })
+                .collect();
+            for overlay in args.opt_list("overlay") {
+                let (dev, file) = overlay
+                    .split_once('=')
+                    .ok_or_else(|| format!("invalid value for --overlay: {overlay}"))?;
+                disks.push(SnapshotDisk {
+                    target_dev: dev.to_string(),
+                    mode: DiskSnapshotMode::External,
+                    file: Some(file.to_string()),
+                });
+            }
+            let params = CreateSnapshotParams {
+                kind,
+                disks,
+                memory_file: args.opt("memory-file").map(str::to_string),
+                quiesce: args.switch("quiesce"),
+                atomic: !args.switch("no-atomic"),
+                ..CreateSnapshotParams::new(
+                    args.pos(1, "name")?,
+                    args.opt("description").unwrap_or(""),
+                )
+            };
+            run_job(&format!("Snapshotting {}", vm.name), args, |job| {
+                backend::snapshot::create_snapshot_with_progress(uri, &vm.uuid, &params, job)
+            })?;
+            Ok(Output::done(format!("Created snapshot {} of {}", params.name, vm.name)))
+        }
+        "revert" => {
+            let vm = find_vm(uri, args.pos(0, "vm")?)?;
+            let name = args.pos(1, "name")?;
+            backend::snapshot::revert_snapshot(uri, &vm.uuid, name)?;
+            Ok(Output::done(format!("Reverted {} to {name}", vm.name)))
+        }
+        "delete" => {
+            let vm = find_vm(uri, args.pos(0, "vm")?)?;
+            let name = args.pos(1, "name")?;
+            run_job(&format!("Deleting snapshot {name}"), args, |job| {
+                backend::snapshot::delete_snapshot_with_progress(uri, &vm.uuid, name, job)
+            })?;
+            Ok(Output::done(format!("Deleted snapshot {name} of {}", vm.name)))
+        }
+        "diff" => {
+            let vm = find_vm(uri, args.pos(0, "vm")?)?;
+            let name = args.pos(1, "name")?;
+            let changes = backend::snapshot::compare_with_current(uri, &vm.uuid, name)?;
+            let text = if changes.is_empty() {
+                format!("{} is configured as at {name}", vm.name)
+            } else {
+                changes
+                    .iter()
+                    .map(|c| {
+                        let value = |v: &Option<String>| v.as_deref().unwrap_or("-").to_string();
+                        format!(
+                            "{:<8}  {:<24}  {} -> {}",
+                            c.section.label(),
+                            c.item,
+                            value(&c.before),
+                            value(&c.after)
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+            let json = changes
+                .iter()
+                .map(|c| {
+                    json!({
+                        "section": c.section.label(),
+                        "item": c.item,
+                        "before": c.before,
+                        "after": c.after,
+                    })
+                })
+                .collect();
+            Ok(Output {
+                json: Value::Array(json),
+                text,
+            })
+        }
+        _ => Err(CliError::Usage(
+            "snapshot subcommands: list, create, revert, delete, diff".to_string(),
+        )),
+    }
+}
+
+fn snapshot_json(snap: &SnapshotInfo) -> Value {
+    json!({
+        "name": snap.name,
+        "description": snap.description,
+        "state": snap.state.label(),
+        "creation_time": snap.creation_time,
+        "is_current": snap.is_current,
+        "parent": snap.parent,
+        "external": snap.is_external(),
+        "memory_file": snap.memory_file,
+        "disks": snap.disks.iter().map(|d| json!({
+            "target": d.target_dev,
+            "snapshot": d.mode.as_str(),
+            "file": d.file,
+        })).collect::<Vec<_>>(),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// pool / volume
+// ---------------------------------------------------------------------------
+
+fn pool_command(uri: &str, sub: &str, args: &Args) -> Result<Output, CliError> {
+    match sub {
+        "list" => {
+            let pools = backend::storage::list_all_pools(uri)?;
+            let text = pools
+                .iter()
+                .map(|p| format!("{:<36}  {:<10}  {}", p.uuid, p.state.label(), p.name))
+                .collect::<Vec<_>>()
+                .join("\n");
+            Ok(Output {
+                json: Value::Array(pools.iter().map(pool_json).collect()),
+                text,
+            })
+        }
+        "start" | "stop" | "refresh" | "delete" => {
+            let pool = find_pool(uri, args.pos(0, "pool")?)?;
+            match sub {
+                "start" => backend::storage::start_pool(uri, &pool.uuid)?,
+                "stop" => backend::storage::stop_pool(uri, &pool.uuid)?,
+                "refresh" => backend::storage::refresh_pool(uri, &pool.uuid)?,
+                _ => backend::storage::delete_pool(uri, &pool.uuid)?,
+            }
+            Ok(Output::done(format!("{sub}: {}", pool.name)))
+        }
+        "autostart" => {
+            let pool = find_pool(uri, args.pos(0, "pool")?)?;
+            let enabled = parse_on_off(args.pos(1, "on|off")?)?;
+            backend::storage::set_pool_autostart(uri, &pool.uuid, enabled)?;
+            Ok(Output::done(format!("Autostart {} for {}", on_off(enabled), pool.name)))
+        }
+        "create" => {
+            let name = args.pos(0, "name")?;
+            let pool_type = args.opt("type").unwrap_or("dir");
+            let params = PoolCreateParams {
+                target_path: args.opt("target").unwrap_or("").to_string(),
+                source_device: args.opt("source-device").unwrap_or("").to_string(),
+                source_host: args.opt("source-host").unwrap_or("").to_string(),
+                source_dir: args.opt("source-dir").unwrap_or("").to_string(),
+                source_name: args.opt("source-name").unwrap_or("").to_string(),
+                source_format: args.opt("source-format").unwrap_or("").to_string(),
+            };
+            backend::storage::create_pool(uri, name, pool_type, &params)?;
+            Ok(Output::done(format!("Created pool {name}")))
+        }
+        _ => Err(CliError::Usage(
+            "pool subcommands: list, start, stop, refresh, delete, autostart, create".to_string(),
+        )),
+    }
+}
+
+fn volume_command(uri: &str, sub: &str, args: &Args) -> Result<Output, CliError> {
+    let pool = find_pool(uri, args.pos(0, "pool")?)?;
+
+    match sub {
+        "list" => {
+            let volumes = backend::storage::list_pool_volumes(uri, &pool.uuid)?;
+            let text = volumes
+                .iter()
+                .map(|v| {
+                    format!(
+                        "{:<32}  {:>10}  {}",
+                        v.name,
+                        backend::types::format_bytes(v.capacity),
+                        v.path
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            Ok(Output {
+                json: Value::Array(volumes.iter().map(volume_json).collect()),
+                text,
+            })
+        }
+        "create" => {
+            let name = args.pos(1, "name")?;
+            let size_gib: u64 = args
+                .pos(2, "size-gib")?
+                .parse()
+                .map_err(|_| "invalid <size-gib>".to_string())?;
+            let format = args.opt("format").unwrap_or("qcow2");
+            backend::storage::create_volume(uri, &pool.uuid, name, size_gib * 1024 * 1024 * 1024, format)?;
+            Ok(Output::done(format!("Created volume {name} in {}", pool.name)))
+        }
+        "delete" => {
+            let name = args.pos(1, "name")?;
+            backend::storage::delete_volume(uri, &pool.uuid, name)?;
+            Ok(Output::done(format!("Deleted volume {name} from {}", pool.name)))
+        }
+        "upload" => {
+            let file = args.pos(1, "file")?;
+            let convert = args.switch("convert");
+            let name = match args.opt("name") {
+                Some(n) => n.to_string(),
+                None => {
+                    let name = std::path::Path::new(file)
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .ok_or_else(|| format!("cannot derive a volume name from {file}"))?;
+                    if convert && backend::storage::image_format(file)?.is_foreign() {
+                        backend::storage::qcow2_volume_name(&name)
+                    } else {
+                        name
+                    }
+                }
+            };
+            run_job(&format!("Uploading {name}"), args, |job| {
+                backend::storage::upload_volume_with_progress(
+                    uri, &pool.uuid, file, &name, convert, job,
+                )
+            })?;
+            Ok(Output::done(format!("Uploaded {file} to {}/{name}", pool.name)))
+        }
+        "download" => {
+            let name = args.pos(1, "name")?;
+            let file = args.pos(2, "file")?;
+            run_job(&format!("Downloading {name}"), args, |job| {
+                backend::storage::download_volume_with_progress(uri, &pool.uuid, name, file, job)
+            })?;
+            Ok(Output::done(format!("Downloaded {}/{name} to {file}", pool.name)))
+        }
+        "info" => {
+            let name = args.pos(1, "name")?;
+            let details = backend::storage::volume_details(uri, &pool.uuid, name)?;
+            let mut text = format!(
+                "{}  {}  {}\nformat {}, {} allocated of {}",
+                details.name,
+                details.kind,
+                details.path,
+                if details.format.is_empty() { "unknown" } else { &details.format },
+                backend::types::format_bytes(details.allocation),
+                backend::types::format_bytes(details.capacity),
+            );
+            for backing in &details.backing_chain {
+                text.push_str(&format!("\nbacked by {} ({})", backing.path, backing.format));
+            }
+            if !details.used_by.is_empty() {
+                text.push_str(&format!("\nused by {}", details.used_by.join(", ")));
+            }
+            Ok(Output {
+                json: json!({
+                    "name": details.name,
+                    "path": details.path,
+                    "type": details.kind.label(),
+                    "format": details.format,
+                    "capacity": details.capacity,
+                    "allocation": details.allocation,
+                    "backing_chain": details.backing_chain.iter().map(|b| json!({
+                        "path": b.path,
+                        "format": b.format,
+                    })).collect::<Vec<_>>(),
+                    "used_by": details.used_by,
+                }),
+                text,
+            })
+        }
+        "resize" => {
+            let name = args.pos(1, "name")?;
+            let size_gib: f64 = args
+                .pos(2, "size-gib")?
+                .parse()
+                .map_err(|_| "invalid <size-gib>".to_string())?;
+            let capacity = (size_gib * 1024.0 * 1024.0 * 1024.0) as u64;
+            backend::storage::resize_volume(
+                uri,
+                &pool.uuid,
+                name,
+                capacity,
+                args.switch("allocate"),
+                args.switch("shrink"),
+            )?;
+            Ok(Output::done(format!("Resized {}/{name} to {size_gib} GiB", pool.name)))
+        }
+        "wipe" => {
+            let name = args.pos(1, "name")?;
+            let algorithm = match args.opt("algorithm") {
+                None => WipeAlgorithm::Zero,
+                Some(a) => WipeAlgorithm::parse(a).ok_or_else(|| {
+                    let names: Vec<_> = WipeAlgorithm::ALL.iter().map(|a| a.as_str()).collect();
+                    format!("unknown --algorithm {a}; expected one of {}", names.join(", "))
+                })?,
+            };
+            run_job(&format!("Wiping {name}"), args, |_| {
+                backend::storage::wipe_volume(uri, &pool.uuid, name, algorithm)
+            })?;
+            Ok(Output::done(format!("Wiped {}/{name}", pool.name)))
+        }
+        "clone" => {
+            let name = args.pos(1, "name")?;
+            let new_name = args.pos(2, "new-name")?;
+            let dst_pool = match args.opt("to-pool") {
+                Some(p) => find_pool(uri, p)?,
+                None => pool.clone(),
+            };
+            run_job(&format!("Cloning {name}"), args, |job| {
+                backend::storage::clone_pool_volume(
+                    uri, &pool.uuid, name, &dst_pool.uuid, new_name, job,
+                )
+            })?;
+            Ok(Output::done(format!(
+                "Cloned {}/{name} to {}/{new_name}",
+                pool.name, dst_pool.name
+            )))
+        }
+        _ => Err(CliError::Usage(
+            "volume subcommands: list, create, delete, upload, download, info, resize, wipe, clone"
+                .to_string(),
+        )),
+    }
+}
+
+fn find_pool(uri: &str, key: &str) -> Result<PoolInfo, CliError> {
+    backend::storage::list_all_pools(uri)?
+        .into_iter()
+        .find(|p| p.uuid == key || p.name == key)
+        .ok_or_else(|| CliError::Usage(format!("no such pool: {key}")))
+}
+
+fn pool_json(pool: &PoolInfo) -> Value {
+    json!({
+        "name": pool.name,
+        "uuid": pool.uuid,
+        "state": pool.state.label(),
+        "active": pool.active,
+        "persistent": pool.persistent,
+        "autostart": pool.autostart,
+        "capacity": pool.capacity,
+        "allocation": pool.allocation,
+        "available": pool.available,
+    })
+}
+
+fn volume_json(vol: &VolumeInfo) -> Value {
+    json!({
+        "name": vol.name,
+        "path": vol.path,
+        "type": vol.kind.label(),
+        "capacity": vol.capacity,
+        "allocation": vol.allocation,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// network
+// ---------------------------------------------------------------------------
+
+fn network_command(uri: &str, sub: &str, args: &Args) -> Result<Output, CliError> {
+    match sub {
+        "list" => {
+            let networks = backend::network::list_all_networks(uri)?;
+            let text = networks
+                .iter()
+                .map(|n| format!("{:<36}  {:<10}  {:<10}  {}", n.uuid, n.state.label(), n.forward_mode.as_str(), n.name))
+                .collect::<Vec<_>>()
+                .join("\n");
+            Ok(Output {
+                json: Value::Array(networks.iter().map(network_json).collect()),
+                text,
+            })
+        }
+        "start" | "stop" | "delete" => {
+            let net = find_network(uri, args.pos(0, "network")?)?;
+            match sub {
+                "start" => backend::network::start_network(uri, &net.uuid)?,
+                "stop" => backend::network::stop_network(uri, &net.uuid)?,
+                _ => backend::network::delete_network(uri, &net.uuid)?,
+            }
+            Ok(Output::done(format!("{sub}: {}", net.name)))
+        }
+        "autostart" => {
+            let net = find_network(uri, args.pos(0, "network")?)?;
+            let enabled = parse_on_off(args.pos(1, "on|off")?)?;
+            backend::network::set_network_autostart(uri, &net.uuid, enabled)?;
+            Ok(Output::done(format!("Autostart {} for {}", on_off(enabled), net.name)))
+        }
+        "create" => {
+            let dhcp_start = args.opt("dhcp-start").unwrap_or("").to_string();
+            let dhcp_end = args.opt("dhcp-end").unwrap_or("").to_string();
+            let params = NetworkCreateParams {
+                name: args.pos(0, "name")?.to_string(),
+                forward_mode: ForwardMode::from_name(args.opt("forward").unwrap_or("nat")),
+                bridge_name: args.opt("bridge").unwrap_or("").to_string(),
+                ip_address: args.opt("ip").unwrap_or("").to_string(),
+                ip_netmask: args.opt("netmask").unwrap_or("255.255.255.0").to_string(),
+                dhcp_enabled: !dhcp_start.is_empty() && !dhcp_end.is_empty(),
+                dhcp_start,
+                dhcp_end,
+            };
+            backend::network::create_network(uri, &params)?;
+            Ok(Output::done(format!("Created network {}", params.name)))
+        }
+        _ => Err(CliError::Usage(
+            "network subcommands: list, start, stop, delete, autostart, create".to_string(),
+        )),
+    }
+}
+
+fn find_network(uri: &str, key: &str) -> Result<VirtNetworkInfo, CliError> {
+    backend::network::list_all_networks(uri)?
+        .into_iter()
+        .find(|n| n.uuid == key || n.name == key)
+        .ok_or_else(|| CliError::Usage(format!("no such network: {key}")))
+}
+
+fn network_json(net: &VirtNetworkInfo) -> Value {
+    json!({
+        "name": net.name,
+        "uuid": net.uuid,
+        "state": net.state.label(),
+        "active": net.active,
+        "persistent": net.persistent,
+        "autostart": net.autostart,
+        "forward_mode": net.forward_mode.as_str(),
+        "bridge": net.bridge_name,
+        "ip_address": net.ip_address,
+        "ip_netmask": net.ip_netmask,
+        "dhcp_start": net.dhcp_start,
+        "dhcp_end": net.dhcp_end,
+    })
+}
+
+// ---------------------------------------------------------------------------
+
+fn parse_on_off(value: &str) -> Result<bool, CliError> {
+    match value {
+        "on" | "true" | "yes" => Ok(true),
+        "off" | "false" | "no" => Ok(false),
+        other => Err(CliError::Usage(format!("expected on or off, got {other}"))),
+    }
+}
+
+fn on_off(enabled: bool) -> &'static str {
+    if enabled { "on" } else { "off" }
+}
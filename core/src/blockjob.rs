@@ -0,0 +1,230 @@
+//! Flattening disk backing chains with libvirt block jobs: committing
+//! overlays down into the images below them, or pulling the backing data up
+//! into the image a running VM writes to.
+
+use std::ffi::CString;
+
+use virt::domain::Domain;
+use virt::sys;
+
+use crate::connection::get_conn;
+use crate::domain::with_domain;
+use crate::error::AppError;
+use crate::jobs::{watch_block_job, Job};
+use crate::types::ChainMerge;
+
+/// The images of disk `target_dev`, starting with the one the VM writes to
+/// and followed by its backing images, nearest first.
+pub fn backing_chain(uri: &str, uuid: &str, target_dev: &str) -> Result<Vec<String>, AppError> {
+    with_domain(uri, uuid, |domain| disk_chain(domain, target_dev))
+}
+
+fn disk_chain(domain: &Domain, target_dev: &str) -> Result<Vec<String>, AppError> {
+    let details = crate::domain_xml::parse_domain_xml(&domain.get_xml_desc(0)?)?;
+    let disk = details
+        .disks
+        .into_iter()
+        .find(|d| d.target_dev == target_dev && d.device_type != "cdrom")
+        .ok_or_else(|| AppError::Backend(format!("VM has no disk {target_dev}")))?;
+    let top = disk
+        .source_file
+        .ok_or_else(|| AppError::Backend(format!("{target_dev} has no image")))?;
+    let mut chain = vec![top];
+    chain.extend(disk.backing_chain);
+    Ok(chain)
+}
+
+/// Where image `top` of a disk's `chain` (as from [`backing_chain`]) gets
+/// committed to: the image right below it, and whether `top` is the image
+/// the VM writes to, which takes an active commit and a pivot. `None` when
+/// `top` is not in the chain or nothing is below it.
+pub fn commit_target<'a>(chain: &'a [String], top: &str) -> Option<(&'a str, bool)> {
+    let index = chain.iter().position(|image| image == top)?;
+    let base = chain.get(index + 1)?;
+    Some((base, index == 0))
+}
+
+pub fn consolidate_disk(
+    uri: &str,
+    uuid: &str,
+    target_dev: &str,
+    merge: ChainMerge,
+    force: bool,
+) -> Result<(), AppError> {
+    let job = Job::detached("Consolidate");
+    consolidate_disk_with_progress(uri, uuid, target_dev, merge, force, &job)
+}
+
+/// Flatten the backing chain of disk `target_dev` of a running VM into a
+/// single image, as `merge` says. Progress is reported on `job`, and
+/// cancelling it leaves the chain as it was.
+///
+/// A commit is refused when other VMs use the base image, and, unless
+/// `force` is set, when external snapshots of the VM still refer to the
+/// overlays it deletes.
+pub fn consolidate_disk_with_progress(
+    uri: &str,
+    uuid: &str,
+    target_dev: &str,
+    merge: ChainMerge,
+    force: bool,
+    job: &Job,
+) -> Result<(), AppError> {
+    let unused = with_domain(uri, uuid, |domain| {
+        if !domain.is_active()? {
+            return Err(AppError::Backend(
+                "Start the VM to consolidate its disks; block jobs need it running".into(),
+            ));
+        }
+        let chain = disk_chain(domain, target_dev)?;
+        if chain.len() < 2 {
+            return Err(AppError::Backend(format!("{target_dev} has no backing images")));
+        }
+        let disk = c_string(target_dev)?;
+
+        match merge {
+            ChainMerge::Pull => {
+                job.set_detail(&format!("Pulling {} images into {}", chain.len() - 1, chain[0]));
+                watch_block_job(domain, target_dev, false, job, || {
+                    // SAFETY: `disk` is NUL-terminated and outlives the call
+                    unsafe { sys::virDomainBlockPull(domain.as_ptr(), disk.as_ptr(), 0, 0) }
+                })?;
+                // Other VMs or snapshots may still be built on them
+                Ok(Vec::new())
+            }
+            ChainMerge::Commit => {
+                let base = &chain[chain.len() - 1];
+                let overlays = &chain[..chain.len() - 1];
+                ensure_not_shared(domain, base)?;
+                if !force {
+                    ensure_no_snapshot_refers(domain, overlays)?;
+                }
+                job.set_detail(&format!("Committing {} images into {base}", chain.len() - 1));
+                watch_block_job(domain, target_dev, true, job, || {
+                    // SAFETY: as above; null base and top mean the whole chain
+                    unsafe {
+                        sys::virDomainBlockCommit(
+                            domain.as_ptr(),
+                            disk.as_ptr(),
+                            std::ptr::null(),
+                            std::ptr::null(),
+                            0,
+                            sys::VIR_DOMAIN_BLOCK_COMMIT_ACTIVE,
+                        )
+                    }
+                })?;
+                // The VM now writes to the base; nothing uses the overlays
+                Ok(overlays.to_vec())
+            }
+        }
+    })?;
+    remove_images(uri, &unused)
+}
+
+/// Commit image `top` of disk `target_dev` into the image below it and
+/// wait for that. Returns false, leaving everything as it was, when `top`
+/// is not part of the disk's current chain. Fails when other VMs use the
+/// image below.
+pub(crate) fn commit_image(
+    domain: &Domain,
+    target_dev: &str,
+    top: &str,
+    job: &Job,
+) -> Result<bool, AppError> {
+    let chain = disk_chain(domain, target_dev)?;
+    let Some((base, active)) = commit_target(&chain, top) else {
+        log::warn!("{top} is not above another image of {target_dev}; not committing it");
+        return Ok(false);
+    };
+    ensure_not_shared(domain, base)?;
+    let disk = c_string(target_dev)?;
+    let top_c = c_string(top)?;
+    let base_c = c_string(base)?;
+    let flags = if active { sys::VIR_DOMAIN_BLOCK_COMMIT_ACTIVE } else { 0 };
+    watch_block_job(domain, target_dev, active, job, || {
+        // SAFETY: the strings are NUL-terminated and outlive the call
+        unsafe {
+            sys::virDomainBlockCommit(
+                domain.as_ptr(),
+                disk.as_ptr(),
+                base_c.as_ptr(),
+                top_c.as_ptr(),
+                0,
+                flags,
+            )
+        }
+    })?;
+    Ok(true)
+}
+
+/// Fail if VMs other than `domain` have disks on `image` or built on it.
+/// Writing into it would change the data they see.
+fn ensure_not_shared(domain: &Domain, image: &str) -> Result<(), AppError> {
+    let conn = domain.get_connect()?;
+    let users = crate::storage::image_users(&conn, image, &domain.get_uuid_string()?)?;
+    if users.is_empty() {
+        return Ok(());
+    }
+    Err(AppError::Backend(format!(
+        "{image} is also used by {}; committing into it would change their disks. \
+         Pull the data into the top image instead",
+        users.join(", ")
+    )))
+}
+
+/// Fail if external snapshots of `domain` refer to any of `images`; their
+/// metadata would point at deleted files.
+fn ensure_no_snapshot_refers(domain: &Domain, images: &[String]) -> Result<(), AppError> {
+    let mut snapshots = Vec::new();
+    for snap in domain.list_all_snapshots(0)? {
+        let Some(info) = crate::snapshot::parse_snapshot_xml(&snap.get_xml_desc(0)?) else {
+            continue;
+        };
+        let refers = info
+            .disks
+            .iter()
+            .filter_map(|d| d.file.as_deref())
+            .any(|file| images.iter().any(|image| image == file));
+        if refers {
+            snapshots.push(info.name);
+        }
+    }
+    if snapshots.is_empty() {
+        return Ok(());
+    }
+    Err(AppError::Backend(format!(
+        "Snapshots {} use the overlays a commit would delete; delete them first or force it",
+        snapshots.join(", ")
+    )))
+}
+
+/// Delete the images at `paths` through their storage pools. The active
+/// pools are refreshed first, so that files libvirt created on its own
+/// (snapshot overlays, memory files) are known to them.
+pub(crate) fn remove_images(uri: &str, paths: &[String]) -> Result<(), AppError> {
+    if paths.is_empty() {
+        return Ok(());
+    }
+    let conn = get_conn(uri)?;
+    for pool in conn.list_all_storage_pools(sys::VIR_CONNECT_LIST_STORAGE_POOLS_ACTIVE)? {
+        let _ = pool.refresh(0);
+    }
+    let errors: Vec<String> = paths
+        .iter()
+        .filter_map(|path| {
+            let e = crate::storage::delete_volume_by_path(uri, path).err()?;
+            Some(format!("{path}: {e}"))
+        })
+        .collect();
+    if !errors.is_empty() {
+        return Err(AppError::Backend(format!(
+            "Merged, but some images could not be removed: {}",
+            errors.join("; ")
+        )));
+    }
+    Ok(())
+}
+
+fn c_string(s: &str) -> Result<CString, AppError> {
+    CString::new(s).map_err(|_| AppError::Backend(format!("Bad name {s:?}")))
+}
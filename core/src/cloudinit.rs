@@ -0,0 +1,112 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Volume label cloud-init's NoCloud datasource looks for.
+pub const SEED_VOLUME_ID: &str = "cidata";
+
+/// A user account cloud-init creates on first boot.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CloudInitUser {
+    pub name: String,
+    pub ssh_authorized_keys: Vec<String>,
+    /// Passwordless sudo.
+    pub sudo: bool,
+}
+
+/// First-boot provisioning for a VM created from a cloud image, delivered as
+/// a NoCloud `cidata` seed ISO.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CloudInitConfig {
+    pub hostname: String,
+    pub users: Vec<CloudInitUser>,
+    /// Keys for the image's default user ("ubuntu", "fedora", ...).
+    pub ssh_authorized_keys: Vec<String>,
+    pub packages: Vec<String>,
+    /// Shell commands run once at the end of first boot.
+    pub runcmd: Vec<String>,
+}
+
+impl CloudInitConfig {
+    /// The `user-data` file: a `#cloud-config` document.
+    pub fn user_data(&self) -> String {
+        let mut out = String::from("#cloud-config\n");
+        if !self.hostname.is_empty() {
+            out.push_str(&format!("hostname: {}\n", yaml_str(&self.hostname)));
+        }
+        if !self.ssh_authorized_keys.is_empty() {
+            out.push_str("ssh_authorized_keys:\n");
+            push_list(&mut out, "  ", &self.ssh_authorized_keys);
+        }
+        if !self.users.is_empty() {
+            // "default" keeps the image's own user alongside ours
+            out.push_str("users:\n  - default\n");
+            for user in &self.users {
+                out.push_str(&format!("  - name: {}\n", yaml_str(&user.name)));
+                out.push_str("    shell: /bin/bash\n");
+                if user.sudo {
+                    out.push_str("    sudo: \"ALL=(ALL) NOPASSWD:ALL\"\n");
+                }
+                if !user.ssh_authorized_keys.is_empty() {
+                    out.push_str("    ssh_authorized_keys:\n");
+                    push_list(&mut out, "      ", &user.ssh_authorized_keys);
+                }
+            }
+        }
+        if !self.packages.is_empty() {
+            out.push_str("package_update: true\npackages:\n");
+            push_list(&mut out, "  ", &self.packages);
+        }
+        if !self.runcmd.is_empty() {
+            out.push_str("runcmd:\n");
+            push_list(&mut out, "  ", &self.runcmd);
+        }
+        out
+    }
+
+    /// The `meta-data` file. cloud-init only provisions again when the
+    /// instance id changes, so it is unique per creation.
+    pub fn meta_data(&self, vm_name: &str) -> String {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let hostname = if self.hostname.is_empty() { vm_name } else { &self.hostname };
+        format!(
+            "instance-id: {}\nlocal-hostname: {}\n",
+            yaml_str(&format!("iid-{vm_name}-{stamp}")),
+            yaml_str(hostname)
+        )
+    }
+
+    /// A complete seed image for `vm_name`.
+    pub fn seed_iso(&self, vm_name: &str) -> Vec<u8> {
+        crate::iso9660::build_iso(SEED_VOLUME_ID, &[
+            ("meta-data", self.meta_data(vm_name).as_bytes()),
+            ("user-data", self.user_data().as_bytes()),
+        ])
+    }
+}
+
+fn push_list(out: &mut String, indent: &str, items: &[String]) {
+    for item in items {
+        out.push_str(&format!("{indent}- {}\n", yaml_str(item)));
+    }
+}
+
+/// A double-quoted YAML scalar. JSON escaping is valid YAML, and quoting
+/// everything keeps values like `yes`, `1.10` or `#x` from changing type.
+fn yaml_str(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
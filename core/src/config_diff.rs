@@ -0,0 +1,197 @@
+//! Comparing two parsed configurations of a VM, such as the one a snapshot
+//! recorded and the current one.
+
+use crate::types::{ConfigChange, ConfigSection, DomainDetails};
+
+/// The differences from `before` to `after`, in the order of
+/// [`ConfigSection`]. Devices are matched by what identifies them (a disk
+/// by its target, a NIC by its MAC address), so a device that changed is
+/// one change rather than a removal and an addition.
+pub fn diff_details(before: &DomainDetails, after: &DomainDetails) -> Vec<ConfigChange> {
+    let mut diff = Diff(Vec::new());
+
+    diff.keyed(ConfigSection::General, general(before), general(after));
+    diff.keyed(ConfigSection::Cpu, cpu(before), cpu(after));
+    diff.keyed(ConfigSection::Memory, memory(before), memory(after));
+    diff.keyed(ConfigSection::Disks, disks(before), disks(after));
+    diff.keyed(ConfigSection::Network, nics(before), nics(after));
+    diff.keyed(ConfigSection::Devices, devices(before), devices(after));
+    diff.0
+}
+
+/// (item, description) pairs; items are unique within one list.
+type Items = Vec<(String, String)>;
+
+struct Diff(Vec<ConfigChange>);
+
+impl Diff {
+    /// Changed and removed items in the order of `before`, then added ones.
+    fn keyed(&mut self, section: ConfigSection, before: Items, after: Items) {
+        for (item, old) in &before {
+            let new = after.iter().find(|(i, _)| i == item).map(|(_, new)| new);
+            if new != Some(old) {
+                self.push(section, item, Some(old), new);
+            }
+        }
+        for (item, new) in &after {
+            if !before.iter().any(|(i, _)| i == item) {
+                self.push(section, item, None, Some(new));
+            }
+        }
+    }
+
+    fn push(
+        &mut self,
+        section: ConfigSection,
+        item: &str,
+        before: Option<&String>,
+        after: Option<&String>,
+    ) {
+        self.0.push(ConfigChange {
+            section,
+            item: item.to_string(),
+            before: before.cloned(),
+            after: after.cloned(),
+        });
+    }
+}
+
+fn item(name: &str, value: impl Into<String>) -> (String, String) {
+    (name.to_string(), value.into())
+}
+
+/// Number repeated items ("USB Tablet", "USB Tablet (2)") so that each
+/// can be matched on its own.
+fn numbered(items: Items) -> Items {
+    let mut seen: Vec<String> = Vec::new();
+    items
+        .into_iter()
+        .map(|(name, value)| {
+            let count = seen.iter().filter(|s| **s == name).count();
+            seen.push(name.clone());
+            if count == 0 {
+                (name, value)
+            } else {
+                (format!("{name} ({})", count + 1), value)
+            }
+        })
+        .collect()
+}
+
+fn general(d: &DomainDetails) -> Items {
+    let boot_order: Vec<&str> = d.boot_order.iter().map(|b| b.label()).collect();
+    vec![
+        item("Firmware", d.firmware.label()),
+        item("Boot Order", boot_order.join(", ")),
+    ]
+}
+
+fn cpu(d: &DomainDetails) -> Items {
+    let model = match &d.cpu_model {
+        Some(model) => format!("{} ({model})", d.cpu_mode.label()),
+        None => d.cpu_mode.label().to_string(),
+    };
+    let mut items = vec![item("vCPUs", d.vcpus.to_string()), item("CPU Model", model)];
+    for pin in &d.cpu_tune.vcpu_pins {
+        items.push(item(&format!("vCPU {} Pinning", pin.vcpu), pin.cpuset.clone()));
+    }
+    if let Some(cpuset) = &d.cpu_tune.emulatorpin {
+        items.push(item("Emulator Pinning", cpuset.clone()));
+    }
+    items
+}
+
+fn memory(d: &DomainDetails) -> Items {
+    vec![item("Memory", format!("{} MiB", d.memory_kib / 1024))]
+}
+
+fn disks(d: &DomainDetails) -> Items {
+    d.disks
+        .iter()
+        .map(|disk| {
+            let source = disk.source_file.as_deref().unwrap_or("No media");
+            let kind = if disk.device_type == "cdrom" { "CD-ROM, " } else { "" };
+            item(&format!("/dev/{}", disk.target_dev), format!("{source} ({kind}{})", disk.bus))
+        })
+        .collect()
+}
+
+fn nics(d: &DomainDetails) -> Items {
+    let items = d
+        .networks
+        .iter()
+        .enumerate()
+        .map(|(i, nic)| {
+            let name = match &nic.mac_address {
+                Some(mac) => format!("NIC {mac}"),
+                None => format!("NIC {}", i + 1),
+            };
+            let model = nic.model_type.as_deref().unwrap_or("default model");
+            item(&name, format!("{} ({model})", nic.display_source()))
+        })
+        .collect();
+    numbered(items)
+}
+
+fn devices(d: &DomainDetails) -> Items {
+    let mut items = Vec::new();
+    if let Some(graphics) = &d.graphics {
+        items.push(item("Graphics", graphics.graphics_type.label()));
+    }
+    if let Some(video) = &d.video {
+        let accel = if video.accel3d { " with 3D acceleration" } else { "" };
+        items.push(item("Video", format!("{}{accel}", video.model.label())));
+    }
+    if let Some(sound) = &d.sound {
+        items.push(item("Sound", sound.model.label()));
+    }
+    if let Some(tpm) = &d.tpm {
+        items.push(item("TPM", format!("{} {}", tpm.model.label(), tpm.version)));
+    }
+    if let Some(rng) = &d.rng {
+        items.push(item("RNG", rng.label()));
+    }
+    if let Some(watchdog) = &d.watchdog {
+        items.push(item(
+            "Watchdog",
+            format!("{}, {}", watchdog.model.label(), watchdog.action.label()),
+        ));
+    }
+    if let Some(panic) = &d.panic {
+        items.push(item("Panic Notifier", panic.label()));
+    }
+    if let Some(smartcard) = &d.smartcard {
+        items.push(item("Smartcard", smartcard.label()));
+    }
+    if let Some(memballoon) = &d.memballoon {
+        items.push(item("Memory Balloon", memballoon.label()));
+    }
+    for fs in &d.filesystems {
+        items.push(item(
+            &format!("Shared Folder {}", fs.target_dir),
+            format!("{} ({})", fs.source_dir, fs.driver),
+        ));
+    }
+    for hostdev in &d.hostdevs {
+        items.push(item(&hostdev.display_name, hostdev.display_subtitle()));
+    }
+    for serial in &d.serials {
+        items.push(item(&serial.display_name(), serial.display_subtitle()));
+    }
+    for input in &d.inputs {
+        items.push(item(&input.display_name(), input.display_subtitle()));
+    }
+    for channel in &d.channels {
+        items.push(item(&channel.display_name(), channel.display_subtitle()));
+    }
+    for controller in &d.controllers {
+        items.push(item(&controller.display_name(), controller.display_subtitle()));
+    }
+    for parallel in &d.parallels {
+        items.push(item(&parallel.display_name(), parallel.display_subtitle()));
+    }
+    for usbredir in &d.usbredirs {
+        items.push(item(&usbredir.display_name(), usbredir.display_subtitle()));
+    }
+    numbered(items)
+}
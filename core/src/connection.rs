@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::{Mutex, OnceLock};
+use virt::connect::Connect;
+use crate::types::{HostInfo, VmInfo, VmState};
+use crate::error::AppError;
+
+// ---------------------------------------------------------------------------
+// Connection registry
+//
+// libvirt connections use several file descriptors each (Unix socket + internal
+// pipes for the event loop). Opening a new connection on every 2-second poll
+// tick exhausts the process fd limit over hours of use.
+//
+// Instead we keep one connection per URI in a registry, so several hosts
+// (qemu:///system, qemu+ssh://..., test:///default) can stay connected at the
+// same time. Each caller gets a cheap virConnectRef clone — no new socket is
+// opened. A registry entry keeps the underlying connection alive between
+// calls. Dead entries are reopened on the next call for that URI only; the
+// other hosts are left untouched.
+// ---------------------------------------------------------------------------
+
+static CONN_REGISTRY: OnceLock<Mutex<HashMap<String, Connect>>> = OnceLock::new();
+
+fn conn_registry() -> &'static Mutex<HashMap<String, Connect>> {
+    CONN_REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// A reference to a registered connection, released again when dropped.
+/// `Connect` itself never closes, so every reference taken with
+/// virConnectRef would otherwise keep the socket open for good.
+pub struct Conn(Connect);
+
+impl Deref for Conn {
+    type Target = Connect;
+
+    fn deref(&self) -> &Connect {
+        &self.0
+    }
+}
+
+impl Drop for Conn {
+    fn drop(&mut self) {
+        let _ = self.0.close();
+    }
+}
+
+/// Return a reference to the registered connection for `uri`.
+/// Opens a new connection (and registers it) if none exists or the existing
+/// one is no longer alive. The registry is not locked while opening, so a
+/// slow or unreachable host does not hold up calls to the others.
+pub fn get_conn(uri: &str) -> Result<Conn, AppError> {
+    if let Some(conn) = registered_conn(uri) {
+        return Ok(conn);
+    }
+
+    let conn = Connect::open(Some(uri))?;
+    let mut guard = conn_registry()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    match guard.get(uri) {
+        // Another thread opened one meanwhile; keep that and close ours
+        Some(theirs) if theirs.is_alive().unwrap_or(false) => {
+            let theirs = Conn(theirs.clone());
+            drop(guard);
+            drop(Conn(conn));
+            Ok(theirs)
+        }
+        _ => {
+            // Clone increments virConnectRef; the registry entry keeps the
+            // master open.
+            let ours = Conn(conn.clone());
+            let dead = guard.insert(uri.to_string(), conn);
+            drop(guard);
+            drop(dead.map(Conn));
+            Ok(ours)
+        }
+    }
+}
+
+/// The registered connection for `uri`, if it is still alive. A dead one
+/// is removed and closed.
+fn registered_conn(uri: &str) -> Option<Conn> {
+    let mut guard = conn_registry()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    match guard.get(uri) {
+        Some(conn) if conn.is_alive().unwrap_or(false) => Some(Conn(conn.clone())),
+        Some(_) => {
+            let dead = guard.remove(uri);
+            drop(guard);
+            drop(dead.map(Conn));
+            None
+        }
+        None => None,
+    }
+}
+
+/// Open (or reuse) the connection for `uri` so that it shows up in the
+/// registry. Used when a host is added in the connection manager to fail
+/// early on a bad URI or missing credentials.
+pub fn open_conn(uri: &str) -> Result<(), AppError> {
+    get_conn(uri).map(|_| ())
+}
+
+/// Pass `result` through, dropping the registered connection for `uri` first
+/// if the call failed because the connection itself is gone. Other errors
+/// (missing domain, wrong state, ...) leave the cached connection alone.
+pub(crate) fn check_conn<T>(uri: &str, result: Result<T, AppError>) -> Result<T, AppError> {
+    if matches!(result, Err(ref e) if e.is_connection_lost()) {
+        log::warn!("Connection to {uri} lost; dropping it from the registry");
+        invalidate_conn(uri);
+    }
+    result
+}
+
+/// Close the registered connection for `uri` (e.g. after a fatal error or
+/// explicit disconnect). The socket goes once the callers still holding a
+/// [`Conn`] drop theirs. The next call to `get_conn` will open a fresh
+/// connection for that URI.
+pub fn invalidate_conn(uri: &str) {
+    let removed = conn_registry()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(uri);
+    drop(removed.map(Conn));
+}
+
+// ---------------------------------------------------------------------------
+
+pub fn get_host_info(uri: &str) -> Result<HostInfo, AppError> {
+    let conn = get_conn(uri)?;
+
+    let hostname = conn.get_hostname().unwrap_or_else(|_| "Unknown".to_string());
+    let node_info = conn.get_node_info()?;
+
+    let lib_version = conn.get_lib_version().unwrap_or(0);
+    let lib_major = lib_version / 1_000_000;
+    let lib_minor = (lib_version / 1_000) % 1_000;
+    let lib_micro = lib_version % 1_000;
+    let libvirt_version = format!("{lib_major}.{lib_minor}.{lib_micro}");
+
+    let hv_version = conn.get_hyp_version().unwrap_or(0);
+    let hv_major = hv_version / 1_000_000;
+    let hv_minor = (hv_version / 1_000) % 1_000;
+    let hv_micro = hv_version % 1_000;
+    let hypervisor_version = format!("{hv_major}.{hv_minor}.{hv_micro}");
+
+    Ok(HostInfo {
+        hostname,
+        uri: uri.to_string(),
+        libvirt_version,
+        hypervisor_version,
+        cpu_model: node_info.model.clone(),
+        cpu_cores: node_info.cores,
+        cpu_threads: node_info.threads,
+        cpu_mhz: node_info.mhz,
+        cpu_sockets: node_info.sockets,
+        cpu_nodes: node_info.nodes,
+        memory_kib: node_info.memory,
+    })
+}
+
+pub fn list_all_vms(uri: &str) -> Result<Vec<VmInfo>, AppError> {
+    let conn = get_conn(uri)?;
+
+    let flags = virt::sys::VIR_CONNECT_LIST_DOMAINS_ACTIVE
+        | virt::sys::VIR_CONNECT_LIST_DOMAINS_INACTIVE;
+
+    let domains = conn.list_all_domains(flags)?;
+    let mut vms = Vec::with_capacity(domains.len());
+
+    for domain in &domains {
+        let name = domain.get_name()?;
+        let uuid = domain.get_uuid_string()?;
+        let info = domain.get_info()?;
+        let mut state = VmState::from_libvirt(info.state);
+        if state == VmState::Shutoff && domain.has_managed_save(0).unwrap_or(false) {
+            state = VmState::Saved;
+        }
+        let id = if info.state == 1 {
+            domain.get_id()
+        } else {
+            None
+        };
+
+        // One unreadable (or meanwhile vanished) domain must not fail the list
+        let is_template = crate::template::domain_template(domain)
+            .unwrap_or_else(|e| {
+                log::warn!("Cannot tell whether {name} is a template: {e}");
+                None
+            })
+            .is_some();
+
+        vms.push(VmInfo {
+            name,
+            uuid,
+            state,
+            vcpus: info.nr_virt_cpu,
+            memory_kib: info.memory,
+            id,
+            is_template,
+        });
+    }
+
+    vms.sort_by_key(|a| a.name.to_lowercase());
+    Ok(vms)
+}
@@ -0,0 +1,508 @@
+use virt::domain::Domain;
+use virt::storage_pool::StoragePool;
+use virt::storage_vol::StorageVol;
+use crate::connection::{check_conn, get_conn};
+use crate::domain_model::DomainXml;
+use crate::error::AppError;
+use crate::jobs::{watch_domain_job, Job};
+use crate::types::{CloneParams, DiskSource, GuestResize, NvramClone};
+
+pub(crate) fn with_domain<F, R>(uri: &str, uuid: &str, f: F) -> Result<R, AppError>
+where
+    F: FnOnce(&Domain) -> Result<R, AppError>,
+{
+    let conn = get_conn(uri)?;
+    let result = Domain::lookup_by_uuid_string(&conn, uuid)
+        .map_err(AppError::from)
+        .and_then(|domain| f(&domain));
+    check_conn(uri, result)
+}
+
+pub fn start_vm(uri: &str, uuid: &str) -> Result<(), AppError> {
+    with_domain(uri, uuid, |domain| {
+        crate::template::ensure_not_template(domain)?;
+        domain.create()?;
+        Ok(())
+    })
+}
+
+pub fn shutdown_vm(uri: &str, uuid: &str) -> Result<(), AppError> {
+    with_domain(uri, uuid, |domain| {
+        domain.shutdown()?;
+        Ok(())
+    })
+}
+
+pub fn force_stop_vm(uri: &str, uuid: &str) -> Result<(), AppError> {
+    with_domain(uri, uuid, |domain| {
+        domain.destroy()?;
+        Ok(())
+    })
+}
+
+pub fn pause_vm(uri: &str, uuid: &str) -> Result<(), AppError> {
+    with_domain(uri, uuid, |domain| {
+        domain.suspend()?;
+        Ok(())
+    })
+}
+
+pub fn resume_vm(uri: &str, uuid: &str) -> Result<(), AppError> {
+    with_domain(uri, uuid, |domain| {
+        domain.resume()?;
+        Ok(())
+    })
+}
+
+pub fn reboot_vm(uri: &str, uuid: &str) -> Result<(), AppError> {
+    with_domain(uri, uuid, |domain| {
+        domain.reboot(0)?;
+        Ok(())
+    })
+}
+
+pub fn managed_save_vm(uri: &str, uuid: &str) -> Result<(), AppError> {
+    managed_save_vm_with_progress(uri, uuid, &Job::detached("Save state"))
+}
+
+/// Save the VM's memory to libvirt's managed save image and stop it. The
+/// next start resumes from the image instead of booting.
+pub fn managed_save_vm_with_progress(uri: &str, uuid: &str, job: &Job) -> Result<(), AppError> {
+    with_domain(uri, uuid, |domain| {
+        watch_domain_job(domain, job, || {
+            domain.managed_save(0)?;
+            Ok(())
+        })
+    })
+}
+
+/// Drop the managed save image, so the next start boots afresh.
+pub fn discard_saved_state(uri: &str, uuid: &str) -> Result<(), AppError> {
+    with_domain(uri, uuid, |domain| {
+        domain.managed_save_remove(0)?;
+        Ok(())
+    })
+}
+
+pub fn save_vm_to_file(uri: &str, uuid: &str, path: &str) -> Result<(), AppError> {
+    save_vm_to_file_with_progress(uri, uuid, path, &Job::detached("Save to file"))
+}
+
+/// Save the VM's memory to `path` and stop it. The path is on the host the
+/// connection points at; [`restore_vm_from_file`] starts the VM from it.
+pub fn save_vm_to_file_with_progress(
+    uri: &str,
+    uuid: &str,
+    path: &str,
+    job: &Job,
+) -> Result<(), AppError> {
+    let c_path = std::ffi::CString::new(path)
+        .map_err(|_| AppError::Backend(format!("Invalid path: {path}")))?;
+    with_domain(uri, uuid, |domain| {
+        watch_domain_job(domain, job, || {
+            // SAFETY: the domain pointer and `c_path` outlive the call; a
+            // null dxml keeps the domain's own definition.
+            let ret = unsafe {
+                virt::sys::virDomainSaveFlags(
+                    domain.as_ptr(),
+                    c_path.as_ptr(),
+                    std::ptr::null(),
+                    0,
+                )
+            };
+            if ret < 0 {
+                return Err(virt::error::Error::last_error().into());
+            }
+            Ok(())
+        })
+    })
+}
+
+/// Start the VM saved in `path` (on the connection's host) from where it
+/// was saved.
+pub fn restore_vm_from_file(uri: &str, path: &str) -> Result<(), AppError> {
+    let c_path = std::ffi::CString::new(path)
+        .map_err(|_| AppError::Backend(format!("Invalid path: {path}")))?;
+    let conn = get_conn(uri)?;
+    // SAFETY: the connection pointer and `c_path` outlive the call.
+    let ret = unsafe { virt::sys::virDomainRestore(conn.as_ptr(), c_path.as_ptr()) };
+    if ret < 0 {
+        return check_conn(uri, Err(virt::error::Error::last_error().into()));
+    }
+    Ok(())
+}
+
+pub fn delete_vm(uri: &str, uuid: &str) -> Result<(), AppError> {
+    with_domain(uri, uuid, |domain| {
+        // Try to destroy if running; ignore error if already stopped.
+        let _ = domain.destroy();
+        // Use undefine_flags to handle EFI VMs (NVRAM), snapshot metadata,
+        // managed save state, and checkpoint metadata cleanly.
+        let flags = virt::sys::VIR_DOMAIN_UNDEFINE_MANAGED_SAVE
+            | virt::sys::VIR_DOMAIN_UNDEFINE_SNAPSHOTS_METADATA
+            | virt::sys::VIR_DOMAIN_UNDEFINE_NVRAM
+            | virt::sys::VIR_DOMAIN_UNDEFINE_CHECKPOINTS_METADATA;
+        domain.undefine_flags(flags)?;
+        Ok(())
+    })
+}
+
+/// Return the source file paths of all non-cdrom disks attached to the VM.
+pub fn get_vm_disk_paths(uri: &str, uuid: &str) -> Result<Vec<String>, AppError> {
+    let xml = get_domain_xml(uri, uuid)?;
+    let details = crate::domain_xml::parse_domain_xml(&xml)?;
+    let paths = details
+        .disks
+        .into_iter()
+        .filter(|d| d.device_type != "cdrom")
+        .filter_map(|d| d.source_file)
+        .collect();
+    Ok(paths)
+}
+
+/// Grow disk `target_dev` by `grow_by` bytes. A running VM's disk is grown
+/// live with `virDomainBlockResize` and, with `grow_filesystems`, the guest
+/// agent then grows the partitions and filesystems on it. A shut-off VM's
+/// volume is resized in its pool. Returns what happened inside the guest.
+pub fn grow_disk(
+    uri: &str,
+    uuid: &str,
+    target_dev: &str,
+    grow_by: u64,
+    grow_filesystems: bool,
+) -> Result<GuestResize, AppError> {
+    with_domain(uri, uuid, |domain| {
+        let xml = domain.get_xml_desc(0)?;
+        let details = crate::domain_xml::parse_domain_xml(&xml)?;
+        let disk = details
+            .disks
+            .iter()
+            .find(|d| d.target_dev == target_dev && d.device_type != "cdrom")
+            .ok_or_else(|| AppError::Backend(format!("VM has no disk {target_dev}")))?;
+        let conn = domain.get_connect()?;
+
+        if !domain.is_active()? {
+            let path = disk.source_file.as_deref().ok_or_else(|| {
+                AppError::Backend(format!("{target_dev} has no image to resize"))
+            })?;
+            let vol = StorageVol::lookup_by_path(&conn, path).map_err(|_| {
+                AppError::Backend(format!("{path} is not in a storage pool"))
+            })?;
+            let capacity = vol.get_info()?.capacity;
+            vol.resize(capacity + grow_by, 0)?;
+            return Ok(GuestResize::NotRequested);
+        }
+
+        let capacity = domain.get_block_info(target_dev, 0)?.capacity;
+        domain.block_resize(
+            target_dev,
+            capacity + grow_by,
+            virt::sys::VIR_DOMAIN_BLOCK_RESIZE_BYTES,
+        )?;
+        // Let the pool pick up the volume's new capacity
+        if let Some(path) = &disk.source_file {
+            if let Ok(vol) = StorageVol::lookup_by_path(&conn, path) {
+                if let Ok(pool) = StoragePool::lookup_by_volume(&vol) {
+                    let _ = pool.refresh(0);
+                }
+            }
+        }
+        if !grow_filesystems {
+            return Ok(GuestResize::NotRequested);
+        }
+        Ok(crate::guest_agent::grow_filesystems(domain, target_dev))
+    })
+}
+
+/// Undefine a VM and optionally delete the given storage volumes by path.
+pub fn delete_vm_with_storage(
+    uri: &str,
+    uuid: &str,
+    vol_paths: Vec<String>,
+) -> Result<(), AppError> {
+    delete_vm(uri, uuid)?;
+    let mut errors: Vec<String> = Vec::new();
+    for path in &vol_paths {
+        if let Err(e) = crate::storage::delete_volume_by_path(uri, path) {
+            errors.push(format!("{path}: {e}"));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(AppError::Backend(format!(
+            "VM deleted but some volumes could not be removed: {}",
+            errors.join("; ")
+        )));
+    }
+    Ok(())
+}
+
+pub fn get_domain_xml(uri: &str, uuid: &str) -> Result<String, AppError> {
+    with_domain(uri, uuid, |domain| {
+        let xml = domain.get_xml_desc(0)?;
+        Ok(xml)
+    })
+}
+
+pub fn get_domain_name(uri: &str, uuid: &str) -> Result<String, AppError> {
+    with_domain(uri, uuid, |domain| {
+        let name = domain.get_name()?;
+        Ok(name)
+    })
+}
+
+pub fn update_domain_xml(uri: &str, xml: &str) -> Result<(), AppError> {
+    let conn = get_conn(uri)?;
+    Domain::define_xml(&conn, xml)?;
+    Ok(())
+}
+
+pub fn get_autostart(uri: &str, uuid: &str) -> Result<bool, AppError> {
+    with_domain(uri, uuid, |domain| {
+        let autostart = domain.get_autostart()?;
+        Ok(autostart)
+    })
+}
+
+pub fn set_autostart(uri: &str, uuid: &str, enabled: bool) -> Result<(), AppError> {
+    with_domain(uri, uuid, |domain| {
+        if enabled {
+            crate::template::ensure_not_template(domain)?;
+        }
+        domain.set_autostart(enabled)?;
+        Ok(())
+    })
+}
+
+pub fn list_networks(uri: &str) -> Result<Vec<String>, AppError> {
+    let conn = get_conn(uri)?;
+    let networks = conn.list_networks()?;
+    Ok(networks)
+}
+
+/// Open the VM console: `grustyvman-viewer` for SPICE, otherwise `virt-viewer`.
+pub fn launch_console(uri: &str, uuid: &str) -> Result<(), AppError> {
+    // Fetch the running domain XML (with VIR_DOMAIN_XML_SECURE=1 to get passwd)
+    let xml = with_domain(uri, uuid, |domain| Ok(domain.get_xml_desc(1)?))?;
+    let details = crate::domain_xml::parse_domain_xml(&xml)?;
+
+    match details.graphics {
+        Some(ref g) if g.graphics_type == crate::types::GraphicsType::Spice => {
+            let port: i32 = match g.port {
+                Some(p) if p > 0 => p,
+                _ => {
+                    return Err(AppError::Backend(
+                        "SPICE port not yet allocated — is the VM running?".to_string(),
+                    ))
+                }
+            };
+            // Resolve the host: treat 0.0.0.0 and empty as localhost
+            let host = g
+                .listen_address
+                .as_deref()
+                .map(|a: &str| if a == "0.0.0.0" || a.is_empty() { "127.0.0.1" } else { a })
+                .unwrap_or("127.0.0.1");
+
+            let viewer = find_viewer_binary();
+            eprintln!("grustyvman: launching viewer binary {}", viewer.display());
+            log::debug!("Launching SPICE viewer: {}", viewer.display());
+            let name = get_domain_name(uri, uuid)?;
+            let mut cmd = std::process::Command::new(&viewer);
+            cmd.arg("--host").arg(host)
+                .arg("--port").arg(port.to_string())
+                .arg("--uri").arg(uri)
+                .arg("--uuid").arg(uuid)
+                .arg("--title").arg(format!("{name} — SPICE Console"));
+            if let Some(ref pw) = g.password {
+                cmd.arg("--password").arg(pw);
+            }
+            cmd.spawn()?;
+        }
+        _ => {
+            // VNC or no graphics: fall back to virt-viewer
+            let name = get_domain_name(uri, uuid)?;
+            std::process::Command::new("virt-viewer")
+                .arg("--connect")
+                .arg(uri)
+                .arg("--wait")
+                .arg(&name)
+                .spawn()?;
+        }
+    }
+    Ok(())
+}
+
+fn find_viewer_binary() -> std::path::PathBuf {
+    if let Ok(path) = std::env::var("GRUSTYVMAN_VIEWER") {
+        let candidate = std::path::PathBuf::from(path);
+        if candidate.is_file() {
+            return candidate;
+        }
+    }
+
+    let mut candidates: Vec<std::path::PathBuf> = Vec::new();
+
+    if let Ok(cwd) = std::env::current_dir() {
+        candidates.push(
+            cwd.join("viewer")
+                .join("target")
+                .join("debug")
+                .join("grustyvman-viewer"),
+        );
+        candidates.push(
+            cwd.join("viewer")
+                .join("target")
+                .join("release")
+                .join("grustyvman-viewer"),
+        );
+        candidates.push(cwd.join("viewer").join("grustyvman-viewer"));
+    }
+
+    if let Ok(exe) = std::env::current_exe() {
+        if let Some(dir) = exe.parent() {
+            if let Some(parent) = dir.parent() {
+                if let Some(repo_root) = parent.parent() {
+                    candidates.push(
+                        repo_root
+                            .join("viewer")
+                            .join("target")
+                            .join("debug")
+                            .join("grustyvman-viewer"),
+                    );
+                    candidates.push(
+                        repo_root
+                            .join("viewer")
+                            .join("target")
+                            .join("release")
+                            .join("grustyvman-viewer"),
+                    );
+                    candidates.push(repo_root.join("viewer").join("grustyvman-viewer"));
+                }
+
+                candidates.push(parent.join("debug").join("grustyvman-viewer"));
+                candidates.push(parent.join("release").join("grustyvman-viewer"));
+            }
+
+            candidates.push(dir.join("grustyvman-viewer"));
+        }
+    }
+
+    for candidate in candidates {
+        if candidate.is_file() {
+            return candidate;
+        }
+    }
+
+    std::path::PathBuf::from("grustyvman-viewer")
+}
+
+/// Rename a shutoff domain by redefining it with a new name.
+pub fn rename_domain(uri: &str, uuid: &str, new_name: &str) -> Result<(), AppError> {
+    let xml = get_domain_xml(uri, uuid)?;
+    let new_xml = crate::domain_xml::rename_domain_xml(&xml, new_name)?;
+    // Undefine old, define new
+    with_domain(uri, uuid, |domain| {
+        domain.undefine()?;
+        Ok(())
+    })?;
+    update_domain_xml(uri, &new_xml)?;
+    Ok(())
+}
+
+/// Clone a shutoff domain. If `full_clone` is true, copies disk images fully;
+/// otherwise creates linked (backing-store) clones.
+pub fn clone_domain(
+    uri: &str,
+    uuid: &str,
+    new_name: &str,
+    full_clone: bool,
+) -> Result<(), AppError> {
+    let params = CloneParams::new(new_name, full_clone);
+    clone_domain_with_progress(uri, uuid, &params, &Job::detached("Clone"))
+}
+
+/// Clone a shut-off VM. Its writable disks are copied (or, unless
+/// `params.full_clone`, overlaid) by libvirt inside their own pools, so this
+/// works on remote connections and on any pool type libvirt can copy
+/// between. CD-ROMs and shared disks are attached as they are. The NVRAM
+/// is taken from the source or started afresh as `params` says; the TPM
+/// state always starts afresh. Progress
+/// is reported on `job`, disk by disk. Everything created before a failure
+/// or cancellation is removed again.
+pub fn clone_domain_with_progress(
+    uri: &str,
+    uuid: &str,
+    params: &CloneParams,
+    job: &Job,
+) -> Result<(), AppError> {
+    let new_name = params.new_name.as_str();
+    let xml = with_domain(uri, uuid, |domain| {
+        if domain.is_active()? {
+            return Err(AppError::Backend(format!(
+                "Shut down {} before cloning it",
+                domain.get_name()?
+            )));
+        }
+        Ok(domain.get_xml_desc(virt::sys::VIR_DOMAIN_XML_INACTIVE)?)
+    })?;
+    let mut domain = DomainXml::parse(&xml)?;
+    let sources = domain.clone_sources();
+    let src_nvram = domain.nvram_path().filter(|_| params.nvram == NvramClone::Copy);
+
+    job.set_cancellable(true);
+    let mut created: Vec<String> = Vec::new();
+    let result = (|| {
+        let mut disk_map = Vec::with_capacity(sources.len());
+        for (index, source) in sources.iter().enumerate() {
+            job.check_cancelled()?;
+            let src_path = match source {
+                DiskSource::Path(path) => path.clone(),
+                DiskSource::Volume { pool, volume } => {
+                    crate::storage::volume_path(uri, pool, volume)?
+                }
+            };
+            job.set_detail(&format!("Disk {} of {}", index + 1, sources.len()));
+            let (vol_name, vol_path) = crate::storage::clone_volume(
+                uri,
+                &src_path,
+                new_name,
+                index,
+                !params.full_clone,
+                job,
+            )?;
+            created.push(vol_path.clone());
+            disk_map.push(match source {
+                DiskSource::Path(path) => (path.clone(), vol_path),
+                DiskSource::Volume { volume, .. } => (volume.clone(), vol_name),
+            });
+        }
+        job.check_cancelled()?;
+        job.set_cancellable(false);
+        domain.prepare_clone(new_name, &disk_map);
+        // libvirt fills a missing vars file from the template on the first
+        // start, so the source's vars become the clone's without a copy here
+        if let Some(src) = &src_nvram {
+            domain.set_nvram_path(&clone_nvram_path(src, new_name));
+            domain.set_nvram_template(src);
+        }
+
+        let conn = get_conn(uri)?;
+        Domain::define_xml(&conn, &domain.to_xml())?;
+        Ok(())
+    })();
+
+    if result.is_err() {
+        for path in &created {
+            let _ = crate::storage::delete_volume_by_path(uri, path);
+        }
+    }
+    result
+}
+
+/// NVRAM path for clone `new_name`: next to the source's, named the way
+/// libvirt names generated ones.
+fn clone_nvram_path(src: &str, new_name: &str) -> String {
+    let path = std::path::Path::new(src);
+    let dir = path.parent().map(|p| p.to_string_lossy()).unwrap_or_default();
+    let ext = path.extension().map(|e| e.to_string_lossy()).unwrap_or("fd".into());
+    format!("{dir}/{new_name}_VARS.{ext}")
+}
@@ -0,0 +1,1080 @@
+use crate::error::AppError;
+use crate::types::{
+    BootDevice, ChangeNetworkSourceParams, ChannelInfo, ControllerInfo, CpuMode, CpuTune, DiskInfo,
+    DiskSource, DomainDetails, FilesystemInfo, FirmwareType, GraphicsInfo, GraphicsType,
+    HostdevInfo, InputInfo, MemballoonModel, NetworkInfo, NetworkSourceType, NewDiskParams,
+    NewNetworkParams, PanicModel, ParallelInfo, RngBackend, SerialInfo, SmartcardMode, SoundInfo,
+    SoundModel, TemplateInfo, TpmInfo, TpmModel, UsbredirInfo, VcpuPin, VideoInfo, VideoModel,
+    WatchdogAction, WatchdogInfo, WatchdogModel,};
+use crate::template::{is_template_element, template_info};
+use crate::xml_tree::{Document, Element};
+
+/// Typed view of a libvirt `<domain>` definition.
+///
+/// The getters read the parts the UI understands into the `types` structs;
+/// the setters edit just those elements of the underlying
+/// [`Document`]. Everything else (addresses, aliases, `<qemu:commandline>`,
+/// comments, formatting) is carried through untouched, so
+/// `parse -> modify -> to_xml` never loses data.
+#[derive(Debug, Clone)]
+pub struct DomainXml {
+    doc: Document,
+}
+
+impl DomainXml {
+    pub fn parse(xml: &str) -> Result<Self, AppError> {
+        let doc = Document::parse(xml)?;
+        if doc.root().name() != "domain" {
+            return Err(AppError::Xml(format!(
+                "expected a <domain> document, found <{}>",
+                doc.root().name()
+            )));
+        }
+        Ok(DomainXml { doc })
+    }
+
+    pub fn to_xml(&self) -> String {
+        self.doc.to_xml()
+    }
+
+    fn root(&self) -> &Element {
+        self.doc.root()
+    }
+
+    fn root_mut(&mut self) -> &mut Element {
+        self.doc.root_mut()
+    }
+
+    fn devices(&self) -> impl Iterator<Item = &Element> {
+        self.root().child("devices").into_iter().flat_map(|devices| devices.children())
+    }
+
+    fn devices_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.devices().filter(move |e| e.name() == name)
+    }
+
+    fn devices_mut(&mut self) -> &mut Element {
+        self.root_mut().ensure_child("devices")
+    }
+
+    /// Everything [`DomainDetails`] shows, read in one go.
+    pub fn details(&self) -> DomainDetails {
+        let graphics = self.graphics();
+        DomainDetails {
+            name: self.name(),
+            uuid: self.uuid(),
+            memory_kib: self.memory_kib(),
+            vcpus: self.vcpus(),
+            os_type: self.os_type(),
+            arch: self.arch(),
+            disks: self.disks(),
+            networks: self.networks(),
+            has_graphics: graphics.is_some(),
+            boot_order: self.boot_order(),
+            cpu_mode: self.cpu_mode(),
+            cpu_model: self.cpu_model(),
+            firmware: self.firmware(),
+            graphics,
+            video: self.video(),
+            sound: self.sound(),
+            cpu_tune: self.cpu_tune(),
+            tpm: self.tpm(),
+            filesystems: self.filesystems(),
+            hostdevs: self.hostdevs(),
+            serials: self.serials(),
+            rng: self.rng(),
+            watchdog: self.watchdog(),
+            inputs: self.inputs(),
+            channels: self.channels(),
+            controllers: self.controllers(),
+            parallels: self.parallels(),
+            panic: self.panic(),
+            usbredirs: self.usbredirs(),
+            smartcard: self.smartcard(),
+            memballoon: self.memballoon(),
+        }
+    }
+
+    // --- General ---
+
+    pub fn name(&self) -> String {
+        self.root().child_text("name").unwrap_or_default()
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+        self.root_mut().ensure_child("name").set_text(name);
+    }
+
+    pub fn uuid(&self) -> String {
+        self.root().child_text("uuid").unwrap_or_default()
+    }
+
+    pub fn remove_uuid(&mut self) {
+        self.root_mut().remove_where(|e| e.name() == "uuid");
+    }
+
+    pub fn memory_kib(&self) -> u64 {
+        self.root()
+            .child("memory")
+            .map(|m| to_kib(m.text().trim().parse().unwrap_or(0), m.attr("unit").as_deref()))
+            .unwrap_or(0)
+    }
+
+    /// Set both `<memory>` and `<currentMemory>` (if present).
+    pub fn set_memory_mib(&mut self, memory_mib: u64) {
+        let kib = (memory_mib * 1024).to_string();
+        let root = self.root_mut();
+        let memory = root.ensure_child("memory");
+        memory.set_attr("unit", "KiB");
+        memory.set_text(&kib);
+        if let Some(current) = root.child_mut("currentMemory") {
+            current.set_attr("unit", "KiB");
+            current.set_text(&kib);
+        }
+    }
+
+    pub fn vcpus(&self) -> u32 {
+        self.root()
+            .child_text("vcpu")
+            .and_then(|t| t.trim().parse().ok())
+            .unwrap_or(0)
+    }
+
+    pub fn set_vcpus(&mut self, vcpus: u32) {
+        self.root_mut().ensure_child("vcpu").set_text(&vcpus.to_string());
+    }
+
+    // --- OS / Firmware / Boot ---
+
+    pub fn os_type(&self) -> String {
+        self.root()
+            .child("os")
+            .and_then(|os| os.child_text("type"))
+            .unwrap_or_default()
+    }
+
+    pub fn arch(&self) -> String {
+        self.root()
+            .child("os")
+            .and_then(|os| os.child_attr("type", "arch"))
+            .unwrap_or_default()
+    }
+
+    pub fn firmware(&self) -> FirmwareType {
+        let Some(os) = self.root().child("os") else {
+            return FirmwareType::Bios;
+        };
+        // Legacy UEFI setups have no firmware attribute, just a pflash loader
+        if os.attr_is("firmware", "efi") || os.child_attr("loader", "type").as_deref() == Some("pflash")
+        {
+            FirmwareType::Efi
+        } else {
+            FirmwareType::Bios
+        }
+    }
+
+    /// Switch between BIOS and firmware auto-selected UEFI. Explicit
+    /// `<loader>`/`<nvram>` paths are dropped so libvirt picks matching
+    /// ones; UEFI also needs SMM enabled.
+    pub fn set_firmware(&mut self, firmware: FirmwareType) {
+        let root = self.root_mut();
+        let os = root.ensure_child("os");
+        os.remove_where(|e| matches!(e.name(), "loader" | "nvram"));
+        match firmware {
+            FirmwareType::Efi => os.set_attr("firmware", "efi"),
+            FirmwareType::Bios => {
+                os.remove_attr("firmware");
+                os.remove_where(|e| e.name() == "firmware");
+            }
+        }
+
+        match firmware {
+            FirmwareType::Efi => root
+                .ensure_child("features")
+                .ensure_child("smm")
+                .set_attr("state", "on"),
+            FirmwareType::Bios => {
+                if let Some(features) = root.child_mut("features") {
+                    features.remove_where(|e| e.name() == "smm");
+                }
+            }
+        }
+    }
+
+    pub fn boot_order(&self) -> Vec<BootDevice> {
+        self.root()
+            .child("os")
+            .into_iter()
+            .flat_map(|os| os.children_named("boot"))
+            .filter_map(|b| b.attr("dev").and_then(|d| BootDevice::from_name(&d)))
+            .collect()
+    }
+
+    pub fn set_boot_order(&mut self, devices: &[BootDevice]) {
+        let os = self.root_mut().ensure_child("os");
+        os.remove_where(|e| e.name() == "boot");
+        for dev in devices {
+            os.append(Element::new("boot").with_attr("dev", dev.as_str()));
+        }
+    }
+
+    // --- CPU ---
+
+    pub fn cpu_mode(&self) -> CpuMode {
+        self.root()
+            .child_attr("cpu", "mode")
+            .map(|m| CpuMode::from_name(&m))
+            .unwrap_or(CpuMode::HostPassthrough)
+    }
+
+    pub fn cpu_model(&self) -> Option<String> {
+        self.root().child("cpu").and_then(|cpu| cpu.child_text("model"))
+    }
+
+    /// Set the CPU mode. `Custom` pins the named model (`qemu64` if none);
+    /// the other modes drop the model but keep topology and features.
+    pub fn set_cpu(&mut self, mode: CpuMode, model: Option<&str>) {
+        let cpu = self.root_mut().ensure_child("cpu");
+        cpu.set_attr("mode", mode.as_str());
+        match mode {
+            CpuMode::Custom => {
+                cpu.set_attr("match", "exact");
+                let model_elem = Element::new("model")
+                    .with_attr("fallback", "forbid")
+                    .with_text(model.unwrap_or("qemu64"));
+                cpu.replace_child("model", Some(model_elem));
+            }
+            _ => {
+                cpu.remove_attr("match");
+                cpu.remove_where(|e| e.name() == "model");
+            }
+        }
+    }
+
+    pub fn cpu_tune(&self) -> CpuTune {
+        let mut tune = CpuTune::default();
+        let Some(cputune) = self.root().child("cputune") else {
+            return tune;
+        };
+        for pin in cputune.children_named("vcpupin") {
+            let cpuset = pin.attr("cpuset").unwrap_or_default();
+            if !cpuset.is_empty() {
+                tune.vcpu_pins.push(VcpuPin {
+                    vcpu: parse_u32(pin.attr("vcpu")),
+                    cpuset,
+                });
+            }
+        }
+        tune.emulatorpin = cputune.child_attr("emulatorpin", "cpuset");
+        tune
+    }
+
+    /// Replace the pinning in `<cputune>`. Other tunables in there (shares,
+    /// quotas, iothread pins) are kept; an empty element is removed.
+    pub fn set_cpu_tune(&mut self, tune: &CpuTune) {
+        let root = self.root_mut();
+        let is_empty = tune.vcpu_pins.is_empty() && tune.emulatorpin.is_none();
+        if is_empty && root.child("cputune").is_none() {
+            return;
+        }
+        let cputune = root.ensure_child("cputune");
+        cputune.remove_where(|e| matches!(e.name(), "vcpupin" | "emulatorpin"));
+        for pin in &tune.vcpu_pins {
+            cputune.append(
+                Element::new("vcpupin")
+                    .with_attr("vcpu", &pin.vcpu.to_string())
+                    .with_attr("cpuset", &pin.cpuset),
+            );
+        }
+        if let Some(ref cpuset) = tune.emulatorpin {
+            cputune.append(Element::new("emulatorpin").with_attr("cpuset", cpuset));
+        }
+        if cputune.children().next().is_none() {
+            root.remove_where(|e| e.name() == "cputune");
+        }
+    }
+
+    // --- Disks ---
+
+    pub fn disks(&self) -> Vec<DiskInfo> {
+        self.devices_named("disk")
+            .map(|disk| DiskInfo {
+                target_dev: disk.child_attr("target", "dev").unwrap_or_default(),
+                source_file: disk.child_attr("source", "file"),
+                bus: disk.child_attr("target", "bus").unwrap_or_default(),
+                device_type: disk.attr("device").unwrap_or_else(|| "disk".to_string()),
+                backing_chain: backing_chain(disk),
+            })
+            .collect()
+    }
+
+    /// Source files of all non-CD-ROM disks.
+    pub fn disk_paths(&self) -> Vec<String> {
+        self.devices_named("disk")
+            .filter(|d| !d.attr_is("device", "cdrom"))
+            .filter_map(|d| d.child_attr("source", "file"))
+            .collect()
+    }
+
+    pub fn add_disk(&mut self, params: &NewDiskParams) {
+        let disk = Element::new("disk")
+            .with_attr("type", "file")
+            .with_attr("device", &params.device_type)
+            .with_child(
+                Element::new("driver")
+                    .with_attr("name", "qemu")
+                    .with_attr("type", &params.driver_type),
+            )
+            .with_child(Element::new("source").with_attr("file", &params.source_file))
+            .with_child(
+                Element::new("target")
+                    .with_attr("dev", &params.target_dev)
+                    .with_attr("bus", &params.bus),
+            );
+        self.devices_mut().append(disk);
+    }
+
+    pub fn remove_disk(&mut self, target_dev: &str) {
+        self.devices_mut()
+            .remove_where(|e| e.name() == "disk" && targets_dev(e, target_dev));
+    }
+
+    fn disk_mut(&mut self, target_dev: &str, cdrom: bool) -> Option<&mut Element> {
+        self.devices_mut().children_named_mut("disk").find(|d| {
+            d.attr_is("device", "cdrom") == cdrom && targets_dev(d, target_dev)
+        })
+    }
+
+    /// Remove the medium from a CD-ROM drive.
+    pub fn eject_cdrom(&mut self, target_dev: &str) {
+        if let Some(disk) = self.disk_mut(target_dev, true) {
+            disk.remove_where(|e| e.name() == "source");
+        }
+    }
+
+    /// Point a disk (or, with `cdrom`, a CD-ROM drive) at a new image file.
+    pub fn set_disk_source(&mut self, target_dev: &str, path: &str, cdrom: bool) {
+        let Some(disk) = self.disk_mut(target_dev, cdrom) else {
+            return;
+        };
+        disk.set_attr("type", "file");
+        let source = Element::new("source").with_attr("file", path);
+        if disk.child("source").is_some() {
+            disk.replace_child("source", Some(source));
+        } else {
+            disk.insert_before("target", source);
+        }
+    }
+
+    // --- Network Interfaces ---
+
+    pub fn networks(&self) -> Vec<NetworkInfo> {
+        self.devices_named("interface")
+            .map(|iface| NetworkInfo {
+                mac_address: iface.child_attr("mac", "address"),
+                interface_type: iface.attr("type").unwrap_or_default(),
+                source_network: iface.child_attr("source", "network"),
+                source_bridge: iface.child_attr("source", "bridge"),
+                source_dev: iface.child_attr("source", "dev"),
+                model_type: iface.child_attr("model", "type"),
+            })
+            .collect()
+    }
+
+    /// Host-side device names (`<target dev>`) of the interfaces.
+    pub fn interface_targets(&self) -> Vec<String> {
+        self.devices_named("interface")
+            .filter_map(|iface| iface.child_attr("target", "dev"))
+            .collect()
+    }
+
+    pub fn add_interface(&mut self, params: &NewNetworkParams) {
+        let mut iface = Element::new("interface").with_attr("type", "network");
+        if let Some(mac) = params.mac_address.as_deref().filter(|m| !m.is_empty()) {
+            iface = iface.with_child(Element::new("mac").with_attr("address", mac));
+        }
+        iface = iface
+            .with_child(Element::new("source").with_attr("network", &params.source_network))
+            .with_child(Element::new("model").with_attr("type", &params.model_type));
+        self.devices_mut().append(iface);
+    }
+
+    pub fn remove_interface(&mut self, mac_address: &str) {
+        self.devices_mut()
+            .remove_where(|e| e.name() == "interface" && has_mac(e, mac_address));
+    }
+
+    /// Reconnect an interface to a different network, bridge or device.
+    pub fn set_interface_source(&mut self, mac_address: &str, params: &ChangeNetworkSourceParams) {
+        let Some(iface) = self
+            .devices_mut()
+            .children_named_mut("interface")
+            .find(|e| has_mac(e, mac_address))
+        else {
+            return;
+        };
+        let (iface_type, source) = match params.source_type {
+            NetworkSourceType::VirtualNetwork => (
+                "network",
+                Element::new("source").with_attr("network", &params.value),
+            ),
+            NetworkSourceType::Bridge => (
+                "bridge",
+                Element::new("source").with_attr("bridge", &params.value),
+            ),
+            NetworkSourceType::Macvtap => (
+                "direct",
+                Element::new("source")
+                    .with_attr("dev", &params.value)
+                    .with_attr("mode", "vepa"),
+            ),
+            NetworkSourceType::Vdpa => (
+                "vdpa",
+                Element::new("source").with_attr("dev", &params.value),
+            ),
+        };
+        iface.set_attr("type", iface_type);
+        iface.replace_child("source", Some(source));
+    }
+
+    // --- Graphics / Video / Sound ---
+
+    pub fn graphics(&self) -> Option<GraphicsInfo> {
+        self.devices_named("graphics").next().map(|g| GraphicsInfo {
+            graphics_type: GraphicsType::from_name(&g.attr("type").unwrap_or_default()),
+            port: g.attr("port").and_then(|p| p.parse().ok()),
+            autoport: g.attr_is("autoport", "yes"),
+            listen_address: g.attr("listen"),
+            password: g.attr("passwd").filter(|p| !p.is_empty()),
+        })
+    }
+
+    pub fn set_graphics(&mut self, graphics_type: GraphicsType) {
+        let new = (graphics_type != GraphicsType::None).then(|| {
+            Element::new("graphics")
+                .with_attr("type", graphics_type.as_str())
+                .with_attr("autoport", "yes")
+        });
+        self.devices_mut().replace_child("graphics", new);
+    }
+
+    pub fn video(&self) -> Option<VideoInfo> {
+        let model = self.devices_named("video").next()?.child("model")?;
+        Some(VideoInfo {
+            model: VideoModel::from_name(&model.attr("type").unwrap_or_default()),
+            vram: model.attr("vram").and_then(|v| v.parse().ok()),
+            heads: model.attr("heads").and_then(|v| v.parse().ok()),
+            accel3d: model.child_attr("acceleration", "accel3d").as_deref() == Some("yes"),
+        })
+    }
+
+    pub fn set_video(&mut self, video_model: VideoModel, accel3d: bool) {
+        let new = (video_model != VideoModel::None).then(|| {
+            let mut model = Element::new("model").with_attr("type", video_model.as_str());
+            if video_model == VideoModel::Virtio {
+                model = model.with_child(
+                    Element::new("acceleration")
+                        .with_attr("accel3d", if accel3d { "yes" } else { "no" }),
+                );
+            }
+            Element::new("video").with_child(model)
+        });
+        self.devices_mut().replace_child("video", new);
+    }
+
+    pub fn sound(&self) -> Option<SoundInfo> {
+        self.devices_named("sound").next().map(|s| SoundInfo {
+            model: SoundModel::from_name(&s.attr("model").unwrap_or_default()),
+        })
+    }
+
+    pub fn set_sound(&mut self, sound_model: SoundModel) {
+        let new = (sound_model != SoundModel::None)
+            .then(|| Element::new("sound").with_attr("model", sound_model.as_str()));
+        self.devices_mut().replace_child("sound", new);
+    }
+
+    // --- TPM ---
+
+    pub fn tpm(&self) -> Option<TpmInfo> {
+        let tpm = self.devices_named("tpm").next()?;
+        let model = TpmModel::from_name(&tpm.attr("model").unwrap_or_default());
+        if model == TpmModel::None {
+            return None;
+        }
+        Some(TpmInfo {
+            model,
+            version: tpm
+                .child_attr("backend", "version")
+                .unwrap_or_else(|| "2.0".to_string()),
+        })
+    }
+
+    pub fn set_tpm(&mut self, tpm_model: TpmModel) {
+        let new = (tpm_model != TpmModel::None).then(|| {
+            Element::new("tpm").with_attr("model", tpm_model.as_str()).with_child(
+                Element::new("backend")
+                    .with_attr("type", "emulated")
+                    .with_attr("version", "2.0"),
+            )
+        });
+        self.devices_mut().replace_child("tpm", new);
+    }
+
+    // --- Filesystems ---
+
+    pub fn filesystems(&self) -> Vec<FilesystemInfo> {
+        self.devices_named("filesystem")
+            .map(|fs| FilesystemInfo {
+                driver: fs
+                    .child_attr("driver", "type")
+                    .unwrap_or_else(|| "9p".to_string()),
+                source_dir: fs.child_attr("source", "dir").unwrap_or_default(),
+                target_dir: fs.child_attr("target", "dir").unwrap_or_default(),
+                accessmode: fs.attr("accessmode"),
+            })
+            .collect()
+    }
+
+    pub fn add_filesystem(&mut self, info: &FilesystemInfo) {
+        let mut fs = Element::new("filesystem").with_attr("type", "mount");
+        if info.driver == "virtiofs" {
+            fs = fs.with_child(Element::new("driver").with_attr("type", "virtiofs"));
+        } else {
+            fs = fs.with_attr("accessmode", info.accessmode.as_deref().unwrap_or("mapped"));
+        }
+        fs = fs
+            .with_child(Element::new("source").with_attr("dir", &info.source_dir))
+            .with_child(Element::new("target").with_attr("dir", &info.target_dir));
+        self.devices_mut().append(fs);
+    }
+
+    pub fn remove_filesystem(&mut self, target_dir: &str) {
+        self.devices_mut().remove_where(|e| {
+            e.name() == "filesystem" && e.child_attr("target", "dir").as_deref() == Some(target_dir)
+        });
+    }
+
+    // --- Host Devices ---
+
+    pub fn hostdevs(&self) -> Vec<HostdevInfo> {
+        self.devices_named("hostdev")
+            .map(|hd| {
+                let device_type = hd.attr("type").unwrap_or_default();
+                let source = hd.child("source");
+                let address = source
+                    .and_then(|s| s.child("address"))
+                    .filter(|_| device_type == "pci");
+                let mut info = HostdevInfo {
+                    pci_domain: address.and_then(|a| a.attr("domain")),
+                    pci_bus: address.and_then(|a| a.attr("bus")),
+                    pci_slot: address.and_then(|a| a.attr("slot")),
+                    pci_function: address.and_then(|a| a.attr("function")),
+                    usb_vendor: source.and_then(|s| s.child_attr("vendor", "id")),
+                    usb_product: source.and_then(|s| s.child_attr("product", "id")),
+                    device_type,
+                    display_name: String::new(),
+                };
+                info.display_name = info.display_subtitle();
+                info
+            })
+            .collect()
+    }
+
+    pub fn add_hostdev(&mut self, info: &HostdevInfo) {
+        let source = if info.device_type == "pci" {
+            Element::new("source").with_child(
+                Element::new("address")
+                    .with_attr("domain", info.pci_domain.as_deref().unwrap_or("0x0000"))
+                    .with_attr("bus", info.pci_bus.as_deref().unwrap_or("0x00"))
+                    .with_attr("slot", info.pci_slot.as_deref().unwrap_or("0x00"))
+                    .with_attr("function", info.pci_function.as_deref().unwrap_or("0x0")),
+            )
+        } else {
+            Element::new("source")
+                .with_child(
+                    Element::new("vendor")
+                        .with_attr("id", info.usb_vendor.as_deref().unwrap_or("")),
+                )
+                .with_child(
+                    Element::new("product")
+                        .with_attr("id", info.usb_product.as_deref().unwrap_or("")),
+                )
+        };
+        let hostdev = Element::new("hostdev")
+            .with_attr("mode", "subsystem")
+            .with_attr("type", &info.device_type)
+            .with_attr("managed", "yes")
+            .with_child(source);
+        self.devices_mut().append(hostdev);
+    }
+
+    pub fn remove_hostdev(&mut self, info: &HostdevInfo) {
+        self.devices_mut()
+            .remove_where(|e| e.name() == "hostdev" && hostdev_matches(e, info));
+    }
+
+    // --- Serial / Console ---
+
+    pub fn serials(&self) -> Vec<SerialInfo> {
+        self.devices()
+            .filter(|e| matches!(e.name(), "serial" | "console"))
+            .map(|e| SerialInfo {
+                is_console: e.name() == "console",
+                target_type: e
+                    .child_attr("target", "type")
+                    .unwrap_or_else(|| "isa-serial".to_string()),
+                port: parse_u32(e.child_attr("target", "port")),
+            })
+            .collect()
+    }
+
+    pub fn add_serial(&mut self, info: &SerialInfo) {
+        let tag = if info.is_console { "console" } else { "serial" };
+        let elem = Element::new(tag).with_attr("type", "pty").with_child(
+            Element::new("target")
+                .with_attr("type", &info.target_type)
+                .with_attr("port", &info.port.to_string()),
+        );
+        self.devices_mut().append(elem);
+    }
+
+    pub fn remove_serial(&mut self, info: &SerialInfo) {
+        let tag = if info.is_console { "console" } else { "serial" };
+        self.devices_mut().remove_where(|e| {
+            e.name() == tag && e.child_attr("target", "port").and_then(|p| p.parse().ok()) == Some(info.port)
+        });
+    }
+
+    // --- RNG / Watchdog / Panic ---
+
+    pub fn rng(&self) -> Option<RngBackend> {
+        let rng = self.devices_named("rng").next()?;
+        let path = rng.child_text("backend").unwrap_or_default();
+        match path.trim() {
+            "" => Some(RngBackend::Urandom),
+            path => RngBackend::from_path(path),
+        }
+    }
+
+    pub fn set_rng(&mut self, backend: Option<RngBackend>) {
+        let new = backend.map(|b| {
+            Element::new("rng").with_attr("model", "virtio").with_child(
+                Element::new("backend")
+                    .with_attr("model", "random")
+                    .with_text(b.path()),
+            )
+        });
+        self.devices_mut().replace_child("rng", new);
+    }
+
+    pub fn watchdog(&self) -> Option<WatchdogInfo> {
+        let wd = self.devices_named("watchdog").next()?;
+        let model = WatchdogModel::from_name(&wd.attr("model").unwrap_or_default());
+        if model == WatchdogModel::None {
+            return None;
+        }
+        let action = wd
+            .attr("action")
+            .map(|a| WatchdogAction::from_name(&a))
+            .unwrap_or(WatchdogAction::Reset);
+        Some(WatchdogInfo { model, action })
+    }
+
+    pub fn set_watchdog(&mut self, model: WatchdogModel, action: WatchdogAction) {
+        let new = (model != WatchdogModel::None).then(|| {
+            Element::new("watchdog")
+                .with_attr("model", model.as_str())
+                .with_attr("action", action.as_str())
+        });
+        self.devices_mut().replace_child("watchdog", new);
+    }
+
+    pub fn panic(&self) -> Option<PanicModel> {
+        self.devices_named("panic")
+            .map(|p| PanicModel::from_name(&p.attr("model").unwrap_or_default()))
+            .find(|m| *m != PanicModel::None)
+    }
+
+    pub fn set_panic(&mut self, model: PanicModel) {
+        let new = (model != PanicModel::None)
+            .then(|| Element::new("panic").with_attr("model", model.as_str()));
+        self.devices_mut().replace_child("panic", new);
+    }
+
+    // --- Input / Channels / Controllers ---
+
+    pub fn inputs(&self) -> Vec<InputInfo> {
+        self.devices_named("input")
+            .filter_map(|e| {
+                let input_type = e.attr("type").filter(|t| !t.is_empty())?;
+                Some(InputInfo {
+                    input_type,
+                    bus: e.attr("bus").unwrap_or_default(),
+                })
+            })
+            .collect()
+    }
+
+    pub fn add_input(&mut self, info: &InputInfo) {
+        self.devices_mut().append(
+            Element::new("input")
+                .with_attr("type", &info.input_type)
+                .with_attr("bus", &info.bus),
+        );
+    }
+
+    pub fn remove_input(&mut self, info: &InputInfo) {
+        self.devices_mut().remove_first(|e| {
+            e.name() == "input"
+                && e.attr("type").unwrap_or_default() == info.input_type
+                && e.attr("bus").unwrap_or_default() == info.bus
+        });
+    }
+
+    pub fn channels(&self) -> Vec<ChannelInfo> {
+        self.devices_named("channel")
+            .filter_map(|e| {
+                let target_name = e.child_attr("target", "name").filter(|n| !n.is_empty())?;
+                Some(ChannelInfo {
+                    channel_type: e.attr("type").unwrap_or_default(),
+                    target_name,
+                })
+            })
+            .collect()
+    }
+
+    pub fn add_channel(&mut self, info: &ChannelInfo) {
+        self.devices_mut().append(
+            Element::new("channel")
+                .with_attr("type", &info.channel_type)
+                .with_child(
+                    Element::new("target")
+                        .with_attr("type", "virtio")
+                        .with_attr("name", &info.target_name),
+                ),
+        );
+    }
+
+    pub fn remove_channel(&mut self, target_name: &str) {
+        self.devices_mut().remove_where(|e| {
+            e.name() == "channel" && e.child_attr("target", "name").as_deref() == Some(target_name)
+        });
+    }
+
+    /// Controllers shown in the UI; PCI/IDE/SATA plumbing is left out.
+    pub fn controllers(&self) -> Vec<ControllerInfo> {
+        self.devices_named("controller")
+            .filter_map(controller_info)
+            .filter(|c| !c.is_system())
+            .collect()
+    }
+
+    pub fn add_controller(&mut self, info: &ControllerInfo) {
+        let mut ctrl = Element::new("controller")
+            .with_attr("type", &info.controller_type)
+            .with_attr("index", &info.index.to_string());
+        if let Some(ref model) = info.model {
+            ctrl = ctrl.with_attr("model", model);
+        }
+        self.devices_mut().append(ctrl);
+    }
+
+    pub fn remove_controller(&mut self, info: &ControllerInfo) {
+        self.devices_mut().remove_first(|e| {
+            e.name() == "controller" && controller_info(e).as_ref() == Some(info)
+        });
+    }
+
+    // --- Parallel / USB Redirection ---
+
+    pub fn parallels(&self) -> Vec<ParallelInfo> {
+        self.devices_named("parallel")
+            .map(|e| ParallelInfo {
+                port: parse_u32(e.child_attr("target", "port")),
+            })
+            .collect()
+    }
+
+    /// Add a PTY-backed parallel port on the next free port number.
+    pub fn add_parallel(&mut self) {
+        let port = self.devices_named("parallel").count();
+        self.devices_mut().append(
+            Element::new("parallel").with_attr("type", "pty").with_child(
+                Element::new("target")
+                    .with_attr("type", "isa-parallel")
+                    .with_attr("port", &port.to_string()),
+            ),
+        );
+    }
+
+    pub fn remove_parallel(&mut self, port: u32) {
+        self.devices_mut().remove_first(|e| {
+            e.name() == "parallel"
+                && e.child_attr("target", "port").and_then(|p| p.parse().ok()) == Some(port)
+        });
+    }
+
+    pub fn usbredirs(&self) -> Vec<UsbredirInfo> {
+        (0..self.devices_named("redirdev").count() as u32)
+            .map(|index| UsbredirInfo { index })
+            .collect()
+    }
+
+    pub fn add_usbredir(&mut self) {
+        self.devices_mut().append(
+            Element::new("redirdev")
+                .with_attr("bus", "usb")
+                .with_attr("type", "spicevmc"),
+        );
+    }
+
+    /// Remove the `index`-th `<redirdev>`.
+    pub fn remove_usbredir(&mut self, index: u32) {
+        let mut seen = 0;
+        self.devices_mut().remove_first(|e| {
+            if e.name() != "redirdev" {
+                return false;
+            }
+            seen += 1;
+            seen - 1 == index
+        });
+    }
+
+    // --- Smartcard / Memory Balloon ---
+
+    pub fn smartcard(&self) -> Option<SmartcardMode> {
+        self.devices_named("smartcard")
+            .map(|e| SmartcardMode::from_name(&e.attr("mode").unwrap_or_default()))
+            .find(|m| *m != SmartcardMode::None)
+    }
+
+    pub fn set_smartcard(&mut self, mode: SmartcardMode) {
+        let new = match mode {
+            SmartcardMode::Passthrough => Some(
+                Element::new("smartcard")
+                    .with_attr("mode", "passthrough")
+                    .with_attr("type", "spicevmc"),
+            ),
+            SmartcardMode::Host => Some(Element::new("smartcard").with_attr("mode", "host")),
+            SmartcardMode::None => None,
+        };
+        self.devices_mut().replace_child("smartcard", new);
+    }
+
+    pub fn memballoon(&self) -> Option<MemballoonModel> {
+        self.devices_named("memballoon")
+            .next()
+            .map(|e| MemballoonModel::from_name(&e.attr("model").unwrap_or_default()))
+    }
+
+    /// Set the balloon model; `None` writes `model="none"`, which is how
+    /// libvirt is told not to add its default balloon.
+    pub fn set_memballoon(&mut self, model: MemballoonModel) {
+        let balloon = Element::new("memballoon").with_attr("model", model.as_str());
+        self.devices_mut().replace_child("memballoon", Some(balloon));
+    }
+
+    // --- Template ---
+
+    /// The grustyvman template record in `<metadata>`, if this is a template.
+    pub fn template(&self) -> Option<TemplateInfo> {
+        self.root()
+            .child("metadata")?
+            .children()
+            .find(|e| is_template_element(e))
+            .map(template_info)
+    }
+
+    /// Drop the template record, leaving other applications' metadata alone.
+    pub fn remove_template(&mut self) {
+        if let Some(metadata) = self.root_mut().child_mut("metadata") {
+            metadata.remove_where(is_template_element);
+        }
+    }
+
+    // --- Cloning ---
+
+    /// Disks a clone needs its own copy of: writable, unshared disks on a
+    /// file, block device or pool volume. CD-ROMs and read-only or
+    /// shareable disks are attached to the clone as they are.
+    pub fn clone_sources(&self) -> Vec<DiskSource> {
+        self.devices_named("disk")
+            .filter(|d| d.attr("device").is_none_or(|dev| dev == "disk"))
+            .filter(|d| d.child("readonly").is_none() && d.child("shareable").is_none())
+            .filter_map(|d| {
+                let source = d.child("source")?;
+                match d.attr("type").as_deref() {
+                    Some("volume") => Some(DiskSource::Volume {
+                        pool: source.attr("pool")?,
+                        volume: source.attr("volume")?,
+                    }),
+                    Some("block") => source.attr("dev").map(DiskSource::Path),
+                    _ => source.attr("file").map(DiskSource::Path),
+                }
+            })
+            .collect()
+    }
+
+    /// Turn this definition into one for a clone: new name, no UUID or MAC
+    /// addresses (libvirt generates fresh ones), no template record (a clone
+    /// of a template is an ordinary VM) and disk sources remapped through
+    /// `disk_map` (old path or pool volume name, new one).
+    ///
+    /// The clone also gets its own firmware and TPM state: the NVRAM path
+    /// and any explicit swtpm state location are dropped so libvirt creates
+    /// fresh ones for the new name and UUID.
+    pub fn prepare_clone(&mut self, new_name: &str, disk_map: &[(String, String)]) {
+        self.set_name(new_name);
+        self.remove_uuid();
+        self.remove_template();
+        self.reset_nvram();
+        let devices = self.devices_mut();
+        for iface in devices.children_named_mut("interface") {
+            iface.remove_where(|e| e.name() == "mac");
+        }
+        for disk in devices.children_named_mut("disk") {
+            if let Some(source) = disk.child_mut("source") {
+                for attr in ["file", "dev", "volume"] {
+                    let Some(old) = source.attr(attr) else {
+                        continue;
+                    };
+                    if let Some((_, new)) = disk_map.iter().find(|(from, _)| *from == old) {
+                        source.set_attr(attr, new);
+                    }
+                }
+            }
+        }
+        for tpm in devices.children_named_mut("tpm") {
+            if let Some(backend) = tpm.child_mut("backend") {
+                backend.remove_where(|e| e.name() == "source");
+            }
+        }
+    }
+
+    /// Path of the UEFI variable store, if one is set.
+    pub fn nvram_path(&self) -> Option<String> {
+        let nvram = self.root().child("os")?.child("nvram")?;
+        let path = nvram.text().trim().to_string();
+        if path.is_empty() {
+            nvram.child_attr("source", "file")
+        } else {
+            Some(path)
+        }
+    }
+
+    /// Point the UEFI variable store at `path`, keeping its template.
+    pub fn set_nvram_path(&mut self, path: &str) {
+        let nvram = self.root_mut().ensure_child("os").ensure_child("nvram");
+        nvram.remove_attr("type");
+        nvram.set_text(path);
+    }
+
+    /// Have libvirt create a missing UEFI variable store as a copy of
+    /// `template`.
+    pub fn set_nvram_template(&mut self, template: &str) {
+        let nvram = self.root_mut().ensure_child("os").ensure_child("nvram");
+        nvram.set_attr("template", template);
+    }
+
+    /// Version and explicitly configured state directory of an emulated
+    /// TPM. Without one, libvirt keeps the state under its swtpm dir by UUID.
+    pub fn emulated_tpm(&self) -> Option<(String, Option<String>)> {
+        let tpm = self.devices_named("tpm").next()?;
+        let backend = tpm.child("backend")?;
+        if !backend.attr_is("type", "emulated") {
+            return None;
+        }
+        let version = backend.attr("version").unwrap_or_else(|| "2.0".to_string());
+        Some((version, backend.child_attr("source", "path")))
+    }
+
+    /// Forget the UEFI variable store so libvirt creates one from the
+    /// firmware's template. An explicit template is kept.
+    fn reset_nvram(&mut self) {
+        let Some(os) = self.root_mut().child_mut("os") else {
+            return;
+        };
+        let has_template = os.child("nvram").is_some_and(|n| n.attr("template").is_some());
+        if has_template {
+            if let Some(nvram) = os.child_mut("nvram") {
+                nvram.remove_attr("type");
+                nvram.set_text("");
+            }
+        } else {
+            os.remove_where(|e| e.name() == "nvram");
+        }
+    }
+}
+
+fn parse_u32(value: Option<String>) -> u32 {
+    value.and_then(|v| v.parse().ok()).unwrap_or(0)
+}
+
+/// Convert a libvirt memory value to KiB (the unit defaults to KiB).
+fn to_kib(value: u64, unit: Option<&str>) -> u64 {
+    match unit.unwrap_or("KiB") {
+        "b" | "bytes" => value / 1024,
+        "KB" => value * 1000 / 1024,
+        "MB" => value * 1000 * 1000 / 1024,
+        "M" | "MiB" => value * 1024,
+        "GB" => value * 1000 * 1000 * 1000 / 1024,
+        "G" | "GiB" => value * 1024 * 1024,
+        "T" | "TiB" => value * 1024 * 1024 * 1024,
+        _ => value,
+    }
+}
+
+fn targets_dev(disk: &Element, target_dev: &str) -> bool {
+    disk.child_attr("target", "dev").as_deref() == Some(target_dev)
+}
+
+/// Files of the nested `<backingStore>` elements of a disk, nearest first.
+/// The chain ends at an empty `<backingStore/>` or one without a file.
+fn backing_chain(disk: &Element) -> Vec<String> {
+    let mut chain = Vec::new();
+    let mut next = disk.child("backingStore");
+    while let Some(store) = next {
+        let Some(file) = store.child_attr("source", "file") else { break };
+        chain.push(file);
+        next = store.child("backingStore");
+    }
+    chain
+}
+
+fn has_mac(iface: &Element, mac_address: &str) -> bool {
+    iface.child_attr("mac", "address").as_deref() == Some(mac_address)
+}
+
+fn controller_info(e: &Element) -> Option<ControllerInfo> {
+    let controller_type = e.attr("type").filter(|t| !t.is_empty())?;
+    Some(ControllerInfo {
+        controller_type,
+        model: e.attr("model"),
+        index: parse_u32(e.attr("index")),
+    })
+}
+
+/// PCI devices match on the host address (the domain only if `info` has
+/// one), USB devices on vendor and product id.
+fn hostdev_matches(hostdev: &Element, info: &HostdevInfo) -> bool {
+    if !hostdev.attr_is("type", &info.device_type) {
+        return false;
+    }
+    let Some(source) = hostdev.child("source") else {
+        return false;
+    };
+    match info.device_type.as_str() {
+        "pci" => source.children_named("address").any(|addr| {
+            (info.pci_domain.is_none() || addr.attr("domain") == info.pci_domain)
+                && addr.attr("bus") == info.pci_bus
+                && addr.attr("slot") == info.pci_slot
+                && addr.attr("function") == info.pci_function
+        }),
+        "usb" => {
+            source.child_attr("vendor", "id") == info.usb_vendor
+                && source.child_attr("product", "id") == info.usb_product
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,539 @@
+use crate::cloudinit::CloudInitConfig;
+use crate::domain_model::DomainXml;
+use crate::error::AppError;
+use crate::unattend::WindowsUnattendConfig;
+use crate::types::{
+    BootDevice, ChangeNetworkSourceParams, ChannelInfo, ControllerInfo, CpuMode, CpuTune,
+    DiskBus, DiskFormat, DomainDetails, FilesystemInfo, FirmwareType, GraphicsType, HostdevInfo,
+    InputInfo, InstallSource, MemballoonModel, NetworkSourceType, NewDiskParams, NewNetworkParams,
+    NewVmNetworkConfig,
+    PanicModel, RngBackend, SerialInfo, SmartcardMode, SoundModel, TpmModel, VideoModel,
+    WatchdogAction, WatchdogModel,
+};
+
+#[derive(Debug, Clone)]
+pub struct NewVmParams {
+    pub name: String,
+    pub vcpus: u32,
+    pub memory_mib: u64,
+    pub disk_size_gib: u64, // ignored when importing a disk
+    pub disk_format: DiskFormat,
+    pub install: InstallSource,
+    pub firmware: FirmwareType,
+    pub network: NewVmNetworkConfig,
+    pub tpm_model: Option<TpmModel>, // None = no TPM
+    pub storage_pool: Option<String>, // None = "default" or first active pool
+    pub disk_bus: DiskBus,
+    pub video: VideoModel,
+    /// Hyper-V enlightenments and a localtime clock, for Windows guests.
+    pub hyperv: bool,
+    /// libosinfo id of the guest OS, recorded in `<metadata>`.
+    pub os_id: Option<String>,
+    /// First-boot provisioning, attached as a NoCloud seed ISO.
+    pub cloud_init: Option<CloudInitConfig>,
+    /// Answers for Windows Setup, attached as an autounattend.xml ISO.
+    pub unattend: Option<WindowsUnattendConfig>,
+    /// virtio-win driver ISO, attached after the installer.
+    pub virtio_win_iso: Option<String>,
+}
+
+pub fn extract_interface_targets(xml: &str) -> Vec<String> {
+    DomainXml::parse(xml)
+        .map(|d| d.interface_targets())
+        .unwrap_or_default()
+}
+
+pub fn generate_domain_xml(params: &NewVmParams, disk_path: &str) -> String {
+    let memory_kib = params.memory_mib * 1024;
+
+    let os_tag = match params.firmware {
+        FirmwareType::Efi => r#"<os firmware="efi">"#,
+        FirmwareType::Bios => "<os>",
+    };
+
+    let smm_feature = match params.firmware {
+        FirmwareType::Efi => "\n    <smm state=\"on\"/>",
+        FirmwareType::Bios => "",
+    };
+
+    // Hyper-V enlightenments make Windows guests far less CPU hungry; Windows
+    // also expects the RTC to run in local time.
+    let (hyperv_feature, clock) = if params.hyperv {
+        (
+            r#"
+    <hyperv mode="custom">
+      <relaxed state="on"/>
+      <vapic state="on"/>
+      <spinlocks state="on" retries="8191"/>
+      <vpindex state="on"/>
+      <synic state="on"/>
+      <stimer state="on"/>
+    </hyperv>"#,
+            r#"
+  <clock offset="localtime">
+    <timer name="rtc" tickpolicy="catchup"/>
+    <timer name="pit" tickpolicy="delay"/>
+    <timer name="hpet" present="no"/>
+    <timer name="hypervclock" present="yes"/>
+  </clock>"#,
+        )
+    } else {
+        ("", "")
+    };
+
+    let metadata = match params.os_id {
+        Some(ref id) => format!(
+            r#"
+  <metadata>
+    <libosinfo:libosinfo xmlns:libosinfo="http://libosinfo.org/xmlns/libvirt/domain/1.0">
+      <libosinfo:os id="{id}"/>
+    </libosinfo:libosinfo>
+  </metadata>"#
+        ),
+        None => String::new(),
+    };
+
+    // q35 has no IDE controller; guests that need IDE get the i440fx machine.
+    let machine = match params.disk_bus {
+        DiskBus::Ide => "pc",
+        _ => "q35",
+    };
+
+    let net = &params.network;
+    let (iface_type, source_elem) = match net.source_type {
+        NetworkSourceType::VirtualNetwork => (
+            "network",
+            format!(r#"<source network="{}"/>"#, net.source_value),
+        ),
+        NetworkSourceType::Bridge => (
+            "bridge",
+            format!(r#"<source bridge="{}"/>"#, net.source_value),
+        ),
+        NetworkSourceType::Macvtap => (
+            "direct",
+            format!(r#"<source dev="{}" mode="vepa"/>"#, net.source_value),
+        ),
+        NetworkSourceType::Vdpa => (
+            "vdpa",
+            format!(r#"<source dev="{}"/>"#, net.source_value),
+        ),
+    };
+    let iface_xml = format!(
+        "    <interface type=\"{iface_type}\">\n      {source_elem}\n      <model type=\"{model}\"/>\n    </interface>",
+        model = net.model.as_str(),
+    );
+
+    let tpm_xml = match params.tpm_model {
+        Some(model) => format!(
+            "\n    <tpm model=\"{}\"><backend type=\"emulated\" version=\"2.0\"/></tpm>",
+            model.as_str()
+        ),
+        None => String::new(),
+    };
+
+    let scsi_controller = match params.disk_bus {
+        DiskBus::Scsi => "    <controller type=\"scsi\" model=\"virtio-scsi\"/>\n",
+        _ => "",
+    };
+
+    let video_xml = match params.video {
+        VideoModel::None => String::new(),
+        model => format!(
+            "\n    <video>\n      <model type=\"{}\"/>\n    </video>",
+            model.as_str()
+        ),
+    };
+
+    let iso_path = params.install.iso_path();
+    let cdrom_devices: String = new_vm_cdroms(params)
+        .iter()
+        .enumerate()
+        .map(|(i, path)| {
+            let (bus, target) = cdrom_slot(params.disk_bus, i);
+            format!(
+                "    <disk type=\"file\" device=\"cdrom\">\n      <driver name=\"qemu\" type=\"raw\"/>\n      <source file=\"{path}\"/>\n      <target dev=\"{target}\" bus=\"{bus}\"/>\n      <readonly/>\n    </disk>\n"
+            )
+        })
+        .collect();
+
+    let xml = format!(
+        r#"<domain type="kvm">
+  <name>{name}</name>{metadata}
+  <memory unit="KiB">{memory_kib}</memory>
+  <vcpu placement="static">{vcpus}</vcpu>
+  {os_tag}
+    <type arch="x86_64" machine="{machine}">hvm</type>
+    <boot dev="hd"/>
+{cdrom_boot}  </os>
+  <features>
+    <acpi/>
+    <apic/>{hyperv_feature}{smm_feature}
+  </features>
+  <cpu mode="host-passthrough"/>{clock}
+  <devices>
+    <emulator>/usr/bin/qemu-system-x86_64</emulator>
+    <disk type="file" device="disk">
+      <driver name="qemu" type="{disk_format}"/>
+      <source file="{disk_path}"/>
+      <target dev="{disk_target}" bus="{disk_bus}"/>
+    </disk>
+{cdrom_device}{scsi_controller}{iface_xml}{tpm_xml}
+    <graphics type="spice" autoport="yes"/>{video_xml}
+    <channel type="unix">
+      <target type="virtio" name="org.qemu.guest_agent.0"/>
+    </channel>
+    <channel type="spicevmc">
+      <target type="virtio" name="com.redhat.spice.0"/>
+    </channel>
+    <input type="tablet" bus="usb"/>
+    <console type="pty"/>
+  </devices>
+</domain>"#,
+        name = params.name,
+        memory_kib = memory_kib,
+        vcpus = params.vcpus,
+        os_tag = os_tag,
+        smm_feature = smm_feature,
+        disk_format = params.disk_format.as_str(),
+        disk_path = disk_path,
+        disk_target = params.disk_bus.first_target(),
+        disk_bus = params.disk_bus.as_str(),
+        iface_xml = iface_xml,
+        tpm_xml = tpm_xml,
+        cdrom_boot = if iso_path.is_some() {
+            "    <boot dev=\"cdrom\"/>\n"
+        } else {
+            ""
+        },
+        cdrom_device = cdrom_devices,
+    );
+
+    xml
+}
+
+/// CD-ROMs `generate_domain_xml` gives a new VM: the installer (booted
+/// first), then the virtio-win driver ISO.
+fn new_vm_cdroms(params: &NewVmParams) -> Vec<&str> {
+    params
+        .install
+        .iso_path()
+        .into_iter()
+        .chain(params.virtio_win_iso.as_deref())
+        .collect()
+}
+
+/// Bus and target of the `index`th CD-ROM of a new VM: next to an IDE disk
+/// (IDE has only four slots), else on SATA after any sdX system disk.
+fn cdrom_slot(disk_bus: DiskBus, index: usize) -> (&'static str, String) {
+    match disk_bus {
+        DiskBus::Ide => ("ide", ["hdc", "hdd", "hdb"][index.min(2)].to_string()),
+        DiskBus::Sata | DiskBus::Scsi => ("sata", format!("sd{}", (b'b' + index as u8) as char)),
+        DiskBus::Virtio => ("sata", format!("sd{}", (b'a' + index as u8) as char)),
+    }
+}
+
+pub fn create_vm(uri: &str, params: &NewVmParams) -> Result<(), AppError> {
+    let disk_path = match params.install {
+        // An imported image is used in place
+        InstallSource::ImportDisk(ref path) => path.clone(),
+        // A cloud image stays pristine as the backing file of a new overlay
+        InstallSource::CloudImage(ref base) => crate::storage::create_vm_overlay(
+            uri,
+            &params.name,
+            params.disk_size_gib,
+            base,
+            params.storage_pool.as_deref(),
+        )?,
+        // Create the disk volume via libvirt so it lands in the default storage
+        // pool and is properly registered — no direct filesystem access needed.
+        _ => crate::storage::create_vm_disk(
+            uri,
+            &params.name,
+            params.disk_size_gib,
+            params.disk_format.as_str(),
+            params.disk_format.extension(),
+            params.storage_pool.as_deref(),
+        )?,
+    };
+
+    let mut xml = generate_domain_xml(params, &disk_path);
+
+    // Generated provisioning media goes on the next free CD-ROM: cloud-init
+    // finds its NoCloud seed by the "cidata" label, Windows Setup scans every
+    // removable drive for autounattend.xml.
+    let media = match (&params.cloud_init, &params.unattend) {
+        (Some(cloud_init), _) => Some(("cidata", cloud_init.seed_iso(&params.name))),
+        (None, Some(unattend)) => Some(("unattend", unattend.media_iso(params.firmware))),
+        (None, None) => None,
+    };
+    if let Some((suffix, image)) = media {
+        let media_path = crate::storage::upload_vm_image(
+            uri,
+            &format!("{}-{suffix}.iso", params.name),
+            &image,
+            params.storage_pool.as_deref(),
+        )?;
+        let (bus, target_dev) = cdrom_slot(params.disk_bus, new_vm_cdroms(params).len());
+        xml = add_disk_device(
+            &xml,
+            &NewDiskParams {
+                source_file: media_path,
+                target_dev,
+                bus: bus.to_string(),
+                device_type: "cdrom".to_string(),
+                driver_type: "raw".to_string(),
+                create_new: false,
+                size_gib: 0,
+            },
+        )?;
+    }
+
+    let conn = crate::connection::get_conn(uri)?;
+    let domain = virt::domain::Domain::define_xml(&conn, &xml)?;
+    drop(domain);
+
+    Ok(())
+}
+
+/// Run one edit against a domain definition and serialise the result.
+fn edit(xml: &str, f: impl FnOnce(&mut DomainXml)) -> Result<String, AppError> {
+    let mut domain = DomainXml::parse(xml)?;
+    f(&mut domain);
+    Ok(domain.to_xml())
+}
+
+/// Parse a full domain definition (as from `virDomainGetXMLDesc`) into the
+/// fields the UI understands.
+pub fn parse_domain_xml(xml: &str) -> Result<DomainDetails, AppError> {
+    Ok(DomainXml::parse(xml)?.details())
+}
+
+pub fn modify_graphics(xml: &str, graphics_type: GraphicsType) -> Result<String, AppError> {
+    edit(xml, |d| d.set_graphics(graphics_type))
+}
+
+pub fn modify_video(xml: &str, video_model: VideoModel, accel3d: bool) -> Result<String, AppError> {
+    edit(xml, |d| d.set_video(video_model, accel3d))
+}
+
+pub fn modify_sound(xml: &str, sound_model: SoundModel) -> Result<String, AppError> {
+    edit(xml, |d| d.set_sound(sound_model))
+}
+
+pub fn modify_domain_xml(xml: &str, new_vcpus: u32, new_memory_mib: u64) -> Result<String, AppError> {
+    edit(xml, |d| {
+        d.set_vcpus(new_vcpus);
+        d.set_memory_mib(new_memory_mib);
+    })
+}
+
+pub fn modify_cpu_model(
+    xml: &str,
+    cpu_mode: CpuMode,
+    cpu_model: Option<&str>,
+) -> Result<String, AppError> {
+    edit(xml, |d| d.set_cpu(cpu_mode, cpu_model))
+}
+
+pub fn modify_boot_order(
+    xml: &str,
+    boot_devices: &[BootDevice],
+) -> Result<String, AppError> {
+    edit(xml, |d| d.set_boot_order(boot_devices))
+}
+
+pub fn modify_firmware(
+    xml: &str,
+    firmware: FirmwareType,
+) -> Result<String, AppError> {
+    edit(xml, |d| d.set_firmware(firmware))
+}
+
+pub fn add_disk_device(xml: &str, params: &NewDiskParams) -> Result<String, AppError> {
+    edit(xml, |d| d.add_disk(params))
+}
+
+pub fn remove_disk_device(xml: &str, target_dev: &str) -> Result<String, AppError> {
+    edit(xml, |d| d.remove_disk(target_dev))
+}
+
+pub fn add_network_device(xml: &str, params: &NewNetworkParams) -> Result<String, AppError> {
+    edit(xml, |d| d.add_interface(params))
+}
+
+pub fn eject_cdrom(xml: &str, target_dev: &str) -> Result<String, AppError> {
+    edit(xml, |d| d.eject_cdrom(target_dev))
+}
+
+pub fn change_cdrom_media(
+    xml: &str,
+    target_dev: &str,
+    iso_path: &str,
+) -> Result<String, AppError> {
+    edit(xml, |d| d.set_disk_source(target_dev, iso_path, true))
+}
+
+pub fn change_disk_image(
+    xml: &str,
+    target_dev: &str,
+    new_path: &str,
+) -> Result<String, AppError> {
+    edit(xml, |d| d.set_disk_source(target_dev, new_path, false))
+}
+
+pub fn modify_cputune(xml: &str, cpu_tune: &CpuTune) -> Result<String, AppError> {
+    edit(xml, |d| d.set_cpu_tune(cpu_tune))
+}
+
+pub fn remove_network_device(xml: &str, mac_address: &str) -> Result<String, AppError> {
+    edit(xml, |d| d.remove_interface(mac_address))
+}
+
+pub fn modify_tpm(xml: &str, tpm_model: TpmModel) -> Result<String, AppError> {
+    edit(xml, |d| d.set_tpm(tpm_model))
+}
+
+pub fn add_filesystem(xml: &str, info: &FilesystemInfo) -> Result<String, AppError> {
+    edit(xml, |d| d.add_filesystem(info))
+}
+
+pub fn remove_filesystem(xml: &str, target_dir: &str) -> Result<String, AppError> {
+    edit(xml, |d| d.remove_filesystem(target_dir))
+}
+
+pub fn add_hostdev_device(xml: &str, info: &HostdevInfo) -> Result<String, AppError> {
+    edit(xml, |d| d.add_hostdev(info))
+}
+
+pub fn remove_hostdev_device(xml: &str, info: &HostdevInfo) -> Result<String, AppError> {
+    edit(xml, |d| d.remove_hostdev(info))
+}
+
+// ---- Rename ----
+
+pub fn rename_domain_xml(xml: &str, new_name: &str) -> Result<String, AppError> {
+    edit(xml, |d| d.set_name(new_name))
+}
+
+// ---- Clone helpers ----
+
+/// Extract source file paths of disk images (skips CDROMs with no source).
+pub fn extract_disk_paths(xml: &str) -> Vec<String> {
+    DomainXml::parse(xml)
+        .map(|d| d.disk_paths())
+        .unwrap_or_default()
+}
+
+/// Prepare XML for a VM clone: new name, no UUID (libvirt assigns one),
+/// updated disk paths, and MAC addresses removed (libvirt assigns new ones).
+pub fn prepare_clone_xml(
+    xml: &str,
+    new_name: &str,
+    disk_map: &[(String, String)],
+) -> Result<String, AppError> {
+    edit(xml, |d| d.prepare_clone(new_name, disk_map))
+}
+
+// ---- Serial / Console ----
+
+pub fn add_serial_device(xml: &str, info: &SerialInfo) -> Result<String, AppError> {
+    edit(xml, |d| d.add_serial(info))
+}
+
+pub fn remove_serial_device(xml: &str, info: &SerialInfo) -> Result<String, AppError> {
+    edit(xml, |d| d.remove_serial(info))
+}
+
+// ---- RNG ----
+
+pub fn modify_rng(xml: &str, backend: Option<RngBackend>) -> Result<String, AppError> {
+    edit(xml, |d| d.set_rng(backend))
+}
+
+// ---- Input Devices ----
+
+pub fn add_input_device(xml: &str, info: &InputInfo) -> Result<String, AppError> {
+    edit(xml, |d| d.add_input(info))
+}
+
+pub fn remove_input_device(xml: &str, info: &InputInfo) -> Result<String, AppError> {
+    edit(xml, |d| d.remove_input(info))
+}
+
+// ---- Channels ----
+
+pub fn add_channel_device(xml: &str, info: &ChannelInfo) -> Result<String, AppError> {
+    edit(xml, |d| d.add_channel(info))
+}
+
+pub fn remove_channel_device(xml: &str, target_name: &str) -> Result<String, AppError> {
+    edit(xml, |d| d.remove_channel(target_name))
+}
+
+// ---- Watchdog ----
+
+pub fn modify_watchdog(
+    xml: &str,
+    model: WatchdogModel,
+    action: WatchdogAction,
+) -> Result<String, AppError> {
+    edit(xml, |d| d.set_watchdog(model, action))
+}
+
+// ---- Controllers ----
+
+pub fn add_controller(xml: &str, info: &ControllerInfo) -> Result<String, AppError> {
+    edit(xml, |d| d.add_controller(info))
+}
+
+pub fn remove_controller(xml: &str, info: &ControllerInfo) -> Result<String, AppError> {
+    edit(xml, |d| d.remove_controller(info))
+}
+
+// ---- Parallel Ports ----
+
+pub fn add_parallel_device(xml: &str) -> Result<String, AppError> {
+    edit(xml, |d| d.add_parallel())
+}
+
+pub fn remove_parallel_device(xml: &str, port: u32) -> Result<String, AppError> {
+    edit(xml, |d| d.remove_parallel(port))
+}
+
+// ---- Panic Device ----
+
+pub fn modify_panic(xml: &str, model: PanicModel) -> Result<String, AppError> {
+    edit(xml, |d| d.set_panic(model))
+}
+
+// ---- USB Redirection ----
+
+pub fn add_usbredir(xml: &str) -> Result<String, AppError> {
+    edit(xml, |d| d.add_usbredir())
+}
+
+pub fn remove_usbredir(xml: &str, index: u32) -> Result<String, AppError> {
+    edit(xml, |d| d.remove_usbredir(index))
+}
+
+// ---- Smartcard ----
+
+pub fn modify_smartcard(xml: &str, mode: SmartcardMode) -> Result<String, AppError> {
+    edit(xml, |d| d.set_smartcard(mode))
+}
+
+// ---- Memory Balloon ----
+
+pub fn modify_memballoon(xml: &str, model: MemballoonModel) -> Result<String, AppError> {
+    edit(xml, |d| d.set_memballoon(model))
+}
+
+// ---- Change Network Source ----
+
+pub fn change_network_source(
+    xml: &str,
+    mac_address: &str,
+    params: &ChangeNetworkSourceParams,
+) -> Result<String, AppError> {
+    edit(xml, |d| d.set_interface_source(mac_address, params))
+}
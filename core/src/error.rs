@@ -0,0 +1,191 @@
+use std::fmt;
+
+use virt::error::{ErrorDomain, ErrorLevel, ErrorNumber};
+
+/// What libvirt reported for a failed call: the error code, the subsystem
+/// (domain) that raised it, its level and the message.
+#[derive(Debug, Clone)]
+pub struct LibvirtError {
+    pub code: ErrorNumber,
+    pub domain: ErrorDomain,
+    pub level: ErrorLevel,
+    pub message: String,
+}
+
+impl fmt::Display for LibvirtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<virt::error::Error> for LibvirtError {
+    fn from(err: virt::error::Error) -> Self {
+        LibvirtError {
+            code: err.code(),
+            domain: err.domain(),
+            level: err.level(),
+            message: err.message().to_string(),
+        }
+    }
+}
+
+/// Error type returned by every backend operation.
+///
+/// libvirt failures are classified by error code so callers can react to
+/// them (e.g. drop a cached connection only on `ConnectionLost`); anything
+/// not classified ends up in `Libvirt`.
+#[derive(Debug)]
+pub enum AppError {
+    /// Any other libvirt failure.
+    Libvirt(LibvirtError),
+    /// The domain, pool, volume, network, snapshot or device does not exist.
+    NotFound(LibvirtError),
+    /// The object is in the wrong state for the operation
+    /// (e.g. "domain is not running").
+    InvalidState(LibvirtError),
+    /// Authentication failed or access was denied by libvirt or polkit.
+    PermissionDenied(LibvirtError),
+    /// The connection to the host is gone (daemon restarted, SSH dropped).
+    ConnectionLost(LibvirtError),
+    /// libvirt rejected an XML document (schema or semantic validation).
+    XmlSchema(LibvirtError),
+    /// A helper program such as `qemu-img` exited with an error.
+    ExternalToolFailed {
+        tool: String,
+        status: Option<i32>,
+        stderr: String,
+    },
+    /// A backend precondition failed without libvirt itself reporting an
+    /// error (no usable storage pool, event loop not running, ...).
+    Backend(String),
+    /// Local XML parsing or serialisation failed.
+    Xml(String),
+    Io(std::io::Error),
+    /// The user cancelled the job running the operation.
+    Cancelled,
+}
+
+impl AppError {
+    /// Build an `ExternalToolFailed` from a finished process.
+    pub fn tool_failed(tool: &str, output: &std::process::Output) -> Self {
+        AppError::ExternalToolFailed {
+            tool: tool.to_string(),
+            status: output.status.code(),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        }
+    }
+
+    /// The libvirt error behind this one, if libvirt reported it.
+    pub fn libvirt(&self) -> Option<&LibvirtError> {
+        match self {
+            AppError::Libvirt(e)
+            | AppError::NotFound(e)
+            | AppError::InvalidState(e)
+            | AppError::PermissionDenied(e)
+            | AppError::ConnectionLost(e)
+            | AppError::XmlSchema(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    pub fn is_connection_lost(&self) -> bool {
+        matches!(self, AppError::ConnectionLost(_))
+    }
+
+    /// A short suggestion for the user on how to resolve the error, if there
+    /// is an obvious one.
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            AppError::PermissionDenied(_) => {
+                Some("Check that your user is in the libvirt group or use qemu:///session")
+            }
+            AppError::ConnectionLost(_) => {
+                Some("The connection will be reopened on the next refresh")
+            }
+            AppError::InvalidState(_) => Some("Refresh the VM and try again"),
+            AppError::ExternalToolFailed { tool, .. } if tool == "qemu-img" => {
+                Some("Make sure qemu-img is installed and the image is not in use")
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Libvirt(e) => write!(f, "Libvirt error: {e}"),
+            AppError::NotFound(e) => write!(f, "Not found: {e}"),
+            AppError::InvalidState(e) => write!(f, "Invalid state: {e}"),
+            AppError::PermissionDenied(e) => write!(f, "Permission denied: {e}"),
+            AppError::ConnectionLost(e) => write!(f, "Connection lost: {e}"),
+            AppError::XmlSchema(e) => write!(f, "Invalid XML: {e}"),
+            AppError::ExternalToolFailed { tool, status, stderr } => {
+                match status {
+                    Some(code) => write!(f, "{tool} failed (exit {code})")?,
+                    None => write!(f, "{tool} was terminated")?,
+                }
+                if !stderr.is_empty() {
+                    write!(f, ": {stderr}")?;
+                }
+                Ok(())
+            }
+            AppError::Backend(msg) => f.write_str(msg),
+            AppError::Xml(msg) => write!(f, "XML error: {msg}"),
+            AppError::Io(err) => write!(f, "IO error: {err}"),
+            AppError::Cancelled => f.write_str("Cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AppError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<virt::error::Error> for AppError {
+    fn from(err: virt::error::Error) -> Self {
+        let err = LibvirtError::from(err);
+        match err.code {
+            ErrorNumber::NoDomain
+            | ErrorNumber::NoNetwork
+            | ErrorNumber::NoStoragePool
+            | ErrorNumber::NoStorageVolume
+            | ErrorNumber::NoDomainSnapshot
+            | ErrorNumber::NoNodeDevice
+            | ErrorNumber::NoInterface
+            | ErrorNumber::NoSecret => AppError::NotFound(err),
+            ErrorNumber::OperationInvalid => AppError::InvalidState(err),
+            ErrorNumber::AuthFailed
+            | ErrorNumber::AuthCancelled
+            | ErrorNumber::AuthUnavailable
+            | ErrorNumber::AccessDenied
+            | ErrorNumber::OperationDenied => AppError::PermissionDenied(err),
+            ErrorNumber::NoConnect | ErrorNumber::InvalidConn => AppError::ConnectionLost(err),
+            // "End of file while reading data" and friends from the RPC layer
+            ErrorNumber::SystemError if err.domain == ErrorDomain::Rpc => {
+                AppError::ConnectionLost(err)
+            }
+            ErrorNumber::XmlError | ErrorNumber::XmlDetail | ErrorNumber::XmlInvalidSchema => {
+                AppError::XmlSchema(err)
+            }
+            _ => AppError::Libvirt(err),
+        }
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(err: std::io::Error) -> Self {
+        AppError::Io(err)
+    }
+}
+
+impl From<quick_xml::Error> for AppError {
+    fn from(err: quick_xml::Error) -> Self {
+        AppError::Xml(err.to_string())
+    }
+}
@@ -0,0 +1,481 @@
+use std::collections::HashMap;
+use std::ffi::{c_char, c_int, c_void, CStr};
+use std::sync::{mpsc, Mutex, Once, OnceLock};
+use virt::connect::Connect;
+use virt::domain::Domain;
+use virt::sys;
+
+use crate::connection::{get_conn, Conn};
+use crate::types::{HostEvent, HostEventKind};
+use crate::error::AppError;
+
+// ---------------------------------------------------------------------------
+// Lifecycle events
+//
+// libvirt delivers domain, pool and network events through its default event
+// loop implementation. `init_event_loop` registers that implementation (it
+// must run before the first connection is opened) and drives it from a
+// dedicated thread. `subscribe` registers the callbacks we care about on a
+// host's registry connection; every callback forwards a `HostEvent` over an
+// async_channel so the GTK main loop can refresh just the affected list.
+//
+// Callbacks are tied to the connection they were registered on. If that
+// connection dies, `is_subscribed` turns false and the caller re-subscribes
+// on the reopened connection (polling in the meantime).
+// ---------------------------------------------------------------------------
+
+const UUID_BUFLEN: usize = sys::VIR_UUID_STRING_BUFLEN as usize;
+
+static EVENT_LOOP: Once = Once::new();
+static EVENT_LOOP_OK: OnceLock<bool> = OnceLock::new();
+
+struct Subscription {
+    conn: Conn,
+    domain_ids: Vec<c_int>,
+    pool_ids: Vec<c_int>,
+    network_ids: Vec<c_int>,
+}
+
+static SUBSCRIPTIONS: OnceLock<Mutex<HashMap<String, Subscription>>> = OnceLock::new();
+
+fn subscriptions() -> &'static Mutex<HashMap<String, Subscription>> {
+    SUBSCRIPTIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Per-callback state handed to libvirt as the opaque pointer. Freed by
+/// libvirt through `free_context` when the callback is deregistered.
+struct CallbackContext {
+    uri: String,
+    kind: HostEventKind,
+    tx: async_channel::Sender<HostEvent>,
+}
+
+/// Register libvirt's default event loop and start the thread that runs it.
+/// Safe to call more than once; only the first call has an effect.
+pub fn init_event_loop() {
+    EVENT_LOOP.call_once(|| {
+        let ok = unsafe { sys::virEventRegisterDefaultImpl() } == 0;
+        let _ = EVENT_LOOP_OK.set(ok);
+        if !ok {
+            log::warn!("Failed to register libvirt event loop; falling back to polling");
+            return;
+        }
+
+        let spawned = std::thread::Builder::new()
+            .name("libvirt-events".to_string())
+            .spawn(|| loop {
+                if unsafe { sys::virEventRunDefaultImpl() } < 0 {
+                    log::error!("libvirt event loop iteration failed");
+                    std::thread::sleep(std::time::Duration::from_secs(1));
+                }
+            });
+        if let Err(e) = spawned {
+            log::error!("Failed to start libvirt event thread: {e}");
+        }
+    });
+}
+
+fn event_loop_running() -> bool {
+    EVENT_LOOP_OK.get().copied().unwrap_or(false)
+}
+
+/// Register domain, pool and network lifecycle callbacks for `uri`. Events are
+/// sent to `tx` until `unsubscribe` is called or the connection is lost.
+pub fn subscribe(uri: &str, tx: async_channel::Sender<HostEvent>) -> Result<(), AppError> {
+    if !event_loop_running() {
+        return Err(AppError::Backend("libvirt event loop is not running".to_string()));
+    }
+
+    // Replace any stale registration (e.g. from a connection that died)
+    unsubscribe(uri);
+
+    let conn = get_conn(uri)?;
+    let conn_ptr = conn.as_ptr();
+    let context = |kind| {
+        Box::into_raw(Box::new(CallbackContext {
+            uri: uri.to_string(),
+            kind,
+            tx: tx.clone(),
+        })) as *mut c_void
+    };
+
+    let mut sub = Subscription {
+        conn,
+        domain_ids: Vec::new(),
+        pool_ids: Vec::new(),
+        network_ids: Vec::new(),
+    };
+
+    // SAFETY: each callback is registered with the signature libvirt expects
+    // for its event ID; the generic callback type is only the C ABI carrier
+    // (same as the VIR_DOMAIN_EVENT_CALLBACK() cast in C).
+    unsafe {
+        let domain_events: [(u32, HostEventKind, sys::virConnectDomainEventGenericCallback); 4] = [
+            (
+                sys::VIR_DOMAIN_EVENT_ID_LIFECYCLE,
+                HostEventKind::DomainLifecycle,
+                std::mem::transmute::<
+                    unsafe extern "C" fn(sys::virConnectPtr, sys::virDomainPtr, c_int, c_int, *mut c_void) -> c_int,
+                    sys::virConnectDomainEventGenericCallback,
+                >(domain_lifecycle_cb),
+            ),
+            (
+                sys::VIR_DOMAIN_EVENT_ID_REBOOT,
+                HostEventKind::DomainReboot,
+                Some(domain_generic_cb),
+            ),
+            (
+                sys::VIR_DOMAIN_EVENT_ID_DEVICE_ADDED,
+                HostEventKind::DomainDeviceAdded,
+                std::mem::transmute::<
+                    unsafe extern "C" fn(sys::virConnectPtr, sys::virDomainPtr, *const c_char, *mut c_void),
+                    sys::virConnectDomainEventGenericCallback,
+                >(domain_device_cb),
+            ),
+            (
+                sys::VIR_DOMAIN_EVENT_ID_DEVICE_REMOVED,
+                HostEventKind::DomainDeviceRemoved,
+                std::mem::transmute::<
+                    unsafe extern "C" fn(sys::virConnectPtr, sys::virDomainPtr, *const c_char, *mut c_void),
+                    sys::virConnectDomainEventGenericCallback,
+                >(domain_device_cb),
+            ),
+        ];
+
+        for (event_id, kind, cb) in domain_events {
+            let opaque = context(kind);
+            let id = sys::virConnectDomainEventRegisterAny(
+                conn_ptr,
+                std::ptr::null_mut(),
+                event_id as c_int,
+                cb,
+                opaque,
+                Some(free_context),
+            );
+            if id < 0 {
+                // libvirt only takes ownership of the opaque on success
+                free_context(opaque);
+                release(uri, &sub);
+                return Err(AppError::Backend(format!(
+                    "Failed to register domain events on {uri}"
+                )));
+            }
+            sub.domain_ids.push(id);
+        }
+
+        let pool_events: [(u32, HostEventKind, sys::virConnectStoragePoolEventGenericCallback); 2] = [
+            (
+                sys::VIR_STORAGE_POOL_EVENT_ID_LIFECYCLE,
+                HostEventKind::PoolLifecycle,
+                std::mem::transmute::<
+                    unsafe extern "C" fn(sys::virConnectPtr, sys::virStoragePoolPtr, c_int, c_int, *mut c_void),
+                    sys::virConnectStoragePoolEventGenericCallback,
+                >(pool_lifecycle_cb),
+            ),
+            (
+                sys::VIR_STORAGE_POOL_EVENT_ID_REFRESH,
+                HostEventKind::PoolRefresh,
+                Some(pool_generic_cb),
+            ),
+        ];
+
+        for (event_id, kind, cb) in pool_events {
+            let opaque = context(kind);
+            let id = sys::virConnectStoragePoolEventRegisterAny(
+                conn_ptr,
+                std::ptr::null_mut(),
+                event_id as c_int,
+                cb,
+                opaque,
+                Some(free_context),
+            );
+            // Some drivers (e.g. older remote daemons) lack pool events;
+            // domain events alone are still worth keeping.
+            if id >= 0 {
+                sub.pool_ids.push(id);
+            } else {
+                free_context(opaque);
+                log::warn!("Storage pool events unavailable on {uri}");
+            }
+        }
+
+        let opaque = context(HostEventKind::NetworkLifecycle);
+        let id = sys::virConnectNetworkEventRegisterAny(
+            conn_ptr,
+            std::ptr::null_mut(),
+            sys::VIR_NETWORK_EVENT_ID_LIFECYCLE as c_int,
+            std::mem::transmute::<
+                unsafe extern "C" fn(sys::virConnectPtr, sys::virNetworkPtr, c_int, c_int, *mut c_void),
+                sys::virConnectNetworkEventGenericCallback,
+            >(network_lifecycle_cb),
+            opaque,
+            Some(free_context),
+        );
+        if id >= 0 {
+            sub.network_ids.push(id);
+        } else {
+            free_context(opaque);
+            log::warn!("Network events unavailable on {uri}");
+        }
+    }
+
+    let replaced = subscriptions()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(uri.to_string(), sub);
+    if let Some(old) = replaced {
+        release(uri, &old);
+    }
+    Ok(())
+}
+
+/// Deregister every callback registered for `uri`.
+pub fn unsubscribe(uri: &str) {
+    let sub = subscriptions()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(uri);
+    if let Some(sub) = sub {
+        release(uri, &sub);
+    }
+}
+
+/// Whether `uri` has callbacks registered on a connection that is still
+/// alive. When this is false the caller should poll.
+pub fn is_subscribed(uri: &str) -> bool {
+    subscriptions()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(uri)
+        .map(|sub| sub.conn.is_alive().unwrap_or(false))
+        .unwrap_or(false)
+}
+
+fn release(uri: &str, sub: &Subscription) {
+    // Deregistering on a dead connection fails; libvirt frees the contexts
+    // when the connection object goes away, so the errors are harmless.
+    let ptr = sub.conn.as_ptr();
+    unsafe {
+        for id in &sub.domain_ids {
+            sys::virConnectDomainEventDeregisterAny(ptr, *id);
+        }
+        for id in &sub.pool_ids {
+            sys::virConnectStoragePoolEventDeregisterAny(ptr, *id);
+        }
+        for id in &sub.network_ids {
+            sys::virConnectNetworkEventDeregisterAny(ptr, *id);
+        }
+    }
+    log::debug!("Deregistered events for {uri}");
+}
+
+// ---------------------------------------------------------------------------
+// Block job events
+//
+// A block commit or pull reports its end (and an active commit its readiness
+// to pivot) through VIR_DOMAIN_EVENT_ID_BLOCK_JOB_2. While one runs,
+// `watch_block_jobs` registers a callback for just that domain on its own
+// connection; the returned handle deregisters it when dropped.
+// ---------------------------------------------------------------------------
+
+/// A change in a block job, as reported by its event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BlockJobEvent {
+    Completed,
+    Failed,
+    Cancelled,
+    /// A mirroring job (an active commit) has caught up and can pivot.
+    Ready,
+}
+
+struct BlockJobContext {
+    tx: mpsc::Sender<(String, BlockJobEvent)>,
+}
+
+/// Block job events of one domain, until dropped.
+pub(crate) struct BlockJobEvents {
+    conn: Connect,
+    id: c_int,
+    rx: mpsc::Receiver<(String, BlockJobEvent)>,
+}
+
+impl BlockJobEvents {
+    /// The next event about disk `target_dev` that has arrived, if any.
+    /// Events about other disks are dropped.
+    pub(crate) fn try_next(&self, target_dev: &str) -> Option<BlockJobEvent> {
+        self.rx
+            .try_iter()
+            .find(|(disk, _)| disk == target_dev)
+            .map(|(_, event)| event)
+    }
+}
+
+impl Drop for BlockJobEvents {
+    fn drop(&mut self) {
+        // SAFETY: `id` was returned by the registration on this connection
+        unsafe { sys::virConnectDomainEventDeregisterAny(self.conn.as_ptr(), self.id) };
+    }
+}
+
+/// Start receiving the block job events of `domain`. `None` when the event
+/// loop is not running (as in the CLI), leaving the caller to poll.
+pub(crate) fn watch_block_jobs(domain: &Domain) -> Option<BlockJobEvents> {
+    if !event_loop_running() {
+        return None;
+    }
+    let conn = domain.get_connect().ok()?;
+    let (tx, rx) = mpsc::channel();
+    let opaque = Box::into_raw(Box::new(BlockJobContext { tx })) as *mut c_void;
+
+    // SAFETY: the callback has the signature libvirt expects for
+    // BLOCK_JOB_2; the generic type is only the C ABI carrier. libvirt owns
+    // `opaque` once the registration succeeds.
+    let id = unsafe {
+        sys::virConnectDomainEventRegisterAny(
+            conn.as_ptr(),
+            domain.as_ptr(),
+            sys::VIR_DOMAIN_EVENT_ID_BLOCK_JOB_2 as c_int,
+            std::mem::transmute::<
+                unsafe extern "C" fn(sys::virConnectPtr, sys::virDomainPtr, *const c_char, c_int, c_int, *mut c_void),
+                sys::virConnectDomainEventGenericCallback,
+            >(block_job_cb),
+            opaque,
+            Some(free_block_job_context),
+        )
+    };
+    if id < 0 {
+        // SAFETY: libvirt did not take ownership of `opaque`
+        unsafe { free_block_job_context(opaque) };
+        log::warn!("Block job events unavailable; polling instead");
+        return None;
+    }
+    Some(BlockJobEvents { conn, id, rx })
+}
+
+// --- C callbacks ---
+
+unsafe fn forward(opaque: *mut c_void, uuid: String) {
+    let ctx = &*(opaque as *const CallbackContext);
+    let _ = ctx.tx.try_send(HostEvent {
+        uri: ctx.uri.clone(),
+        kind: ctx.kind,
+        uuid,
+    });
+}
+
+unsafe fn buf_to_string(buf: &[c_char; UUID_BUFLEN]) -> String {
+    CStr::from_ptr(buf.as_ptr()).to_string_lossy().into_owned()
+}
+
+unsafe fn domain_uuid(dom: sys::virDomainPtr) -> String {
+    let mut buf = [0 as c_char; UUID_BUFLEN];
+    if sys::virDomainGetUUIDString(dom, buf.as_mut_ptr()) < 0 {
+        return String::new();
+    }
+    buf_to_string(&buf)
+}
+
+unsafe extern "C" fn domain_lifecycle_cb(
+    _conn: sys::virConnectPtr,
+    dom: sys::virDomainPtr,
+    _event: c_int,
+    _detail: c_int,
+    opaque: *mut c_void,
+) -> c_int {
+    forward(opaque, domain_uuid(dom));
+    0
+}
+
+unsafe extern "C" fn domain_generic_cb(
+    _conn: sys::virConnectPtr,
+    dom: sys::virDomainPtr,
+    opaque: *mut c_void,
+) {
+    forward(opaque, domain_uuid(dom));
+}
+
+unsafe extern "C" fn domain_device_cb(
+    _conn: sys::virConnectPtr,
+    dom: sys::virDomainPtr,
+    _dev_alias: *const c_char,
+    opaque: *mut c_void,
+) {
+    forward(opaque, domain_uuid(dom));
+}
+
+unsafe fn pool_uuid(pool: sys::virStoragePoolPtr) -> String {
+    let mut buf = [0 as c_char; UUID_BUFLEN];
+    if sys::virStoragePoolGetUUIDString(pool, buf.as_mut_ptr()) < 0 {
+        return String::new();
+    }
+    buf_to_string(&buf)
+}
+
+unsafe extern "C" fn pool_lifecycle_cb(
+    _conn: sys::virConnectPtr,
+    pool: sys::virStoragePoolPtr,
+    _event: c_int,
+    _detail: c_int,
+    opaque: *mut c_void,
+) {
+    forward(opaque, pool_uuid(pool));
+}
+
+unsafe extern "C" fn pool_generic_cb(
+    _conn: sys::virConnectPtr,
+    pool: sys::virStoragePoolPtr,
+    opaque: *mut c_void,
+) {
+    forward(opaque, pool_uuid(pool));
+}
+
+unsafe extern "C" fn network_lifecycle_cb(
+    _conn: sys::virConnectPtr,
+    net: sys::virNetworkPtr,
+    _event: c_int,
+    _detail: c_int,
+    opaque: *mut c_void,
+) {
+    let mut buf = [0 as c_char; UUID_BUFLEN];
+    let uuid = if sys::virNetworkGetUUIDString(net, buf.as_mut_ptr()) < 0 {
+        String::new()
+    } else {
+        buf_to_string(&buf)
+    };
+    forward(opaque, uuid);
+}
+
+unsafe extern "C" fn block_job_cb(
+    _conn: sys::virConnectPtr,
+    _dom: sys::virDomainPtr,
+    disk: *const c_char,
+    _job_type: c_int,
+    status: c_int,
+    opaque: *mut c_void,
+) {
+    let ctx = &*(opaque as *const BlockJobContext);
+    let event = match status as u32 {
+        sys::VIR_DOMAIN_BLOCK_JOB_COMPLETED => BlockJobEvent::Completed,
+        sys::VIR_DOMAIN_BLOCK_JOB_FAILED => BlockJobEvent::Failed,
+        sys::VIR_DOMAIN_BLOCK_JOB_CANCELED => BlockJobEvent::Cancelled,
+        sys::VIR_DOMAIN_BLOCK_JOB_READY => BlockJobEvent::Ready,
+        _ => return,
+    };
+    if disk.is_null() {
+        return;
+    }
+    let disk = CStr::from_ptr(disk).to_string_lossy().into_owned();
+    let _ = ctx.tx.send((disk, event));
+}
+
+unsafe extern "C" fn free_block_job_context(opaque: *mut c_void) {
+    if !opaque.is_null() {
+        drop(Box::from_raw(opaque as *mut BlockJobContext));
+    }
+}
+
+unsafe extern "C" fn free_context(opaque: *mut c_void) {
+    if !opaque.is_null() {
+        drop(Box::from_raw(opaque as *mut CallbackContext));
+    }
+}
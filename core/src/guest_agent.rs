@@ -0,0 +1,209 @@
+//! Work done inside a running guest through the QEMU guest agent: finding
+//! the filesystems on a disk and growing them after the disk was grown.
+
+use std::ffi::{c_char, c_void, CStr};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use virt::domain::Domain;
+
+use crate::error::AppError;
+use crate::types::GuestResize;
+
+/// How long a command started with `guest-exec` may run.
+const EXEC_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How often a `guest-exec` command is checked for having exited.
+const EXEC_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A mounted guest filesystem, as reported by `virDomainGetFSInfo`.
+struct GuestFilesystem {
+    mountpoint: String,
+    /// Guest device name, such as "vda1".
+    name: String,
+    fstype: String,
+    /// Target devices of the VM's disks the filesystem lives on.
+    disks: Vec<String>,
+}
+
+/// Send one agent command and return the `return` member of the reply.
+fn agent_command(domain: &Domain, command: Value) -> Result<Value, AppError> {
+    let reply = domain.qemu_agent_command(
+        &command.to_string(),
+        virt::sys::VIR_DOMAIN_QEMU_AGENT_COMMAND_DEFAULT,
+        0,
+    )?;
+    let mut reply: Value = serde_json::from_str(&reply)
+        .map_err(|e| AppError::Backend(format!("Bad guest agent reply: {e}")))?;
+    Ok(reply["return"].take())
+}
+
+/// Whether the guest agent of a running VM answers.
+pub fn agent_available(domain: &Domain) -> bool {
+    agent_command(domain, json!({ "execute": "guest-ping" })).is_ok()
+}
+
+/// Run `argv` in the guest and wait for it to exit. Returns the exit code.
+fn guest_exec(domain: &Domain, argv: &[String]) -> Result<i64, AppError> {
+    let started = agent_command(
+        domain,
+        json!({
+            "execute": "guest-exec",
+            "arguments": { "path": argv[0], "arg": argv[1..], "capture-output": true },
+        }),
+    )?;
+    let pid = started["pid"]
+        .as_i64()
+        .ok_or_else(|| AppError::Backend("guest-exec returned no pid".into()))?;
+
+    let deadline = Instant::now() + EXEC_TIMEOUT;
+    loop {
+        let status = agent_command(
+            domain,
+            json!({ "execute": "guest-exec-status", "arguments": { "pid": pid } }),
+        )?;
+        if status["exited"].as_bool() == Some(true) {
+            return Ok(status["exitcode"].as_i64().unwrap_or(-1));
+        }
+        if Instant::now() > deadline {
+            return Err(AppError::Backend(format!("{} did not finish in the guest", argv[0])));
+        }
+        thread::sleep(EXEC_POLL_INTERVAL);
+    }
+}
+
+/// The guest's mounted filesystems. libvirt maps the agent's view of the
+/// disks onto the VM's target devices.
+fn filesystems(domain: &Domain) -> Result<Vec<GuestFilesystem>, AppError> {
+    let mut info: *mut virt::sys::virDomainFSInfoPtr = std::ptr::null_mut();
+    // SAFETY: on success libvirt points `info` at `count` entries it
+    // allocated; they are freed below and nowhere else.
+    let count = unsafe { virt::sys::virDomainGetFSInfo(domain.as_ptr(), &mut info, 0) };
+    if count < 0 {
+        return Err(virt::error::Error::last_error().into());
+    }
+
+    let mut result = Vec::with_capacity(count as usize);
+    for i in 0..count as usize {
+        // SAFETY: `i` is below `count`, and the strings in each entry are
+        // NUL-terminated or null. Each entry is read once, then freed.
+        unsafe {
+            let fs = *info.add(i);
+            let disks = (0..(*fs).ndevAlias)
+                .map(|j| c_string(*(*fs).devAlias.add(j)))
+                .collect();
+            result.push(GuestFilesystem {
+                mountpoint: c_string((*fs).mountpoint),
+                name: c_string((*fs).name),
+                fstype: c_string((*fs).fstype),
+                disks,
+            });
+            virt::sys::virDomainFSInfoFree(fs);
+        }
+    }
+    // SAFETY: the array itself was malloc'd by libvirt and is no longer read.
+    unsafe { libc::free(info as *mut c_void) };
+    Ok(result)
+}
+
+/// # Safety
+/// `ptr` must be null or point to a NUL-terminated string.
+unsafe fn c_string(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    CStr::from_ptr(ptr).to_string_lossy().into_owned()
+}
+
+/// Grow the partitions and filesystems on disk `target_dev` to fill it,
+/// after the disk itself was grown with the VM running.
+pub fn grow_filesystems(domain: &Domain, target_dev: &str) -> GuestResize {
+    if !agent_available(domain) {
+        return GuestResize::NoAgent;
+    }
+    let filesystems = match filesystems(domain) {
+        Ok(filesystems) => filesystems,
+        Err(e) => return GuestResize::Failed(e.to_string()),
+    };
+
+    let mut grown: Vec<String> = Vec::new();
+    let mut done: Vec<&str> = Vec::new();
+    for fs in filesystems.iter().filter(|fs| fs.disks.iter().any(|d| d == target_dev)) {
+        // Subvolumes and bind mounts show the same device more than once
+        if done.contains(&fs.name.as_str()) {
+            continue;
+        }
+        let Some(commands) = grow_commands(&fs.name, &fs.fstype, &fs.mountpoint) else {
+            return GuestResize::Failed(format!(
+                "{} ({} on {}) has to be grown inside the guest",
+                fs.mountpoint, fs.fstype, fs.name
+            ));
+        };
+        for argv in &commands {
+            match guest_exec(domain, argv) {
+                Ok(0) => {}
+                // growpart exits with 1 when the partition already fills the disk
+                Ok(1) if argv[0] == "growpart" => {}
+                Ok(code) => {
+                    return GuestResize::Failed(format!("`{}` exited with {code}", argv.join(" ")))
+                }
+                Err(e) => return GuestResize::Failed(format!("{}: {e}", argv[0])),
+            }
+        }
+        done.push(&fs.name);
+        grown.push(fs.mountpoint.clone());
+    }
+    GuestResize::Grown(grown)
+}
+
+/// The guest commands that grow filesystem `fstype` on guest device `name`,
+/// mounted at `mountpoint`, to the end of its disk: the partition first (with
+/// cloud-utils' growpart), then the filesystem. `None` for filesystems this
+/// cannot grow, such as ones on LVM or RAID or of an unknown type.
+pub fn grow_commands(name: &str, fstype: &str, mountpoint: &str) -> Option<Vec<Vec<String>>> {
+    let argv = |parts: &[&str]| parts.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+
+    if fstype.eq_ignore_ascii_case("ntfs") {
+        // Windows reports volumes by GUID, mounted at "C:\"
+        let letter = mountpoint.strip_suffix(":\\").filter(|l| l.len() == 1)?;
+        let script = format!(
+            "Resize-Partition -DriveLetter {letter} \
+             -Size (Get-PartitionSupportedSize -DriveLetter {letter}).SizeMax"
+        );
+        return Some(vec![argv(&["powershell.exe", "-NoProfile", "-Command", &script])]);
+    }
+    if name.starts_with("dm-") || name.starts_with("md") {
+        return None;
+    }
+
+    let device = format!("/dev/{name}");
+    let grow_fs = match fstype {
+        "ext2" | "ext3" | "ext4" => argv(&["resize2fs", &device]),
+        "xfs" => argv(&["xfs_growfs", mountpoint]),
+        "btrfs" => argv(&["btrfs", "filesystem", "resize", "max", mountpoint]),
+        _ => return None,
+    };
+    let mut commands = Vec::new();
+    if let Some((disk, number)) = split_partition(name) {
+        commands.push(argv(&["growpart", &format!("/dev/{disk}"), &number.to_string()]));
+    }
+    commands.push(grow_fs);
+    Some(commands)
+}
+
+/// "vda1" → ("vda", 1), "nvme0n1p2" → ("nvme0n1", 2). `None` for whole
+/// disks such as "vdb" or "nvme0n1".
+pub fn split_partition(name: &str) -> Option<(&str, u32)> {
+    let disk = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    let number = name[disk.len()..].parse().ok()?;
+    let numbered = |b: &&str| b.ends_with(|c: char| c.is_ascii_digit());
+    if let Some(base) = disk.strip_suffix('p').filter(numbered) {
+        return Some((base, number));
+    }
+    // Disks whose names end in a number only number partitions after a "p"
+    if disk.contains(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    Some((disk, number))
+}
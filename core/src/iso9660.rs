@@ -0,0 +1,187 @@
+// Minimal ISO 9660 image writer for generated install media (cloud-init
+// seeds, Windows answer files).
+//
+// Just enough of the format for a single root directory holding a handful
+// of small files. A Joliet supplementary descriptor carries the real
+// lower-case, hyphenated file names, which plain ISO 9660 level 1 cannot
+// represent.
+
+const SECTOR: usize = 2048;
+
+// Fixed layout: descriptors at 16-18, path tables at 19-22, the two root
+// directories at 23-24, file data from 25.
+const PVD_LBA: usize = 16;
+const PATH_TABLE_LBA: u32 = 19;
+const ROOT_LBA: u32 = 23;
+const JOLIET_ROOT_LBA: u32 = 24;
+const DATA_LBA: u32 = 25;
+
+/// Build an ISO 9660 image (with Joliet names) labelled `volume_id` and
+/// holding `files` in its root directory. The image is deterministic: all
+/// timestamps are left unset.
+pub fn build_iso(volume_id: &str, files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut files: Vec<(&str, &[u8])> = files.to_vec();
+    files.sort_by_key(|(name, _)| *name);
+
+    let mut extents = Vec::with_capacity(files.len());
+    let mut next = DATA_LBA;
+    for (_, data) in &files {
+        extents.push(next);
+        next += data.len().div_ceil(SECTOR) as u32;
+    }
+    let total_sectors = next as usize;
+    let mut iso = vec![0u8; total_sectors * SECTOR];
+
+    let primary_names: Vec<Vec<u8>> = files.iter().map(|(n, _)| iso9660_name(n)).collect();
+    let joliet_names: Vec<Vec<u8>> = files
+        .iter()
+        .map(|(n, _)| ucs2(&format!("{n};1")))
+        .collect();
+
+    for (joliet, root_lba) in [(false, ROOT_LBA), (true, JOLIET_ROOT_LBA)] {
+        let names = if joliet { &joliet_names } else { &primary_names };
+        let mut dir = Vec::with_capacity(SECTOR);
+        dir.extend(dir_record(root_lba, SECTOR as u32, true, &[0]));
+        dir.extend(dir_record(root_lba, SECTOR as u32, true, &[1]));
+        for (i, (_, data)) in files.iter().enumerate() {
+            dir.extend(dir_record(extents[i], data.len() as u32, false, &names[i]));
+        }
+        assert!(dir.len() <= SECTOR, "ISO root directory overflow");
+        write_at(&mut iso, root_lba as usize * SECTOR, &dir);
+
+        let table_lba = PATH_TABLE_LBA + if joliet { 2 } else { 0 };
+        write_at(&mut iso, table_lba as usize * SECTOR, &path_table(root_lba, false));
+        write_at(&mut iso, (table_lba + 1) as usize * SECTOR, &path_table(root_lba, true));
+
+        let pvd = volume_descriptor(volume_id, joliet, total_sectors as u32, table_lba, root_lba);
+        write_at(&mut iso, (PVD_LBA + joliet as usize) * SECTOR, &pvd);
+    }
+
+    // Volume descriptor set terminator
+    write_at(&mut iso, (PVD_LBA + 2) * SECTOR, &[255, b'C', b'D', b'0', b'0', b'1', 1]);
+
+    for (i, (_, data)) in files.iter().enumerate() {
+        write_at(&mut iso, extents[i] as usize * SECTOR, data);
+    }
+    iso
+}
+
+fn volume_descriptor(
+    volume_id: &str,
+    joliet: bool,
+    sectors: u32,
+    table_lba: u32,
+    root_lba: u32,
+) -> Vec<u8> {
+    let mut d = vec![0u8; SECTOR];
+    d[0] = if joliet { 2 } else { 1 };
+    d[1..6].copy_from_slice(b"CD001");
+    d[6] = 1;
+    let text = |s: &str, len: usize| {
+        if joliet {
+            let mut v = ucs2(s);
+            while v.len() < len {
+                v.extend_from_slice(&[0, b' ']);
+            }
+            v.truncate(len);
+            v
+        } else {
+            let mut v = s.as_bytes().to_vec();
+            v.resize(len, b' ');
+            v.truncate(len);
+            v
+        }
+    };
+    d[8..40].copy_from_slice(&text("", 32));
+    d[40..72].copy_from_slice(&text(volume_id, 32));
+    d[80..88].copy_from_slice(&both_u32(sectors));
+    if joliet {
+        // UCS-2 level 3
+        d[88..91].copy_from_slice(b"%/E");
+    }
+    d[120..124].copy_from_slice(&both_u16(1));
+    d[124..128].copy_from_slice(&both_u16(1));
+    d[128..132].copy_from_slice(&both_u16(SECTOR as u16));
+    d[132..140].copy_from_slice(&both_u32(PATH_TABLE_LEN as u32));
+    d[140..144].copy_from_slice(&table_lba.to_le_bytes());
+    d[148..152].copy_from_slice(&(table_lba + 1).to_be_bytes());
+    d[156..190].copy_from_slice(&dir_record(root_lba, SECTOR as u32, true, &[0]));
+    // Volume set, publisher, preparer, application and file identifiers
+    for range in [190..318, 318..446, 446..574, 574..702, 702..739, 739..776, 776..813] {
+        let len = range.len();
+        d[range].copy_from_slice(&text("", len));
+    }
+    // Creation, modification, expiration and effective dates: "not specified"
+    for start in [813, 830, 847, 864] {
+        d[start..start + 16].fill(b'0');
+    }
+    d[881] = 1;
+    d
+}
+
+const PATH_TABLE_LEN: usize = 10;
+
+/// Path table with just the root directory, little- or big-endian.
+fn path_table(root_lba: u32, big_endian: bool) -> Vec<u8> {
+    let mut t = vec![1, 0];
+    if big_endian {
+        t.extend(root_lba.to_be_bytes());
+        t.extend(1u16.to_be_bytes());
+    } else {
+        t.extend(root_lba.to_le_bytes());
+        t.extend(1u16.to_le_bytes());
+    }
+    t.extend([0, 0]); // root identifier and padding
+    t
+}
+
+fn dir_record(lba: u32, size: u32, is_dir: bool, name: &[u8]) -> Vec<u8> {
+    let len = 33 + name.len() + (name.len() + 1) % 2;
+    let mut r = vec![0u8; len];
+    r[0] = len as u8;
+    r[2..10].copy_from_slice(&both_u32(lba));
+    r[10..18].copy_from_slice(&both_u32(size));
+    // 18..25: recording time left unset
+    r[25] = if is_dir { 2 } else { 0 };
+    r[28..32].copy_from_slice(&both_u16(1));
+    r[32] = name.len() as u8;
+    r[33..33 + name.len()].copy_from_slice(name);
+    r
+}
+
+/// Level 1 name ("user-data" -> "USER_DAT.;1"); Joliet carries the real one.
+fn iso9660_name(name: &str) -> Vec<u8> {
+    let (stem, ext) = name.split_once('.').unwrap_or((name, ""));
+    let clean = |s: &str, len: usize| -> String {
+        s.chars()
+            .map(|c| match c.to_ascii_uppercase() {
+                c @ ('A'..='Z' | '0'..='9') => c,
+                _ => '_',
+            })
+            .take(len)
+            .collect()
+    };
+    format!("{}.{};1", clean(stem, 8), clean(ext, 3)).into_bytes()
+}
+
+fn ucs2(s: &str) -> Vec<u8> {
+    s.encode_utf16().flat_map(u16::to_be_bytes).collect()
+}
+
+fn both_u32(v: u32) -> [u8; 8] {
+    let mut b = [0u8; 8];
+    b[..4].copy_from_slice(&v.to_le_bytes());
+    b[4..].copy_from_slice(&v.to_be_bytes());
+    b
+}
+
+fn both_u16(v: u16) -> [u8; 4] {
+    let mut b = [0u8; 4];
+    b[..2].copy_from_slice(&v.to_le_bytes());
+    b[2..].copy_from_slice(&v.to_be_bytes());
+    b
+}
+
+fn write_at(buf: &mut [u8], offset: usize, data: &[u8]) {
+    buf[offset..offset + data.len()].copy_from_slice(data);
+}
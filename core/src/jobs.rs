@@ -0,0 +1,325 @@
+use std::ffi::CString;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use virt::domain::Domain;
+
+use crate::error::AppError;
+use crate::events::BlockJobEvent;
+use crate::types::{JobInfo, JobStatus};
+
+/// Progress updates closer together than this are not passed on.
+const NOTIFY_INTERVAL: Duration = Duration::from_millis(200);
+
+/// How often a libvirt job's statistics are polled.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+type Notify = Box<dyn Fn(JobInfo) + Send + Sync>;
+
+struct Shared {
+    info: Mutex<JobInfo>,
+    cancelled: AtomicBool,
+    last_notify: Mutex<Option<Instant>>,
+    notify: Option<Notify>,
+}
+
+/// Handle to a long-running operation, passed to the backend call doing the
+/// work. The call reports progress on it and stops early once it has been
+/// cancelled; clones of the handle share one job.
+#[derive(Clone)]
+pub struct Job {
+    shared: Arc<Shared>,
+}
+
+impl Job {
+    /// A job nobody watches, for callers that only want the result.
+    pub fn detached(title: &str) -> Self {
+        Self::new(title, None)
+    }
+
+    /// A job that hands `notify` a snapshot of itself whenever it changes.
+    /// Progress updates are passed on at most a few times a second.
+    pub fn watched(title: &str, notify: impl Fn(JobInfo) + Send + Sync + 'static) -> Self {
+        Self::new(title, Some(Box::new(notify)))
+    }
+
+    fn new(title: &str, notify: Option<Notify>) -> Self {
+        let info = JobInfo {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            title: title.to_string(),
+            detail: String::new(),
+            done: 0,
+            total: 0,
+            cancellable: false,
+            status: JobStatus::Running,
+        };
+        Job {
+            shared: Arc::new(Shared {
+                info: Mutex::new(info),
+                cancelled: AtomicBool::new(false),
+                last_notify: Mutex::new(None),
+                notify,
+            }),
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.info().id
+    }
+
+    pub fn info(&self) -> JobInfo {
+        self.shared.info.lock().expect("job lock").clone()
+    }
+
+    fn update(&self, throttle: bool, f: impl FnOnce(&mut JobInfo)) {
+        let info = {
+            let mut info = self.shared.info.lock().expect("job lock");
+            f(&mut info);
+            info.clone()
+        };
+        let Some(notify) = &self.shared.notify else {
+            return;
+        };
+        {
+            let mut last = self.shared.last_notify.lock().expect("job lock");
+            if throttle && last.is_some_and(|t| t.elapsed() < NOTIFY_INTERVAL) {
+                return;
+            }
+            *last = Some(Instant::now());
+        }
+        notify(info);
+    }
+
+    /// Say what the job is busy with, e.g. "Disk 2 of 3".
+    pub fn set_detail(&self, detail: &str) {
+        self.update(false, |info| info.detail = detail.to_string());
+    }
+
+    /// Report `done` of `total` units (usually bytes).
+    pub fn set_progress(&self, done: u64, total: u64) {
+        self.update(done < total, |info| {
+            info.done = done;
+            info.total = total;
+        });
+    }
+
+    /// Whether [`Job::cancel`] currently has any effect.
+    pub fn set_cancellable(&self, cancellable: bool) {
+        self.update(false, |info| info.cancellable = cancellable);
+    }
+
+    /// Ask the operation to stop. It notices at its next check, rolls back
+    /// what it can and fails with [`AppError::Cancelled`].
+    pub fn cancel(&self) {
+        self.shared.cancelled.store(true, Ordering::Relaxed);
+        self.update(false, |info| info.detail = "Cancelling…".to_string());
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.shared.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// `Err(AppError::Cancelled)` once the job has been cancelled.
+    pub fn check_cancelled(&self) -> Result<(), AppError> {
+        if self.is_cancelled() {
+            Err(AppError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Record how the operation ended.
+    pub fn finish<T>(&self, result: &Result<T, AppError>) {
+        self.update(false, |info| {
+            info.cancellable = false;
+            info.detail.clear();
+            info.status = match result {
+                Ok(_) => JobStatus::Succeeded,
+                Err(AppError::Cancelled) => JobStatus::Cancelled,
+                Err(e) => JobStatus::Failed(e.to_string()),
+            };
+        });
+    }
+}
+
+/// The jobs of one front end. Each change to one of them is sent to the
+/// receiver handed out by [`JobManager::new`].
+pub struct JobManager {
+    jobs: Mutex<Vec<Job>>,
+    tx: async_channel::Sender<JobInfo>,
+}
+
+impl JobManager {
+    pub fn new() -> (Self, async_channel::Receiver<JobInfo>) {
+        let (tx, rx) = async_channel::unbounded();
+        let manager = JobManager {
+            jobs: Mutex::new(Vec::new()),
+            tx,
+        };
+        (manager, rx)
+    }
+
+    /// Register a new running job.
+    pub fn start(&self, title: &str) -> Job {
+        let tx = self.tx.clone();
+        let job = Job::watched(title, move |info| {
+            let _ = tx.try_send(info);
+        });
+        self.jobs.lock().expect("jobs lock").push(job.clone());
+        let _ = self.tx.try_send(job.info());
+        job
+    }
+
+    /// All jobs, oldest first.
+    pub fn jobs(&self) -> Vec<JobInfo> {
+        self.jobs.lock().expect("jobs lock").iter().map(Job::info).collect()
+    }
+
+    pub fn cancel(&self, id: u64) {
+        if let Some(job) = self.jobs.lock().expect("jobs lock").iter().find(|j| j.id() == id) {
+            job.cancel();
+        }
+    }
+
+    /// Forget the jobs that have ended.
+    pub fn clear_finished(&self) {
+        self.jobs.lock().expect("jobs lock").retain(|j| j.info().is_running());
+    }
+}
+
+/// Run `op`, a libvirt call that blocks while `domain` runs a job (saving
+/// memory, migrating), on a worker thread. Meanwhile the domain job's data
+/// counters are reported on `job`, and cancelling `job` aborts it.
+pub(crate) fn watch_domain_job<R: Send>(
+    domain: &Domain,
+    job: &Job,
+    op: impl FnOnce() -> Result<R, AppError> + Send,
+) -> Result<R, AppError> {
+    job.set_cancellable(true);
+    let result = std::thread::scope(|scope| {
+        let worker = scope.spawn(op);
+        let mut aborted = false;
+        while !worker.is_finished() {
+            std::thread::sleep(POLL_INTERVAL);
+            if job.is_cancelled() && !aborted {
+                abort_domain_job(domain);
+                aborted = true;
+            }
+            if let Ok(stats) = domain.get_job_info() {
+                if let (Some(done), Some(total)) = (stats.data_processed, stats.data_total) {
+                    if total > 0 {
+                        job.set_progress(done, total);
+                    }
+                }
+            }
+        }
+        worker.join().expect("libvirt job thread panicked")
+    });
+    job.set_cancellable(false);
+    match result {
+        Err(_) if job.is_cancelled() => Err(AppError::Cancelled),
+        other => other,
+    }
+}
+
+/// Abort the job `domain` is running, if any.
+fn abort_domain_job(domain: &Domain) {
+    // SAFETY: the pointer stays valid while `domain` is borrowed;
+    // virDomainAbortJob may be called while another thread waits on the job.
+    let _ = unsafe { virt::sys::virDomainAbortJob(domain.as_ptr()) };
+}
+
+/// Start a block job on disk `target_dev` of `domain` with `start` (a
+/// libvirt call returning 0 or -1) and wait for it to end, reporting its
+/// progress on `job`. Events are watched from before the start, so a short
+/// job cannot finish unseen. With `pivot` (an active commit) the disk is
+/// switched over to the commit's base once the job has caught up.
+/// Cancelling `job` aborts the block job, leaving the disk as it was.
+pub(crate) fn watch_block_job(
+    domain: &Domain,
+    target_dev: &str,
+    pivot: bool,
+    job: &Job,
+    start: impl FnOnce() -> i32,
+) -> Result<(), AppError> {
+    let disk = CString::new(target_dev)
+        .map_err(|_| AppError::Backend(format!("Bad disk name {target_dev}")))?;
+    let events = crate::events::watch_block_jobs(domain);
+    if start() < 0 {
+        return Err(virt::error::Error::last_error().into());
+    }
+    let failed = || AppError::Backend(format!("The block job on {target_dev} failed"));
+
+    job.set_cancellable(true);
+    let result = loop {
+        std::thread::sleep(POLL_INTERVAL);
+        if job.is_cancelled() {
+            // SAFETY: `disk` is NUL-terminated and outlives the call
+            unsafe { virt::sys::virDomainBlockJobAbort(domain.as_ptr(), disk.as_ptr(), 0) };
+            break Err(AppError::Cancelled);
+        }
+
+        let mut info = virt::sys::virDomainBlockJobInfo {
+            type_: 0,
+            bandwidth: 0,
+            cur: 0,
+            end: 0,
+        };
+        // SAFETY: `info` is a valid out-parameter for the duration of the call
+        let found = unsafe {
+            virt::sys::virDomainGetBlockJobInfo(domain.as_ptr(), disk.as_ptr(), &mut info, 0)
+        };
+        if found < 0 {
+            break Err(virt::error::Error::last_error().into());
+        }
+        let event = events.as_ref().and_then(|e| e.try_next(target_dev));
+        match event {
+            Some(BlockJobEvent::Completed) => break Ok(()),
+            Some(BlockJobEvent::Failed) => break Err(failed()),
+            Some(BlockJobEvent::Cancelled) => {
+                break Err(AppError::Backend(format!(
+                    "The block job on {target_dev} was cancelled elsewhere"
+                )))
+            }
+            _ => {}
+        }
+        if found == 0 {
+            // Gone without an event: ended, one way or the other
+            match events.as_ref().and_then(|e| e.try_next(target_dev)) {
+                Some(BlockJobEvent::Failed) => break Err(failed()),
+                _ => break Ok(()),
+            }
+        }
+        if info.end > 0 {
+            job.set_progress(info.cur, info.end);
+        }
+
+        // A mirror that has copied everything is ready, even if the event
+        // for that is missing
+        let announced = event == Some(BlockJobEvent::Ready);
+        let ready = announced || (info.end > 0 && info.cur == info.end);
+        if pivot && ready {
+            // SAFETY: as above; without the ASYNC flag this returns once the
+            // disk has been switched over
+            let pivoted = unsafe {
+                virt::sys::virDomainBlockJobAbort(
+                    domain.as_ptr(),
+                    disk.as_ptr(),
+                    virt::sys::VIR_DOMAIN_BLOCK_JOB_ABORT_PIVOT,
+                )
+            };
+            if pivoted == 0 {
+                break Ok(());
+            }
+            // Polled readiness can be premature; an announced one cannot
+            if announced {
+                break Err(virt::error::Error::last_error().into());
+            }
+        }
+    };
+    job.set_cancellable(false);
+    result
+}
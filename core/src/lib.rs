@@ -0,0 +1,57 @@
+//! libvirt backend shared by the grustyvman GUI, the SPICE viewer and
+//! `grustyvman-cli`.
+//!
+//! Nothing in here depends on GTK. Every operation takes the libvirt
+//! connection URI (connections are cached per URI, see [`connection`]) and
+//! returns `Result<_, AppError>`. Domains, pools and networks are addressed
+//! by UUID string.
+//!
+//! - [`connection`]: connection registry, host info, VM listing
+//! - [`domain`]: VM lifecycle, delete/rename/clone, console launch
+//! - [`migration`]: moving VMs between connected hosts
+//! - [`template`]: marking VMs as templates and instantiating linked clones
+//!   of them
+//! - [`domain_xml`]: parsing domain XML into [`types::DomainDetails`] and the
+//!   pure `&str -> String` transformations behind [`types::ConfigAction`],
+//!   built on the typed [`domain_model::DomainXml`] and the lossless
+//!   [`xml_tree`]
+//! - [`storage`], [`network`], [`snapshot`]: pool/volume, network and
+//!   snapshot management
+//! - [`blockjob`]: flattening disk backing chains with block commit and pull
+//! - [`config_diff`]: what changed between two [`types::DomainDetails`],
+//!   such as a snapshot's and the current configuration
+//! - [`events`]: libvirt lifecycle event subscription
+//! - [`guest_agent`]: growing guest filesystems through the QEMU guest agent
+//! - [`jobs`]: progress reporting and cancellation for long-running
+//!   operations
+//! - [`cloudinit`], [`unattend`]: first-boot provisioning media for cloud
+//!   images and unattended Windows installs, written by [`iso9660`]
+//! - [`osinfo`]: guest OS detection from installer ISOs and per-OS hardware
+//!   defaults for new VMs
+//! - [`performance`], [`nodedev`]: stats sampling and host device discovery
+
+pub mod blockjob;
+pub mod cloudinit;
+pub mod config_diff;
+pub mod connection;
+pub mod domain;
+pub mod domain_model;
+pub mod domain_xml;
+pub mod error;
+pub mod events;
+pub mod guest_agent;
+pub mod iso9660;
+pub mod jobs;
+pub mod migration;
+pub mod network;
+pub mod nodedev;
+pub mod osinfo;
+pub mod performance;
+pub mod snapshot;
+pub mod storage;
+pub mod template;
+pub mod types;
+pub mod unattend;
+pub mod xml_tree;
+
+pub use error::AppError;
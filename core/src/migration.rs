@@ -0,0 +1,146 @@
+//! Moving VMs between connected hosts with libvirt migration.
+
+use virt::domain::{Domain, MigrateParameters};
+use virt::sys;
+
+use crate::connection::get_conn;
+use crate::domain::with_domain;
+use crate::error::AppError;
+use crate::jobs::{watch_domain_job, Job};
+use crate::snapshot::escape_xml;
+use crate::types::{CpuMode, MigrateParams, MigrationMode};
+
+pub fn migrate_vm(uri: &str, uuid: &str, params: &MigrateParams) -> Result<(), AppError> {
+    migrate_vm_with_progress(uri, uuid, params, &Job::detached("Migrate"))
+}
+
+/// Migrate the VM to `params.dest_uri`, after checking that the destination
+/// host can provide the guest's CPU. Progress comes from the domain job on
+/// the source, and cancelling `job` aborts the migration.
+pub fn migrate_vm_with_progress(
+    uri: &str,
+    uuid: &str,
+    params: &MigrateParams,
+    job: &Job,
+) -> Result<(), AppError> {
+    if params.dest_uri == uri {
+        return Err(AppError::Backend("The VM is already on that host".into()));
+    }
+    check_cpu_compatibility(uri, uuid, &params.dest_uri)?;
+    let dest_conn = get_conn(&params.dest_uri)?;
+
+    with_domain(uri, uuid, |domain| {
+        let flags = migrate_flags(params, domain.is_active()?)?;
+        let parameters = MigrateParameters {
+            bandwidth: params.bandwidth_mib,
+            ..Default::default()
+        };
+        let dest_host = dest_conn.get_hostname().unwrap_or_else(|_| params.dest_uri.clone());
+        job.set_detail(&format!("To {dest_host}"));
+        watch_domain_job(domain, job, || {
+            if params.peer_to_peer {
+                domain.migrate_to_uri3(Some(&params.dest_uri), parameters, flags)?;
+            } else {
+                domain.migrate3(&dest_conn, parameters, flags)?;
+            }
+            Ok(())
+        })
+    })
+}
+
+/// `virDomainMigrate*` flags for `params`, refusing combinations libvirt
+/// would reject half-way. `running` is the VM's current state.
+pub fn migrate_flags(params: &MigrateParams, running: bool) -> Result<u32, AppError> {
+    let mut flags = 0;
+    match params.mode {
+        MigrationMode::Live => {
+            if !running {
+                return Err(AppError::Backend(
+                    "The VM is shut off; only an offline migration can move it".into(),
+                ));
+            }
+            flags |= sys::VIR_MIGRATE_LIVE;
+            if params.copy_storage {
+                flags |= sys::VIR_MIGRATE_NON_SHARED_DISK;
+            }
+            if params.compressed {
+                flags |= sys::VIR_MIGRATE_COMPRESSED;
+            }
+            if params.persistent {
+                flags |= sys::VIR_MIGRATE_PERSIST_DEST;
+            }
+        }
+        MigrationMode::Offline => {
+            if params.copy_storage {
+                return Err(AppError::Backend(
+                    "An offline migration cannot copy storage".into(),
+                ));
+            }
+            // Without the definition there would be nothing on the destination
+            flags |= sys::VIR_MIGRATE_OFFLINE | sys::VIR_MIGRATE_PERSIST_DEST;
+        }
+    }
+    if params.peer_to_peer {
+        flags |= sys::VIR_MIGRATE_PEER2PEER;
+    }
+    if params.undefine_source {
+        flags |= sys::VIR_MIGRATE_UNDEFINE_SOURCE;
+    }
+    Ok(flags)
+}
+
+/// Fail unless the destination host's CPU can run the guest's.
+fn check_cpu_compatibility(uri: &str, uuid: &str, dest_uri: &str) -> Result<(), AppError> {
+    let xml = with_domain(uri, uuid, |domain: &Domain| Ok(domain.get_xml_desc(0)?))?;
+    let details = crate::domain_xml::parse_domain_xml(&xml)?;
+    let capabilities = get_conn(uri)?.get_capabilities()?;
+    let Some(cpu_xml) =
+        required_cpu_xml(details.cpu_mode, details.cpu_model.as_deref(), &capabilities)
+    else {
+        return Ok(());
+    };
+
+    let result = match get_conn(dest_uri)?.compare_cpu(&cpu_xml, 0) {
+        Ok(result) => result,
+        Err(e) => {
+            // Drivers without CPU comparison leave the check to the migration
+            log::warn!("Cannot compare CPUs on {dest_uri}: {e}");
+            return Ok(());
+        }
+    };
+    if result != sys::VIR_CPU_COMPARE_INCOMPATIBLE {
+        return Ok(());
+    }
+    let wanted = match (details.cpu_mode, details.cpu_model) {
+        (CpuMode::Custom, Some(model)) => format!("the {model} CPU model"),
+        (mode, _) => format!("this host's CPU ({mode})"),
+    };
+    Err(AppError::Backend(format!(
+        "The destination host's CPU cannot provide {wanted}"
+    )))
+}
+
+/// The CPU the destination has to provide, as XML for
+/// `virConnectCompareCPU`: the named model of a custom CPU, or else the
+/// source host's CPU, which host-passthrough and host-model guests are
+/// shaped after. `None` when there is nothing to compare.
+pub fn required_cpu_xml(
+    cpu_mode: CpuMode,
+    cpu_model: Option<&str>,
+    source_capabilities: &str,
+) -> Option<String> {
+    match (cpu_mode, cpu_model) {
+        (CpuMode::Custom, Some(model)) => Some(format!(
+            "<cpu match='exact'><model fallback='forbid'>{}</model></cpu>",
+            escape_xml(model)
+        )),
+        (CpuMode::Custom, None) => None,
+        _ => host_cpu_xml(source_capabilities),
+    }
+}
+
+/// The `<cpu>` element of the `<host>` in a capabilities document.
+fn host_cpu_xml(capabilities: &str) -> Option<String> {
+    let doc = crate::xml_tree::Document::parse(capabilities).ok()?;
+    Some(doc.root().child("host")?.child("cpu")?.to_xml())
+}
@@ -1,7 +1,7 @@
 use virt::network::Network;
-use crate::backend::connection::get_conn;
+use crate::connection::{check_conn, get_conn};
 
-use crate::backend::types::{
+use crate::types::{
     ForwardMode, NetworkCreateParams, NetworkState, VirtNetworkInfo,
 };
 use crate::error::AppError;
@@ -11,8 +11,10 @@ where
     F: FnOnce(&Network) -> Result<R, AppError>,
 {
     let conn = get_conn(uri)?;
-    let network = Network::lookup_by_uuid_string(&conn, uuid)?;
-    f(&network)
+    let result = Network::lookup_by_uuid_string(&conn, uuid)
+        .map_err(AppError::from)
+        .and_then(|network| f(&network));
+    check_conn(uri, result)
 }
 
 pub fn list_all_networks(uri: &str) -> Result<Vec<VirtNetworkInfo>, AppError> {
@@ -52,7 +54,7 @@ pub fn list_all_networks(uri: &str) -> Result<Vec<VirtNetworkInfo>, AppError> {
         });
     }
 
-    result.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    result.sort_by_key(|a| a.name.to_lowercase());
     Ok(result)
 }
 
@@ -126,7 +128,7 @@ fn parse_network_xml(xml: &str) -> ParsedNetwork {
                             if attr.key.as_ref() == b"mode" {
                                 let mode_str =
                                     String::from_utf8_lossy(&attr.value).to_string();
-                                forward_mode = Some(ForwardMode::from_str(&mode_str));
+                                forward_mode = Some(ForwardMode::from_name(&mode_str));
                             }
                         }
                     }
@@ -1,7 +1,7 @@
 use std::fs;
 use std::path::Path;
 
-use crate::backend::types::HostdevInfo;
+use crate::types::HostdevInfo;
 
 fn read_sysfs_attr(path: &Path, attr: &str) -> Option<String> {
     fs::read_to_string(path.join(attr))
@@ -0,0 +1,608 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::error::AppError;
+use crate::types::{DiskBus, FirmwareType, NetworkModel, TpmModel, VideoModel};
+use crate::xml_tree::{Document, Element};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OsFamily {
+    Windows,
+    Linux,
+    Bsd,
+    Other,
+}
+
+impl OsFamily {
+    /// Map an osinfo-db `<family>` value ("winnt", "linux", "freebsd", ...).
+    pub fn from_osinfo(family: &str) -> Self {
+        match family {
+            "winnt" | "win9x" | "win16" => OsFamily::Windows,
+            "linux" => OsFamily::Linux,
+            "freebsd" | "netbsd" | "openbsd" | "dragonfly" => OsFamily::Bsd,
+            _ => OsFamily::Other,
+        }
+    }
+}
+
+/// Hardware a new VM should get for a particular guest OS.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OsDefaults {
+    pub vcpus: u32,
+    pub memory_mib: u64,
+    pub disk_size_gib: u64,
+    pub firmware: FirmwareType,
+    pub tpm: Option<TpmModel>,
+    pub disk_bus: DiskBus,
+    pub nic_model: NetworkModel,
+    pub video: VideoModel,
+    /// Hyper-V enlightenments and a localtime RTC (Windows guests).
+    pub hyperv: bool,
+}
+
+impl OsDefaults {
+    /// Modern paravirtualised hardware, for guests with in-box virtio drivers.
+    pub fn virtio() -> Self {
+        Self {
+            vcpus: 2,
+            memory_mib: 2048,
+            disk_size_gib: 20,
+            firmware: FirmwareType::Bios,
+            tpm: None,
+            disk_bus: DiskBus::Virtio,
+            nic_model: NetworkModel::Virtio,
+            video: VideoModel::Virtio,
+            hyperv: false,
+        }
+    }
+
+    /// Emulated hardware that Windows setup can use without extra drivers.
+    pub fn windows() -> Self {
+        Self {
+            vcpus: 2,
+            memory_mib: 4096,
+            disk_size_gib: 64,
+            firmware: FirmwareType::Bios,
+            tpm: None,
+            disk_bus: DiskBus::Sata,
+            nic_model: NetworkModel::E1000e,
+            video: VideoModel::Qxl,
+            hyperv: true,
+        }
+    }
+
+    /// Fallbacks for old guests whose kernels predate virtio.
+    pub fn legacy() -> Self {
+        Self {
+            vcpus: 1,
+            memory_mib: 1024,
+            disk_size_gib: 10,
+            firmware: FirmwareType::Bios,
+            tpm: None,
+            disk_bus: DiskBus::Ide,
+            nic_model: NetworkModel::E1000,
+            video: VideoModel::Vga,
+            hyperv: false,
+        }
+    }
+}
+
+/// A guest operating system the VM creation wizard can pick or detect.
+#[derive(Debug, Clone)]
+pub struct OsVariant {
+    /// libosinfo short id ("win11", "fedora40", ...).
+    pub short_id: String,
+    /// libosinfo id URI, recorded in the new domain's `<metadata>`.
+    pub id: String,
+    pub name: String,
+    pub family: OsFamily,
+    /// ISO release date as YYYY-MM-DD, used to prefer newer matches.
+    pub release_date: Option<String>,
+    pub defaults: OsDefaults,
+    volume_ids: Vec<Regex>,
+}
+
+impl OsVariant {
+    /// Whether an ISO volume label identifies this OS.
+    pub fn matches_volume_id(&self, label: &str) -> bool {
+        self.volume_ids.iter().any(|re| re.is_match(label))
+    }
+}
+
+/// Known guest operating systems: the system osinfo-db when one is
+/// installed, otherwise a small built-in table of common installers.
+#[derive(Debug, Clone)]
+pub struct OsDatabase {
+    variants: Vec<OsVariant>,
+}
+
+impl OsDatabase {
+    /// The database for this process, loaded on first use. Reading osinfo-db
+    /// parses about a thousand files, so call this off the GTK main thread.
+    pub fn system() -> &'static OsDatabase {
+        static DB: OnceLock<OsDatabase> = OnceLock::new();
+        DB.get_or_init(|| {
+            for dir in osinfo_dirs() {
+                match Self::from_osinfo_dir(&dir) {
+                    Ok(db) if !db.variants.is_empty() => return db,
+                    Ok(_) => {}
+                    Err(e) => log::warn!("Ignoring osinfo-db at {}: {e}", dir.display()),
+                }
+            }
+            Self::builtin()
+        })
+    }
+
+    pub fn builtin() -> Self {
+        let variants = BUILTIN
+            .iter()
+            .map(|b| OsVariant {
+                short_id: b.short_id.to_string(),
+                id: b.id.to_string(),
+                name: b.name.to_string(),
+                family: b.family,
+                release_date: None,
+                defaults: (b.defaults)(),
+                volume_ids: vec![Regex::new(b.volume_id).expect("built-in volume id")],
+            })
+            .collect();
+        Self { variants }
+    }
+
+    /// Load an osinfo-db tree (the directory containing `os/`).
+    pub fn from_osinfo_dir(dir: &Path) -> Result<Self, AppError> {
+        let mut files = Vec::new();
+        collect_xml_files(&dir.join("os"), &mut files)?;
+        files.sort();
+
+        let mut entries = HashMap::new();
+        for file in &files {
+            let xml = std::fs::read_to_string(file)?;
+            let doc = match Document::parse(&xml) {
+                Ok(doc) => doc,
+                Err(e) => {
+                    log::warn!("Skipping {}: {e}", file.display());
+                    continue;
+                }
+            };
+            for os in doc.root().children_named("os") {
+                if let Some(entry) = OsEntry::parse(os) {
+                    entries.insert(entry.id.clone(), entry);
+                }
+            }
+        }
+        Ok(Self::from_entries(&entries))
+    }
+
+    fn from_entries(entries: &HashMap<String, OsEntry>) -> Self {
+        let mut variants: Vec<OsVariant> = entries
+            .values()
+            .filter(|e| !e.short_id.is_empty())
+            .map(|e| e.resolve(entries))
+            .collect();
+        variants.sort_by_key(|v| v.name.to_lowercase());
+        Self { variants }
+    }
+
+    pub fn variants(&self) -> &[OsVariant] {
+        &self.variants
+    }
+
+    /// Variants that can be recognised from installer media, for the OS
+    /// picker (osinfo-db also lists many OSes nobody installs from an ISO).
+    pub fn installable(&self) -> impl Iterator<Item = &OsVariant> {
+        self.variants.iter().filter(|v| !v.volume_ids.is_empty())
+    }
+
+    pub fn find(&self, short_id: &str) -> Option<&OsVariant> {
+        self.variants.iter().find(|v| v.short_id == short_id)
+    }
+
+    /// The OS an ISO with this volume label installs. When several entries
+    /// match (e.g. Windows 10 and 11 media share a label prefix), the most
+    /// recently released one wins.
+    pub fn detect(&self, volume_label: &str) -> Option<&OsVariant> {
+        let label = volume_label.trim();
+        if label.is_empty() {
+            return None;
+        }
+        self.variants
+            .iter()
+            .filter(|v| v.matches_volume_id(label))
+            .max_by(|a, b| a.release_date.cmp(&b.release_date))
+    }
+}
+
+/// Byte offset and length of the ISO 9660 primary volume descriptor.
+pub const ISO_PVD_OFFSET: u64 = 16 * 2048;
+pub const ISO_PVD_LEN: u64 = 2048;
+
+/// Volume label from an ISO 9660 primary volume descriptor, i.e. the
+/// `ISO_PVD_LEN` bytes found at `ISO_PVD_OFFSET` of the image.
+pub fn iso_volume_label(pvd: &[u8]) -> Option<String> {
+    if pvd.len() < 72 || pvd[0] != 1 || &pvd[1..6] != b"CD001" {
+        return None;
+    }
+    let label = String::from_utf8_lossy(&pvd[40..72]).trim().to_string();
+    (!label.is_empty()).then_some(label)
+}
+
+/// Read the volume label of an installer ISO. The image is read through
+/// libvirt so root-owned pools and remote hosts work; paths libvirt does not
+/// know about are read directly.
+pub fn read_iso_label(uri: &str, path: &str) -> Result<Option<String>, AppError> {
+    let pvd = match crate::storage::read_volume(uri, path, ISO_PVD_OFFSET, ISO_PVD_LEN) {
+        Ok(data) => data,
+        Err(AppError::NotFound(_)) => read_local(path)?,
+        Err(e) => return Err(e),
+    };
+    Ok(iso_volume_label(&pvd))
+}
+
+/// Detect the guest OS an installer ISO contains.
+pub fn detect_iso(uri: &str, path: &str) -> Result<Option<OsVariant>, AppError> {
+    let Some(label) = read_iso_label(uri, path)? else {
+        return Ok(None);
+    };
+    let variant = OsDatabase::system().detect(&label).cloned();
+    match &variant {
+        Some(v) => log::info!("ISO label {label:?} detected as {}", v.short_id),
+        None => log::info!("ISO label {label:?} not recognised"),
+    }
+    Ok(variant)
+}
+
+fn read_local(path: &str) -> Result<Vec<u8>, AppError> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut file = std::fs::File::open(path)?;
+    file.seek(SeekFrom::Start(ISO_PVD_OFFSET))?;
+    let mut buf = Vec::with_capacity(ISO_PVD_LEN as usize);
+    file.take(ISO_PVD_LEN).read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+// ---- osinfo-db ----
+
+fn osinfo_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(dir) = std::env::var_os("OSINFO_SYSTEM_DIR") {
+        dirs.push(PathBuf::from(dir));
+    }
+    dirs.push(PathBuf::from("/usr/share/osinfo"));
+    dirs.push(PathBuf::from("/usr/local/share/osinfo"));
+    dirs.into_iter().filter(|d| d.join("os").is_dir()).collect()
+}
+
+fn collect_xml_files(dir: &Path, out: &mut Vec<PathBuf>) -> Result<(), AppError> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_xml_files(&path, out)?;
+        } else if path.extension().is_some_and(|e| e == "xml") {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+const VIRTIO_BLK: &[&str] = &["1af4/1001", "1af4/1042"];
+const VIRTIO_NET: &[&str] = &["1af4/1000", "1af4/1041"];
+const VIRTIO_GPU: &[&str] = &["1af4/1050"];
+const AHCI: &[&str] = &["8086/2922"];
+const E1000E: &[&str] = &["8086/10d3"];
+const QXL: &[&str] = &["1b36/0100"];
+/// Windows releases that refuse to install without TPM 2.0 and Secure Boot.
+const NEEDS_TPM: &[&str] = &["win11", "win2k25"];
+
+/// One `<os>` element as written in osinfo-db, before inheritance.
+#[derive(Debug, Default)]
+struct OsEntry {
+    id: String,
+    short_id: String,
+    name: String,
+    family: Option<String>,
+    release_date: Option<String>,
+    derives_from: Option<String>,
+    volume_ids: Vec<String>,
+    n_cpus: Option<u32>,
+    ram: Option<u64>,
+    storage: Option<u64>,
+    devices: Vec<String>,
+    efi: bool,
+}
+
+impl OsEntry {
+    fn parse(os: &Element) -> Option<Self> {
+        let id = os.attr("id")?;
+        let mut entry = OsEntry {
+            id,
+            short_id: os.child_text("short-id").unwrap_or_default(),
+            name: os
+                .children_named("name")
+                .find(|n| n.attr("xml:lang").is_none())
+                .map(Element::text)
+                .unwrap_or_default(),
+            family: os.child_text("family"),
+            release_date: os.child_text("release-date"),
+            derives_from: os.child_attr("derives-from", "id"),
+            ..Default::default()
+        };
+
+        for media in os.children_named("media") {
+            if !is_x86_64(media) {
+                continue;
+            }
+            for iso in media.children_named("iso") {
+                entry.volume_ids.extend(iso.children_named("volume-id").map(Element::text));
+            }
+        }
+
+        for resources in os.children_named("resources") {
+            if !is_x86_64(resources) {
+                continue;
+            }
+            // Recommended values win over minimums
+            for level in ["minimum", "recommended"] {
+                let Some(res) = resources.child(level) else {
+                    continue;
+                };
+                let num = |name: &str| res.child_text(name).and_then(|t| t.trim().parse().ok());
+                entry.n_cpus = num("n-cpus").map(|n: u64| n as u32).or(entry.n_cpus);
+                entry.ram = num("ram").or(entry.ram);
+                entry.storage = num("storage").or(entry.storage);
+            }
+        }
+
+        if let Some(devices) = os.child("devices") {
+            entry.devices = devices
+                .children_named("device")
+                .filter_map(|d| d.attr("id"))
+                .collect();
+        }
+
+        entry.efi = os
+            .children_named("firmware")
+            .any(|f| is_x86_64(f) && f.attr_is("type", "efi") && !f.attr_is("supported", "no"));
+
+        Some(entry)
+    }
+
+    /// Fill in what this entry inherits through `derives-from` and turn it
+    /// into an `OsVariant`.
+    fn resolve(&self, entries: &HashMap<String, OsEntry>) -> OsVariant {
+        let mut chain = vec![self];
+        let mut seen = HashSet::from([self.id.as_str()]);
+        let mut parent = self.derives_from.as_deref();
+        while let Some(id) = parent {
+            let Some(entry) = entries.get(id).filter(|_| seen.insert(id)) else {
+                break;
+            };
+            chain.push(entry);
+            parent = entry.derives_from.as_deref();
+        }
+        let inherited = |f: &dyn Fn(&OsEntry) -> Option<u64>| chain.iter().find_map(|e| f(e));
+
+        let family = chain
+            .iter()
+            .find_map(|e| e.family.as_deref())
+            .map(OsFamily::from_osinfo)
+            .unwrap_or(OsFamily::Other);
+        let has = |ids: &[&str]| {
+            chain.iter().any(|e| {
+                e.devices
+                    .iter()
+                    .any(|d| ids.iter().any(|id| d.ends_with(&format!("/pci/{id}"))))
+            })
+        };
+
+        let mut defaults = match family {
+            OsFamily::Windows => OsDefaults::windows(),
+            _ if has(VIRTIO_BLK) => OsDefaults::virtio(),
+            _ => OsDefaults::legacy(),
+        };
+        if family != OsFamily::Windows {
+            defaults.disk_bus = if has(VIRTIO_BLK) {
+                DiskBus::Virtio
+            } else if has(AHCI) {
+                DiskBus::Sata
+            } else {
+                DiskBus::Ide
+            };
+            defaults.nic_model = if has(VIRTIO_NET) {
+                NetworkModel::Virtio
+            } else if has(E1000E) {
+                NetworkModel::E1000e
+            } else {
+                NetworkModel::E1000
+            };
+            defaults.video = if has(VIRTIO_GPU) {
+                VideoModel::Virtio
+            } else if has(QXL) {
+                VideoModel::Qxl
+            } else {
+                VideoModel::Vga
+            };
+        }
+        if let Some(n) = inherited(&|e| e.n_cpus.map(u64::from)) {
+            defaults.vcpus = n.clamp(1, 8) as u32;
+        }
+        if let Some(bytes) = inherited(&|e| e.ram) {
+            defaults.memory_mib = (bytes / (1024 * 1024)).max(512);
+        }
+        if let Some(bytes) = inherited(&|e| e.storage) {
+            defaults.disk_size_gib = bytes.div_ceil(1024 * 1024 * 1024).max(8);
+        }
+        if NEEDS_TPM.contains(&self.short_id.as_str()) {
+            defaults.tpm = Some(TpmModel::Crb);
+            defaults.firmware = FirmwareType::Efi;
+        } else if chain.iter().any(|e| e.efi) && defaults.disk_bus != DiskBus::Ide {
+            defaults.firmware = FirmwareType::Efi;
+        }
+
+        OsVariant {
+            short_id: self.short_id.clone(),
+            id: self.id.clone(),
+            name: self.name.clone(),
+            family,
+            release_date: self.release_date.clone(),
+            defaults,
+            volume_ids: self
+                .volume_ids
+                .iter()
+                .filter_map(|v| match Regex::new(v) {
+                    Ok(re) => Some(re),
+                    Err(e) => {
+                        log::debug!("Unusable volume-id {v:?} for {}: {e}", self.short_id);
+                        None
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+fn is_x86_64(e: &Element) -> bool {
+    matches!(e.attr("arch").as_deref(), None | Some("x86_64") | Some("all"))
+}
+
+// ---- Built-in table ----
+
+struct BuiltinOs {
+    short_id: &'static str,
+    id: &'static str,
+    name: &'static str,
+    family: OsFamily,
+    volume_id: &'static str,
+    defaults: fn() -> OsDefaults,
+}
+
+/// Used when osinfo-db is not installed. Volume ids follow osinfo-db.
+const BUILTIN: &[BuiltinOs] = &[
+    BuiltinOs {
+        short_id: "win11",
+        id: "http://microsoft.com/win/11",
+        name: "Microsoft Windows 11",
+        family: OsFamily::Windows,
+        volume_id: "^(CCCOMA|CPBA|CCSA)_X64FRE",
+        defaults: || OsDefaults {
+            vcpus: 4,
+            memory_mib: 8192,
+            disk_size_gib: 80,
+            firmware: FirmwareType::Efi,
+            tpm: Some(TpmModel::Crb),
+            ..OsDefaults::windows()
+        },
+    },
+    BuiltinOs {
+        short_id: "win2k22",
+        id: "http://microsoft.com/win/2k22",
+        name: "Microsoft Windows Server 2022",
+        family: OsFamily::Windows,
+        volume_id: "^SSS_X64FRE",
+        defaults: || OsDefaults {
+            memory_mib: 4096,
+            disk_size_gib: 64,
+            firmware: FirmwareType::Efi,
+            ..OsDefaults::windows()
+        },
+    },
+    BuiltinOs {
+        short_id: "win7",
+        id: "http://microsoft.com/win/7",
+        name: "Microsoft Windows 7",
+        family: OsFamily::Windows,
+        volume_id: "^(GRMC|GSP1RMC)",
+        defaults: || OsDefaults {
+            memory_mib: 2048,
+            disk_size_gib: 32,
+            nic_model: NetworkModel::E1000,
+            video: VideoModel::Vga,
+            ..OsDefaults::windows()
+        },
+    },
+    BuiltinOs {
+        short_id: "fedora-unknown",
+        id: "http://fedoraproject.org/fedora/unknown",
+        name: "Fedora Linux",
+        family: OsFamily::Linux,
+        volume_id: "^Fedora-",
+        defaults: || OsDefaults {
+            memory_mib: 4096,
+            disk_size_gib: 30,
+            firmware: FirmwareType::Efi,
+            ..OsDefaults::virtio()
+        },
+    },
+    BuiltinOs {
+        short_id: "ubuntu-lts-latest",
+        id: "http://ubuntu.com/ubuntu/lts-latest",
+        name: "Ubuntu",
+        family: OsFamily::Linux,
+        volume_id: "^Ubuntu",
+        defaults: || OsDefaults {
+            memory_mib: 4096,
+            disk_size_gib: 25,
+            ..OsDefaults::virtio()
+        },
+    },
+    BuiltinOs {
+        short_id: "debiantesting",
+        id: "http://debian.org/debian/testing",
+        name: "Debian",
+        family: OsFamily::Linux,
+        volume_id: "^Debian",
+        defaults: OsDefaults::virtio,
+    },
+    BuiltinOs {
+        short_id: "rhel-unknown",
+        id: "http://redhat.com/rhel/unknown",
+        name: "Red Hat Enterprise Linux and rebuilds",
+        family: OsFamily::Linux,
+        volume_id: "^(RHEL|CentOS-Stream|Rocky|AlmaLinux|OL)-(8|9|10)",
+        defaults: || OsDefaults {
+            memory_mib: 2048,
+            disk_size_gib: 20,
+            video: VideoModel::Vga,
+            ..OsDefaults::virtio()
+        },
+    },
+    BuiltinOs {
+        short_id: "centos5.11",
+        id: "http://centos.org/centos/5.11",
+        name: "CentOS 5 / RHEL 5 and older",
+        family: OsFamily::Linux,
+        volume_id: "^(CentOS|RHEL|SL)[-_ ]?[2-5]([-_. ]|$)",
+        defaults: OsDefaults::legacy,
+    },
+    BuiltinOs {
+        short_id: "opensuse-unknown",
+        id: "http://opensuse.org/opensuse/unknown",
+        name: "openSUSE",
+        family: OsFamily::Linux,
+        volume_id: "^openSUSE",
+        defaults: OsDefaults::virtio,
+    },
+    BuiltinOs {
+        short_id: "archlinux",
+        id: "http://archlinux.org/archlinux/rolling",
+        name: "Arch Linux",
+        family: OsFamily::Linux,
+        volume_id: "^ARCH_[0-9]{6}",
+        defaults: OsDefaults::virtio,
+    },
+    BuiltinOs {
+        short_id: "freebsd-unknown",
+        id: "http://freebsd.org/freebsd/unknown",
+        name: "FreeBSD",
+        family: OsFamily::Bsd,
+        volume_id: "^[0-9]+_[0-9]+_RELEASE_AMD64",
+        defaults: || OsDefaults {
+            video: VideoModel::Vga,
+            ..OsDefaults::virtio()
+        },
+    },
+];
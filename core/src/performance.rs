@@ -1,7 +1,7 @@
 use virt::domain::Domain;
-use crate::backend::connection::get_conn;
+use crate::connection::get_conn;
 
-use crate::backend::types::RawPerfSample;
+use crate::types::RawPerfSample;
 use crate::error::AppError;
 
 pub fn collect_perf_sample(
@@ -43,7 +43,7 @@ pub fn collect_perf_sample(
     let live_iface_targets = domain
         .get_xml_desc(0)
         .ok()
-        .map(|xml| crate::backend::domain_xml::extract_interface_targets(&xml))
+        .map(|xml| crate::domain_xml::extract_interface_targets(&xml))
         .filter(|targets| !targets.is_empty());
     let iface_targets = live_iface_targets.as_deref().unwrap_or(iface_targets);
 
@@ -0,0 +1,317 @@
+//! VM snapshots: internal ones inside the qcow2 images, external ones in
+//! overlay files, and the parent/child tree they form.
+
+use std::path::Path;
+
+use virt::domain::Domain;
+use virt::domain_snapshot::DomainSnapshot;
+use virt::sys;
+
+use crate::domain::with_domain;
+use crate::jobs::{watch_domain_job, Job};
+use crate::types::{
+    ConfigChange, CreateSnapshotParams, DiskSnapshotMode, SnapshotDisk, SnapshotInfo,
+    SnapshotKind, SnapshotState,
+};
+use crate::error::AppError;
+use crate::xml_tree::{Document, Element};
+
+pub fn list_snapshots(uri: &str, uuid: &str) -> Result<Vec<SnapshotInfo>, AppError> {
+    with_domain(uri, uuid, |domain| {
+        let snapshots = domain.list_all_snapshots(0)?;
+
+        // Get current snapshot name, if any
+        let current_name = DomainSnapshot::current(domain, 0)
+            .ok()
+            .and_then(|snap| snap.get_name().ok());
+
+        let mut infos: Vec<SnapshotInfo> = Vec::new();
+        for snap in &snapshots {
+            let xml = snap.get_xml_desc(0)?;
+            if let Some(mut info) = parse_snapshot_xml(&xml) {
+                info.is_current = current_name.as_deref() == Some(&info.name);
+                infos.push(info);
+            }
+        }
+
+        // Sort newest first
+        infos.sort_by_key(|s| std::cmp::Reverse(s.creation_time));
+        Ok(infos)
+    })
+}
+
+pub fn create_snapshot(
+    uri: &str,
+    uuid: &str,
+    params: &CreateSnapshotParams,
+) -> Result<(), AppError> {
+    create_snapshot_with_progress(uri, uuid, params, &Job::detached("Snapshot"))
+}
+
+/// Like [`create_snapshot`]. Saving a running VM's memory is reported on
+/// `job` and can be cancelled through it.
+pub fn create_snapshot_with_progress(
+    uri: &str,
+    uuid: &str,
+    params: &CreateSnapshotParams,
+    job: &Job,
+) -> Result<(), AppError> {
+    with_domain(uri, uuid, |domain| {
+        let running = domain.is_active()?;
+        let flags = snapshot_flags(params, running)?;
+        let memory_file = match (params.kind, running) {
+            (SnapshotKind::External, true) => match &params.memory_file {
+                Some(file) => Some(file.clone()),
+                None => Some(default_memory_file(domain, &params.name)?),
+            },
+            _ => None,
+        };
+        let xml = snapshot_xml(params, running, memory_file.as_deref());
+        watch_domain_job(domain, job, || {
+            DomainSnapshot::create_xml(domain, &xml, flags)?;
+            Ok(())
+        })
+    })
+}
+
+/// `virDomainSnapshotCreateXML` flags for `params`. `running` is the VM's
+/// current state.
+pub fn snapshot_flags(params: &CreateSnapshotParams, running: bool) -> Result<u32, AppError> {
+    let mut flags = 0;
+    // A shut-off VM has no memory to save, so its external snapshots are
+    // disk-only too
+    let disk_only = match params.kind {
+        SnapshotKind::Internal => false,
+        SnapshotKind::External => !running,
+        SnapshotKind::DiskOnly => true,
+    };
+    if disk_only {
+        flags |= sys::VIR_DOMAIN_SNAPSHOT_CREATE_DISK_ONLY;
+    }
+    if params.quiesce {
+        if !(disk_only && running) {
+            return Err(AppError::Backend(
+                "Only disk-only snapshots of a running VM can be quiesced".into(),
+            ));
+        }
+        flags |= sys::VIR_DOMAIN_SNAPSHOT_CREATE_QUIESCE;
+    }
+    if params.atomic {
+        flags |= sys::VIR_DOMAIN_SNAPSHOT_CREATE_ATOMIC;
+    }
+    Ok(flags)
+}
+
+/// The `<domainsnapshot>` definition for `params`. `memory_file` is where
+/// an external snapshot of a running VM saves its memory.
+pub fn snapshot_xml(
+    params: &CreateSnapshotParams,
+    running: bool,
+    memory_file: Option<&str>,
+) -> String {
+    let mut root = Element::new("domainsnapshot")
+        .with_child(Element::new("name").with_text(&params.name))
+        .with_child(Element::new("description").with_text(&params.description));
+
+    match (params.kind, memory_file) {
+        (SnapshotKind::Internal, _) => {}
+        (SnapshotKind::External, Some(file)) if running => {
+            root.append(
+                Element::new("memory")
+                    .with_attr("snapshot", "external")
+                    .with_attr("file", file),
+            );
+        }
+        _ => root.append(Element::new("memory").with_attr("snapshot", "no")),
+    }
+
+    if !params.disks.is_empty() {
+        let mut disks = Element::new("disks");
+        for disk in &params.disks {
+            let mut element = Element::new("disk")
+                .with_attr("name", &disk.target_dev)
+                .with_attr("snapshot", disk.mode.as_str());
+            if let (DiskSnapshotMode::External, Some(file)) = (disk.mode, &disk.file) {
+                element = element.with_child(Element::new("source").with_attr("file", file));
+            }
+            disks.append(element);
+        }
+        root.append(disks);
+    }
+    root.to_xml()
+}
+
+/// `{vm}-{snapshot}.mem` in the directory of the VM's first disk image.
+fn default_memory_file(domain: &Domain, snap_name: &str) -> Result<String, AppError> {
+    let details = crate::domain_xml::parse_domain_xml(&domain.get_xml_desc(0)?)?;
+    let dir = details
+        .disks
+        .iter()
+        .filter(|d| d.device_type != "cdrom")
+        .find_map(|d| d.source_file.as_deref())
+        .and_then(|path| Path::new(path).parent())
+        .ok_or_else(|| {
+            AppError::Backend("The VM has no disk image to save its memory beside".into())
+        })?;
+    let file_name = format!("{}-{snap_name}.mem", domain.get_name()?);
+    Ok(dir.join(file_name).to_string_lossy().into_owned())
+}
+
+pub fn delete_snapshot(uri: &str, uuid: &str, snap_name: &str) -> Result<(), AppError> {
+    delete_snapshot_with_progress(uri, uuid, snap_name, &Job::detached("Delete snapshot"))
+}
+
+/// Like [`delete_snapshot`]. The overlays of an external snapshot are first
+/// committed into the images below them while the VM runs, reported on
+/// `job`; then its metadata goes, and with it the overlay and memory files.
+pub fn delete_snapshot_with_progress(
+    uri: &str,
+    uuid: &str,
+    snap_name: &str,
+    job: &Job,
+) -> Result<(), AppError> {
+    let unused = with_domain(uri, uuid, |domain| {
+        let snap = DomainSnapshot::lookup_by_name(domain, snap_name, 0)?;
+        let info = parse_snapshot_xml(&snap.get_xml_desc(0)?)
+            .ok_or_else(|| AppError::Backend(format!("Cannot read snapshot {snap_name}")))?;
+        if !info.is_external() {
+            snap.delete(0)?;
+            return Ok(Vec::new());
+        }
+        if !domain.is_active()? {
+            return Err(AppError::Backend(format!(
+                "Start the VM to merge the overlays of external snapshot {snap_name}"
+            )));
+        }
+
+        let overlays: Vec<(&str, &str)> = info
+            .disks
+            .iter()
+            .filter(|d| d.mode == DiskSnapshotMode::External)
+            .filter_map(|d| Some((d.target_dev.as_str(), d.file.as_deref()?)))
+            .collect();
+        let mut unused = Vec::new();
+        for (i, (target_dev, file)) in overlays.iter().enumerate() {
+            job.set_detail(&format!("Merging {target_dev} ({} of {})", i + 1, overlays.len()));
+            if crate::blockjob::commit_image(domain, target_dev, file, job)? {
+                unused.push(file.to_string());
+            }
+        }
+        snap.delete(sys::VIR_DOMAIN_SNAPSHOT_DELETE_METADATA_ONLY)?;
+        unused.extend(info.memory_file);
+        Ok(unused)
+    })?;
+    crate::blockjob::remove_images(uri, &unused)
+}
+
+pub fn revert_snapshot(uri: &str, uuid: &str, snap_name: &str) -> Result<(), AppError> {
+    with_domain(uri, uuid, |domain| {
+        let snap = DomainSnapshot::lookup_by_name(domain, snap_name, 0)?;
+        snap.revert(0)?;
+        Ok(())
+    })
+}
+
+/// What changed in the VM's configuration since snapshot `snap_name` was
+/// taken: `before` in each change is the snapshot's, `after` the current.
+pub fn compare_with_current(
+    uri: &str,
+    uuid: &str,
+    snap_name: &str,
+) -> Result<Vec<ConfigChange>, AppError> {
+    with_domain(uri, uuid, |domain| {
+        let snap = DomainSnapshot::lookup_by_name(domain, snap_name, 0)?;
+        let recorded = embedded_domain_xml(&snap.get_xml_desc(0)?).ok_or_else(|| {
+            AppError::Backend(format!("Snapshot {snap_name} has no VM configuration"))
+        })?;
+        let before = crate::domain_xml::parse_domain_xml(&recorded)?;
+        let after = crate::domain_xml::parse_domain_xml(&domain.get_xml_desc(0)?)?;
+        Ok(crate::config_diff::diff_details(&before, &after))
+    })
+}
+
+/// The `<domain>` a `<domainsnapshot>` definition recorded.
+pub fn embedded_domain_xml(snapshot_xml: &str) -> Option<String> {
+    let doc = Document::parse(snapshot_xml).ok()?;
+    Some(doc.root().child("domain")?.to_xml())
+}
+
+/// Read a `<domainsnapshot>` definition. Only its own top-level elements
+/// count: the `<domain>` copy inside it has a `<name>` of its own.
+pub fn parse_snapshot_xml(xml: &str) -> Option<SnapshotInfo> {
+    let doc = Document::parse(xml).ok()?;
+    let root = doc.root();
+    let name = root.child_text("name").filter(|n| !n.is_empty())?;
+
+    let memory_file = root
+        .child("memory")
+        .filter(|m| m.attr_is("snapshot", "external"))
+        .and_then(|m| m.attr("file"));
+    let disks = root
+        .child("disks")
+        .map(|disks| {
+            disks
+                .children_named("disk")
+                .filter_map(|disk| {
+                    Some(SnapshotDisk {
+                        target_dev: disk.attr("name")?,
+                        mode: DiskSnapshotMode::parse(&disk.attr("snapshot")?)?,
+                        file: disk.child_attr("source", "file"),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(SnapshotInfo {
+        name,
+        description: root.child_text("description").unwrap_or_default(),
+        state: root
+            .child_text("state")
+            .map(|s| SnapshotState::from_xml_str(&s))
+            .unwrap_or(SnapshotState::Other),
+        creation_time: root
+            .child_text("creationTime")
+            .and_then(|t| t.trim().parse().ok())
+            .unwrap_or(0),
+        is_current: false,
+        parent: root.child("parent").and_then(|p| p.child_text("name")),
+        disks,
+        memory_file,
+    })
+}
+
+/// `snapshots` in tree order: every snapshot followed by its children,
+/// siblings oldest first, each with its depth below the roots. Snapshots
+/// whose parent is missing from the list are roots.
+pub fn snapshot_tree(snapshots: &[SnapshotInfo]) -> Vec<(usize, &SnapshotInfo)> {
+    let mut by_age: Vec<&SnapshotInfo> = snapshots.iter().collect();
+    by_age.sort_by_key(|s| s.creation_time);
+    let is_root = |s: &SnapshotInfo| match &s.parent {
+        Some(parent) => !snapshots.iter().any(|p| &p.name == parent),
+        None => true,
+    };
+
+    let mut tree = Vec::with_capacity(snapshots.len());
+    let mut stack: Vec<(usize, &SnapshotInfo)> =
+        by_age.iter().rev().filter(|s| is_root(s)).map(|s| (0, *s)).collect();
+    while let Some((depth, snap)) = stack.pop() {
+        tree.push((depth, snap));
+        stack.extend(
+            by_age
+                .iter()
+                .rev()
+                .filter(|c| c.parent.as_deref() == Some(snap.name.as_str()))
+                .map(|c| (depth + 1, *c)),
+        );
+    }
+    tree
+}
+
+pub(crate) fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
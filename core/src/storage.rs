@@ -0,0 +1,1209 @@
+use virt::storage_pool::StoragePool;
+use virt::storage_vol::StorageVol;
+use virt::stream::Stream;
+use std::path::Path;
+use crate::connection::{check_conn, get_conn};
+use crate::jobs::Job;
+use crate::snapshot::escape_xml;
+
+use crate::types::{
+    format_bytes, BackingStore, ImageFormat, PoolCreateParams, PoolInfo, PoolState,
+    VolumeDetails, VolumeInfo, VolumeType, WipeAlgorithm,
+};
+use crate::error::AppError;
+
+fn with_pool<F, R>(uri: &str, uuid: &str, f: F) -> Result<R, AppError>
+where
+    F: FnOnce(&StoragePool) -> Result<R, AppError>,
+{
+    let conn = get_conn(uri)?;
+    let result = StoragePool::lookup_by_uuid_string(&conn, uuid)
+        .map_err(AppError::from)
+        .and_then(|pool| f(&pool));
+    check_conn(uri, result)
+}
+
+pub fn list_all_pools(uri: &str) -> Result<Vec<PoolInfo>, AppError> {
+    let conn = get_conn(uri)?;
+    let pools = conn.list_all_storage_pools(0)?;
+
+    let mut result = Vec::new();
+    for pool in &pools {
+        let name = pool.get_name()?;
+        let uuid = pool.get_uuid_string()?;
+        let info = pool.get_info()?;
+        let active = pool.is_active().unwrap_or(false);
+        let persistent = pool.is_persistent().unwrap_or(false);
+        let autostart = pool.get_autostart().unwrap_or(false);
+
+        result.push(PoolInfo {
+            name,
+            uuid,
+            state: PoolState::from_libvirt(info.state),
+            capacity: info.capacity,
+            allocation: info.allocation,
+            available: info.available,
+            active,
+            persistent,
+            autostart,
+        });
+    }
+
+    result.sort_by_key(|a| a.name.to_lowercase());
+    Ok(result)
+}
+
+pub fn start_pool(uri: &str, uuid: &str) -> Result<(), AppError> {
+    with_pool(uri, uuid, |pool| {
+        pool.create(0)?;
+        Ok(())
+    })
+}
+
+pub fn stop_pool(uri: &str, uuid: &str) -> Result<(), AppError> {
+    with_pool(uri, uuid, |pool| {
+        pool.destroy()?;
+        Ok(())
+    })
+}
+
+pub fn delete_pool(uri: &str, uuid: &str) -> Result<(), AppError> {
+    with_pool(uri, uuid, |pool| {
+        let _ = pool.destroy();
+        pool.undefine()?;
+        Ok(())
+    })
+}
+
+pub fn refresh_pool(uri: &str, uuid: &str) -> Result<(), AppError> {
+    with_pool(uri, uuid, |pool| {
+        pool.refresh(0)?;
+        Ok(())
+    })
+}
+
+pub fn set_pool_autostart(uri: &str, uuid: &str, autostart: bool) -> Result<(), AppError> {
+    with_pool(uri, uuid, |pool| {
+        pool.set_autostart(autostart)?;
+        Ok(())
+    })
+}
+
+pub fn get_pool_xml(uri: &str, uuid: &str) -> Result<String, AppError> {
+    with_pool(uri, uuid, |pool| {
+        let xml = pool.get_xml_desc(0)?;
+        Ok(xml)
+    })
+}
+
+pub fn create_pool(
+    uri: &str,
+    name: &str,
+    pool_type: &str,
+    params: &PoolCreateParams,
+) -> Result<(), AppError> {
+    let xml = build_pool_xml(name, pool_type, params);
+
+    let conn = get_conn(uri)?;
+    let pool = StoragePool::define_xml(&conn, &xml, 0)?;
+    let _ = pool.build(0);
+    pool.create(0)?;
+    Ok(())
+}
+
+fn build_pool_xml(name: &str, pool_type: &str, params: &PoolCreateParams) -> String {
+    let mut xml = format!("<pool type=\"{pool_type}\">\n  <name>{name}</name>\n");
+
+    match pool_type {
+        "fs" => {
+            xml.push_str("  <source>\n");
+            xml.push_str(&format!("    <device path=\"{}\"/>\n", params.source_device));
+            if !params.source_format.is_empty() {
+                xml.push_str(&format!("    <format type=\"{}\"/>\n", params.source_format));
+            }
+            xml.push_str("  </source>\n");
+        }
+        "netfs" => {
+            xml.push_str("  <source>\n");
+            xml.push_str(&format!("    <host name=\"{}\"/>\n", params.source_host));
+            xml.push_str(&format!("    <dir path=\"{}\"/>\n", params.source_dir));
+            xml.push_str(&format!("    <format type=\"{}\"/>\n", params.source_format));
+            xml.push_str("  </source>\n");
+        }
+        "logical" => {
+            xml.push_str("  <source>\n");
+            xml.push_str(&format!("    <device path=\"{}\"/>\n", params.source_device));
+            xml.push_str(&format!("    <name>{}</name>\n", params.source_name));
+            xml.push_str("  </source>\n");
+        }
+        "iscsi" => {
+            xml.push_str("  <source>\n");
+            xml.push_str(&format!("    <host name=\"{}\"/>\n", params.source_host));
+            xml.push_str(&format!("    <device path=\"{}\"/>\n", params.source_device));
+            xml.push_str("  </source>\n");
+        }
+        "disk" => {
+            xml.push_str("  <source>\n");
+            xml.push_str(&format!("    <device path=\"{}\"/>\n", params.source_device));
+            if !params.source_format.is_empty() {
+                xml.push_str(&format!("    <format type=\"{}\"/>\n", params.source_format));
+            }
+            xml.push_str("  </source>\n");
+        }
+        _ => {} // "dir" needs no source
+    }
+
+    xml.push_str(&format!("  <target>\n    <path>{}</path>\n  </target>\n", params.target_path));
+    xml.push_str("</pool>");
+    xml
+}
+
+/// Returns all active pools paired with their volumes. Used by the storage volume picker in
+/// the VM config dialog so images can be chosen via libvirt without requiring direct filesystem
+/// access (the directory may be owned by root/qemu).
+pub fn list_all_pool_volumes(uri: &str) -> Result<Vec<(String, Vec<VolumeInfo>)>, AppError> {
+    let conn = get_conn(uri)?;
+    let pools = conn.list_all_storage_pools(0)?;
+
+    let mut result = Vec::new();
+    for pool in &pools {
+        if !pool.is_active().unwrap_or(false) {
+            continue;
+        }
+        let name = pool.get_name().unwrap_or_default();
+        let vol_names = pool.list_volumes().unwrap_or_default();
+        let mut volumes = Vec::new();
+        for vol_name in &vol_names {
+            if let Ok(vol) = StorageVol::lookup_by_name(pool, vol_name) {
+                let path = vol.get_path().unwrap_or_default();
+                let info = vol.get_info().unwrap_or(virt::storage_vol::StorageVolInfo {
+                    kind: 0,
+                    capacity: 0,
+                    allocation: 0,
+                });
+                volumes.push(VolumeInfo {
+                    name: vol_name.clone(),
+                    path,
+                    kind: VolumeType::from_libvirt(info.kind),
+                    capacity: info.capacity,
+                    allocation: info.allocation,
+                });
+            }
+        }
+        volumes.sort_by_key(|a| a.name.to_lowercase());
+        result.push((name, volumes));
+    }
+    result.sort_by_key(|a| a.0.to_lowercase());
+    Ok(result)
+}
+
+pub fn list_pool_volumes(uri: &str, pool_uuid: &str) -> Result<Vec<VolumeInfo>, AppError> {
+    with_pool(uri, pool_uuid, |pool| {
+        let vol_names = pool.list_volumes().unwrap_or_default();
+        let mut volumes = Vec::new();
+
+        for vol_name in &vol_names {
+            if let Ok(vol) = StorageVol::lookup_by_name(pool, vol_name) {
+                let path = vol.get_path().unwrap_or_default();
+                let info = vol.get_info().unwrap_or(virt::storage_vol::StorageVolInfo {
+                    kind: 0,
+                    capacity: 0,
+                    allocation: 0,
+                });
+
+                volumes.push(VolumeInfo {
+                    name: vol_name.clone(),
+                    path,
+                    kind: VolumeType::from_libvirt(info.kind),
+                    capacity: info.capacity,
+                    allocation: info.allocation,
+                });
+            }
+        }
+
+        volumes.sort_by_key(|a| a.name.to_lowercase());
+        Ok(volumes)
+    })
+}
+
+pub fn create_volume(
+    uri: &str,
+    pool_uuid: &str,
+    name: &str,
+    capacity_bytes: u64,
+    format: &str,
+) -> Result<(), AppError> {
+    with_pool(uri, pool_uuid, |pool| {
+        let xml = format!(
+            r#"<volume>
+  <name>{name}</name>
+  <capacity unit="bytes">{capacity_bytes}</capacity>
+  <target>
+    <format type="{format}"/>
+  </target>
+</volume>"#
+        );
+
+        StorageVol::create_xml(pool, &xml, 0)?;
+        Ok(())
+    })
+}
+
+pub fn delete_volume(uri: &str, pool_uuid: &str, vol_name: &str) -> Result<(), AppError> {
+    with_pool(uri, pool_uuid, |pool| {
+        let vol = StorageVol::lookup_by_name(pool, vol_name)?;
+        vol.delete(0)?;
+        Ok(())
+    })
+}
+
+/// The pool new VM volumes go to: `preferred_pool`, then "default", then the
+/// first active pool.
+fn new_vm_pool(
+    conn: &virt::connect::Connect,
+    preferred_pool: Option<&str>,
+) -> Result<StoragePool, AppError> {
+    let pools = conn.list_all_storage_pools(0)?;
+
+    let active_named = |name: &str| {
+        pools.iter().position(|p| {
+            p.is_active().unwrap_or(false)
+                && p.get_name().map(|n| n == name).unwrap_or(false)
+        })
+    };
+
+    let idx = preferred_pool
+        .and_then(active_named)
+        .or_else(|| active_named("default"))
+        .or_else(|| pools.iter().position(|p| p.is_active().unwrap_or(false)))
+        .ok_or_else(|| AppError::Backend("No active storage pool found".to_string()))?;
+    Ok(pools.into_iter().nth(idx).expect("pool index"))
+}
+
+/// Create a disk volume for a new VM in the default storage pool (falling back
+/// to the first active pool). Returns the absolute path to the new volume.
+/// Uses libvirt so the daemon handles permissions — no direct filesystem access needed.
+pub fn create_vm_disk(
+    uri: &str,
+    name: &str,
+    capacity_gib: u64,
+    format: &str,
+    extension: &str,
+    preferred_pool: Option<&str>,
+) -> Result<String, AppError> {
+    create_vm_volume(uri, &format!("{name}.{extension}"), capacity_gib, format, preferred_pool)
+}
+
+/// Like [`create_vm_disk`], with the volume name given in full.
+pub fn create_vm_volume(
+    uri: &str,
+    vol_name: &str,
+    capacity_gib: u64,
+    format: &str,
+    preferred_pool: Option<&str>,
+) -> Result<String, AppError> {
+    let conn = get_conn(uri)?;
+    let pool = new_vm_pool(&conn, preferred_pool)?;
+
+    let capacity_bytes = capacity_gib * 1024 * 1024 * 1024;
+    let xml = format!(
+        r#"<volume>
+  <name>{}</name>
+  <capacity unit="bytes">{capacity_bytes}</capacity>
+  <target>
+    <format type="{format}"/>
+  </target>
+</volume>"#,
+        escape_xml(vol_name)
+    );
+
+    let vol = StorageVol::create_xml(&pool, &xml, 0)?;
+    let path = vol.get_path()?;
+    Ok(path)
+}
+
+/// Create a qcow2 disk for a new VM backed by `base_path` (e.g. a cloud
+/// image), in the same pool `create_vm_disk` would use. The base is left
+/// untouched; the VM only writes to the overlay. Returns the overlay's path.
+pub fn create_vm_overlay(
+    uri: &str,
+    name: &str,
+    capacity_gib: u64,
+    base_path: &str,
+    preferred_pool: Option<&str>,
+) -> Result<String, AppError> {
+    let conn = get_conn(uri)?;
+    let pool = new_vm_pool(&conn, preferred_pool)?;
+
+    // Cloud images are often qcow2 despite an .img name, so ask libvirt
+    let base = StorageVol::lookup_by_path(&conn, base_path)?;
+    let base_format = volume_format(&base.get_xml_desc(0)?).unwrap_or_else(|| "raw".to_string());
+    // The overlay can't be smaller than its base
+    let capacity_bytes = (capacity_gib * 1024 * 1024 * 1024).max(base.get_info()?.capacity);
+
+    let xml = format!(
+        r#"<volume>
+  <name>{}.qcow2</name>
+  <capacity unit="bytes">{capacity_bytes}</capacity>
+  <target>
+    <format type="qcow2"/>
+  </target>
+  <backingStore>
+    <path>{}</path>
+    <format type="{}"/>
+  </backingStore>
+</volume>"#,
+        escape_xml(name),
+        escape_xml(base_path),
+        escape_xml(&base_format)
+    );
+
+    let vol = StorageVol::create_xml(&pool, &xml, 0)?;
+    let path = vol.get_path()?;
+    Ok(path)
+}
+
+/// `<target><format type=...>` of a volume definition.
+fn volume_format(xml: &str) -> Option<String> {
+    let doc = crate::xml_tree::Document::parse(xml).ok()?;
+    doc.root().child("target")?.child_attr("format", "type")
+}
+
+/// Name of the copy of volume `src_name` for disk `index` of VM `vm_name`:
+/// the VM's name, numbered from the second disk on, with the extension of
+/// the source (or `.qcow2` for a linked clone, which is always qcow2).
+pub fn clone_volume_name(src_name: &str, vm_name: &str, index: usize, linked: bool) -> String {
+    let stem = if index == 0 {
+        vm_name.to_string()
+    } else {
+        format!("{vm_name}-{index}")
+    };
+    let extension = if linked {
+        Some("qcow2")
+    } else {
+        std::path::Path::new(src_name)
+            .extension()
+            .and_then(|e| e.to_str())
+    };
+    match extension {
+        Some(ext) => format!("{stem}.{ext}"),
+        None => stem,
+    }
+}
+
+/// Copy the volume at `src_path` for disk `index` of the clone `vm_name`,
+/// in the source's own pool. A full clone is copied by libvirt in the
+/// source's format; a linked clone is a qcow2 overlay backed by the source.
+/// A full copy reports the bytes written on `job`; libvirt cannot stop it
+/// halfway, so cancelling takes effect once it is done. Returns the new
+/// volume's name and path.
+pub fn clone_volume(
+    uri: &str,
+    src_path: &str,
+    vm_name: &str,
+    index: usize,
+    linked: bool,
+    job: &Job,
+) -> Result<(String, String), AppError> {
+    let conn = get_conn(uri)?;
+    let src = StorageVol::lookup_by_path(&conn, src_path).map_err(|e| {
+        if e.code() == virt::error::ErrorNumber::NoStorageVolume {
+            AppError::Backend(format!(
+                "{src_path} is not in a storage pool; add its directory as a pool to clone it"
+            ))
+        } else {
+            e.into()
+        }
+    })?;
+    let pool = StoragePool::lookup_by_volume(&src)?;
+    let src_format = volume_format(&src.get_xml_desc(0)?).unwrap_or_else(|| "raw".to_string());
+    let src_info = src.get_info()?;
+    let vol_name = clone_volume_name(&src.get_name()?, vm_name, index, linked);
+
+    if linked {
+        let xml = format!(
+            r#"<volume>
+  <name>{}</name>
+  <capacity unit="bytes">{}</capacity>
+  <target>
+    <format type="qcow2"/>
+  </target>
+  <backingStore>
+    <path>{}</path>
+    <format type="{src_format}"/>
+  </backingStore>
+</volume>"#,
+            escape_xml(&vol_name),
+            src_info.capacity,
+            escape_xml(src_path)
+        );
+        let vol = StorageVol::create_xml(&pool, &xml, 0)?;
+        return Ok((vol_name, vol.get_path()?));
+    }
+
+    let vol = copy_volume(&pool, &src, &vol_name, job)?;
+    Ok((vol_name, vol.get_path()?))
+}
+
+/// Full copy of `src` named `vol_name` in `pool`, in the source's format,
+/// with the bytes copied so far reported on `job`.
+fn copy_volume(
+    pool: &StoragePool,
+    src: &StorageVol,
+    vol_name: &str,
+    job: &Job,
+) -> Result<StorageVol, AppError> {
+    let src_format = volume_format(&src.get_xml_desc(0)?).unwrap_or_else(|| "raw".to_string());
+    let src_info = src.get_info()?;
+    let xml = format!(
+        r#"<volume>
+  <name>{}</name>
+  <capacity unit="bytes">{}</capacity>
+  <target>
+    <format type="{src_format}"/>
+  </target>
+</volume>"#,
+        escape_xml(vol_name),
+        src_info.capacity
+    );
+    // The copy blocks until done, so it runs on its own thread while this
+    // one watches the new volume fill up
+    let total = src_info.allocation;
+    let vol = std::thread::scope(|scope| {
+        let copy = scope.spawn(|| StorageVol::create_xml_from(pool, &xml, src, 0));
+        while !copy.is_finished() {
+            std::thread::sleep(std::time::Duration::from_millis(500));
+            let copied = StorageVol::lookup_by_name(pool, vol_name).and_then(|v| v.get_info());
+            if let Ok(info) = copied {
+                job.set_progress(info.allocation.min(total), total);
+            }
+        }
+        copy.join().expect("volume copy thread panicked")
+    })?;
+    job.set_progress(total, total);
+    Ok(vol)
+}
+
+/// Copy volume `vol_name` to a new volume `new_name` in the pool
+/// `dst_pool_uuid`, which may be its own. Returns the new volume's path.
+pub fn clone_pool_volume(
+    uri: &str,
+    pool_uuid: &str,
+    vol_name: &str,
+    dst_pool_uuid: &str,
+    new_name: &str,
+    job: &Job,
+) -> Result<String, AppError> {
+    let conn = get_conn(uri)?;
+    let pool = StoragePool::lookup_by_uuid_string(&conn, pool_uuid)?;
+    let src = StorageVol::lookup_by_name(&pool, vol_name)?;
+    let dst_pool = StoragePool::lookup_by_uuid_string(&conn, dst_pool_uuid)?;
+    let vol = copy_volume(&dst_pool, &src, new_name, job)?;
+    Ok(vol.get_path()?)
+}
+
+/// Change the capacity of volume `vol_name`. With `allocate` the new space
+/// is allocated up front. Shrinking cuts off whatever data lies past the new
+/// end, so it is refused unless `allow_shrink` is set. Volumes of running VMs
+/// are refused too; those are resized through the VM.
+pub fn resize_volume(
+    uri: &str,
+    pool_uuid: &str,
+    vol_name: &str,
+    capacity: u64,
+    allocate: bool,
+    allow_shrink: bool,
+) -> Result<(), AppError> {
+    let conn = get_conn(uri)?;
+    let pool = StoragePool::lookup_by_uuid_string(&conn, pool_uuid)?;
+    let vol = StorageVol::lookup_by_name(&pool, vol_name)?;
+    refuse_if_running(&conn, &vol)?;
+
+    let current = vol.get_info()?.capacity;
+    let mut flags = 0;
+    if capacity < current {
+        if !allow_shrink {
+            return Err(AppError::Backend(format!(
+                "{vol_name} holds {}; shrinking it to {} would lose the data past the new end",
+                format_bytes(current),
+                format_bytes(capacity)
+            )));
+        }
+        flags |= virt::sys::VIR_STORAGE_VOL_RESIZE_SHRINK;
+    }
+    if allocate {
+        flags |= virt::sys::VIR_STORAGE_VOL_RESIZE_ALLOCATE;
+    }
+    vol.resize(capacity, flags)?;
+    Ok(())
+}
+
+/// Overwrite the data of volume `vol_name` using `algorithm`. Volumes of
+/// running VMs are refused.
+pub fn wipe_volume(
+    uri: &str,
+    pool_uuid: &str,
+    vol_name: &str,
+    algorithm: WipeAlgorithm,
+) -> Result<(), AppError> {
+    let conn = get_conn(uri)?;
+    let pool = StoragePool::lookup_by_uuid_string(&conn, pool_uuid)?;
+    let vol = StorageVol::lookup_by_name(&pool, vol_name)?;
+    refuse_if_running(&conn, &vol)?;
+    vol.wipe_pattern(algorithm.to_libvirt(), 0)?;
+    Ok(())
+}
+
+fn refuse_if_running(conn: &virt::connect::Connect, vol: &StorageVol) -> Result<(), AppError> {
+    let running = volume_users(conn, &vol.get_path()?, virt::sys::VIR_CONNECT_LIST_DOMAINS_ACTIVE)?;
+    match running.first() {
+        Some(vm) => Err(AppError::Backend(format!(
+            "{} is in use by the running VM {vm}",
+            vol.get_name()?
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Names of the domains listed with `flags` that have a disk on `path`.
+fn volume_users(
+    conn: &virt::connect::Connect,
+    path: &str,
+    flags: u32,
+) -> Result<Vec<String>, AppError> {
+    let mut users = Vec::new();
+    for domain in conn.list_all_domains(flags)? {
+        let xml = domain.get_xml_desc(0)?;
+        if crate::domain_xml::extract_disk_paths(&xml).iter().any(|p| p == path) {
+            users.push(domain.get_name()?);
+        }
+    }
+    users.sort_by_key(|name| name.to_lowercase());
+    Ok(users)
+}
+
+/// Format, backing chain and users of volume `vol_name`.
+pub fn volume_details(
+    uri: &str,
+    pool_uuid: &str,
+    vol_name: &str,
+) -> Result<VolumeDetails, AppError> {
+    let conn = get_conn(uri)?;
+    let pool = StoragePool::lookup_by_uuid_string(&conn, pool_uuid)?;
+    let vol = StorageVol::lookup_by_name(&pool, vol_name)?;
+    let path = vol.get_path()?;
+    let info = vol.get_info()?;
+    let xml = vol.get_xml_desc(0)?;
+
+    let backing_chain = pool_backing_chain(&conn, &xml);
+    let all = virt::sys::VIR_CONNECT_LIST_DOMAINS_ACTIVE
+        | virt::sys::VIR_CONNECT_LIST_DOMAINS_INACTIVE;
+    Ok(VolumeDetails {
+        name: vol_name.to_string(),
+        kind: VolumeType::from_libvirt(info.kind),
+        format: volume_format(&xml).unwrap_or_default(),
+        capacity: info.capacity,
+        allocation: info.allocation,
+        backing_chain,
+        used_by: volume_users(&conn, &path, all)?,
+        path,
+    })
+}
+
+/// The backing files of the volume described by `xml`, nearest first.
+/// Each volume only names its immediate backing file, so the chain is
+/// followed through the pools that hold them.
+fn pool_backing_chain(conn: &virt::connect::Connect, xml: &str) -> Vec<BackingStore> {
+    let mut backing_chain: Vec<BackingStore> = Vec::new();
+    let mut next = volume_backing_store(xml);
+    while let Some(backing) = next.take() {
+        if backing_chain.len() >= 16 || backing_chain.iter().any(|b| b.path == backing.path) {
+            break;
+        }
+        next = StorageVol::lookup_by_path(conn, &backing.path)
+            .and_then(|v| v.get_xml_desc(0))
+            .ok()
+            .and_then(|xml| volume_backing_store(&xml));
+        backing_chain.push(backing);
+    }
+    backing_chain
+}
+
+/// Names of the domains other than `uuid` with a disk on image `path` or
+/// on an image built on it, such as the linked clones of a template.
+/// Backing files are taken from running VMs' disks and from the pools.
+pub(crate) fn image_users(
+    conn: &virt::connect::Connect,
+    path: &str,
+    uuid: &str,
+) -> Result<Vec<String>, AppError> {
+    let all = virt::sys::VIR_CONNECT_LIST_DOMAINS_ACTIVE
+        | virt::sys::VIR_CONNECT_LIST_DOMAINS_INACTIVE;
+    let mut users = Vec::new();
+    for domain in conn.list_all_domains(all)? {
+        if domain.get_uuid_string()? == uuid {
+            continue;
+        }
+        let details = crate::domain_xml::parse_domain_xml(&domain.get_xml_desc(0)?)?;
+        let uses = details.disks.iter().any(|disk| {
+            let Some(source) = &disk.source_file else {
+                return false;
+            };
+            source == path
+                || disk.backing_chain.iter().any(|b| b == path)
+                || StorageVol::lookup_by_path(conn, source)
+                    .and_then(|v| v.get_xml_desc(0))
+                    .is_ok_and(|xml| pool_backing_chain(conn, &xml).iter().any(|b| b.path == path))
+        });
+        if uses {
+            users.push(domain.get_name()?);
+        }
+    }
+    users.sort_by_key(|name| name.to_lowercase());
+    Ok(users)
+}
+
+/// The backing file named in a volume's XML description.
+pub fn volume_backing_store(xml: &str) -> Option<BackingStore> {
+    let doc = crate::xml_tree::Document::parse(xml).ok()?;
+    let backing = doc.root().child("backingStore")?;
+    let path = backing.child_text("path").filter(|p| !p.is_empty())?;
+    Some(BackingStore {
+        path,
+        format: backing.child_attr("format", "type").unwrap_or_default(),
+    })
+}
+
+/// Path of volume `volume` in pool `pool`.
+pub fn volume_path(uri: &str, pool: &str, volume: &str) -> Result<String, AppError> {
+    let conn = get_conn(uri)?;
+    let pool = StoragePool::lookup_by_name(&conn, pool)?;
+    let path = StorageVol::lookup_by_name(&pool, volume)?.get_path()?;
+    Ok(path)
+}
+
+/// Upload an in-memory image (e.g. a generated seed ISO) as a new raw volume
+/// in the pool `create_vm_disk` would use. Returns the volume's path.
+pub fn upload_vm_image(
+    uri: &str,
+    vol_name: &str,
+    data: &[u8],
+    preferred_pool: Option<&str>,
+) -> Result<String, AppError> {
+    let pool_uuid = {
+        let conn = get_conn(uri)?;
+        new_vm_pool(&conn, preferred_pool)?.get_uuid_string()?
+    };
+
+    // upload_volume streams from a file, so stage the image in a temp file
+    let tmp = std::env::temp_dir().join(format!("grustyvman-{}-{vol_name}", std::process::id()));
+    std::fs::write(&tmp, data)?;
+    let result = upload_volume(uri, &pool_uuid, &tmp.to_string_lossy(), vol_name);
+    let _ = std::fs::remove_file(&tmp);
+    result
+}
+
+/// Delete a storage volume by its absolute path. Ignores errors from volumes
+/// that are not tracked by any pool (e.g. manually placed files).
+pub fn delete_volume_by_path(uri: &str, path: &str) -> Result<(), AppError> {
+    let conn = get_conn(uri)?;
+    let vol = StorageVol::lookup_by_path(&conn, path)?;
+    vol.delete(0)?;
+    Ok(())
+}
+
+/// Upload a local file into a storage pool volume via the libvirt stream API.
+/// The daemon handles file creation and permissions — no direct filesystem
+/// access required, so this works even when the pool directory is root-owned.
+/// Returns the path of the new volume.
+pub fn upload_volume(
+    uri: &str,
+    pool_uuid: &str,
+    src_path: &str,
+    vol_name: &str,
+) -> Result<String, AppError> {
+    let job = Job::detached("Upload");
+    upload_volume_with_progress(uri, pool_uuid, src_path, vol_name, false, &job)
+}
+
+/// Like [`upload_volume`], reporting the bytes sent on `job`. Cancelling it
+/// aborts the transfer and deletes the partial volume. With `convert`, an
+/// image from another hypervisor (see [`ImageFormat::is_foreign`]) is
+/// converted to qcow2 with the local qemu-img first.
+pub fn upload_volume_with_progress(
+    uri: &str,
+    pool_uuid: &str,
+    src_path: &str,
+    vol_name: &str,
+    convert: bool,
+    job: &Job,
+) -> Result<String, AppError> {
+    let format = image_format(src_path)?;
+    if convert && format.is_foreign() {
+        let tmp = std::env::temp_dir()
+            .join(format!("grustyvman-{}-{}.qcow2", std::process::id(), job.id()));
+        job.set_detail(&format!("Converting {} to qcow2", format.label()));
+        let result = convert_image(src_path, format, &tmp, job).and_then(|()| {
+            job.set_detail("Uploading");
+            send_volume(uri, pool_uuid, &tmp, vol_name, ImageFormat::Qcow2, job)
+        });
+        let _ = std::fs::remove_file(&tmp);
+        return result;
+    }
+    if !format.libvirt_supported() {
+        return Err(AppError::Backend(format!(
+            "Storage pools cannot hold {} images; convert it to qcow2",
+            format.label()
+        )));
+    }
+    send_volume(uri, pool_uuid, Path::new(src_path), vol_name, format, job)
+}
+
+/// Create `vol_name` in the pool and stream `src_path` into it. Holes in the
+/// file are sent as holes when the pool backend accepts sparse streams.
+fn send_volume(
+    uri: &str,
+    pool_uuid: &str,
+    src_path: &Path,
+    vol_name: &str,
+    format: ImageFormat,
+    job: &Job,
+) -> Result<String, AppError> {
+    use std::os::unix::fs::FileExt;
+
+    let file = std::fs::File::open(src_path)?;
+    let file_size = file.metadata()?.len();
+
+    let conn = get_conn(uri)?;
+    let pool = StoragePool::lookup_by_uuid_string(&conn, pool_uuid)?;
+
+    let xml = format!(
+        r#"<volume>
+  <name>{}</name>
+  <capacity unit="bytes">{file_size}</capacity>
+  <target>
+    <format type="{}"/>
+  </target>
+</volume>"#,
+        escape_xml(vol_name),
+        format.as_str()
+    );
+    let vol = StorageVol::create_xml(&pool, &xml, 0)?;
+
+    // Older daemons and some pool backends cannot take holes
+    let sparse_stream = Stream::new(&conn, 0)?;
+    let sparse_flag = virt::sys::VIR_STORAGE_VOL_UPLOAD_SPARSE_STREAM;
+    let opened = match vol.upload(&sparse_stream, 0, file_size, sparse_flag) {
+        Ok(_) => Ok((sparse_stream, true)),
+        Err(_) => Stream::new(&conn, 0)
+            .and_then(|stream| vol.upload(&stream, 0, file_size, 0).map(|_| (stream, false))),
+    };
+    let (stream, sparse) = match opened {
+        Ok(opened) => opened,
+        Err(e) => {
+            let _ = vol.delete(0);
+            return Err(e.into());
+        }
+    };
+
+    job.set_cancellable(true);
+    let send_result: Result<(), AppError> = (|| {
+        let mut buf = vec![0u8; 256 * 1024];
+        let mut pos = 0u64;
+        while pos < file_size {
+            let (is_data, end) = next_extent(&file, pos, file_size);
+            if !is_data && sparse {
+                job.check_cancelled()?;
+                send_hole(&stream, end - pos)?;
+                pos = end;
+                job.set_progress(pos, file_size);
+                continue;
+            }
+            if !is_data {
+                buf.fill(0);
+            }
+            while pos < end {
+                job.check_cancelled()?;
+                let len = buf.len().min((end - pos) as usize);
+                if is_data {
+                    file.read_exact_at(&mut buf[..len], pos)?;
+                }
+                let mut sent = 0;
+                while sent < len {
+                    sent += stream.send(&buf[sent..len])?;
+                }
+                pos += len as u64;
+                job.set_progress(pos, file_size);
+            }
+        }
+        Ok(())
+    })();
+    job.set_cancellable(false);
+
+    match send_result {
+        Ok(()) => {
+            stream.finish()?;
+            Ok(vol.get_path()?)
+        }
+        Err(e) => {
+            let _ = stream.abort();
+            let _ = vol.delete(0);
+            Err(e)
+        }
+    }
+}
+
+/// Whether `file` holds data at `pos`, and where that data or hole ends.
+/// Filesystems that cannot report holes have a single data extent.
+fn next_extent(file: &std::fs::File, pos: u64, size: u64) -> (bool, u64) {
+    use std::os::unix::io::AsRawFd;
+
+    let fd = file.as_raw_fd();
+    // SAFETY: lseek on a descriptor `file` keeps open. Only the file offset
+    // changes, and reads go through read_at, which does not use it.
+    let data = unsafe { libc::lseek(fd, pos as libc::off_t, libc::SEEK_DATA) };
+    if data < 0 {
+        let only_hole_left =
+            std::io::Error::last_os_error().raw_os_error() == Some(libc::ENXIO);
+        return (!only_hole_left, size);
+    }
+    let data = (data as u64).min(size);
+    if data > pos {
+        return (false, data);
+    }
+    // SAFETY: as above.
+    let hole = unsafe { libc::lseek(fd, pos as libc::off_t, libc::SEEK_HOLE) };
+    if hole <= pos as libc::off_t {
+        return (true, size);
+    }
+    (true, (hole as u64).min(size))
+}
+
+/// Tell the receiving end of an upload stream to skip `len` bytes.
+fn send_hole(stream: &Stream, len: u64) -> Result<(), AppError> {
+    // SAFETY: the stream pointer is valid while `stream` is borrowed.
+    let ret = unsafe { virt::sys::virStreamSendHole(stream.as_ptr(), len as i64, 0) };
+    if ret < 0 {
+        return Err(virt::error::Error::last_error().into());
+    }
+    Ok(())
+}
+
+/// Tell an image's format from its first bytes (512 are enough). Anything
+/// unrecognised, ISO images included, is raw.
+pub fn detect_image_format(header: &[u8]) -> ImageFormat {
+    const VDI_SIGNATURE: [u8; 4] = 0xbeda_107f_u32.to_le_bytes();
+
+    if header.starts_with(b"QFI\xfb") {
+        ImageFormat::Qcow2
+    } else if header.starts_with(b"KDMV") {
+        ImageFormat::Vmdk
+    } else if header.starts_with(b"vhdxfile") {
+        ImageFormat::Vhdx
+    } else if header.starts_with(b"conectix") {
+        ImageFormat::Vpc
+    } else if header.get(64..68) == Some(&VDI_SIGNATURE[..]) {
+        ImageFormat::Vdi
+    } else {
+        ImageFormat::Raw
+    }
+}
+
+/// The format of the image file at `path`, from its header.
+pub fn image_format(path: &str) -> Result<ImageFormat, AppError> {
+    use std::io::Read;
+
+    let mut header = Vec::with_capacity(512);
+    std::fs::File::open(path)?.take(512).read_to_end(&mut header)?;
+    Ok(detect_image_format(&header))
+}
+
+/// Convert `src` to a qcow2 image at `dst` with qemu-img, following its
+/// progress output on `job`. Cancelling `job` stops qemu-img.
+fn convert_image(src: &str, format: ImageFormat, dst: &Path, job: &Job) -> Result<(), AppError> {
+    use std::io::Read;
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new("qemu-img")
+        .args(["convert", "-p", "-f", format.as_str(), "-O", "qcow2", src])
+        .arg(dst)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => AppError::Backend(format!(
+                "qemu-img is needed to convert {} images",
+                format.label()
+            )),
+            _ => AppError::Io(e),
+        })?;
+
+    job.set_cancellable(true);
+    let src_size = std::fs::metadata(src).map(|m| m.len()).unwrap_or(0);
+    let mut stdout = child.stdout.take().expect("piped stdout");
+    let mut buf = [0u8; 256];
+    let mut output = String::new();
+    loop {
+        if job.is_cancelled() {
+            let _ = child.kill();
+            let _ = child.wait();
+            job.set_cancellable(false);
+            return Err(AppError::Cancelled);
+        }
+        let n = stdout.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        output.push_str(&String::from_utf8_lossy(&buf[..n]));
+        if let Some(percent) = output.rsplit(['\r', '\n']).find_map(parse_qemu_img_progress) {
+            job.set_progress((src_size as f64 * percent / 100.0) as u64, src_size);
+        }
+        // Keep only the line being redrawn
+        if let Some(i) = output.rfind(['\r', '\n']) {
+            output.drain(..=i);
+        }
+    }
+    job.set_cancellable(false);
+
+    let result = child.wait_with_output()?;
+    if !result.status.success() {
+        return Err(AppError::tool_failed("qemu-img", &result));
+    }
+    Ok(())
+}
+
+/// `name` with the extension of a foreign image format, if any, replaced by
+/// `.qcow2`, for the volume an image is converted into.
+pub fn qcow2_volume_name(name: &str) -> String {
+    let stem = [".vmdk", ".vdi", ".vhdx", ".vhd"]
+        .iter()
+        .find_map(|ext| {
+            let split = name.len().checked_sub(ext.len())?;
+            let (stem, tail) = name.split_at_checked(split)?;
+            tail.eq_ignore_ascii_case(ext).then_some(stem)
+        })
+        .unwrap_or(name);
+    format!("{stem}.qcow2")
+}
+
+/// The percentage in one of qemu-img's `(12.34/100%)` progress lines.
+fn parse_qemu_img_progress(line: &str) -> Option<f64> {
+    line.trim().strip_prefix('(')?.strip_suffix("/100%)")?.parse().ok()
+}
+
+/// Download a storage pool volume into the local file `dst_path` via the
+/// libvirt stream API, the reverse of [`upload_volume`]. Holes in the volume
+/// stay holes in the file where the pool backend can report them.
+pub fn download_volume(
+    uri: &str,
+    pool_uuid: &str,
+    vol_name: &str,
+    dst_path: &str,
+) -> Result<(), AppError> {
+    download_volume_with_progress(uri, pool_uuid, vol_name, dst_path, &Job::detached("Download"))
+}
+
+/// Like [`download_volume`], reporting the bytes received on `job`.
+/// Cancelling it aborts the transfer and removes the partial file.
+pub fn download_volume_with_progress(
+    uri: &str,
+    pool_uuid: &str,
+    vol_name: &str,
+    dst_path: &str,
+    job: &Job,
+) -> Result<(), AppError> {
+    use std::io::{Seek, SeekFrom, Write};
+
+    let conn = get_conn(uri)?;
+    let pool = StoragePool::lookup_by_uuid_string(&conn, pool_uuid)?;
+    let vol = StorageVol::lookup_by_name(&pool, vol_name)?;
+
+    // The stream carries the volume's bytes as stored, so a qcow2 image is
+    // as long as its file, not its virtual disk
+    let size = match vol.get_info_flags(virt::sys::VIR_STORAGE_VOL_GET_PHYSICAL) {
+        Ok(info) => info.allocation,
+        Err(_) => vol.get_info()?.capacity,
+    };
+
+    // Older daemons and some pool backends cannot send holes
+    let sparse_stream = Stream::new(&conn, 0)?;
+    let sparse_flag = virt::sys::VIR_STORAGE_VOL_DOWNLOAD_SPARSE_STREAM;
+    let (stream, sparse) = match vol.download(&sparse_stream, 0, 0, sparse_flag) {
+        Ok(_) => (sparse_stream, true),
+        Err(_) => {
+            let stream = Stream::new(&conn, 0)?;
+            vol.download(&stream, 0, 0, 0)?;
+            (stream, false)
+        }
+    };
+
+    let mut file = match std::fs::File::create(dst_path) {
+        Ok(file) => file,
+        Err(e) => {
+            let _ = stream.abort();
+            return Err(AppError::Io(e));
+        }
+    };
+
+    job.set_cancellable(true);
+    let recv_result: Result<(), AppError> = (|| {
+        let mut buf = vec![0u8; 256 * 1024];
+        let mut received = 0u64;
+        loop {
+            job.check_cancelled()?;
+            match recv_chunk(&stream, &mut buf, sparse)? {
+                StreamChunk::Data(n) => file.write_all(&buf[..n])?,
+                StreamChunk::Hole(len) => {
+                    file.seek(SeekFrom::Current(len as i64))?;
+                }
+                StreamChunk::End => break,
+            }
+            received = file.stream_position()?;
+            job.set_progress(received, size.max(received));
+        }
+        // A trailing hole was skipped over without writing anything
+        file.set_len(received)?;
+        file.sync_all()?;
+        Ok(())
+    })();
+    job.set_cancellable(false);
+
+    match recv_result {
+        Ok(()) => {
+            stream.finish()?;
+            Ok(())
+        }
+        Err(e) => {
+            let _ = stream.abort();
+            drop(file);
+            let _ = std::fs::remove_file(dst_path);
+            Err(e)
+        }
+    }
+}
+
+enum StreamChunk {
+    Data(usize),
+    Hole(u64),
+    End,
+}
+
+/// Receive the next piece of a download stream. With `sparse`, the stream
+/// was opened with `VIR_STORAGE_VOL_DOWNLOAD_SPARSE_STREAM` and stops at
+/// holes instead of sending their zeroes.
+fn recv_chunk(stream: &Stream, buf: &mut [u8], sparse: bool) -> Result<StreamChunk, AppError> {
+    if !sparse {
+        let n = stream.recv(buf)?;
+        return Ok(if n == 0 { StreamChunk::End } else { StreamChunk::Data(n) });
+    }
+
+    // SAFETY: the stream pointer is valid while `stream` is borrowed and
+    // `buf` is writable for `buf.len()` bytes.
+    let ret = unsafe {
+        virt::sys::virStreamRecvFlags(
+            stream.as_ptr(),
+            buf.as_mut_ptr() as *mut std::ffi::c_char,
+            buf.len(),
+            virt::sys::VIR_STREAM_RECV_STOP_AT_HOLE,
+        )
+    };
+    match ret {
+        0 => Ok(StreamChunk::End),
+        n if n > 0 => Ok(StreamChunk::Data(n as usize)),
+        // The stream sits at a hole; ask how long it is
+        -3 => {
+            let mut len: std::ffi::c_longlong = 0;
+            // SAFETY: as above; `len` outlives the call.
+            let ret = unsafe { virt::sys::virStreamRecvHole(stream.as_ptr(), &mut len, 0) };
+            if ret < 0 {
+                return Err(virt::error::Error::last_error().into());
+            }
+            Ok(StreamChunk::Hole(len.max(0) as u64))
+        }
+        _ => Err(virt::error::Error::last_error().into()),
+    }
+}
+
+/// Read `length` bytes at `offset` of the volume at `path` via the libvirt
+/// stream API. Returns fewer bytes if the volume ends first.
+pub fn read_volume(uri: &str, path: &str, offset: u64, length: u64) -> Result<Vec<u8>, AppError> {
+    let conn = get_conn(uri)?;
+    let vol = StorageVol::lookup_by_path(&conn, path)?;
+
+    let stream = Stream::new(&conn, 0)?;
+    vol.download(&stream, offset, length, 0)?;
+
+    let mut data = Vec::with_capacity(length as usize);
+    let mut buf = vec![0u8; 64 * 1024];
+    let recv_result: Result<(), AppError> = (|| {
+        while (data.len() as u64) < length {
+            let n = stream.recv(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            data.extend_from_slice(&buf[..n]);
+        }
+        Ok(())
+    })();
+
+    match recv_result {
+        Ok(()) => {
+            stream.finish()?;
+            data.truncate(length as usize);
+            Ok(data)
+        }
+        Err(e) => {
+            let _ = stream.abort();
+            Err(e)
+        }
+    }
+}
+
+pub fn extract_pool_type_and_path(xml: &str) -> (String, String) {
+    use quick_xml::events::Event;
+    use quick_xml::Reader;
+
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut pool_type = String::new();
+    let mut path = String::new();
+    let mut in_target = false;
+    let mut in_path = false;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                match name.as_str() {
+                    "pool" => {
+                        for attr in e.attributes().flatten() {
+                            if attr.key.as_ref() == b"type" {
+                                pool_type =
+                                    String::from_utf8_lossy(&attr.value).to_string();
+                            }
+                        }
+                    }
+                    "target" => in_target = true,
+                    "path" if in_target => in_path = true,
+                    _ => {}
+                }
+            }
+            Ok(Event::Text(ref e)) if in_path => {
+                path = e.unescape().unwrap_or_default().to_string();
+                in_path = false;
+            }
+            Ok(Event::End(ref e)) => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if name == "target" {
+                    in_target = false;
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(_) => break,
+            _ => {}
+        }
+    }
+
+    (pool_type, path)
+}
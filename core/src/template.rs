@@ -0,0 +1,262 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use virt::domain::Domain;
+use virt::error::ErrorNumber;
+
+use crate::cloudinit::{CloudInitConfig, SEED_VOLUME_ID};
+use crate::connection::get_conn;
+use crate::domain::{get_domain_name, update_domain_xml, with_domain};
+use crate::domain_model::DomainXml;
+use crate::error::AppError;
+use crate::snapshot::escape_xml;
+use crate::types::{NewDiskParams, TemplateInfo};
+use crate::xml_tree::{Document, Element};
+
+/// Namespace of the template record in a domain's `<metadata>`.
+pub const TEMPLATE_NS: &str = "https://github.com/jwenzel2/grustyvman/xmlns/template/1.0";
+
+/// Prefix libvirt writes the record with.
+const TEMPLATE_PREFIX: &str = "grustyvman";
+
+const METADATA_ELEMENT: i32 = virt::sys::VIR_DOMAIN_METADATA_ELEMENT as i32;
+
+/// How [`instantiate_template`] names and provisions new VMs.
+#[derive(Debug, Clone, Default)]
+pub struct InstantiateParams {
+    /// Name of each instance. `{n}` becomes a number (the lowest ones not
+    /// taken yet, from 1) and `{template}` the template's name.
+    pub name_pattern: String,
+    pub count: u32,
+    /// Give every instance a seed ISO built from this, with the hostname
+    /// set to the instance's name.
+    pub cloud_init: Option<CloudInitConfig>,
+}
+
+// ---- Metadata record ----
+
+/// The record as handed to `virDomainSetMetadata`, which moves it into
+/// [`TEMPLATE_NS`].
+pub fn template_metadata_xml(info: &TemplateInfo) -> String {
+    format!(
+        "<template><description>{}</description><created>{}</created></template>",
+        escape_xml(&info.description),
+        info.created
+    )
+}
+
+/// Whether `e` is the template record, whatever prefix it was written with.
+pub fn is_template_element(e: &Element) -> bool {
+    let (xmlns, local) = match e.name().split_once(':') {
+        Some((prefix, local)) => (format!("xmlns:{prefix}"), local),
+        None => ("xmlns".to_string(), e.name()),
+    };
+    local == "template" && e.attr_is(&xmlns, TEMPLATE_NS)
+}
+
+/// Fields of a template record; missing ones are left at their defaults.
+pub fn template_info(e: &Element) -> TemplateInfo {
+    let field = |name: &str| {
+        e.children()
+            .find(|c| c.name().rsplit(':').next() == Some(name))
+            .map(|c| c.text())
+            .unwrap_or_default()
+    };
+    TemplateInfo {
+        description: field("description"),
+        created: field("created").trim().parse().unwrap_or(0),
+    }
+}
+
+/// The template record of `domain`, or `None` for an ordinary VM.
+pub(crate) fn domain_template(domain: &Domain) -> Result<Option<TemplateInfo>, AppError> {
+    match domain.get_metadata(METADATA_ELEMENT, Some(TEMPLATE_NS), 0) {
+        // A record we cannot read still marks a template
+        Ok(xml) => Ok(Some(
+            Document::parse(&xml)
+                .map(|doc| template_info(doc.root()))
+                .unwrap_or_default(),
+        )),
+        Err(e) if e.code() == ErrorNumber::NoDomainMetadata => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Fail if `domain` is a template. Templates stay shut off so their disks
+/// remain valid backing files for the instances.
+pub(crate) fn ensure_not_template(domain: &Domain) -> Result<(), AppError> {
+    if domain_template(domain)?.is_some() {
+        return Err(AppError::Backend(format!(
+            "{} is a template; instantiate it instead of starting it",
+            domain.get_name()?
+        )));
+    }
+    Ok(())
+}
+
+// ---- Marking ----
+
+pub fn get_template(uri: &str, uuid: &str) -> Result<Option<TemplateInfo>, AppError> {
+    with_domain(uri, uuid, domain_template)
+}
+
+/// Turn a shut-off VM into a template. Autostart is switched off so the
+/// host never boots it either.
+pub fn mark_template(uri: &str, uuid: &str, description: &str) -> Result<(), AppError> {
+    with_domain(uri, uuid, |domain| {
+        if domain.is_active()? {
+            return Err(AppError::Backend(format!(
+                "Shut down {} before turning it into a template",
+                domain.get_name()?
+            )));
+        }
+        let info = TemplateInfo {
+            description: description.to_string(),
+            created: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        };
+        domain.set_metadata(
+            METADATA_ELEMENT,
+            Some(&template_metadata_xml(&info)),
+            Some(TEMPLATE_PREFIX),
+            Some(TEMPLATE_NS),
+            virt::sys::VIR_DOMAIN_AFFECT_CONFIG,
+        )?;
+        domain.set_autostart(false)?;
+        Ok(())
+    })
+}
+
+/// Turn a template back into an ordinary VM.
+pub fn unmark_template(uri: &str, uuid: &str) -> Result<(), AppError> {
+    with_domain(uri, uuid, |domain| {
+        domain.set_metadata(
+            METADATA_ELEMENT,
+            None,
+            None,
+            Some(TEMPLATE_NS),
+            virt::sys::VIR_DOMAIN_AFFECT_CONFIG,
+        )?;
+        Ok(())
+    })
+}
+
+// ---- Instantiation ----
+
+/// Names for `count` instances of `template_name`, skipping those in
+/// `taken`. A pattern without `{n}` is used as is for a single instance
+/// and gets "-{n}" appended for several.
+pub fn instance_names(
+    pattern: &str,
+    template_name: &str,
+    count: u32,
+    taken: &[String],
+) -> Result<Vec<String>, AppError> {
+    let mut pattern = pattern.trim().replace("{template}", template_name);
+    if pattern.is_empty() {
+        return Err(AppError::Backend("The name pattern is empty".to_string()));
+    }
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+    if !pattern.contains("{n}") {
+        if count == 1 {
+            if taken.contains(&pattern) {
+                return Err(AppError::Backend(format!("A VM named {pattern} already exists")));
+            }
+            return Ok(vec![pattern]);
+        }
+        pattern.push_str("-{n}");
+    }
+
+    let mut names = Vec::with_capacity(count as usize);
+    let mut n = 1;
+    while names.len() < count as usize {
+        let name = pattern.replace("{n}", &n.to_string());
+        if !taken.contains(&name) {
+            names.push(name);
+        }
+        n += 1;
+    }
+    Ok(names)
+}
+
+/// Attach the seed ISO at `seed_path`. A CD-ROM still holding an earlier
+/// seed (a template made from a cloud image keeps its own) gets the new
+/// one, so the guest never sees two `cidata` volumes; otherwise the seed
+/// goes on a new SATA CD-ROM.
+pub fn attach_seed_xml(xml: &str, seed_path: &str) -> Result<String, AppError> {
+    let mut domain = DomainXml::parse(xml)?;
+    let disks = domain.disks();
+    let seed_suffix = format!("-{SEED_VOLUME_ID}.iso");
+
+    let old_seed = disks.iter().find(|d| {
+        d.device_type == "cdrom"
+            && d.source_file.as_deref().is_some_and(|f| f.ends_with(&seed_suffix))
+    });
+    match old_seed {
+        Some(cdrom) => domain.set_disk_source(&cdrom.target_dev, seed_path, true),
+        None => {
+            let target_dev = (b'a'..=b'z')
+                .map(|c| format!("sd{}", c as char))
+                .find(|t| !disks.iter().any(|d| d.target_dev == *t))
+                .ok_or_else(|| {
+                    AppError::Backend("No free SATA slot for the cloud-init seed".to_string())
+                })?;
+            domain.add_disk(&NewDiskParams {
+                source_file: seed_path.to_string(),
+                target_dev,
+                bus: "sata".to_string(),
+                device_type: "cdrom".to_string(),
+                driver_type: "raw".to_string(),
+                create_new: false,
+                size_gib: 0,
+            });
+        }
+    }
+    Ok(domain.to_xml())
+}
+
+/// Create linked clones of a template, named after `params.name_pattern`.
+/// Each gets fresh MAC addresses and, with `params.cloud_init`, its own
+/// seed ISO. Returns the names of the new VMs.
+pub fn instantiate_template(
+    uri: &str,
+    uuid: &str,
+    params: &InstantiateParams,
+) -> Result<Vec<String>, AppError> {
+    let template_name = get_domain_name(uri, uuid)?;
+    if get_template(uri, uuid)?.is_none() {
+        return Err(AppError::Backend(format!("{template_name} is not a template")));
+    }
+
+    let taken: Vec<String> = crate::connection::list_all_vms(uri)?
+        .into_iter()
+        .map(|vm| vm.name)
+        .collect();
+    let names = instance_names(&params.name_pattern, &template_name, params.count, &taken)?;
+
+    for name in &names {
+        crate::domain::clone_domain(uri, uuid, name, false)?;
+
+        if let Some(cloud_init) = &params.cloud_init {
+            let seed = CloudInitConfig {
+                hostname: name.clone(),
+                ..cloud_init.clone()
+            };
+            let seed_path = crate::storage::upload_vm_image(
+                uri,
+                &format!("{name}-{SEED_VOLUME_ID}.iso"),
+                &seed.seed_iso(name),
+                None,
+            )?;
+            let xml = {
+                let conn = get_conn(uri)?;
+                Domain::lookup_by_name(&conn, name)?.get_xml_desc(0)?
+            };
+            update_domain_xml(uri, &attach_seed_xml(&xml, &seed_path)?)?;
+        }
+    }
+    Ok(names)
+}
@@ -49,6 +49,70 @@ impl fmt::Display for SnapshotState {
     }
 }
 
+/// Where a snapshot keeps the VM's state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotKind {
+    /// Inside the qcow2 images (and, for a running VM, its memory too).
+    /// Needs qcow2 disks and no pflash firmware.
+    Internal,
+    /// New overlay files take further writes; a running VM's memory goes
+    /// to a separate file.
+    External,
+    /// Overlays only, without memory: the guest resumes as after a crash.
+    DiskOnly,
+}
+
+impl SnapshotKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SnapshotKind::Internal => "Internal",
+            SnapshotKind::External => "External",
+            SnapshotKind::DiskOnly => "Disks Only",
+        }
+    }
+
+    pub const ALL: &[SnapshotKind] =
+        &[SnapshotKind::Internal, SnapshotKind::External, SnapshotKind::DiskOnly];
+}
+
+/// What a snapshot does with one disk, as in `<disk snapshot='...'>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiskSnapshotMode {
+    Internal,
+    External,
+    /// Left out of the snapshot.
+    Excluded,
+}
+
+impl DiskSnapshotMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiskSnapshotMode::Internal => "internal",
+            DiskSnapshotMode::External => "external",
+            DiskSnapshotMode::Excluded => "no",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "internal" => Some(DiskSnapshotMode::Internal),
+            "external" => Some(DiskSnapshotMode::External),
+            "no" => Some(DiskSnapshotMode::Excluded),
+            _ => None,
+        }
+    }
+}
+
+/// One disk in a snapshot's `<disks>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotDisk {
+    pub target_dev: String,
+    pub mode: DiskSnapshotMode,
+    /// The overlay of an external disk snapshot. When creating, `None` lets
+    /// libvirt name it after the image and the snapshot.
+    pub file: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct SnapshotInfo {
     pub name: String,
@@ -56,12 +120,89 @@ pub struct SnapshotInfo {
     pub state: SnapshotState,
     pub creation_time: i64,
     pub is_current: bool,
+    /// The snapshot this one was taken on top of.
+    pub parent: Option<String>,
+    pub disks: Vec<SnapshotDisk>,
+    /// Where an external snapshot saved the VM's memory.
+    pub memory_file: Option<String>,
+}
+
+impl SnapshotInfo {
+    /// Whether the snapshot put any disk into an overlay or memory into a file.
+    pub fn is_external(&self) -> bool {
+        self.memory_file.is_some()
+            || self.disks.iter().any(|d| d.mode == DiskSnapshotMode::External)
+    }
 }
 
+/// Options for [`crate::snapshot::create_snapshot_with_progress`].
 #[derive(Debug, Clone)]
 pub struct CreateSnapshotParams {
     pub name: String,
     pub description: String,
+    pub kind: SnapshotKind,
+    /// Per-disk choices; disks not listed get the kind's default.
+    pub disks: Vec<SnapshotDisk>,
+    /// File for a running VM's memory in an external snapshot. `None` puts
+    /// it next to the VM's first disk.
+    pub memory_file: Option<String>,
+    /// Have the guest agent flush the guest's filesystems first. Disk-only
+    /// snapshots of running VMs only.
+    pub quiesce: bool,
+    /// Either every disk is snapshotted or none is.
+    pub atomic: bool,
+}
+
+impl CreateSnapshotParams {
+    /// An atomic internal snapshot of every disk.
+    pub fn new(name: &str, description: &str) -> Self {
+        CreateSnapshotParams {
+            name: name.to_string(),
+            description: description.to_string(),
+            kind: SnapshotKind::Internal,
+            disks: Vec::new(),
+            memory_file: None,
+            quiesce: false,
+            atomic: true,
+        }
+    }
+}
+
+/// Part of a VM's configuration, for grouping [`ConfigChange`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigSection {
+    General,
+    Cpu,
+    Memory,
+    Disks,
+    Network,
+    Devices,
+}
+
+impl ConfigSection {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConfigSection::General => "General",
+            ConfigSection::Cpu => "CPU",
+            ConfigSection::Memory => "Memory",
+            ConfigSection::Disks => "Disks",
+            ConfigSection::Network => "Network",
+            ConfigSection::Devices => "Devices",
+        }
+    }
+}
+
+/// One difference between two configurations of a VM, as found by
+/// [`crate::config_diff::diff_details`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigChange {
+    pub section: ConfigSection,
+    /// What changed, such as "vCPUs" or "/dev/vda".
+    pub item: String,
+    /// `None` when the item was added.
+    pub before: Option<String>,
+    /// `None` when the item was removed.
+    pub after: Option<String>,
 }
 
 // --- Performance Monitoring Types ---
@@ -138,6 +279,7 @@ impl fmt::Display for PoolState {
     }
 }
 
+/// Summary of a storage pool, as returned by [`crate::storage::list_all_pools`].
 #[derive(Debug, Clone)]
 pub struct PoolInfo {
     pub name: String,
@@ -202,6 +344,7 @@ impl fmt::Display for VolumeType {
     }
 }
 
+/// A volume inside a storage pool.
 #[derive(Debug, Clone)]
 pub struct VolumeInfo {
     pub name: String,
@@ -211,6 +354,163 @@ pub struct VolumeInfo {
     pub allocation: u64,
 }
 
+/// A file a volume's contents are layered on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackingStore {
+    pub path: String,
+    /// Empty when libvirt does not know it.
+    pub format: String,
+}
+
+/// Everything the pool view shows about one volume.
+#[derive(Debug, Clone)]
+pub struct VolumeDetails {
+    pub name: String,
+    pub path: String,
+    pub kind: VolumeType,
+    pub format: String,
+    pub capacity: u64,
+    pub allocation: u64,
+    /// Backing files, nearest first.
+    pub backing_chain: Vec<BackingStore>,
+    /// Names of the VMs with a disk on the volume.
+    pub used_by: Vec<String>,
+}
+
+/// How a volume's data is overwritten when it is wiped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WipeAlgorithm {
+    Zero,
+    Random,
+    Nnsa,
+    Dod,
+    Bsi,
+    Gutmann,
+    Schneier,
+    Pfitzner7,
+    Pfitzner33,
+    Trim,
+}
+
+impl WipeAlgorithm {
+    pub fn to_libvirt(self) -> u32 {
+        match self {
+            WipeAlgorithm::Zero => 0,
+            WipeAlgorithm::Random => 8,
+            WipeAlgorithm::Nnsa => 1,
+            WipeAlgorithm::Dod => 2,
+            WipeAlgorithm::Bsi => 3,
+            WipeAlgorithm::Gutmann => 4,
+            WipeAlgorithm::Schneier => 5,
+            WipeAlgorithm::Pfitzner7 => 6,
+            WipeAlgorithm::Pfitzner33 => 7,
+            WipeAlgorithm::Trim => 9,
+        }
+    }
+
+    /// Name as the CLI accepts it.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WipeAlgorithm::Zero => "zero",
+            WipeAlgorithm::Random => "random",
+            WipeAlgorithm::Nnsa => "nnsa",
+            WipeAlgorithm::Dod => "dod",
+            WipeAlgorithm::Bsi => "bsi",
+            WipeAlgorithm::Gutmann => "gutmann",
+            WipeAlgorithm::Schneier => "schneier",
+            WipeAlgorithm::Pfitzner7 => "pfitzner7",
+            WipeAlgorithm::Pfitzner33 => "pfitzner33",
+            WipeAlgorithm::Trim => "trim",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|a| a.as_str() == s)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WipeAlgorithm::Zero => "Zeroes (1 pass)",
+            WipeAlgorithm::Random => "Random data (1 pass)",
+            WipeAlgorithm::Nnsa => "NNSA NAP-14.1-C (4 passes)",
+            WipeAlgorithm::Dod => "DoD 5220.22-M (4 passes)",
+            WipeAlgorithm::Bsi => "BSI (9 passes)",
+            WipeAlgorithm::Gutmann => "Gutmann (35 passes)",
+            WipeAlgorithm::Schneier => "Schneier (7 passes)",
+            WipeAlgorithm::Pfitzner7 => "Pfitzner (7 random passes)",
+            WipeAlgorithm::Pfitzner33 => "Pfitzner (33 random passes)",
+            WipeAlgorithm::Trim => "Discard (TRIM)",
+        }
+    }
+
+    pub const ALL: &[WipeAlgorithm] = &[
+        WipeAlgorithm::Zero,
+        WipeAlgorithm::Random,
+        WipeAlgorithm::Nnsa,
+        WipeAlgorithm::Dod,
+        WipeAlgorithm::Bsi,
+        WipeAlgorithm::Gutmann,
+        WipeAlgorithm::Schneier,
+        WipeAlgorithm::Pfitzner7,
+        WipeAlgorithm::Pfitzner33,
+        WipeAlgorithm::Trim,
+    ];
+}
+
+/// On-disk format of an image file, as told by its header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Raw,
+    Qcow2,
+    Vmdk,
+    Vdi,
+    Vhdx,
+    /// Dynamic VHD (Virtual PC).
+    Vpc,
+}
+
+impl ImageFormat {
+    /// The name libvirt and qemu-img use for the format.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImageFormat::Raw => "raw",
+            ImageFormat::Qcow2 => "qcow2",
+            ImageFormat::Vmdk => "vmdk",
+            ImageFormat::Vdi => "vdi",
+            ImageFormat::Vhdx => "vhdx",
+            ImageFormat::Vpc => "vpc",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ImageFormat::Raw => "Raw",
+            ImageFormat::Qcow2 => "qcow2",
+            ImageFormat::Vmdk => "VMware VMDK",
+            ImageFormat::Vdi => "VirtualBox VDI",
+            ImageFormat::Vhdx => "Hyper-V VHDX",
+            ImageFormat::Vpc => "Virtual PC VHD",
+        }
+    }
+
+    /// Whether the format comes from another hypervisor and is best
+    /// converted to qcow2 before use.
+    pub fn is_foreign(&self) -> bool {
+        !matches!(self, ImageFormat::Raw | ImageFormat::Qcow2)
+    }
+
+    /// Whether a libvirt storage pool can hold images in this format.
+    pub fn libvirt_supported(&self) -> bool {
+        !matches!(self, ImageFormat::Vhdx)
+    }
+}
+
+impl fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct PoolCreateParams {
     pub target_path: String,
@@ -290,7 +590,7 @@ impl ForwardMode {
         }
     }
 
-    pub fn from_str(s: &str) -> Self {
+    pub fn from_name(s: &str) -> Self {
         match s {
             "nat" => ForwardMode::Nat,
             "route" => ForwardMode::Route,
@@ -325,6 +625,8 @@ impl fmt::Display for ForwardMode {
     }
 }
 
+/// Summary of a libvirt virtual network, as returned by
+/// [`crate::network::list_all_networks`].
 #[derive(Debug, Clone)]
 pub struct VirtNetworkInfo {
     pub name: String,
@@ -365,6 +667,7 @@ pub struct NetworkCreateParams {
 
 // --- Host Info Types ---
 
+/// Hypervisor and hardware facts about the host behind a connection.
 #[derive(Debug, Clone)]
 pub struct HostInfo {
     pub hostname: String,
@@ -380,6 +683,76 @@ pub struct HostInfo {
     pub memory_kib: u64,
 }
 
+// --- Event Types ---
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostEventKind {
+    DomainLifecycle,
+    DomainReboot,
+    DomainDeviceAdded,
+    DomainDeviceRemoved,
+    PoolLifecycle,
+    PoolRefresh,
+    NetworkLifecycle,
+}
+
+impl HostEventKind {
+    pub fn is_domain(&self) -> bool {
+        matches!(
+            self,
+            HostEventKind::DomainLifecycle
+                | HostEventKind::DomainReboot
+                | HostEventKind::DomainDeviceAdded
+                | HostEventKind::DomainDeviceRemoved
+        )
+    }
+}
+
+/// A libvirt event forwarded from the event loop thread.
+#[derive(Debug, Clone)]
+pub struct HostEvent {
+    pub uri: String,
+    pub kind: HostEventKind,
+    /// UUID of the domain, pool or network the event is about.
+    pub uuid: String,
+}
+
+// --- Job Types ---
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobStatus {
+    Running,
+    Succeeded,
+    Failed(String),
+    Cancelled,
+}
+
+/// A long-running operation as shown in the jobs list, see [`crate::jobs`].
+#[derive(Debug, Clone)]
+pub struct JobInfo {
+    pub id: u64,
+    pub title: String,
+    /// What the job is busy with right now ("Disk 1 of 2"), may be empty.
+    pub detail: String,
+    /// Progress in bytes (or whatever the job counts); `total` is 0 while
+    /// it is unknown.
+    pub done: u64,
+    pub total: u64,
+    pub cancellable: bool,
+    pub status: JobStatus,
+}
+
+impl JobInfo {
+    /// Progress from 0.0 to 1.0, if known.
+    pub fn fraction(&self) -> Option<f64> {
+        (self.total > 0).then(|| self.done.min(self.total) as f64 / self.total as f64)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.status == JobStatus::Running
+    }
+}
+
 // --- Graphics/Video/Sound Types ---
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -398,7 +771,7 @@ impl GraphicsType {
         }
     }
 
-    pub fn from_str(s: &str) -> Self {
+    pub fn from_name(s: &str) -> Self {
         match s {
             "spice" => GraphicsType::Spice,
             "vnc" => GraphicsType::Vnc,
@@ -458,7 +831,7 @@ impl VideoModel {
         }
     }
 
-    pub fn from_str(s: &str) -> Self {
+    pub fn from_name(s: &str) -> Self {
         match s {
             "virtio" => VideoModel::Virtio,
             "qxl" => VideoModel::Qxl,
@@ -524,7 +897,7 @@ impl SoundModel {
         }
     }
 
-    pub fn from_str(s: &str) -> Self {
+    pub fn from_name(s: &str) -> Self {
         match s {
             "ich9" => SoundModel::Ich9,
             "ich6" => SoundModel::Ich6,
@@ -582,7 +955,7 @@ impl TpmModel {
         }
     }
 
-    pub fn from_str(s: &str) -> Self {
+    pub fn from_name(s: &str) -> Self {
         match s {
             "tpm-crb" => TpmModel::Crb,
             "tpm-tis" => TpmModel::Tis,
@@ -709,6 +1082,13 @@ impl DiskFormat {
         }
     }
 
+    pub fn from_name(s: &str) -> Self {
+        match s {
+            "raw" => DiskFormat::Raw,
+            _ => DiskFormat::Qcow2,
+        }
+    }
+
     pub fn extension(&self) -> &'static str {
         match self {
             DiskFormat::Qcow2 => "qcow2",
@@ -726,6 +1106,77 @@ impl DiskFormat {
     pub const ALL: &[DiskFormat] = &[DiskFormat::Qcow2, DiskFormat::Raw];
 }
 
+/// Bus the system disk of a new VM is attached to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiskBus {
+    Virtio,
+    Sata,
+    Scsi,
+    Ide,
+}
+
+impl DiskBus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiskBus::Virtio => "virtio",
+            DiskBus::Sata => "sata",
+            DiskBus::Scsi => "scsi",
+            DiskBus::Ide => "ide",
+        }
+    }
+
+    pub fn from_name(s: &str) -> Self {
+        match s {
+            "sata" => DiskBus::Sata,
+            "scsi" => DiskBus::Scsi,
+            "ide" => DiskBus::Ide,
+            _ => DiskBus::Virtio,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DiskBus::Virtio => "VirtIO",
+            DiskBus::Sata => "SATA",
+            DiskBus::Scsi => "SCSI",
+            DiskBus::Ide => "IDE",
+        }
+    }
+
+    /// Device name of the first disk on this bus ("vda", "sda", "hda").
+    pub fn first_target(&self) -> &'static str {
+        match self {
+            DiskBus::Virtio => "vda",
+            DiskBus::Sata | DiskBus::Scsi => "sda",
+            DiskBus::Ide => "hda",
+        }
+    }
+
+    pub const ALL: &[DiskBus] = &[DiskBus::Virtio, DiskBus::Sata, DiskBus::Scsi, DiskBus::Ide];
+}
+
+/// Where the operating system of a new VM comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstallSource {
+    /// Blank disk and no installer (network boot or media attached later).
+    None,
+    /// Blank disk plus an installer ISO on a CD-ROM, booted first.
+    Iso(String),
+    /// An existing disk image used as the system disk; no volume is created.
+    ImportDisk(String),
+    /// A cloud image used as the read-only base of a new qcow2 overlay.
+    CloudImage(String),
+}
+
+impl InstallSource {
+    pub fn iso_path(&self) -> Option<&str> {
+        match self {
+            InstallSource::Iso(path) => Some(path),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FirmwareType {
     Bios,
@@ -740,7 +1191,7 @@ impl FirmwareType {
         }
     }
 
-    pub fn from_str(s: &str) -> Self {
+    pub fn from_name(s: &str) -> Self {
         match s {
             "efi" => FirmwareType::Efi,
             _ => FirmwareType::Bios,
@@ -781,7 +1232,7 @@ impl BootDevice {
         }
     }
 
-    pub fn from_str(s: &str) -> Option<Self> {
+    pub fn from_name(s: &str) -> Option<Self> {
         match s {
             "hd" => Some(BootDevice::Hd),
             "cdrom" => Some(BootDevice::Cdrom),
@@ -830,7 +1281,7 @@ impl CpuMode {
         }
     }
 
-    pub fn from_str(s: &str) -> Self {
+    pub fn from_name(s: &str) -> Self {
         match s {
             "host-passthrough" => CpuMode::HostPassthrough,
             "host-model" => CpuMode::HostModel,
@@ -879,6 +1330,7 @@ pub const CPU_MODELS: &[&str] = &[
 
 #[derive(Debug, Clone)]
 pub struct NewDiskParams {
+    /// Image path, or with `create_new` the name of the volume to create.
     pub source_file: String,
     pub target_dev: String,
     pub bus: String,
@@ -895,6 +1347,7 @@ pub struct NewNetworkParams {
     pub mac_address: Option<String>,
 }
 
+/// The fields edited on the General page of the configuration window.
 #[derive(Debug, Clone)]
 pub struct ConfigChanges {
     pub vcpus: u32,
@@ -906,6 +1359,9 @@ pub struct ConfigChanges {
     pub firmware: FirmwareType,
 }
 
+/// A single edit to a domain definition. Each variant maps onto one
+/// `domain_xml::modify_*`/`add_*`/`remove_*` transformation (or, for
+/// `SetAutostart`, a libvirt call) applied to the persistent config.
 #[derive(Debug, Clone)]
 pub enum ConfigAction {
     ApplyGeneral(ConfigChanges),
@@ -921,6 +1377,8 @@ pub enum ConfigAction {
     EjectCdrom(String),
     InsertCdrom(String, String),
     ChangeDiskImage(String, String), // (target_dev, new_image_path)
+    ResizeDisk(String, u64, bool),   // (target_dev, bytes to add, grow guest filesystems)
+    ConsolidateDisk(String, ChainMerge), // (target_dev, how)
     ApplyCpuTune(CpuTune),
     ModifyTpm(TpmModel),
     AddFilesystem(FilesystemInfo),
@@ -946,11 +1404,15 @@ pub enum ConfigAction {
     ModifyMemballoon(MemballoonModel),
 }
 
+/// Coarse domain state, collapsed from `virDomainState`. `Saved` stands
+/// for a shut-off domain with a managed save image.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum VmState {
     Running,
     Paused,
     Shutoff,
+    /// Shut off with a managed save image; starting resumes it.
+    Saved,
     Crashed,
     PmSuspended,
     Other,
@@ -973,6 +1435,7 @@ impl VmState {
             VmState::Running => "status-running",
             VmState::Paused => "status-paused",
             VmState::Shutoff => "status-shutoff",
+            VmState::Saved => "status-saved",
             VmState::Crashed => "status-crashed",
             VmState::PmSuspended => "status-paused",
             VmState::Other => "status-shutoff",
@@ -984,6 +1447,7 @@ impl VmState {
             VmState::Running => "Running",
             VmState::Paused => "Paused",
             VmState::Shutoff => "Shutoff",
+            VmState::Saved => "Saved",
             VmState::Crashed => "Crashed",
             VmState::PmSuspended => "Suspended",
             VmState::Other => "Unknown",
@@ -1001,6 +1465,8 @@ impl fmt::Display for VmState {
     }
 }
 
+/// One row of the VM list: the cheap per-domain summary returned by
+/// [`crate::connection::list_all_vms`].
 #[derive(Debug, Clone)]
 pub struct VmInfo {
     pub name: String,
@@ -1009,6 +1475,9 @@ pub struct VmInfo {
     pub vcpus: u32,
     pub memory_kib: u64,
     pub id: Option<u32>,
+    /// Marked as a template: kept shut off and only used to instantiate
+    /// linked clones.
+    pub is_template: bool,
 }
 
 impl VmInfo {
@@ -1017,6 +1486,9 @@ impl VmInfo {
     }
 
     pub fn subtitle(&self) -> String {
+        if self.is_template {
+            return format!("Template - {} vCPUs, {} MiB", self.vcpus, self.memory_mib());
+        }
         match self.state {
             VmState::Running => format!("{} - {} vCPUs, {} MiB", self.state, self.vcpus, self.memory_mib()),
             _ => self.state.to_string(),
@@ -1024,12 +1496,156 @@ impl VmInfo {
     }
 }
 
+/// What grustyvman records in a template's `<metadata>`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TemplateInfo {
+    pub description: String,
+    /// When the VM was turned into a template, in seconds since the epoch.
+    pub created: u64,
+}
+
+/// Where the data of a disk lives, as far as copying it is concerned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiskSource {
+    /// An image file or block device.
+    Path(String),
+    /// A volume referenced by pool and name (`<disk type="volume">`).
+    Volume { pool: String, volume: String },
+}
+
+/// What a clone gets as its UEFI variable store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NvramClone {
+    /// The source's, keeping boot entries and enrolled keys. libvirt copies
+    /// it on the clone's first start, so the source must have booted once.
+    Copy,
+    /// A fresh one from the firmware's template, as after an install.
+    Reset,
+}
+
+/// Options for [`crate::domain::clone_domain_with_progress`].
+#[derive(Debug, Clone)]
+pub struct CloneParams {
+    pub new_name: String,
+    /// Copy the disks instead of overlaying them.
+    pub full_clone: bool,
+    pub nvram: NvramClone,
+}
+
+impl CloneParams {
+    /// A clone with fresh NVRAM and TPM state.
+    pub fn new(new_name: &str, full_clone: bool) -> Self {
+        CloneParams {
+            new_name: new_name.to_string(),
+            full_clone,
+            nvram: NvramClone::Reset,
+        }
+    }
+}
+
+/// How a VM moves to another host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationMode {
+    /// Memory is copied while the guest keeps running.
+    Live,
+    /// Only the definition moves; the VM does not run on the way.
+    Offline,
+}
+
+impl MigrationMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MigrationMode::Live => "Live",
+            MigrationMode::Offline => "Offline (definition only)",
+        }
+    }
+
+    pub const ALL: &[MigrationMode] = &[MigrationMode::Live, MigrationMode::Offline];
+}
+
+/// Options for [`crate::migration::migrate_vm_with_progress`].
+#[derive(Debug, Clone)]
+pub struct MigrateParams {
+    /// Connection URI of the destination host.
+    pub dest_uri: String,
+    pub mode: MigrationMode,
+    /// Copy the disks too, for hosts that do not share storage.
+    pub copy_storage: bool,
+    /// Let the source libvirtd connect to the destination itself, so
+    /// `dest_uri` must be reachable from the source host. Otherwise this
+    /// client drives both ends.
+    pub peer_to_peer: bool,
+    /// Define the VM on the destination, not just run it there.
+    pub persistent: bool,
+    /// Remove the source's definition once the VM moved.
+    pub undefine_source: bool,
+    /// Bandwidth limit in MiB/s.
+    pub bandwidth_mib: Option<u64>,
+    pub compressed: bool,
+}
+
+impl MigrateParams {
+    /// A live migration over shared storage that keeps the VM defined on
+    /// both hosts.
+    pub fn new(dest_uri: &str) -> Self {
+        MigrateParams {
+            dest_uri: dest_uri.to_string(),
+            mode: MigrationMode::Live,
+            copy_storage: false,
+            peer_to_peer: false,
+            persistent: true,
+            undefine_source: false,
+            bandwidth_mib: None,
+            compressed: false,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DiskInfo {
     pub target_dev: String,
     pub source_file: Option<String>,
     pub bus: String,
     pub device_type: String,
+    /// The images below `source_file`, nearest first, as libvirt reports
+    /// them in `<backingStore>` for running VMs.
+    pub backing_chain: Vec<String>,
+}
+
+/// How [`crate::blockjob::consolidate_disk_with_progress`] flattens a
+/// disk's backing chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainMerge {
+    /// Copy the backing images' data up into the image the VM writes to,
+    /// which then stands alone. The backing images are kept.
+    Pull,
+    /// Merge the overlays down into the bottom image, which the VM then
+    /// writes to. The overlays are deleted.
+    Commit,
+}
+
+impl ChainMerge {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ChainMerge::Pull => "Pull into Top Image",
+            ChainMerge::Commit => "Commit into Base Image",
+        }
+    }
+
+    pub const ALL: &[ChainMerge] = &[ChainMerge::Pull, ChainMerge::Commit];
+}
+
+/// What happened inside the guest after a disk was grown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuestResize {
+    /// Growing the filesystems was not asked for, or the VM was shut off.
+    NotRequested,
+    /// The guest agent did not answer.
+    NoAgent,
+    /// These mountpoints were grown to fill the disk.
+    Grown(Vec<String>),
+    /// The disk grew, but a partition or filesystem on it did not.
+    Failed(String),
 }
 
 #[derive(Debug, Clone)]
@@ -1238,7 +1854,7 @@ impl WatchdogModel {
         }
     }
 
-    pub fn from_str(s: &str) -> Self {
+    pub fn from_name(s: &str) -> Self {
         match s {
             "i6300esb" => WatchdogModel::I6300esb,
             "ib700" => WatchdogModel::Ib700,
@@ -1286,7 +1902,7 @@ impl WatchdogAction {
         }
     }
 
-    pub fn from_str(s: &str) -> Self {
+    pub fn from_name(s: &str) -> Self {
         match s {
             "reset" => WatchdogAction::Reset,
             "shutdown" => WatchdogAction::Shutdown,
@@ -1423,7 +2039,7 @@ impl PanicModel {
         }
     }
 
-    pub fn from_str(s: &str) -> Self {
+    pub fn from_name(s: &str) -> Self {
         match s {
             "hyperv" => PanicModel::Hyperv,
             "isa" => PanicModel::Isa,
@@ -1483,7 +2099,7 @@ impl SmartcardMode {
         }
     }
 
-    pub fn from_str(s: &str) -> Self {
+    pub fn from_name(s: &str) -> Self {
         match s {
             "passthrough" => SmartcardMode::Passthrough,
             "host" => SmartcardMode::Host,
@@ -1518,7 +2134,7 @@ impl MemballoonModel {
         }
     }
 
-    pub fn from_str(s: &str) -> Self {
+    pub fn from_name(s: &str) -> Self {
         match s {
             "virtio" => MemballoonModel::Virtio,
             _ => MemballoonModel::None,
@@ -1557,6 +2173,8 @@ impl ChannelInfo {
 
 // --- Domain Details ---
 
+/// Everything the details and configuration views show about one domain,
+/// as parsed from its XML by [`crate::domain_xml::parse_domain_xml`].
 #[derive(Debug, Clone)]
 pub struct DomainDetails {
     pub name: String,
@@ -0,0 +1,296 @@
+use crate::snapshot::escape_xml;
+use crate::types::FirmwareType;
+
+/// Volume label of the generated answer-file media. Windows Setup finds
+/// `autounattend.xml` on any removable drive, so the label is cosmetic.
+pub const UNATTEND_VOLUME_ID: &str = "UNATTEND";
+
+/// Drive letters Windows PE may give the virtio-win CD-ROM, depending on how
+/// many other drives come before it.
+const DRIVER_LETTERS: &[char] = &['D', 'E', 'F', 'G'];
+
+/// virtio-win driver directories needed during setup (disk, SCSI, network)
+/// and for a usable first boot (balloon, serial, display).
+const VIRTIO_DRIVERS: &[&str] = &["viostor", "vioscsi", "NetKVM", "Balloon", "vioserial", "qxldod"];
+
+/// Answers for an unattended Windows install, written to `autounattend.xml`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowsUnattendConfig {
+    /// Language tag used for setup, input, system and user locale ("en-US").
+    pub locale: String,
+    /// Windows time zone id ("UTC", "W. Europe Standard Time").
+    pub timezone: String,
+    /// `None` lets setup ask for (or skip) the key and the edition.
+    pub product_key: Option<String>,
+    pub computer_name: String,
+    pub admin_user: String,
+    pub admin_password: String,
+    /// virtio-win subdirectory for this release ("w11", "w10", "2k22"), or
+    /// `None` to install without virtio drivers.
+    pub virtio_driver_dir: Option<String>,
+}
+
+impl Default for WindowsUnattendConfig {
+    fn default() -> Self {
+        Self {
+            locale: "en-US".to_string(),
+            timezone: "UTC".to_string(),
+            product_key: None,
+            computer_name: String::new(),
+            admin_user: "Admin".to_string(),
+            admin_password: String::new(),
+            virtio_driver_dir: None,
+        }
+    }
+}
+
+/// virtio-win directory name for a libosinfo short id, if the ISO ships
+/// drivers for it.
+pub fn virtio_driver_dir(short_id: &str) -> Option<&'static str> {
+    match short_id {
+        "win11" => Some("w11"),
+        "win10" => Some("w10"),
+        "win8.1" => Some("w8.1"),
+        "win7" => Some("w7"),
+        "win2k25" => Some("2k25"),
+        "win2k22" => Some("2k22"),
+        "win2k19" => Some("2k19"),
+        "win2k16" => Some("2k16"),
+        _ => None,
+    }
+}
+
+impl WindowsUnattendConfig {
+    /// The answer file. The system disk is wiped and partitioned for
+    /// `firmware`: GPT with EFI and MSR partitions, or MBR with a System
+    /// Reserved partition.
+    pub fn autounattend_xml(&self, firmware: FirmwareType) -> String {
+        let locale = escape_xml(&self.locale);
+        let timezone = escape_xml(&self.timezone);
+        let computer_name = if self.computer_name.is_empty() {
+            "*".to_string()
+        } else {
+            escape_xml(&self.computer_name)
+        };
+        let admin_user = escape_xml(&self.admin_user);
+        let admin_password = escape_xml(&self.admin_password);
+
+        let product_key = match self.product_key.as_deref().map(str::trim) {
+            Some(key) if !key.is_empty() => format!(
+                r#"
+        <ProductKey>
+          <Key>{}</Key>
+          <WillShowUI>OnError</WillShowUI>
+        </ProductKey>"#,
+                escape_xml(key)
+            ),
+            _ => String::new(),
+        };
+
+        let (partitions, install_partition) = match firmware {
+            FirmwareType::Efi => (
+                r#"
+          <CreatePartitions>
+            <CreatePartition wcm:action="add">
+              <Order>1</Order>
+              <Type>EFI</Type>
+              <Size>260</Size>
+            </CreatePartition>
+            <CreatePartition wcm:action="add">
+              <Order>2</Order>
+              <Type>MSR</Type>
+              <Size>16</Size>
+            </CreatePartition>
+            <CreatePartition wcm:action="add">
+              <Order>3</Order>
+              <Type>Primary</Type>
+              <Extend>true</Extend>
+            </CreatePartition>
+          </CreatePartitions>
+          <ModifyPartitions>
+            <ModifyPartition wcm:action="add">
+              <Order>1</Order>
+              <PartitionID>1</PartitionID>
+              <Format>FAT32</Format>
+              <Label>System</Label>
+            </ModifyPartition>
+            <ModifyPartition wcm:action="add">
+              <Order>2</Order>
+              <PartitionID>3</PartitionID>
+              <Format>NTFS</Format>
+              <Label>Windows</Label>
+              <Letter>C</Letter>
+            </ModifyPartition>
+          </ModifyPartitions>"#,
+                3,
+            ),
+            FirmwareType::Bios => (
+                r#"
+          <CreatePartitions>
+            <CreatePartition wcm:action="add">
+              <Order>1</Order>
+              <Type>Primary</Type>
+              <Size>500</Size>
+            </CreatePartition>
+            <CreatePartition wcm:action="add">
+              <Order>2</Order>
+              <Type>Primary</Type>
+              <Extend>true</Extend>
+            </CreatePartition>
+          </CreatePartitions>
+          <ModifyPartitions>
+            <ModifyPartition wcm:action="add">
+              <Order>1</Order>
+              <PartitionID>1</PartitionID>
+              <Format>NTFS</Format>
+              <Label>System Reserved</Label>
+              <Active>true</Active>
+            </ModifyPartition>
+            <ModifyPartition wcm:action="add">
+              <Order>2</Order>
+              <PartitionID>2</PartitionID>
+              <Format>NTFS</Format>
+              <Label>Windows</Label>
+              <Letter>C</Letter>
+            </ModifyPartition>
+          </ModifyPartitions>"#,
+                2,
+            ),
+        };
+
+        let (driver_paths, guest_tools) = match self.virtio_driver_dir.as_deref() {
+            Some(dir) => (driver_paths_component(dir), GUEST_TOOLS_COMMAND),
+            None => (String::new(), ""),
+        };
+
+        format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<unattend xmlns="urn:schemas-microsoft-com:unattend" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State">
+  <settings pass="windowsPE">
+    <component name="Microsoft-Windows-International-Core-WinPE" processorArchitecture="amd64" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS">
+      <SetupUILanguage>
+        <UILanguage>{locale}</UILanguage>
+      </SetupUILanguage>
+      <InputLocale>{locale}</InputLocale>
+      <SystemLocale>{locale}</SystemLocale>
+      <UILanguage>{locale}</UILanguage>
+      <UserLocale>{locale}</UserLocale>
+    </component>{driver_paths}
+    <component name="Microsoft-Windows-Setup" processorArchitecture="amd64" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS">
+      <DiskConfiguration>
+        <Disk wcm:action="add">
+          <DiskID>0</DiskID>
+          <WillWipeDisk>true</WillWipeDisk>{partitions}
+        </Disk>
+      </DiskConfiguration>
+      <ImageInstall>
+        <OSImage>
+          <InstallTo>
+            <DiskID>0</DiskID>
+            <PartitionID>{install_partition}</PartitionID>
+          </InstallTo>
+        </OSImage>
+      </ImageInstall>
+      <UserData>
+        <AcceptEula>true</AcceptEula>{product_key}
+      </UserData>
+    </component>
+  </settings>
+  <settings pass="specialize">
+    <component name="Microsoft-Windows-Shell-Setup" processorArchitecture="amd64" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS">
+      <ComputerName>{computer_name}</ComputerName>
+      <TimeZone>{timezone}</TimeZone>
+    </component>
+  </settings>
+  <settings pass="oobeSystem">
+    <component name="Microsoft-Windows-International-Core" processorArchitecture="amd64" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS">
+      <InputLocale>{locale}</InputLocale>
+      <SystemLocale>{locale}</SystemLocale>
+      <UILanguage>{locale}</UILanguage>
+      <UserLocale>{locale}</UserLocale>
+    </component>
+    <component name="Microsoft-Windows-Shell-Setup" processorArchitecture="amd64" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS">
+      <OOBE>
+        <HideEULAPage>true</HideEULAPage>
+        <HideOEMRegistrationScreen>true</HideOEMRegistrationScreen>
+        <HideOnlineAccountScreens>true</HideOnlineAccountScreens>
+        <HideWirelessSetupInOOBE>true</HideWirelessSetupInOOBE>
+        <ProtectYourPC>3</ProtectYourPC>
+      </OOBE>
+      <UserAccounts>
+        <LocalAccounts>
+          <LocalAccount wcm:action="add">
+            <Name>{admin_user}</Name>
+            <Group>Administrators</Group>
+            <Password>
+              <Value>{admin_password}</Value>
+              <PlainText>true</PlainText>
+            </Password>
+          </LocalAccount>
+        </LocalAccounts>
+      </UserAccounts>
+      <AutoLogon>
+        <Enabled>true</Enabled>
+        <LogonCount>1</LogonCount>
+        <Username>{admin_user}</Username>
+        <Password>
+          <Value>{admin_password}</Value>
+          <PlainText>true</PlainText>
+        </Password>
+      </AutoLogon>
+      <FirstLogonCommands>
+        <SynchronousCommand wcm:action="add">
+          <Order>1</Order>
+          <Description>Keep the password from expiring</Description>
+          <CommandLine>cmd /c wmic useraccount where name="{admin_user}" set PasswordExpires=false</CommandLine>
+        </SynchronousCommand>{guest_tools}
+      </FirstLogonCommands>
+    </component>
+  </settings>
+</unattend>
+"#
+        )
+    }
+
+    /// Media holding `autounattend.xml`, to attach as a CD-ROM next to the
+    /// installer.
+    pub fn media_iso(&self, firmware: FirmwareType) -> Vec<u8> {
+        let xml = self.autounattend_xml(firmware);
+        crate::iso9660::build_iso(UNATTEND_VOLUME_ID, &[("autounattend.xml", xml.as_bytes())])
+    }
+}
+
+/// Windows PE loads storage and network drivers from these paths before it
+/// looks for a disk, so setup can install straight onto virtio.
+fn driver_paths_component(dir: &str) -> String {
+    let mut paths = String::new();
+    let mut key = 1;
+    for letter in DRIVER_LETTERS {
+        for driver in VIRTIO_DRIVERS {
+            paths.push_str(&format!(
+                r#"
+        <PathAndCredentials wcm:action="add" wcm:keyValue="{key}">
+          <Path>{letter}:\{driver}\{dir}\amd64</Path>
+        </PathAndCredentials>"#,
+                dir = escape_xml(dir)
+            ));
+            key += 1;
+        }
+    }
+    format!(
+        r#"
+    <component name="Microsoft-Windows-PnpCustomizationsWinPE" processorArchitecture="amd64" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS">
+      <DriverPaths>{paths}
+      </DriverPaths>
+    </component>"#
+    )
+}
+
+/// Installs the guest agent and remaining drivers from the virtio-win ISO,
+/// wherever it got mounted.
+const GUEST_TOOLS_COMMAND: &str = r#"
+        <SynchronousCommand wcm:action="add">
+          <Order>2</Order>
+          <Description>Install virtio-win guest tools</Description>
+          <CommandLine>cmd /c for %d in (D E F G) do if exist %d:\virtio-win-guest-tools.exe start /wait %d:\virtio-win-guest-tools.exe /quiet /norestart</CommandLine>
+        </SynchronousCommand>"#;
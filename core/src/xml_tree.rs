@@ -0,0 +1,498 @@
+use quick_xml::escape::{escape, unescape};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use crate::error::AppError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Element(Element),
+    /// Character data, kept escaped exactly as it appeared in the input.
+    Text(String),
+    CData(String),
+    Comment(String),
+    /// The `<?xml ...?>` declaration or a processing instruction, without
+    /// the surrounding `<?` and `?>`.
+    Pi(String),
+    DocType(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Attr {
+    name: String,
+    /// Escaped value, as written between the quotes.
+    raw: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Element {
+    name: String,
+    attrs: Vec<Attr>,
+    children: Vec<Node>,
+    /// Start tag contents as parsed, reused verbatim until an attribute
+    /// changes so quoting and attribute spacing survive.
+    raw_tag: Option<String>,
+    self_closing: bool,
+    quote: char,
+}
+
+impl Element {
+    pub fn new(name: &str) -> Self {
+        Element {
+            name: name.to_string(),
+            attrs: Vec::new(),
+            children: Vec::new(),
+            raw_tag: None,
+            self_closing: true,
+            quote: '"',
+        }
+    }
+
+    pub fn with_attr(mut self, name: &str, value: &str) -> Self {
+        self.set_attr(name, value);
+        self
+    }
+
+    pub fn with_child(mut self, child: Element) -> Self {
+        self.children.push(Node::Element(child));
+        self
+    }
+
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.set_text(text);
+        self
+    }
+
+    fn from_start(start: &BytesStart, self_closing: bool) -> Result<Self, AppError> {
+        let raw_tag = String::from_utf8_lossy(start).into_owned();
+        let mut attrs = Vec::new();
+        for attr in start.attributes() {
+            let attr = attr.map_err(|e| AppError::Xml(format!("XML parse error: {e}")))?;
+            attrs.push(Attr {
+                name: String::from_utf8_lossy(attr.key.as_ref()).into_owned(),
+                raw: String::from_utf8_lossy(&attr.value).into_owned(),
+            });
+        }
+        let quote = raw_tag
+            .find('=')
+            .and_then(|pos| raw_tag[pos + 1..].trim_start().chars().next())
+            .filter(|c| *c == '\'')
+            .unwrap_or('"');
+        Ok(Element {
+            name: String::from_utf8_lossy(start.name().as_ref()).into_owned(),
+            attrs,
+            children: Vec::new(),
+            raw_tag: Some(raw_tag),
+            self_closing,
+            quote,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // --- Attributes ---
+
+    /// The unescaped value of an attribute.
+    pub fn attr(&self, name: &str) -> Option<String> {
+        self.attrs
+            .iter()
+            .find(|a| a.name == name)
+            .map(|a| unescape(&a.raw).map(|v| v.into_owned()).unwrap_or_else(|_| a.raw.clone()))
+    }
+
+    pub fn attr_is(&self, name: &str, value: &str) -> bool {
+        self.attr(name).as_deref() == Some(value)
+    }
+
+    /// Set an attribute, keeping its position if it already exists.
+    pub fn set_attr(&mut self, name: &str, value: &str) {
+        let raw = escape(value).into_owned();
+        match self.attrs.iter_mut().find(|a| a.name == name) {
+            Some(attr) if attr.raw == raw => return,
+            Some(attr) => attr.raw = raw,
+            None => self.attrs.push(Attr {
+                name: name.to_string(),
+                raw,
+            }),
+        }
+        self.raw_tag = None;
+    }
+
+    pub fn remove_attr(&mut self, name: &str) {
+        let before = self.attrs.len();
+        self.attrs.retain(|a| a.name != name);
+        if self.attrs.len() != before {
+            self.raw_tag = None;
+        }
+    }
+
+    // --- Text ---
+
+    /// The unescaped text content directly inside this element.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for node in &self.children {
+            match node {
+                Node::Text(raw) => match unescape(raw) {
+                    Ok(t) => text.push_str(&t),
+                    Err(_) => text.push_str(raw),
+                },
+                Node::CData(raw) => text.push_str(raw),
+                _ => {}
+            }
+        }
+        text
+    }
+
+    /// Replace all content with a single text node.
+    pub fn set_text(&mut self, text: &str) {
+        self.children = vec![Node::Text(escape(text).into_owned())];
+    }
+
+    // --- Children ---
+
+    pub fn children(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|n| match n {
+            Node::Element(e) => Some(e),
+            _ => None,
+        })
+    }
+
+    pub fn children_mut(&mut self) -> impl Iterator<Item = &mut Element> {
+        self.children.iter_mut().filter_map(|n| match n {
+            Node::Element(e) => Some(e),
+            _ => None,
+        })
+    }
+
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children().filter(move |e| e.name == name)
+    }
+
+    pub fn children_named_mut<'a>(
+        &'a mut self,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a mut Element> {
+        self.children_mut().filter(move |e| e.name == name)
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children().find(|e| e.name == name)
+    }
+
+    pub fn child_mut(&mut self, name: &str) -> Option<&mut Element> {
+        self.children_mut().find(|e| e.name == name)
+    }
+
+    /// The first child called `name`, appended empty if there is none.
+    pub fn ensure_child(&mut self, name: &str) -> &mut Element {
+        if self.child(name).is_none() {
+            self.append(Element::new(name));
+        }
+        self.child_mut(name).expect("child was just appended")
+    }
+
+    /// Text of the first child called `name`.
+    pub fn child_text(&self, name: &str) -> Option<String> {
+        self.child(name).map(Element::text)
+    }
+
+    /// Attribute of the first child called `name`.
+    pub fn child_attr(&self, name: &str, attr: &str) -> Option<String> {
+        self.child(name).and_then(|e| e.attr(attr))
+    }
+
+    /// Add `child` as the last child element, indented like its siblings.
+    pub fn append(&mut self, child: Element) {
+        let at = match self.children.last() {
+            Some(Node::Text(t)) if is_blank(t) => self.children.len() - 1,
+            _ => self.children.len(),
+        };
+        self.insert_at(at, child);
+    }
+
+    /// Add `child` before the first child element called `sibling`, or at
+    /// the end if there is none.
+    pub fn insert_before(&mut self, sibling: &str, child: Element) {
+        let pos = self
+            .children
+            .iter()
+            .position(|n| matches!(n, Node::Element(e) if e.name == sibling));
+        match pos {
+            Some(pos) => {
+                // Step back over the sibling's indentation so the new element
+                // gets the same one.
+                let at = match pos.checked_sub(1).map(|i| &self.children[i]) {
+                    Some(Node::Text(t)) if is_blank(t) => pos - 1,
+                    _ => pos,
+                };
+                self.insert_at(at, child);
+            }
+            None => self.append(child),
+        }
+    }
+
+    fn insert_at(&mut self, at: usize, child: Element) {
+        match self.child_indent() {
+            Some(indent) => {
+                self.children.insert(at, Node::Text(indent));
+                self.children.insert(at + 1, Node::Element(child));
+            }
+            None => self.children.insert(at, Node::Element(child)),
+        }
+    }
+
+    /// The whitespace in front of the first child element.
+    fn child_indent(&self) -> Option<String> {
+        let first = self
+            .children
+            .iter()
+            .position(|n| matches!(n, Node::Element(_)))?;
+        match first.checked_sub(1).map(|i| &self.children[i]) {
+            Some(Node::Text(t)) if is_blank(t) => Some(t.clone()),
+            _ => None,
+        }
+    }
+
+    /// Remove every child element matching `pred`, together with the
+    /// indentation in front of it. Returns how many were removed.
+    pub fn remove_where(&mut self, mut pred: impl FnMut(&Element) -> bool) -> usize {
+        let mut removed = 0;
+        let mut i = 0;
+        while i < self.children.len() {
+            if matches!(&self.children[i], Node::Element(e) if pred(e)) {
+                i = self.remove_at(i);
+                removed += 1;
+            } else {
+                i += 1;
+            }
+        }
+        removed
+    }
+
+    /// Remove the first child element matching `pred`.
+    pub fn remove_first(&mut self, mut pred: impl FnMut(&Element) -> bool) -> bool {
+        let pos = self
+            .children
+            .iter()
+            .position(|n| matches!(n, Node::Element(e) if pred(e)));
+        match pos {
+            Some(pos) => {
+                self.remove_at(pos);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove the node at `i` and the blank text before it; returns the
+    /// index of the node that followed it.
+    fn remove_at(&mut self, i: usize) -> usize {
+        self.children.remove(i);
+        if i > 0 && matches!(&self.children[i - 1], Node::Text(t) if is_blank(t)) {
+            self.children.remove(i - 1);
+            i - 1
+        } else {
+            i
+        }
+    }
+
+    /// Replace the child elements called `name` with `new`: it takes the
+    /// place of the first one, or is appended if there were none. `None`
+    /// just removes them.
+    pub fn replace_child(&mut self, name: &str, new: Option<Element>) {
+        let first = self
+            .children
+            .iter()
+            .position(|n| matches!(n, Node::Element(e) if e.name == name));
+        match (first, new) {
+            (Some(pos), Some(new)) => {
+                self.children[pos] = Node::Element(new);
+                let mut seen_first = false;
+                self.remove_where(|e| e.name == name && std::mem::replace(&mut seen_first, true));
+            }
+            (None, Some(new)) => self.append(new),
+            (_, None) => {
+                self.remove_where(|e| e.name == name);
+            }
+        }
+    }
+
+    // --- Serialisation ---
+
+    fn write(&self, out: &mut String) {
+        out.push('<');
+        match &self.raw_tag {
+            Some(raw) => out.push_str(raw),
+            None => {
+                out.push_str(&self.name);
+                for attr in &self.attrs {
+                    let quote = if attr.raw.contains(self.quote) {
+                        if self.quote == '"' { '\'' } else { '"' }
+                    } else {
+                        self.quote
+                    };
+                    out.push(' ');
+                    out.push_str(&attr.name);
+                    out.push('=');
+                    out.push(quote);
+                    out.push_str(&attr.raw);
+                    out.push(quote);
+                }
+            }
+        }
+        if self.children.is_empty() && self.self_closing {
+            out.push_str("/>");
+            return;
+        }
+        out.push('>');
+        for node in &self.children {
+            node.write(out);
+        }
+        out.push_str("</");
+        out.push_str(&self.name);
+        out.push('>');
+    }
+
+    pub fn to_xml(&self) -> String {
+        let mut out = String::new();
+        self.write(&mut out);
+        out
+    }
+}
+
+impl Node {
+    fn write(&self, out: &mut String) {
+        match self {
+            Node::Element(e) => e.write(out),
+            Node::Text(raw) => out.push_str(raw),
+            Node::CData(raw) => {
+                out.push_str("<![CDATA[");
+                out.push_str(raw);
+                out.push_str("]]>");
+            }
+            Node::Comment(raw) => {
+                out.push_str("<!--");
+                out.push_str(raw);
+                out.push_str("-->");
+            }
+            Node::Pi(raw) => {
+                out.push_str("<?");
+                out.push_str(raw);
+                out.push_str("?>");
+            }
+            Node::DocType(raw) => {
+                out.push_str("<!DOCTYPE ");
+                out.push_str(raw);
+                out.push('>');
+            }
+        }
+    }
+}
+
+fn is_blank(text: &str) -> bool {
+    text.chars().all(char::is_whitespace)
+}
+
+/// A parsed XML document: the root element plus whatever surrounds it.
+///
+/// Every node the parser sees is kept (unknown elements and attributes,
+/// comments, processing instructions, the whitespace between elements), so
+/// serialising an unmodified document reproduces its input byte for byte
+/// and an edit only rewrites the elements it touches.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    prolog: Vec<Node>,
+    root: Element,
+    epilog: Vec<Node>,
+}
+
+impl Document {
+    pub fn parse(xml: &str) -> Result<Self, AppError> {
+        let mut reader = Reader::from_str(xml);
+        reader.config_mut().trim_text(false);
+
+        let mut stack: Vec<Element> = Vec::new();
+        let mut top: Vec<Node> = Vec::new();
+
+        loop {
+            let event = reader
+                .read_event()
+                .map_err(|e| AppError::Xml(format!("XML parse error: {e}")))?;
+            let node = match event {
+                Event::Start(ref e) => {
+                    stack.push(Element::from_start(e, false)?);
+                    continue;
+                }
+                Event::Empty(ref e) => Node::Element(Element::from_start(e, true)?),
+                Event::End(_) => match stack.pop() {
+                    Some(element) => Node::Element(element),
+                    None => return Err(AppError::Xml("XML parse error: unmatched end tag".into())),
+                },
+                Event::Text(ref e) => Node::Text(String::from_utf8_lossy(e).into_owned()),
+                Event::CData(ref e) => Node::CData(String::from_utf8_lossy(e).into_owned()),
+                Event::Comment(ref e) => Node::Comment(String::from_utf8_lossy(e).into_owned()),
+                Event::Decl(ref e) => Node::Pi(String::from_utf8_lossy(e).into_owned()),
+                Event::PI(ref e) => Node::Pi(String::from_utf8_lossy(e).into_owned()),
+                Event::DocType(ref e) => Node::DocType(String::from_utf8_lossy(e).into_owned()),
+                Event::Eof => break,
+            };
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(node),
+                None => top.push(node),
+            }
+        }
+
+        if let Some(open) = stack.last() {
+            return Err(AppError::Xml(format!(
+                "XML parse error: <{}> is never closed",
+                open.name
+            )));
+        }
+
+        let mut roots = top
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| matches!(n, Node::Element(_)))
+            .map(|(i, _)| i);
+        let root_pos = roots
+            .next()
+            .ok_or_else(|| AppError::Xml("XML parse error: no root element".into()))?;
+        if roots.next().is_some() {
+            return Err(AppError::Xml("XML parse error: more than one root element".into()));
+        }
+
+        let epilog = top.split_off(root_pos + 1);
+        let root = match top.pop() {
+            Some(Node::Element(root)) => root,
+            _ => unreachable!("root_pos points at an element"),
+        };
+        Ok(Document {
+            prolog: top,
+            root,
+            epilog,
+        })
+    }
+
+    pub fn root(&self) -> &Element {
+        &self.root
+    }
+
+    pub fn root_mut(&mut self) -> &mut Element {
+        &mut self.root
+    }
+
+    pub fn to_xml(&self) -> String {
+        let mut out = String::new();
+        for node in &self.prolog {
+            node.write(&mut out);
+        }
+        self.root.write(&mut out);
+        for node in &self.epilog {
+            node.write(&mut out);
+        }
+        out
+    }
+}
@@ -0,0 +1,63 @@
+// Backing chains of running VMs' disks and where overlays get committed.
+
+use grustyvman_core::blockjob::commit_target;
+use grustyvman_core::domain_xml::parse_domain_xml;
+
+const RUNNING_VM: &str = "<domain type='kvm' id='3'>
+  <name>db</name>
+  <uuid>0f5e2c1a-3b4d-4e6f-8a9b-1c2d3e4f5a6b</uuid>
+  <memory unit='KiB'>2097152</memory>
+  <vcpu>2</vcpu>
+  <os><type arch='x86_64' machine='q35'>hvm</type></os>
+  <devices>
+    <disk type='file' device='disk'>
+      <driver name='qemu' type='qcow2'/>
+      <source file='/images/db.after-upgrade' index='3'/>
+      <backingStore type='file' index='2'>
+        <format type='qcow2'/>
+        <source file='/images/db.before-upgrade'/>
+        <backingStore type='file' index='1'>
+          <format type='qcow2'/>
+          <source file='/images/db.qcow2'/>
+          <backingStore/>
+        </backingStore>
+      </backingStore>
+      <target dev='vda' bus='virtio'/>
+    </disk>
+    <disk type='file' device='disk'>
+      <driver name='qemu' type='raw'/>
+      <source file='/images/scratch.img' index='4'/>
+      <backingStore/>
+      <target dev='vdb' bus='virtio'/>
+    </disk>
+  </devices>
+</domain>";
+
+fn chain() -> Vec<String> {
+    ["/images/db.after-upgrade", "/images/db.before-upgrade", "/images/db.qcow2"]
+        .map(str::to_string)
+        .to_vec()
+}
+
+#[test]
+fn backing_chain_is_read_nearest_first() {
+    let details = parse_domain_xml(RUNNING_VM).unwrap();
+    assert_eq!(details.disks[0].backing_chain, chain()[1..]);
+    assert!(details.disks[1].backing_chain.is_empty());
+}
+
+#[test]
+fn commit_target_is_the_image_below() {
+    let chain = chain();
+    // The image the VM writes to needs an active commit
+    assert_eq!(
+        commit_target(&chain, "/images/db.after-upgrade"),
+        Some(("/images/db.before-upgrade", true))
+    );
+    assert_eq!(
+        commit_target(&chain, "/images/db.before-upgrade"),
+        Some(("/images/db.qcow2", false))
+    );
+    assert_eq!(commit_target(&chain, "/images/db.qcow2"), None);
+    assert_eq!(commit_target(&chain, "/images/elsewhere.qcow2"), None);
+}
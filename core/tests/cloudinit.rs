@@ -0,0 +1,106 @@
+// NoCloud user-data rendering and the seed ISO writer.
+
+use grustyvman_core::cloudinit::{self, CloudInitConfig, CloudInitUser};
+use grustyvman_core::{iso9660, osinfo};
+
+fn config() -> CloudInitConfig {
+    CloudInitConfig {
+        hostname: "web-1".to_string(),
+        users: vec![CloudInitUser {
+            name: "deploy".to_string(),
+            ssh_authorized_keys: vec!["ssh-ed25519 AAAAC3Nza deploy@ci".to_string()],
+            sudo: true,
+        }],
+        ssh_authorized_keys: vec!["ssh-ed25519 AAAAC3Nzb me@laptop".to_string()],
+        packages: vec!["nginx".to_string(), "qemu-guest-agent".to_string()],
+        runcmd: vec!["systemctl enable --now nginx".to_string()],
+    }
+}
+
+/// Sector `lba` of an image.
+fn sector(iso: &[u8], lba: usize) -> &[u8] {
+    &iso[lba * 2048..(lba + 1) * 2048]
+}
+
+// --- user-data ---
+
+#[test]
+fn user_data_is_cloud_config() {
+    let expected = "\
+#cloud-config
+hostname: \"web-1\"
+ssh_authorized_keys:
+  - \"ssh-ed25519 AAAAC3Nzb me@laptop\"
+users:
+  - default
+  - name: \"deploy\"
+    shell: /bin/bash
+    sudo: \"ALL=(ALL) NOPASSWD:ALL\"
+    ssh_authorized_keys:
+      - \"ssh-ed25519 AAAAC3Nza deploy@ci\"
+package_update: true
+packages:
+  - \"nginx\"
+  - \"qemu-guest-agent\"
+runcmd:
+  - \"systemctl enable --now nginx\"
+";
+    assert_eq!(config().user_data(), expected);
+}
+
+#[test]
+fn empty_config_only_has_header() {
+    assert_eq!(CloudInitConfig::default().user_data(), "#cloud-config\n");
+}
+
+#[test]
+fn user_data_quotes_yaml_specials() {
+    let cfg = CloudInitConfig {
+        runcmd: vec!["echo \"a\\b\" # not a comment".to_string(), "yes".to_string()],
+        ..Default::default()
+    };
+    let data = cfg.user_data();
+    assert!(data.contains("  - \"echo \\\"a\\\\b\\\" # not a comment\"\n"));
+    assert!(data.contains("  - \"yes\"\n"));
+}
+
+#[test]
+fn meta_data_falls_back_to_vm_name() {
+    let meta = CloudInitConfig::default().meta_data("db-2");
+    assert!(meta.starts_with("instance-id: \"iid-db-2-"));
+    assert!(meta.ends_with("local-hostname: \"db-2\"\n"));
+}
+
+// --- Seed ISO ---
+
+#[test]
+fn seed_iso_is_labelled_cidata() {
+    let iso = config().seed_iso("web-1");
+    assert_eq!(iso.len() % 2048, 0);
+    let label = osinfo::iso_volume_label(sector(&iso, 16));
+    assert_eq!(label.as_deref(), Some(cloudinit::SEED_VOLUME_ID));
+    // Joliet supplementary descriptor, then the set terminator
+    assert_eq!(&sector(&iso, 17)[..6], b"\x02CD001");
+    assert_eq!(&sector(&iso, 17)[88..91], b"%/E");
+    assert_eq!(&sector(&iso, 18)[..6], b"\xffCD001");
+}
+
+#[test]
+fn seed_iso_directories_list_every_file() {
+    let files: &[(&str, &[u8])] = &[("user-data", b"#cloud-config\n"), ("meta-data", b"")];
+    let iso = iso9660::build_iso(cloudinit::SEED_VOLUME_ID, files);
+    let volume_sectors = u32::from_le_bytes(sector(&iso, 16)[80..84].try_into().unwrap());
+    assert_eq!(volume_sectors as usize * 2048, iso.len());
+
+    let root_lba = |vd: &[u8]| u32::from_le_bytes(vd[158..162].try_into().unwrap()) as usize;
+    let primary = sector(&iso, root_lba(sector(&iso, 16)));
+    let joliet = sector(&iso, root_lba(sector(&iso, 17)));
+    let contains = |hay: &[u8], needle: &[u8]| hay.windows(needle.len()).any(|w| w == needle);
+
+    assert!(contains(primary, b"USER_DAT.;1"));
+    assert!(contains(primary, b"META_DAT.;1"));
+    let ucs2 = |s: &str| -> Vec<u8> { s.encode_utf16().flat_map(u16::to_be_bytes).collect() };
+    assert!(contains(joliet, &ucs2("user-data;1")));
+    assert!(contains(joliet, &ucs2("meta-data;1")));
+    assert!(contains(&iso, b"#cloud-config\n"));
+}
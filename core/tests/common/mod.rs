@@ -0,0 +1,30 @@
+// Fixture loading shared by the domain XML test suites.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub fn fixture_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/domain")
+}
+
+/// Contents of one fixture file, e.g. `fixture("q35-efi.xml")`.
+pub fn fixture(name: &str) -> String {
+    fs::read_to_string(fixture_dir().join(name))
+        .unwrap_or_else(|e| panic!("cannot read fixture {name}: {e}"))
+}
+
+/// Every fixture as (file name, contents), sorted by name.
+pub fn fixtures() -> Vec<(String, String)> {
+    let mut all: Vec<(String, String)> = fs::read_dir(fixture_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "xml"))
+        .map(|path| {
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            let xml = fs::read_to_string(&path).unwrap();
+            (name, xml)
+        })
+        .collect();
+    all.sort();
+    all
+}
@@ -0,0 +1,79 @@
+// Differences between two configurations of a VM, as shown when comparing
+// a snapshot with the current state.
+
+mod common;
+
+use grustyvman_core::config_diff::diff_details;
+use grustyvman_core::domain_xml::{
+    modify_domain_xml, modify_sound, parse_domain_xml, remove_disk_device,
+};
+use grustyvman_core::types::{ConfigChange, ConfigSection, SoundModel};
+
+fn change(
+    section: ConfigSection,
+    item: &str,
+    before: Option<&str>,
+    after: Option<&str>,
+) -> ConfigChange {
+    ConfigChange {
+        section,
+        item: item.to_string(),
+        before: before.map(str::to_string),
+        after: after.map(str::to_string),
+    }
+}
+
+#[test]
+fn same_config_has_no_changes() {
+    for (name, xml) in common::fixtures() {
+        let details = parse_domain_xml(&xml).unwrap();
+        assert_eq!(diff_details(&details, &details), [], "{name}");
+    }
+}
+
+#[test]
+fn changes_come_in_section_order() {
+    let xml = common::fixture("q35-efi.xml");
+    let before = parse_domain_xml(&xml).unwrap();
+
+    let xml = modify_sound(&xml, SoundModel::Ac97).unwrap();
+    let xml = remove_disk_device(&xml, "sda").unwrap();
+    let xml = modify_domain_xml(&xml, 6, 4096).unwrap();
+    let after = parse_domain_xml(&xml).unwrap();
+
+    let changes = diff_details(&before, &after);
+    assert_eq!(
+        changes[0],
+        change(ConfigSection::Cpu, "vCPUs", Some("4"), Some("6"))
+    );
+    assert_eq!(
+        changes[1],
+        change(
+            ConfigSection::Memory,
+            "Memory",
+            Some("8192 MiB"),
+            Some("4096 MiB")
+        )
+    );
+    assert_eq!(changes[2].section, ConfigSection::Disks);
+    assert_eq!(changes[2].item, "/dev/sda");
+    assert!(changes[2].before.as_deref().unwrap().contains("CD-ROM"));
+    assert_eq!(changes[2].after, None);
+    assert_eq!(
+        changes.last().unwrap(),
+        &change(ConfigSection::Devices, "Sound", Some("ICH9"), Some("AC97"))
+    );
+}
+
+#[test]
+fn added_devices_have_no_before() {
+    let xml = common::fixture("q35-efi.xml");
+    let with_cdrom = parse_domain_xml(&xml).unwrap();
+    let without = parse_domain_xml(&remove_disk_device(&xml, "sda").unwrap()).unwrap();
+
+    let changes = diff_details(&without, &with_cdrom);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].item, "/dev/sda");
+    assert_eq!(changes[0].before, None);
+    assert!(changes[0].after.is_some());
+}
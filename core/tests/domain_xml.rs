@@ -0,0 +1,1165 @@
+// Behavioural tests for every `domain_xml` transformation, checked by
+// parsing the result back through `parse_domain_xml`.
+
+mod common;
+
+use common::{fixture, fixtures};
+use grustyvman_core::domain_model::DomainXml;
+use grustyvman_core::domain_xml::{self, NewVmParams};
+use grustyvman_core::storage;
+use grustyvman_core::types::{
+    BootDevice, ChangeNetworkSourceParams, ChannelInfo, ControllerInfo, CpuMode, CpuTune, DiskBus,
+    DiskFormat, DiskSource, DomainDetails, FilesystemInfo, FirmwareType, GraphicsType, HostdevInfo, InputInfo,
+    InstallSource, MemballoonModel, NetworkModel, NetworkSourceType, NewDiskParams, NewNetworkParams,
+    NewVmNetworkConfig, PanicModel, RngBackend, SerialInfo, SmartcardMode, SoundModel, TpmModel,
+    VcpuPin, VideoModel, WatchdogAction, WatchdogModel,
+};
+
+fn details(xml: &str) -> DomainDetails {
+    domain_xml::parse_domain_xml(xml).unwrap()
+}
+
+fn q35() -> String {
+    fixture("q35-efi.xml")
+}
+
+fn i440fx() -> String {
+    fixture("i440fx-bios.xml")
+}
+
+fn win11() -> String {
+    fixture("win11-tpm.xml")
+}
+
+fn passthrough() -> String {
+    fixture("passthrough.xml")
+}
+
+fn pci_hostdev(domain: Option<&str>, bus: &str, slot: &str, function: &str) -> HostdevInfo {
+    HostdevInfo {
+        device_type: "pci".to_string(),
+        pci_domain: domain.map(str::to_string),
+        pci_bus: Some(bus.to_string()),
+        pci_slot: Some(slot.to_string()),
+        pci_function: Some(function.to_string()),
+        usb_vendor: None,
+        usb_product: None,
+        display_name: String::new(),
+    }
+}
+
+fn usb_hostdev(vendor: &str, product: &str) -> HostdevInfo {
+    HostdevInfo {
+        device_type: "usb".to_string(),
+        pci_domain: None,
+        pci_bus: None,
+        pci_slot: None,
+        pci_function: None,
+        usb_vendor: Some(vendor.to_string()),
+        usb_product: Some(product.to_string()),
+        display_name: String::new(),
+    }
+}
+
+fn new_vm_params() -> NewVmParams {
+    NewVmParams {
+        name: "new-vm".to_string(),
+        vcpus: 2,
+        memory_mib: 4096,
+        disk_size_gib: 20,
+        disk_format: DiskFormat::Qcow2,
+        install: InstallSource::Iso("/srv/iso/install.iso".to_string()),
+        firmware: FirmwareType::Efi,
+        network: NewVmNetworkConfig::default(),
+        tpm_model: Some(TpmModel::Crb),
+        storage_pool: None,
+        disk_bus: DiskBus::Virtio,
+        video: VideoModel::Virtio,
+        hyperv: false,
+        os_id: None,
+        cloud_init: None,
+        unattend: None,
+        virtio_win_iso: None,
+    }
+}
+
+// --- Parsing ---
+
+#[test]
+fn parse_q35_efi() {
+    let d = details(&q35());
+    assert_eq!(d.name, "fedora-ws");
+    assert_eq!(d.uuid, "5d1c1f1e-7a4b-4d43-9a3e-2f0c6b1d8e42");
+    assert_eq!(d.memory_kib, 8_388_608);
+    assert_eq!(d.vcpus, 4);
+    assert_eq!(d.os_type, "hvm");
+    assert_eq!(d.arch, "x86_64");
+    assert_eq!(d.firmware, FirmwareType::Efi);
+    assert_eq!(d.boot_order, vec![BootDevice::Hd, BootDevice::Cdrom]);
+    assert_eq!(d.cpu_mode, CpuMode::HostPassthrough);
+    assert_eq!(d.cpu_model, None);
+
+    assert_eq!(d.disks.len(), 2);
+    assert_eq!(d.disks[0].target_dev, "vda");
+    assert_eq!(d.disks[0].bus, "virtio");
+    assert_eq!(d.disks[0].device_type, "disk");
+    assert_eq!(d.disks[1].device_type, "cdrom");
+
+    assert_eq!(d.networks.len(), 1);
+    assert_eq!(
+        d.networks[0].mac_address.as_deref(),
+        Some("52:54:00:6b:3c:58")
+    );
+    assert_eq!(d.networks[0].source_network.as_deref(), Some("default"));
+    assert_eq!(d.networks[0].model_type.as_deref(), Some("virtio"));
+
+    let graphics = d.graphics.unwrap();
+    assert!(d.has_graphics);
+    assert_eq!(graphics.graphics_type, GraphicsType::Spice);
+    assert_eq!(graphics.port, Some(5900));
+    assert!(graphics.autoport);
+    assert_eq!(graphics.listen_address.as_deref(), Some("127.0.0.1"));
+
+    let video = d.video.unwrap();
+    assert_eq!(video.model, VideoModel::Virtio);
+    assert_eq!(video.heads, Some(1));
+    assert!(!video.accel3d);
+    assert_eq!(d.sound.unwrap().model, SoundModel::Ich9);
+
+    assert_eq!(d.cpu_tune.vcpu_pins.len(), 2);
+    assert_eq!(d.cpu_tune.vcpu_pins[1].vcpu, 1);
+    assert_eq!(d.cpu_tune.vcpu_pins[1].cpuset, "3");
+    assert_eq!(d.cpu_tune.emulatorpin.as_deref(), Some("0-1"));
+
+    let tpm = d.tpm.unwrap();
+    assert_eq!(tpm.model, TpmModel::Crb);
+    assert_eq!(tpm.version, "2.0");
+
+    assert_eq!(d.serials.len(), 2);
+    assert!(!d.serials[0].is_console);
+    assert!(d.serials[1].is_console);
+    assert_eq!(d.serials[1].target_type, "serial");
+
+    assert_eq!(d.channels.len(), 2);
+    assert_eq!(d.inputs.len(), 3);
+    // pcie-root and sata are system controllers and hidden
+    assert_eq!(
+        d.controllers
+            .iter()
+            .map(|c| c.controller_type.as_str())
+            .collect::<Vec<_>>(),
+        vec!["usb", "virtio-serial"]
+    );
+    assert_eq!(d.usbredirs.len(), 2);
+    assert_eq!(d.usbredirs[1].index, 1);
+    // "itco" is not a model the UI offers
+    assert!(d.watchdog.is_none());
+    assert_eq!(d.rng, Some(RngBackend::Urandom));
+    assert_eq!(d.memballoon, Some(MemballoonModel::Virtio));
+    assert!(d.panic.is_none());
+    assert!(d.smartcard.is_none());
+}
+
+#[test]
+fn parse_i440fx_bios() {
+    let d = details(&i440fx());
+    assert_eq!(d.firmware, FirmwareType::Bios);
+    // unit="MiB" is converted
+    assert_eq!(d.memory_kib, 2048 * 1024);
+    assert_eq!(d.boot_order, vec![BootDevice::Network, BootDevice::Hd]);
+    assert_eq!(d.cpu_mode, CpuMode::Custom);
+    assert_eq!(d.cpu_model.as_deref(), Some("Haswell-noTSX"));
+
+    assert_eq!(d.disks[1].source_file, None);
+    assert_eq!(d.networks[0].interface_type, "bridge");
+    assert_eq!(d.networks[0].source_bridge.as_deref(), Some("br0"));
+    assert_eq!(d.networks[1].interface_type, "direct");
+    assert_eq!(d.networks[1].source_dev.as_deref(), Some("enp3s0"));
+
+    assert_eq!(d.filesystems.len(), 1);
+    assert_eq!(d.filesystems[0].driver, "9p");
+    assert_eq!(d.filesystems[0].accessmode.as_deref(), Some("mapped"));
+    assert_eq!(d.filesystems[0].target_dir, "share");
+
+    assert_eq!(d.hostdevs.len(), 1);
+    assert_eq!(d.hostdevs[0].display_name, "USB 0x046d:0xc52b");
+
+    // <target> without a type defaults to isa-serial
+    assert_eq!(d.serials[0].target_type, "isa-serial");
+    assert_eq!(d.parallels.len(), 1);
+    assert_eq!(d.parallels[0].port, 0);
+    assert_eq!(d.graphics.unwrap().graphics_type, GraphicsType::Vnc);
+    assert_eq!(d.video.as_ref().unwrap().vram, Some(16384));
+    assert_eq!(d.panic, Some(PanicModel::Isa));
+    assert_eq!(d.smartcard, Some(SmartcardMode::Host));
+    assert_eq!(d.memballoon, Some(MemballoonModel::None));
+    assert!(d.tpm.is_none());
+    assert!(d.rng.is_none());
+    assert!(d.sound.is_none());
+    assert!(d.cpu_tune.vcpu_pins.is_empty());
+}
+
+#[test]
+fn parse_windows_with_tpm() {
+    let d = details(&win11());
+    // Legacy UEFI: pflash loader without <os firmware='efi'>
+    assert_eq!(d.firmware, FirmwareType::Efi);
+    assert_eq!(d.boot_order, vec![BootDevice::Cdrom, BootDevice::Hd]);
+    assert_eq!(
+        d.disks.iter().filter(|d| d.device_type == "cdrom").count(),
+        2
+    );
+    assert_eq!(d.tpm.unwrap().model, TpmModel::Crb);
+    assert_eq!(d.video.unwrap().model, VideoModel::Qxl);
+    assert_eq!(d.panic, Some(PanicModel::Hyperv));
+    let watchdog = d.watchdog.unwrap();
+    assert_eq!(watchdog.model, WatchdogModel::I6300esb);
+    assert_eq!(watchdog.action, WatchdogAction::Poweroff);
+    assert_eq!(d.networks[0].model_type.as_deref(), Some("e1000e"));
+    // pcie-root-port is hidden
+    assert_eq!(d.controllers.len(), 2);
+}
+
+#[test]
+fn parse_passthrough() {
+    let d = details(&passthrough());
+    assert_eq!(d.hostdevs.len(), 3);
+    assert_eq!(d.hostdevs[0].device_type, "pci");
+    // The guest-side <address> must not override the host address
+    assert_eq!(d.hostdevs[0].pci_bus.as_deref(), Some("0x0a"));
+    assert_eq!(d.hostdevs[1].pci_function.as_deref(), Some("0x1"));
+    assert_eq!(d.hostdevs[0].display_name, "PCI 0000:0a:00.0");
+    assert_eq!(d.hostdevs[2].usb_vendor.as_deref(), Some("0x1050"));
+    assert_eq!(d.hostdevs[2].pci_bus, None);
+
+    // Block-backed disk has no source file
+    assert_eq!(d.disks[0].source_file, None);
+    assert_eq!(d.networks[1].interface_type, "vdpa");
+    assert_eq!(
+        d.networks[1].source_dev.as_deref(),
+        Some("/dev/vhost-vdpa-0")
+    );
+    assert_eq!(d.filesystems[0].driver, "virtiofs");
+    assert_eq!(d.filesystems[0].source_dir, "/srv/datasets");
+    assert_eq!(d.cpu_tune.vcpu_pins.len(), 6);
+    assert!(d.cpu_tune.emulatorpin.is_none());
+    assert!(!d.has_graphics);
+    assert_eq!(d.video.unwrap().model, VideoModel::None);
+    assert_eq!(d.rng, Some(RngBackend::Random));
+    assert_eq!(d.serials.len(), 3);
+    // pcie-to-pci-bridge is hidden, scsi is not
+    assert_eq!(d.controllers.len(), 2);
+}
+
+#[test]
+fn parse_is_stable_across_round_trips() {
+    for (name, xml) in fixtures() {
+        let first = details(&xml);
+        // A no-op edit goes through parse and serialise once more
+        let again = domain_xml::rename_domain_xml(&xml, &first.name).unwrap();
+        assert_eq!(
+            format!("{:?}", details(&again)),
+            format!("{first:?}"),
+            "{name} parses differently after a round trip"
+        );
+    }
+}
+
+#[test]
+fn extract_interface_targets_reads_live_devices() {
+    assert_eq!(domain_xml::extract_interface_targets(&q35()), vec!["vnet2"]);
+    assert!(domain_xml::extract_interface_targets(&i440fx()).is_empty());
+    assert!(domain_xml::extract_interface_targets("garbage").is_empty());
+}
+
+#[test]
+fn extract_disk_paths_skips_cdroms() {
+    assert_eq!(
+        domain_xml::extract_disk_paths(&win11()),
+        vec!["/var/lib/libvirt/images/win11.qcow2"]
+    );
+    // Block devices have no file to copy
+    assert_eq!(
+        domain_xml::extract_disk_paths(&passthrough()),
+        vec!["/var/lib/libvirt/images/gpu-worker-scratch.qcow2"]
+    );
+}
+
+// --- New VM ---
+
+#[test]
+fn generated_efi_vm_parses_back() {
+    let params = new_vm_params();
+    let xml = domain_xml::generate_domain_xml(&params, "/var/lib/libvirt/images/new-vm.qcow2");
+    let d = details(&xml);
+    assert_eq!(d.name, "new-vm");
+    assert_eq!(d.vcpus, 2);
+    assert_eq!(d.memory_kib, 4096 * 1024);
+    assert_eq!(d.firmware, FirmwareType::Efi);
+    assert_eq!(d.boot_order, vec![BootDevice::Hd, BootDevice::Cdrom]);
+    assert_eq!(d.disks.len(), 2);
+    assert_eq!(
+        d.disks[1].source_file.as_deref(),
+        Some("/srv/iso/install.iso")
+    );
+    assert_eq!(d.tpm.unwrap().model, TpmModel::Crb);
+    assert_eq!(d.networks[0].source_network.as_deref(), Some("default"));
+    assert!(xml.contains("<smm state=\"on\"/>"));
+}
+
+#[test]
+fn generated_bios_vm_has_no_cdrom_or_tpm() {
+    let params = NewVmParams {
+        install: InstallSource::None,
+        firmware: FirmwareType::Bios,
+        tpm_model: None,
+        network: NewVmNetworkConfig {
+            source_type: NetworkSourceType::Bridge,
+            source_value: "br0".to_string(),
+            model: NetworkModel::E1000e,
+        },
+        ..new_vm_params()
+    };
+    let d = details(&domain_xml::generate_domain_xml(&params, "/tmp/disk.qcow2"));
+    assert_eq!(d.firmware, FirmwareType::Bios);
+    assert_eq!(d.disks.len(), 1);
+    assert_eq!(d.boot_order, vec![BootDevice::Hd]);
+    assert!(d.tpm.is_none());
+    assert_eq!(d.networks[0].interface_type, "bridge");
+    assert_eq!(d.networks[0].source_bridge.as_deref(), Some("br0"));
+}
+
+#[test]
+fn generated_windows_vm_uses_emulated_devices_and_hyperv() {
+    let params = NewVmParams {
+        disk_bus: DiskBus::Sata,
+        video: VideoModel::Qxl,
+        hyperv: true,
+        os_id: Some("http://microsoft.com/win/11".to_string()),
+        network: NewVmNetworkConfig {
+            model: NetworkModel::E1000e,
+            ..NewVmNetworkConfig::default()
+        },
+        ..new_vm_params()
+    };
+    let xml = domain_xml::generate_domain_xml(&params, "/tmp/win11.qcow2");
+    let d = details(&xml);
+    assert_eq!(d.disks[0].bus, "sata");
+    assert_eq!(d.disks[0].target_dev, "sda");
+    // The installer CD-ROM must not collide with the SATA system disk
+    assert_eq!(d.disks[1].target_dev, "sdb");
+    assert_eq!(d.video.unwrap().model, VideoModel::Qxl);
+    assert_eq!(d.networks[0].model_type.as_deref(), Some("e1000e"));
+    assert!(xml.contains("<hyperv mode=\"custom\">"));
+    assert!(xml.contains("<clock offset=\"localtime\">"));
+    assert!(xml.contains("<libosinfo:os id=\"http://microsoft.com/win/11\"/>"));
+}
+
+#[test]
+fn generated_ide_vm_uses_i440fx() {
+    let params = NewVmParams {
+        disk_bus: DiskBus::Ide,
+        video: VideoModel::Vga,
+        firmware: FirmwareType::Bios,
+        tpm_model: None,
+        ..new_vm_params()
+    };
+    let xml = domain_xml::generate_domain_xml(&params, "/tmp/old.img");
+    let d = details(&xml);
+    assert!(xml.contains("machine=\"pc\""));
+    assert_eq!(d.disks[0].bus, "ide");
+    assert_eq!(d.disks[0].target_dev, "hda");
+    assert_eq!(d.disks[1].bus, "ide");
+    assert_eq!(d.disks[1].target_dev, "hdc");
+    assert!(!xml.contains("<hyperv"));
+}
+
+#[test]
+fn generated_vm_attaches_virtio_win_after_installer() {
+    let params = NewVmParams {
+        disk_bus: DiskBus::Sata,
+        virtio_win_iso: Some("/srv/iso/virtio-win.iso".to_string()),
+        ..new_vm_params()
+    };
+    let d = details(&domain_xml::generate_domain_xml(&params, "/tmp/win.qcow2"));
+    let cdroms: Vec<(&str, Option<&str>)> = d
+        .disks
+        .iter()
+        .filter(|disk| disk.device_type == "cdrom")
+        .map(|disk| (disk.target_dev.as_str(), disk.source_file.as_deref()))
+        .collect();
+    assert_eq!(
+        cdroms,
+        [
+            ("sdb", Some("/srv/iso/install.iso")),
+            ("sdc", Some("/srv/iso/virtio-win.iso")),
+        ]
+    );
+    assert_eq!(d.boot_order, vec![BootDevice::Hd, BootDevice::Cdrom]);
+}
+
+// --- General ---
+
+#[test]
+fn modify_domain_xml_sets_vcpus_and_memory() {
+    let out = domain_xml::modify_domain_xml(&i440fx(), 6, 3072).unwrap();
+    let d = details(&out);
+    assert_eq!(d.vcpus, 6);
+    assert_eq!(d.memory_kib, 3072 * 1024);
+    assert!(out.contains("<currentMemory unit=\"KiB\">3145728</currentMemory>"));
+    assert!(out.contains("<vcpu placement=\"static\">6</vcpu>"));
+}
+
+#[test]
+fn modify_cpu_model_switches_modes() {
+    let custom = domain_xml::modify_cpu_model(&q35(), CpuMode::Custom, None).unwrap();
+    let d = details(&custom);
+    assert_eq!(d.cpu_mode, CpuMode::Custom);
+    assert_eq!(d.cpu_model.as_deref(), Some("qemu64"));
+    // Topology survives the mode change
+    assert!(custom.contains("<topology sockets='1' dies='1' cores='2' threads='2'/>"));
+
+    let back = domain_xml::modify_cpu_model(&custom, CpuMode::HostPassthrough, None).unwrap();
+    let d = details(&back);
+    assert_eq!(d.cpu_mode, CpuMode::HostPassthrough);
+    assert_eq!(d.cpu_model, None);
+    assert!(!back.contains("match="));
+}
+
+#[test]
+fn modify_cpu_model_adds_missing_cpu() {
+    let xml = "<domain type='kvm'><name>x</name><devices/></domain>";
+    let out = domain_xml::modify_cpu_model(xml, CpuMode::HostModel, None).unwrap();
+    assert_eq!(details(&out).cpu_mode, CpuMode::HostModel);
+}
+
+#[test]
+fn modify_boot_order_replaces_boot_entries() {
+    let out = domain_xml::modify_boot_order(&win11(), &[BootDevice::Hd]).unwrap();
+    assert_eq!(details(&out).boot_order, vec![BootDevice::Hd]);
+    let out = domain_xml::modify_boot_order(&out, &[]).unwrap();
+    assert!(details(&out).boot_order.is_empty());
+}
+
+#[test]
+fn modify_firmware_to_efi_enables_smm() {
+    let out = domain_xml::modify_firmware(&i440fx(), FirmwareType::Efi).unwrap();
+    assert_eq!(details(&out).firmware, FirmwareType::Efi);
+    assert!(out.contains("<os firmware=\"efi\">"));
+    assert!(out.contains("<smm state=\"on\"/>"));
+}
+
+#[test]
+fn modify_firmware_to_bios_drops_loader_and_smm() {
+    // Legacy pflash setup
+    let out = domain_xml::modify_firmware(&win11(), FirmwareType::Bios).unwrap();
+    assert_eq!(details(&out).firmware, FirmwareType::Bios);
+    assert!(!out.contains("<loader"));
+    assert!(!out.contains("<nvram"));
+    assert!(!out.contains("<smm"));
+    // Unrelated features stay
+    assert!(out.contains("<hyperv mode='custom'>"));
+}
+
+#[test]
+fn modify_firmware_efi_to_efi_drops_explicit_paths() {
+    let out = domain_xml::modify_firmware(&q35(), FirmwareType::Efi).unwrap();
+    assert_eq!(details(&out).firmware, FirmwareType::Efi);
+    assert!(!out.contains("OVMF_CODE"));
+    // Firmware feature requests belong to auto-selection and are kept
+    assert!(out.contains("<feature enabled='yes' name='secure-boot'/>"));
+}
+
+#[test]
+fn rename_domain_xml_changes_only_the_name() {
+    let out = domain_xml::rename_domain_xml(&q35(), "renamed & <co>").unwrap();
+    let d = details(&out);
+    assert_eq!(d.name, "renamed & <co>");
+    assert_eq!(d.uuid, "5d1c1f1e-7a4b-4d43-9a3e-2f0c6b1d8e42");
+    assert!(out.contains("<name>renamed &amp; &lt;co&gt;</name>"));
+}
+
+#[test]
+fn prepare_clone_xml_resets_identity() {
+    let disk_map = vec![(
+        "/var/lib/libvirt/images/win11.qcow2".to_string(),
+        "/var/lib/libvirt/images/win11-clone.qcow2".to_string(),
+    )];
+    let out = domain_xml::prepare_clone_xml(&win11(), "win11-clone", &disk_map).unwrap();
+    let d = details(&out);
+    assert_eq!(d.name, "win11-clone");
+    assert_eq!(d.uuid, "");
+    assert!(d.networks.iter().all(|n| n.mac_address.is_none()));
+    assert_eq!(
+        d.disks[0].source_file.as_deref(),
+        Some("/var/lib/libvirt/images/win11-clone.qcow2")
+    );
+    // Installer media is shared, not copied
+    assert_eq!(
+        d.disks[1].source_file.as_deref(),
+        Some("/var/lib/libvirt/images/Win11_23H2_English_x64.iso")
+    );
+}
+
+#[test]
+fn prepare_clone_xml_gets_fresh_firmware_and_tpm_state() {
+    let xml = win11().replace(
+        "<backend type='emulated' version='2.0'/>",
+        "<backend type='emulated' version='2.0'><source type='dir' path='/srv/swtpm/win11'/></backend>",
+    );
+    let out = domain_xml::prepare_clone_xml(&xml, "win11-clone", &[]).unwrap();
+    // The template stays so libvirt makes new vars from it
+    assert!(out.contains("<nvram template='/usr/share/edk2/ovmf/OVMF_VARS.secboot.fd'></nvram>"));
+    assert!(!out.contains("win11_VARS.fd"));
+    assert!(!out.contains("/srv/swtpm/win11"));
+    assert!(details(&out).tpm.is_some());
+
+    let bare = q35().replace(
+        "<nvram template='/usr/share/edk2/ovmf/OVMF_VARS.secboot.fd'>",
+        "<nvram>",
+    );
+    let out = domain_xml::prepare_clone_xml(&bare, "fedora-clone", &[]).unwrap();
+    assert!(!out.contains("<nvram"));
+}
+
+#[test]
+fn clone_sources_skip_shared_media() {
+    let xml = win11().replace(
+        "</devices>",
+        "<disk type='block' device='disk'><source dev='/dev/vg0/data'/><target dev='vdb' bus='virtio'/></disk>\
+         <disk type='volume' device='disk'><source pool='fast' volume='scratch.raw'/><target dev='vdc' bus='virtio'/></disk>\
+         <disk type='file' device='disk'><source file='/srv/shared.img'/><target dev='vdd' bus='virtio'/><shareable/></disk>\
+         </devices>",
+    );
+    let sources = DomainXml::parse(&xml).unwrap().clone_sources();
+    assert_eq!(
+        sources,
+        vec![
+            DiskSource::Path("/var/lib/libvirt/images/win11.qcow2".to_string()),
+            DiskSource::Path("/dev/vg0/data".to_string()),
+            DiskSource::Volume {
+                pool: "fast".to_string(),
+                volume: "scratch.raw".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn prepare_clone_xml_remaps_block_and_volume_disks() {
+    let xml = win11().replace(
+        "</devices>",
+        "<disk type='block' device='disk'><source dev='/dev/vg0/data'/><target dev='vdb' bus='virtio'/></disk>\
+         <disk type='volume' device='disk'><source pool='fast' volume='scratch.raw'/><target dev='vdc' bus='virtio'/></disk>\
+         </devices>",
+    );
+    let disk_map = vec![
+        ("/dev/vg0/data".to_string(), "/dev/vg0/win11-clone-1".to_string()),
+        ("scratch.raw".to_string(), "win11-clone-2.raw".to_string()),
+    ];
+    let out = domain_xml::prepare_clone_xml(&xml, "win11-clone", &disk_map).unwrap();
+    assert!(out.contains("<source dev='/dev/vg0/win11-clone-1'/>"));
+    assert!(out.contains("<source pool='fast' volume='win11-clone-2.raw'/>"));
+}
+
+#[test]
+fn nvram_path_can_be_repointed() {
+    let mut domain = DomainXml::parse(&win11()).unwrap();
+    assert_eq!(
+        domain.nvram_path().as_deref(),
+        Some("/var/lib/libvirt/qemu/nvram/win11_VARS.fd")
+    );
+    domain.set_nvram_path("/var/lib/libvirt/qemu/nvram/copy_VARS.fd");
+    let out = domain.to_xml();
+    assert!(out.contains(
+        "<nvram template='/usr/share/edk2/ovmf/OVMF_VARS.secboot.fd'>/var/lib/libvirt/qemu/nvram/copy_VARS.fd</nvram>"
+    ));
+
+    // The newer form with a <source> child
+    let xml = win11().replace(
+        "<nvram template='/usr/share/edk2/ovmf/OVMF_VARS.secboot.fd'>/var/lib/libvirt/qemu/nvram/win11_VARS.fd</nvram>",
+        "<nvram type='file'><source file='/srv/nvram/win11.fd'/></nvram>",
+    );
+    let mut domain = DomainXml::parse(&xml).unwrap();
+    assert_eq!(domain.nvram_path().as_deref(), Some("/srv/nvram/win11.fd"));
+    domain.set_nvram_path("/srv/nvram/copy.fd");
+    assert!(domain.to_xml().contains("<nvram>/srv/nvram/copy.fd</nvram>"));
+    // A clone made from the source's vars on its first start
+    domain.set_nvram_template("/srv/nvram/win11.fd");
+    assert!(domain
+        .to_xml()
+        .contains("<nvram template='/srv/nvram/win11.fd'>/srv/nvram/copy.fd</nvram>"));
+    assert_eq!(DomainXml::parse(&i440fx()).unwrap().nvram_path(), None);
+}
+
+#[test]
+fn emulated_tpm_state_location() {
+    let domain = DomainXml::parse(&win11()).unwrap();
+    assert_eq!(domain.emulated_tpm(), Some(("2.0".to_string(), None)));
+    let xml = win11().replace(
+        "<backend type='emulated' version='2.0'/>",
+        "<backend type='emulated' version='2.0'><source type='dir' path='/srv/swtpm/win11'/></backend>",
+    );
+    assert_eq!(
+        DomainXml::parse(&xml).unwrap().emulated_tpm(),
+        Some(("2.0".to_string(), Some("/srv/swtpm/win11".to_string())))
+    );
+    let passthrough = win11().replace(
+        "<backend type='emulated' version='2.0'/>",
+        "<backend type='passthrough'><device path='/dev/tpm0'/></backend>",
+    );
+    assert_eq!(DomainXml::parse(&passthrough).unwrap().emulated_tpm(), None);
+}
+
+#[test]
+fn clone_volume_names() {
+    assert_eq!(storage::clone_volume_name("win11.qcow2", "copy", 0, false), "copy.qcow2");
+    assert_eq!(storage::clone_volume_name("data.raw", "copy", 1, false), "copy-1.raw");
+    assert_eq!(storage::clone_volume_name("data.raw", "copy", 1, true), "copy-1.qcow2");
+    // LVM and RBD volumes have no extension
+    assert_eq!(storage::clone_volume_name("lv_data", "copy", 2, false), "copy-2");
+}
+
+// --- CPU Tuning ---
+
+#[test]
+fn modify_cputune_replaces_pins() {
+    let tune = CpuTune {
+        vcpu_pins: vec![
+            VcpuPin {
+                vcpu: 0,
+                cpuset: "6".to_string(),
+            },
+            VcpuPin {
+                vcpu: 1,
+                cpuset: "7".to_string(),
+            },
+        ],
+        emulatorpin: Some("0".to_string()),
+    };
+    let out = domain_xml::modify_cputune(&q35(), &tune).unwrap();
+    let d = details(&out);
+    assert_eq!(d.cpu_tune.vcpu_pins.len(), 2);
+    assert_eq!(d.cpu_tune.vcpu_pins[0].cpuset, "6");
+    assert_eq!(d.cpu_tune.emulatorpin.as_deref(), Some("0"));
+    // Other tunables are kept
+    assert!(out.contains("<shares>2048</shares>"));
+}
+
+#[test]
+fn modify_cputune_adds_and_removes_element() {
+    let tune = CpuTune {
+        vcpu_pins: vec![VcpuPin {
+            vcpu: 0,
+            cpuset: "1".to_string(),
+        }],
+        emulatorpin: None,
+    };
+    let out = domain_xml::modify_cputune(&i440fx(), &tune).unwrap();
+    assert_eq!(details(&out).cpu_tune.vcpu_pins.len(), 1);
+
+    let cleared = domain_xml::modify_cputune(&out, &CpuTune::default()).unwrap();
+    assert!(!cleared.contains("<cputune"));
+    let cleared = domain_xml::modify_cputune(&passthrough(), &CpuTune::default()).unwrap();
+    assert!(!cleared.contains("<cputune"));
+}
+
+// --- Disks ---
+
+#[test]
+fn add_and_remove_disk() {
+    let params = NewDiskParams {
+        source_file: "/srv/vms/extra.raw".to_string(),
+        target_dev: "hdb".to_string(),
+        bus: "ide".to_string(),
+        device_type: "disk".to_string(),
+        driver_type: "raw".to_string(),
+        create_new: true,
+        size_gib: 10,
+    };
+    let out = domain_xml::add_disk_device(&i440fx(), &params).unwrap();
+    let d = details(&out);
+    assert_eq!(d.disks.len(), 3);
+    let disk = d.disks.last().unwrap();
+    assert_eq!(disk.target_dev, "hdb");
+    assert_eq!(disk.bus, "ide");
+    assert_eq!(disk.source_file.as_deref(), Some("/srv/vms/extra.raw"));
+
+    let out = domain_xml::remove_disk_device(&out, "hdb").unwrap();
+    assert_eq!(details(&out).disks.len(), 2);
+    let out = domain_xml::remove_disk_device(&out, "hda").unwrap();
+    assert_eq!(details(&out).disks.len(), 1);
+}
+
+#[test]
+fn eject_cdrom_only_touches_cdroms() {
+    let out = domain_xml::eject_cdrom(&win11(), "sdc").unwrap();
+    let d = details(&out);
+    assert!(d.disks[2].source_file.is_none());
+    assert!(d.disks[1].source_file.is_some());
+    // A hard disk with that target is left alone
+    assert_eq!(domain_xml::eject_cdrom(&win11(), "sda").unwrap(), win11());
+}
+
+#[test]
+fn change_cdrom_media_inserts_source_when_empty() {
+    let out = domain_xml::change_cdrom_media(&i440fx(), "hdc", "/srv/iso/a.iso").unwrap();
+    assert_eq!(
+        details(&out).disks[1].source_file.as_deref(),
+        Some("/srv/iso/a.iso")
+    );
+    // <source> goes before <target>
+    let source = out.find("/srv/iso/a.iso").unwrap();
+    let target = out.find("<target dev=\"hdc\"").unwrap();
+    assert!(source < target);
+
+    let out = domain_xml::change_cdrom_media(&win11(), "sdb", "/srv/iso/b.iso").unwrap();
+    assert_eq!(
+        details(&out).disks[1].source_file.as_deref(),
+        Some("/srv/iso/b.iso")
+    );
+    // Not a CD-ROM
+    assert_eq!(
+        domain_xml::change_cdrom_media(&win11(), "sda", "/srv/iso/b.iso").unwrap(),
+        win11()
+    );
+}
+
+#[test]
+fn change_disk_image_only_touches_disks() {
+    let out = domain_xml::change_disk_image(&passthrough(), "vda", "/srv/vms/root.qcow2").unwrap();
+    let d = details(&out);
+    assert_eq!(
+        d.disks[0].source_file.as_deref(),
+        Some("/srv/vms/root.qcow2")
+    );
+    // The block disk becomes a file disk, keeping its quote style
+    assert!(out.contains("<disk type='file' device='disk'>"));
+    assert_eq!(
+        domain_xml::change_disk_image(&win11(), "sdb", "/srv/vms/x.qcow2").unwrap(),
+        win11()
+    );
+}
+
+// --- Network ---
+
+#[test]
+fn add_and_remove_network_device() {
+    let params = NewNetworkParams {
+        source_network: "isolated".to_string(),
+        model_type: "virtio".to_string(),
+        mac_address: Some("52:54:00:12:34:56".to_string()),
+    };
+    let out = domain_xml::add_network_device(&win11(), &params).unwrap();
+    let d = details(&out);
+    assert_eq!(d.networks.len(), 2);
+    assert_eq!(
+        d.networks[1].mac_address.as_deref(),
+        Some("52:54:00:12:34:56")
+    );
+    assert_eq!(d.networks[1].source_network.as_deref(), Some("isolated"));
+
+    let out = domain_xml::remove_network_device(&out, "52:54:00:1d:9e:77").unwrap();
+    let d = details(&out);
+    assert_eq!(d.networks.len(), 1);
+    assert_eq!(
+        d.networks[0].mac_address.as_deref(),
+        Some("52:54:00:12:34:56")
+    );
+}
+
+#[test]
+fn add_network_device_without_mac() {
+    let params = NewNetworkParams {
+        source_network: "default".to_string(),
+        model_type: "virtio".to_string(),
+        mac_address: Some(String::new()),
+    };
+    let out = domain_xml::add_network_device(&i440fx(), &params).unwrap();
+    assert_eq!(details(&out).networks[2].mac_address, None);
+}
+
+#[test]
+fn change_network_source_for_each_type() {
+    let cases = [
+        (NetworkSourceType::VirtualNetwork, "network"),
+        (NetworkSourceType::Bridge, "bridge"),
+        (NetworkSourceType::Macvtap, "direct"),
+        (NetworkSourceType::Vdpa, "vdpa"),
+    ];
+    for (source_type, iface_type) in cases {
+        let params = ChangeNetworkSourceParams {
+            source_type,
+            value: "target0".to_string(),
+        };
+        let out = domain_xml::change_network_source(&passthrough(), "52:54:00:c0:ff:ee", &params)
+            .unwrap();
+        let nic = &details(&out).networks[0];
+        assert_eq!(nic.interface_type, iface_type);
+        let value = match source_type {
+            NetworkSourceType::VirtualNetwork => nic.source_network.as_deref(),
+            NetworkSourceType::Bridge => nic.source_bridge.as_deref(),
+            NetworkSourceType::Macvtap | NetworkSourceType::Vdpa => nic.source_dev.as_deref(),
+        };
+        assert_eq!(value, Some("target0"));
+        // MAC, model and driver settings are kept
+        assert_eq!(nic.mac_address.as_deref(), Some("52:54:00:c0:ff:ee"));
+        assert!(out.contains("<driver name='vhost' queues='6'/>"));
+    }
+}
+
+// --- Graphics / Video / Sound ---
+
+#[test]
+fn modify_graphics_replaces_or_removes() {
+    let out = domain_xml::modify_graphics(&win11(), GraphicsType::Vnc).unwrap();
+    let d = details(&out);
+    assert_eq!(d.graphics.unwrap().graphics_type, GraphicsType::Vnc);
+    assert_eq!(out.matches("<graphics").count(), 1);
+
+    let out = domain_xml::modify_graphics(&out, GraphicsType::None).unwrap();
+    assert!(!details(&out).has_graphics);
+
+    let out = domain_xml::modify_graphics(&passthrough(), GraphicsType::Spice).unwrap();
+    assert!(details(&out).has_graphics);
+}
+
+#[test]
+fn modify_video_sets_acceleration() {
+    let out = domain_xml::modify_video(&win11(), VideoModel::Virtio, true).unwrap();
+    let video = details(&out).video.unwrap();
+    assert_eq!(video.model, VideoModel::Virtio);
+    assert!(video.accel3d);
+
+    let out = domain_xml::modify_video(&out, VideoModel::None, false).unwrap();
+    assert!(details(&out).video.is_none());
+}
+
+#[test]
+fn modify_sound_replaces_or_removes() {
+    let out = domain_xml::modify_sound(&i440fx(), SoundModel::Ac97).unwrap();
+    assert_eq!(details(&out).sound.unwrap().model, SoundModel::Ac97);
+    let out = domain_xml::modify_sound(&out, SoundModel::None).unwrap();
+    assert!(details(&out).sound.is_none());
+}
+
+// --- TPM ---
+
+#[test]
+fn modify_tpm_adds_changes_and_removes() {
+    let out = domain_xml::modify_tpm(&i440fx(), TpmModel::Tis).unwrap();
+    let tpm = details(&out).tpm.unwrap();
+    assert_eq!(tpm.model, TpmModel::Tis);
+    assert_eq!(tpm.version, "2.0");
+
+    let out = domain_xml::modify_tpm(&win11(), TpmModel::Tis).unwrap();
+    assert_eq!(details(&out).tpm.unwrap().model, TpmModel::Tis);
+    assert_eq!(out.matches("<tpm").count(), 1);
+
+    let out = domain_xml::modify_tpm(&out, TpmModel::None).unwrap();
+    assert!(details(&out).tpm.is_none());
+}
+
+// --- Filesystems ---
+
+#[test]
+fn add_filesystem_virtiofs_and_9p() {
+    let virtiofs = FilesystemInfo {
+        driver: "virtiofs".to_string(),
+        source_dir: "/srv/a".to_string(),
+        target_dir: "a".to_string(),
+        accessmode: None,
+    };
+    let p9 = FilesystemInfo {
+        driver: "9p".to_string(),
+        source_dir: "/srv/b".to_string(),
+        target_dir: "b".to_string(),
+        accessmode: None,
+    };
+    let out = domain_xml::add_filesystem(&q35(), &virtiofs).unwrap();
+    let out = domain_xml::add_filesystem(&out, &p9).unwrap();
+    let d = details(&out);
+    assert_eq!(d.filesystems.len(), 2);
+    assert_eq!(d.filesystems[0].driver, "virtiofs");
+    assert_eq!(d.filesystems[0].accessmode, None);
+    assert_eq!(d.filesystems[1].driver, "9p");
+    assert_eq!(d.filesystems[1].accessmode.as_deref(), Some("mapped"));
+
+    let out = domain_xml::remove_filesystem(&out, "a").unwrap();
+    let d = details(&out);
+    assert_eq!(d.filesystems.len(), 1);
+    assert_eq!(d.filesystems[0].target_dir, "b");
+}
+
+#[test]
+fn remove_filesystem_by_target() {
+    let out = domain_xml::remove_filesystem(&passthrough(), "datasets").unwrap();
+    assert!(details(&out).filesystems.is_empty());
+    assert_eq!(
+        domain_xml::remove_filesystem(&passthrough(), "other").unwrap(),
+        passthrough()
+    );
+}
+
+// --- Host Devices ---
+
+#[test]
+fn add_hostdev_pci_and_usb() {
+    let pci = pci_hostdev(None, "0x03", "0x00", "0x0");
+    let out = domain_xml::add_hostdev_device(&q35(), &pci).unwrap();
+    let out = domain_xml::add_hostdev_device(&out, &usb_hostdev("0x1234", "0xabcd")).unwrap();
+    let d = details(&out);
+    assert_eq!(d.hostdevs.len(), 2);
+    // A missing PCI domain defaults to 0x0000
+    assert_eq!(d.hostdevs[0].pci_domain.as_deref(), Some("0x0000"));
+    assert_eq!(d.hostdevs[0].display_name, "PCI 0000:03:00.0");
+    assert_eq!(d.hostdevs[1].display_name, "USB 0x1234:0xabcd");
+    assert!(out.contains("<hostdev mode=\"subsystem\" type=\"pci\" managed=\"yes\">"));
+}
+
+#[test]
+fn remove_hostdev_matches_pci_address() {
+    // Function 1 of the GPU only
+    let info = pci_hostdev(Some("0x0000"), "0x0a", "0x00", "0x1");
+    let out = domain_xml::remove_hostdev_device(&passthrough(), &info).unwrap();
+    let d = details(&out);
+    assert_eq!(d.hostdevs.len(), 2);
+    assert_eq!(d.hostdevs[0].pci_function.as_deref(), Some("0x0"));
+
+    // Without a domain the address still matches
+    let info = pci_hostdev(None, "0x0a", "0x00", "0x0");
+    let out = domain_xml::remove_hostdev_device(&out, &info).unwrap();
+    assert_eq!(details(&out).hostdevs.len(), 1);
+
+    // The guest-side address (bus 0x06) is not the host address
+    let info = pci_hostdev(None, "0x06", "0x00", "0x0");
+    assert_eq!(
+        domain_xml::remove_hostdev_device(&passthrough(), &info).unwrap(),
+        passthrough()
+    );
+}
+
+#[test]
+fn remove_hostdev_matches_usb_ids() {
+    let out = domain_xml::remove_hostdev_device(&passthrough(), &usb_hostdev("0x1050", "0x0407"))
+        .unwrap();
+    assert!(details(&out)
+        .hostdevs
+        .iter()
+        .all(|h| h.device_type == "pci"));
+    // Vendor alone is not enough
+    assert_eq!(
+        domain_xml::remove_hostdev_device(&passthrough(), &usb_hostdev("0x1050", "0xffff"))
+            .unwrap(),
+        passthrough()
+    );
+}
+
+// --- Serial / Console ---
+
+#[test]
+fn add_and_remove_serial_and_console() {
+    let serial = SerialInfo {
+        is_console: false,
+        target_type: "isa-serial".to_string(),
+        port: 1,
+    };
+    let console = SerialInfo {
+        is_console: true,
+        target_type: "virtio".to_string(),
+        port: 1,
+    };
+    let out = domain_xml::add_serial_device(&win11(), &serial).unwrap();
+    let out = domain_xml::add_serial_device(&out, &console).unwrap();
+    let d = details(&out);
+    assert_eq!(d.serials.len(), 4);
+    assert!(d.serials[3].is_console);
+    assert_eq!(d.serials[3].target_type, "virtio");
+
+    // Removing the console on port 1 leaves the serial on port 1
+    let out = domain_xml::remove_serial_device(&out, &console).unwrap();
+    let d = details(&out);
+    assert_eq!(d.serials.len(), 3);
+    assert!(d.serials.iter().any(|s| !s.is_console && s.port == 1));
+
+    let out = domain_xml::remove_serial_device(&passthrough(), &serial).unwrap();
+    let ports: Vec<u32> = details(&out)
+        .serials
+        .iter()
+        .filter(|s| !s.is_console)
+        .map(|s| s.port)
+        .collect();
+    assert_eq!(ports, vec![0]);
+}
+
+// --- RNG / Watchdog / Panic ---
+
+#[test]
+fn modify_rng_sets_backend() {
+    let out = domain_xml::modify_rng(&i440fx(), Some(RngBackend::Random)).unwrap();
+    assert_eq!(details(&out).rng, Some(RngBackend::Random));
+    let out = domain_xml::modify_rng(&out, Some(RngBackend::Urandom)).unwrap();
+    assert_eq!(details(&out).rng, Some(RngBackend::Urandom));
+    assert_eq!(out.matches("<rng").count(), 1);
+    let out = domain_xml::modify_rng(&out, None).unwrap();
+    assert_eq!(details(&out).rng, None);
+}
+
+#[test]
+fn modify_watchdog_sets_model_and_action() {
+    let out =
+        domain_xml::modify_watchdog(&q35(), WatchdogModel::Ib700, WatchdogAction::Pause).unwrap();
+    let wd = details(&out).watchdog.unwrap();
+    assert_eq!(wd.model, WatchdogModel::Ib700);
+    assert_eq!(wd.action, WatchdogAction::Pause);
+    assert_eq!(out.matches("<watchdog").count(), 1);
+
+    let out =
+        domain_xml::modify_watchdog(&win11(), WatchdogModel::None, WatchdogAction::Reset).unwrap();
+    assert!(details(&out).watchdog.is_none());
+    assert!(!out.contains("<watchdog"));
+}
+
+#[test]
+fn modify_panic_sets_or_removes() {
+    let out = domain_xml::modify_panic(&q35(), PanicModel::Pvpanic).unwrap();
+    assert_eq!(details(&out).panic, Some(PanicModel::Pvpanic));
+    let out = domain_xml::modify_panic(&win11(), PanicModel::None).unwrap();
+    assert!(details(&out).panic.is_none());
+}
+
+// --- Input / Channels / Controllers ---
+
+#[test]
+fn add_and_remove_input_device() {
+    let info = InputInfo {
+        input_type: "keyboard".to_string(),
+        bus: "usb".to_string(),
+    };
+    let out = domain_xml::add_input_device(&i440fx(), &info).unwrap();
+    assert_eq!(details(&out).inputs.len(), 2);
+    let out = domain_xml::remove_input_device(&out, &info).unwrap();
+    assert_eq!(details(&out).inputs.len(), 1);
+
+    // Only the first of two identical inputs is removed
+    let dup = InputInfo {
+        input_type: "keyboard".to_string(),
+        bus: "virtio".to_string(),
+    };
+    let out = domain_xml::remove_input_device(&passthrough(), &dup).unwrap();
+    assert_eq!(details(&out).inputs.len(), 1);
+}
+
+#[test]
+fn add_and_remove_channel() {
+    let info = ChannelInfo {
+        channel_type: "unix".to_string(),
+        target_name: "org.qemu.guest_agent.0".to_string(),
+    };
+    let out = domain_xml::add_channel_device(&win11(), &info).unwrap();
+    let d = details(&out);
+    assert_eq!(d.channels.len(), 2);
+    assert_eq!(d.channels[1].target_name, "org.qemu.guest_agent.0");
+
+    let out = domain_xml::remove_channel_device(&out, "com.redhat.spice.0").unwrap();
+    let d = details(&out);
+    assert_eq!(d.channels.len(), 1);
+    assert_eq!(d.channels[0].channel_type, "unix");
+}
+
+#[test]
+fn add_and_remove_controller() {
+    let scsi = ControllerInfo {
+        controller_type: "scsi".to_string(),
+        model: Some("virtio-scsi".to_string()),
+        index: 0,
+    };
+    let out = domain_xml::add_controller(&win11(), &scsi).unwrap();
+    assert!(details(&out).controllers.contains(&scsi));
+    let out = domain_xml::remove_controller(&out, &scsi).unwrap();
+    assert!(!details(&out).controllers.contains(&scsi));
+
+    // Model must match too
+    let other = ControllerInfo {
+        model: Some("lsilogic".to_string()),
+        ..scsi.clone()
+    };
+    assert_eq!(
+        domain_xml::remove_controller(&passthrough(), &other).unwrap(),
+        passthrough()
+    );
+
+    let serial = ControllerInfo {
+        controller_type: "virtio-serial".to_string(),
+        model: None,
+        index: 0,
+    };
+    let out = domain_xml::add_controller(&i440fx(), &serial).unwrap();
+    assert!(out.contains("<controller type=\"virtio-serial\" index=\"0\"/>"));
+}
+
+// --- Parallel / USB Redirection ---
+
+#[test]
+fn add_and_remove_parallel_ports() {
+    let out = domain_xml::add_parallel_device(&i440fx()).unwrap();
+    let ports: Vec<u32> = details(&out).parallels.iter().map(|p| p.port).collect();
+    assert_eq!(ports, vec![0, 1]);
+
+    let out = domain_xml::remove_parallel_device(&out, 0).unwrap();
+    let ports: Vec<u32> = details(&out).parallels.iter().map(|p| p.port).collect();
+    assert_eq!(ports, vec![1]);
+
+    let out = domain_xml::add_parallel_device(&q35()).unwrap();
+    assert_eq!(details(&out).parallels[0].port, 0);
+}
+
+#[test]
+fn add_and_remove_usbredir() {
+    let out = domain_xml::add_usbredir(&win11()).unwrap();
+    assert_eq!(details(&out).usbredirs.len(), 2);
+
+    let out = domain_xml::remove_usbredir(&q35(), 0).unwrap();
+    assert_eq!(details(&out).usbredirs.len(), 1);
+    // The second redirdev is the one left
+    assert!(out.contains("<alias name='redir1'/>"));
+    assert!(!out.contains("<alias name='redir0'/>"));
+}
+
+// --- Smartcard / Memory Balloon ---
+
+#[test]
+fn modify_smartcard_modes() {
+    let out = domain_xml::modify_smartcard(&q35(), SmartcardMode::Passthrough).unwrap();
+    assert_eq!(details(&out).smartcard, Some(SmartcardMode::Passthrough));
+    assert!(out.contains("<smartcard mode=\"passthrough\" type=\"spicevmc\"/>"));
+    let out = domain_xml::modify_smartcard(&i440fx(), SmartcardMode::None).unwrap();
+    assert!(details(&out).smartcard.is_none());
+}
+
+#[test]
+fn modify_memballoon_models() {
+    let out = domain_xml::modify_memballoon(&passthrough(), MemballoonModel::Virtio).unwrap();
+    assert_eq!(details(&out).memballoon, Some(MemballoonModel::Virtio));
+    let out = domain_xml::modify_memballoon(&out, MemballoonModel::None).unwrap();
+    assert_eq!(details(&out).memballoon, Some(MemballoonModel::None));
+    assert_eq!(out.matches("<memballoon").count(), 1);
+}
+
+// --- Errors ---
+
+#[test]
+fn transformations_reject_invalid_xml() {
+    let bad = "<domain><devices></domain>";
+    assert!(domain_xml::parse_domain_xml(bad).is_err());
+    assert!(domain_xml::add_usbredir(bad).is_err());
+    assert!(domain_xml::modify_tpm(bad, TpmModel::Crb).is_err());
+    assert!(domain_xml::prepare_clone_xml(bad, "x", &[]).is_err());
+}
+
+#[test]
+fn device_edits_create_missing_devices_element() {
+    let xml = "<domain type='kvm'><name>bare</name></domain>";
+    let out = domain_xml::add_usbredir(xml).unwrap();
+    assert_eq!(details(&out).usbredirs.len(), 1);
+}
@@ -0,0 +1,286 @@
+// Golden-file tests for the domain XML model.
+//
+// Every fixture under tests/fixtures/domain must survive parse -> serialise
+// unchanged, and each edit below is compared against the expected output in
+// tests/fixtures/domain/golden. After an intentional change, regenerate the
+// expected files with `UPDATE_GOLDEN=1 cargo test -p grustyvman-core` and
+// review the diff.
+
+mod common;
+
+use std::fs;
+
+use common::{fixture, fixture_dir, fixtures};
+use grustyvman_core::domain_model::DomainXml;
+use grustyvman_core::domain_xml;
+use grustyvman_core::types::{
+    BootDevice, ChangeNetworkSourceParams, ControllerInfo, CpuMode, CpuTune, FirmwareType,
+    GraphicsType, HostdevInfo, MemballoonModel, NetworkSourceType, NewDiskParams, NewNetworkParams,
+    RngBackend, SerialInfo, SmartcardMode, TpmModel, VcpuPin, VideoModel, WatchdogAction,
+    WatchdogModel,
+};
+use grustyvman_core::AppError;
+
+type Edit = fn(&str) -> Result<String, AppError>;
+
+fn check_golden(fixture_name: &str, case: &str, actual: &str) {
+    let stem = fixture_name.trim_end_matches(".xml");
+    let path = fixture_dir()
+        .join("golden")
+        .join(format!("{stem}.{case}.xml"));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("missing golden file {}", path.display()));
+    assert_eq!(
+        actual,
+        expected,
+        "{stem}.{case} differs from {}",
+        path.display()
+    );
+}
+
+#[test]
+fn fixtures_round_trip_unchanged() {
+    for (name, xml) in fixtures() {
+        let domain = DomainXml::parse(&xml).unwrap();
+        assert_eq!(domain.to_xml(), xml, "{name} did not round-trip");
+    }
+}
+
+#[test]
+fn edits_that_change_nothing_keep_the_document() {
+    let xml = fixture("q35-efi.xml");
+    // Same values as the fixture already has
+    assert_eq!(domain_xml::modify_domain_xml(&xml, 4, 8192).unwrap(), xml);
+    assert_eq!(
+        domain_xml::rename_domain_xml(&xml, "fedora-ws").unwrap(),
+        xml
+    );
+    assert_eq!(domain_xml::remove_disk_device(&xml, "vdz").unwrap(), xml);
+    assert_eq!(
+        domain_xml::remove_network_device(&xml, "52:54:00:00:00:00").unwrap(),
+        xml
+    );
+}
+
+fn q35_cases() -> Vec<(&'static str, Edit)> {
+    vec![
+        ("memory-vcpus", |x| {
+            domain_xml::modify_domain_xml(x, 8, 16384)
+        }),
+        ("cpu-custom", |x| {
+            domain_xml::modify_cpu_model(x, CpuMode::Custom, Some("EPYC-Milan"))
+        }),
+        ("boot-order", |x| {
+            domain_xml::modify_boot_order(x, &[BootDevice::Cdrom, BootDevice::Hd])
+        }),
+        ("firmware-bios", |x| {
+            domain_xml::modify_firmware(x, FirmwareType::Bios)
+        }),
+        ("graphics-vnc", |x| {
+            domain_xml::modify_graphics(x, GraphicsType::Vnc)
+        }),
+        ("video-qxl", |x| {
+            domain_xml::modify_video(x, VideoModel::Qxl, false)
+        }),
+        ("tpm-none", |x| domain_xml::modify_tpm(x, TpmModel::None)),
+        ("cputune-clear", |x| {
+            domain_xml::modify_cputune(x, &CpuTune::default())
+        }),
+        ("cputune-pins", |x| {
+            domain_xml::modify_cputune(
+                x,
+                &CpuTune {
+                    vcpu_pins: vec![VcpuPin {
+                        vcpu: 0,
+                        cpuset: "4-5".to_string(),
+                    }],
+                    emulatorpin: None,
+                },
+            )
+        }),
+        ("add-disk", |x| {
+            domain_xml::add_disk_device(
+                x,
+                &NewDiskParams {
+                    source_file: "/var/lib/libvirt/images/data.qcow2".to_string(),
+                    target_dev: "vdb".to_string(),
+                    bus: "virtio".to_string(),
+                    device_type: "disk".to_string(),
+                    driver_type: "qcow2".to_string(),
+                    create_new: false,
+                    size_gib: 0,
+                },
+            )
+        }),
+        ("remove-disk", |x| domain_xml::remove_disk_device(x, "vda")),
+        ("eject-cdrom", |x| domain_xml::eject_cdrom(x, "sda")),
+        ("change-disk-image", |x| {
+            domain_xml::change_disk_image(x, "vda", "/var/lib/libvirt/images/other.qcow2")
+        }),
+        ("add-network", |x| {
+            domain_xml::add_network_device(
+                x,
+                &NewNetworkParams {
+                    source_network: "isolated".to_string(),
+                    model_type: "e1000e".to_string(),
+                    mac_address: None,
+                },
+            )
+        }),
+        ("network-source-bridge", |x| {
+            domain_xml::change_network_source(
+                x,
+                "52:54:00:6b:3c:58",
+                &ChangeNetworkSourceParams {
+                    source_type: NetworkSourceType::Bridge,
+                    value: "br0".to_string(),
+                },
+            )
+        }),
+        ("remove-serial", |x| {
+            domain_xml::remove_serial_device(
+                x,
+                &SerialInfo {
+                    is_console: false,
+                    target_type: "isa-serial".to_string(),
+                    port: 0,
+                },
+            )
+        }),
+        ("rng-random", |x| {
+            domain_xml::modify_rng(x, Some(RngBackend::Random))
+        }),
+        ("watchdog-none", |x| {
+            domain_xml::modify_watchdog(x, WatchdogModel::None, WatchdogAction::Reset)
+        }),
+        ("remove-controller", |x| {
+            domain_xml::remove_controller(
+                x,
+                &ControllerInfo {
+                    controller_type: "usb".to_string(),
+                    model: Some("qemu-xhci".to_string()),
+                    index: 0,
+                },
+            )
+        }),
+        ("remove-usbredir", |x| domain_xml::remove_usbredir(x, 1)),
+        ("memballoon-none", |x| {
+            domain_xml::modify_memballoon(x, MemballoonModel::None)
+        }),
+        ("clone", |x| {
+            domain_xml::prepare_clone_xml(
+                x,
+                "fedora-ws-clone",
+                &[(
+                    "/var/lib/libvirt/images/fedora-ws.qcow2".to_string(),
+                    "/var/lib/libvirt/images/fedora-ws-clone.qcow2".to_string(),
+                )],
+            )
+        }),
+    ]
+}
+
+fn i440fx_cases() -> Vec<(&'static str, Edit)> {
+    vec![
+        ("firmware-efi", |x| {
+            domain_xml::modify_firmware(x, FirmwareType::Efi)
+        }),
+        ("cpu-host-model", |x| {
+            domain_xml::modify_cpu_model(x, CpuMode::HostModel, None)
+        }),
+        ("change-cdrom-media", |x| {
+            domain_xml::change_cdrom_media(x, "hdc", "/srv/iso/CentOS-7 & more.iso")
+        }),
+        ("remove-hostdev", |x| {
+            domain_xml::remove_hostdev_device(
+                x,
+                &HostdevInfo {
+                    device_type: "usb".to_string(),
+                    pci_domain: None,
+                    pci_bus: None,
+                    pci_slot: None,
+                    pci_function: None,
+                    usb_vendor: Some("0x046d".to_string()),
+                    usb_product: Some("0xc52b".to_string()),
+                    display_name: String::new(),
+                },
+            )
+        }),
+        ("add-parallel", domain_xml::add_parallel_device),
+        ("remove-parallel", |x| {
+            domain_xml::remove_parallel_device(x, 0)
+        }),
+        ("remove-bridge-nic", |x| {
+            domain_xml::remove_network_device(x, "52:54:00:aa:bb:01")
+        }),
+        ("smartcard-passthrough", |x| {
+            domain_xml::modify_smartcard(x, SmartcardMode::Passthrough)
+        }),
+        ("remove-filesystem", |x| {
+            domain_xml::remove_filesystem(x, "share")
+        }),
+        ("add-usbredir", domain_xml::add_usbredir),
+    ]
+}
+
+#[test]
+fn q35_edits_match_golden_files() {
+    let xml = fixture("q35-efi.xml");
+    for (case, edit) in q35_cases() {
+        check_golden("q35-efi.xml", case, &edit(&xml).unwrap());
+    }
+}
+
+#[test]
+fn i440fx_edits_match_golden_files() {
+    let xml = fixture("i440fx-bios.xml");
+    for (case, edit) in i440fx_cases() {
+        check_golden("i440fx-bios.xml", case, &edit(&xml).unwrap());
+    }
+}
+
+#[test]
+fn edits_keep_unmodelled_elements() {
+    let xml = fixture("q35-efi.xml");
+    for (case, edit) in q35_cases() {
+        let out = edit(&xml).unwrap();
+        for needle in [
+            "<qemu:commandline>",
+            "string=hello &quot;world&quot;",
+            "<title>Fedora &amp; friends</title>",
+            "<libosinfo:os id=\"http://fedoraproject.org/fedora/40\"/>",
+            "<seclabel type='dynamic' model='selinux' relabel='yes'>",
+            "<audio id='1' type='spice'/>",
+        ] {
+            assert!(out.contains(needle), "{case} dropped {needle}");
+        }
+        // Every edit must produce a document that parses again
+        DomainXml::parse(&out).unwrap_or_else(|e| panic!("{case} produced bad XML: {e}"));
+    }
+}
+
+#[test]
+fn parse_reads_escaped_values() {
+    let details = domain_xml::parse_domain_xml(&fixture("i440fx-bios.xml")).unwrap();
+    assert_eq!(details.memory_kib, 2048 * 1024);
+    assert_eq!(
+        details.disks[0].source_file.as_deref(),
+        Some("/srv/vms/legacy centos & co.img")
+    );
+    assert_eq!(
+        details.graphics.unwrap().password.as_deref(),
+        Some("s3cr<t")
+    );
+}
+
+#[test]
+fn malformed_xml_is_an_error() {
+    assert!(DomainXml::parse("<domain><name>x</domain>").is_err());
+    assert!(DomainXml::parse("<network/>").is_err());
+    assert!(domain_xml::modify_graphics("not xml", GraphicsType::Spice).is_err());
+}
@@ -0,0 +1,50 @@
+// Guest commands that grow a filesystem after its disk was grown.
+
+use grustyvman_core::guest_agent::{grow_commands, split_partition};
+
+fn commands(name: &str, fstype: &str, mountpoint: &str) -> Option<Vec<String>> {
+    grow_commands(name, fstype, mountpoint)
+        .map(|commands| commands.iter().map(|argv| argv.join(" ")).collect())
+}
+
+#[test]
+fn partitions_are_told_from_whole_disks() {
+    assert_eq!(split_partition("vda1"), Some(("vda", 1)));
+    assert_eq!(split_partition("sdb12"), Some(("sdb", 12)));
+    assert_eq!(split_partition("nvme0n1p2"), Some(("nvme0n1", 2)));
+    assert_eq!(split_partition("mmcblk0p1"), Some(("mmcblk0", 1)));
+    assert_eq!(split_partition("vdb"), None);
+    assert_eq!(split_partition("nvme0n1"), None);
+}
+
+#[test]
+fn the_partition_grows_before_the_filesystem() {
+    assert_eq!(
+        commands("vda3", "ext4", "/").unwrap(),
+        ["growpart /dev/vda 3", "resize2fs /dev/vda3"]
+    );
+    assert_eq!(
+        commands("nvme0n1p1", "xfs", "/srv").unwrap(),
+        ["growpart /dev/nvme0n1 1", "xfs_growfs /srv"]
+    );
+    // A filesystem on the whole disk has no partition to grow
+    assert_eq!(
+        commands("vdb", "btrfs", "/data").unwrap(),
+        ["btrfs filesystem resize max /data"]
+    );
+}
+
+#[test]
+fn windows_volumes_grow_by_drive_letter() {
+    let script = commands("\\\\?\\Volume{1234}\\", "NTFS", "C:\\").unwrap();
+    assert_eq!(script.len(), 1);
+    assert!(script[0].starts_with("powershell.exe"));
+    assert!(script[0].contains("Resize-Partition -DriveLetter C"));
+}
+
+#[test]
+fn layered_and_unknown_filesystems_are_left_alone() {
+    assert_eq!(commands("dm-0", "ext4", "/"), None);
+    assert_eq!(commands("md127", "xfs", "/srv"), None);
+    assert_eq!(commands("vda2", "vfat", "/boot/efi"), None);
+}
@@ -0,0 +1,57 @@
+// Image format detection from file headers, for volume uploads.
+
+use grustyvman_core::storage::{detect_image_format, image_format, qcow2_volume_name};
+use grustyvman_core::types::ImageFormat;
+
+fn header(magic: &[u8], at: usize) -> Vec<u8> {
+    let mut header = vec![0u8; 512];
+    header[at..at + magic.len()].copy_from_slice(magic);
+    header
+}
+
+#[test]
+fn formats_are_told_by_their_magic() {
+    let cases = [
+        (header(b"QFI\xfb\0\0\0\x03", 0), ImageFormat::Qcow2),
+        (header(b"KDMV", 0), ImageFormat::Vmdk),
+        (header(b"vhdxfile", 0), ImageFormat::Vhdx),
+        (header(b"conectix", 0), ImageFormat::Vpc),
+        (header(&[0x7f, 0x10, 0xda, 0xbe], 64), ImageFormat::Vdi),
+        (header(b"CD001", 1), ImageFormat::Raw),
+        (Vec::new(), ImageFormat::Raw),
+    ];
+    for (bytes, format) in cases {
+        assert_eq!(detect_image_format(&bytes), format);
+    }
+}
+
+#[test]
+fn file_names_do_not_decide_the_format() {
+    let dir = std::env::temp_dir().join(format!("grustyvman-image-format-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let misnamed = dir.join("disk.qcow2");
+    std::fs::write(&misnamed, header(b"KDMV", 0)).unwrap();
+    let raw = dir.join("disk.img");
+    std::fs::write(&raw, vec![0u8; 100]).unwrap();
+
+    assert_eq!(image_format(&misnamed.to_string_lossy()).unwrap(), ImageFormat::Vmdk);
+    assert_eq!(image_format(&raw.to_string_lossy()).unwrap(), ImageFormat::Raw);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn foreign_formats_are_offered_for_conversion() {
+    assert!(!ImageFormat::Raw.is_foreign());
+    assert!(!ImageFormat::Qcow2.is_foreign());
+    assert!(ImageFormat::Vdi.is_foreign());
+    assert!(ImageFormat::Vmdk.libvirt_supported());
+    assert!(!ImageFormat::Vhdx.libvirt_supported());
+}
+
+#[test]
+fn converted_volumes_are_named_qcow2() {
+    assert_eq!(qcow2_volume_name("win10.vhdx"), "win10.qcow2");
+    assert_eq!(qcow2_volume_name("Appliance.VMDK"), "Appliance.qcow2");
+    assert_eq!(qcow2_volume_name("old.vhd"), "old.qcow2");
+    assert_eq!(qcow2_volume_name("disk"), "disk.qcow2");
+}
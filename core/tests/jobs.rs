@@ -0,0 +1,77 @@
+// Job progress, cancellation and the job manager's bookkeeping.
+
+use std::sync::{Arc, Mutex};
+
+use grustyvman_core::jobs::{Job, JobManager};
+use grustyvman_core::types::JobStatus;
+use grustyvman_core::AppError;
+
+#[test]
+fn progress_is_reported_as_a_fraction() {
+    let job = Job::detached("Upload");
+    assert_eq!(job.info().fraction(), None);
+    job.set_progress(256, 1024);
+    assert_eq!(job.info().fraction(), Some(0.25));
+    job.set_progress(2048, 1024);
+    assert_eq!(job.info().fraction(), Some(1.0));
+}
+
+#[test]
+fn cancelled_jobs_stop_at_the_next_check() {
+    let job = Job::detached("Clone");
+    assert!(job.check_cancelled().is_ok());
+    job.clone().cancel();
+    assert!(matches!(job.check_cancelled(), Err(AppError::Cancelled)));
+}
+
+#[test]
+fn finish_records_the_outcome() {
+    let job = Job::detached("Snapshot");
+    assert!(job.info().is_running());
+    job.finish::<()>(&Err(AppError::Cancelled));
+    assert_eq!(job.info().status, JobStatus::Cancelled);
+
+    let job = Job::detached("Snapshot");
+    job.finish::<()>(&Err(AppError::Backend("no space".into())));
+    assert!(matches!(job.info().status, JobStatus::Failed(msg) if msg.contains("no space")));
+    assert!(!job.info().is_running());
+}
+
+#[test]
+fn watchers_are_told_about_changes() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let job = Job::watched("Clone", {
+        let seen = seen.clone();
+        move |info| seen.lock().unwrap().push(info)
+    });
+    job.set_detail("Disk 1 of 2");
+    // Updates in quick succession are throttled, the final one is not
+    job.set_progress(1, 10);
+    job.set_progress(2, 10);
+    job.set_progress(10, 10);
+    job.finish(&Ok(()));
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen[0].detail, "Disk 1 of 2");
+    assert!(seen.iter().all(|info| info.done != 2));
+    assert!(seen.iter().any(|info| info.done == 10));
+    assert_eq!(seen.last().unwrap().status, JobStatus::Succeeded);
+}
+
+#[test]
+fn manager_tracks_and_cancels_jobs() {
+    let (manager, rx) = JobManager::new();
+    let first = manager.start("Upload a");
+    let second = manager.start("Upload b");
+    assert_eq!(rx.try_recv().unwrap().id, first.id());
+
+    manager.cancel(second.id());
+    assert!(second.is_cancelled());
+    assert!(!first.is_cancelled());
+
+    second.finish::<()>(&Err(AppError::Cancelled));
+    manager.clear_finished();
+    let jobs = manager.jobs();
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].title, "Upload a");
+}
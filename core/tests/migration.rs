@@ -0,0 +1,79 @@
+// Migration flags and the CPU a destination host has to provide.
+
+use grustyvman_core::migration::{migrate_flags, required_cpu_xml};
+use grustyvman_core::types::{CpuMode, MigrateParams, MigrationMode};
+use virt::sys;
+
+const CAPABILITIES: &str = "<capabilities>
+  <host >
+    <uuid>5d1e8f4c-7a53-4b8e-9c1f-2f6a4d3b9e10</uuid>
+    <cpu >
+      <arch>x86_64</arch>
+      <model>Skylake-Client-IBRS</model>
+      <vendor>Intel</vendor>
+      <feature name='vmx'/>
+    </cpu>
+  </host>
+  <guest>
+    <cpu><model>ignored</model></cpu>
+  </guest>
+</capabilities>";
+
+#[test]
+fn live_migration_flags_follow_the_options() {
+    let mut params = MigrateParams::new("qemu+ssh://other/system");
+    params.copy_storage = true;
+    params.compressed = true;
+    params.undefine_source = true;
+    let flags = migrate_flags(&params, true).unwrap();
+    for flag in [
+        sys::VIR_MIGRATE_LIVE,
+        sys::VIR_MIGRATE_NON_SHARED_DISK,
+        sys::VIR_MIGRATE_COMPRESSED,
+        sys::VIR_MIGRATE_PERSIST_DEST,
+        sys::VIR_MIGRATE_UNDEFINE_SOURCE,
+    ] {
+        assert_ne!(flags & flag, 0);
+    }
+    assert_eq!(flags & sys::VIR_MIGRATE_PEER2PEER, 0);
+
+    params.peer_to_peer = true;
+    params.persistent = false;
+    let flags = migrate_flags(&params, true).unwrap();
+    assert_ne!(flags & sys::VIR_MIGRATE_PEER2PEER, 0);
+    assert_eq!(flags & sys::VIR_MIGRATE_PERSIST_DEST, 0);
+}
+
+#[test]
+fn offline_migration_moves_the_definition() {
+    let mut params = MigrateParams::new("qemu+ssh://other/system");
+    params.mode = MigrationMode::Offline;
+    params.persistent = false;
+    let flags = migrate_flags(&params, false).unwrap();
+    assert_ne!(flags & sys::VIR_MIGRATE_OFFLINE, 0);
+    assert_ne!(flags & sys::VIR_MIGRATE_PERSIST_DEST, 0);
+    assert_eq!(flags & sys::VIR_MIGRATE_LIVE, 0);
+
+    params.copy_storage = true;
+    assert!(migrate_flags(&params, false).is_err());
+}
+
+#[test]
+fn shut_off_vms_cannot_migrate_live() {
+    let params = MigrateParams::new("qemu+ssh://other/system");
+    let err = migrate_flags(&params, false).unwrap_err();
+    assert!(err.to_string().contains("offline"));
+}
+
+#[test]
+fn guests_need_their_model_or_the_source_host_cpu() {
+    let custom = required_cpu_xml(CpuMode::Custom, Some("EPYC"), CAPABILITIES).unwrap();
+    assert!(custom.contains("<model fallback='forbid'>EPYC</model>"));
+    assert_eq!(required_cpu_xml(CpuMode::Custom, None, CAPABILITIES), None);
+
+    let host = required_cpu_xml(CpuMode::HostPassthrough, None, CAPABILITIES).unwrap();
+    assert!(host.starts_with("<cpu") && host.ends_with("</cpu>"));
+    assert!(host.contains("Skylake-Client-IBRS"));
+    assert!(!host.contains("ignored"));
+    assert_eq!(required_cpu_xml(CpuMode::HostModel, None, "<capabilities/>"), None);
+}
@@ -0,0 +1,115 @@
+// Guest OS detection and per-OS hardware defaults, against a trimmed-down
+// osinfo-db tree in fixtures/osinfo and the built-in table.
+
+use std::path::Path;
+
+use grustyvman_core::osinfo::{self, OsDatabase, OsFamily};
+use grustyvman_core::types::{DiskBus, FirmwareType, NetworkModel, TpmModel, VideoModel};
+
+fn fixture_db() -> OsDatabase {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/osinfo");
+    OsDatabase::from_osinfo_dir(&dir).unwrap()
+}
+
+fn pvd(label: &str) -> Vec<u8> {
+    let mut pvd = vec![0u8; osinfo::ISO_PVD_LEN as usize];
+    pvd[0] = 1;
+    pvd[1..6].copy_from_slice(b"CD001");
+    pvd[40..72].fill(b' ');
+    pvd[40..40 + label.len()].copy_from_slice(label.as_bytes());
+    pvd
+}
+
+// --- ISO labels ---
+
+#[test]
+fn iso_volume_label_is_trimmed() {
+    let label = osinfo::iso_volume_label(&pvd("CCCOMA_X64FRE_EN-US_DV9"));
+    assert_eq!(label.as_deref(), Some("CCCOMA_X64FRE_EN-US_DV9"));
+}
+
+#[test]
+fn iso_volume_label_rejects_other_descriptors() {
+    let mut data = pvd("Fedora-WS-Live-40");
+    data[0] = 2;
+    assert_eq!(osinfo::iso_volume_label(&data), None);
+    assert_eq!(osinfo::iso_volume_label(&[0u8; 16]), None);
+    assert_eq!(osinfo::iso_volume_label(&pvd("")), None);
+}
+
+// --- osinfo-db ---
+
+#[test]
+fn osinfo_db_loads_every_os() {
+    let db = fixture_db();
+    let mut ids: Vec<&str> = db.variants().iter().map(|v| v.short_id.as_str()).collect();
+    ids.sort();
+    assert_eq!(ids, ["centos4.9", "fedora40", "win10", "win11"]);
+    // Without media there is nothing to detect, so no wizard entry either
+    assert!(db.installable().all(|v| v.short_id != "centos4.9"));
+}
+
+#[test]
+fn detect_prefers_newest_release() {
+    let db = fixture_db();
+    let os = db.detect("CCCOMA_X64FRE_EN-US_DV9").unwrap();
+    assert_eq!(os.short_id, "win11");
+    assert_eq!(os.name, "Microsoft Windows 11");
+    // Only Windows 10 lists the CENA prefix
+    assert_eq!(db.detect("CENA_X64FRE_EN-US_DV5").unwrap().short_id, "win10");
+    assert!(db.detect("SOMETHING_ELSE").is_none());
+    assert!(db.detect("  ").is_none());
+}
+
+#[test]
+fn windows_defaults_inherit_through_derives_from() {
+    let db = fixture_db();
+    let os = db.find("win11").unwrap();
+    assert_eq!(os.family, OsFamily::Windows);
+    let d = &os.defaults;
+    assert_eq!(d.vcpus, 2);
+    assert_eq!(d.memory_mib, 8192);
+    assert_eq!(d.disk_size_gib, 64);
+    assert_eq!(d.disk_bus, DiskBus::Sata);
+    assert_eq!(d.nic_model, NetworkModel::E1000e);
+    assert_eq!(d.tpm, Some(TpmModel::Crb));
+    assert_eq!(d.firmware, FirmwareType::Efi);
+    assert!(d.hyperv);
+
+    let win10 = &db.find("win10").unwrap().defaults;
+    assert_eq!(win10.tpm, None);
+    assert_eq!(win10.memory_mib, 4096);
+}
+
+#[test]
+fn linux_defaults_follow_supported_devices() {
+    let db = fixture_db();
+    let fedora = &db.find("fedora40").unwrap().defaults;
+    assert_eq!(fedora.disk_bus, DiskBus::Virtio);
+    assert_eq!(fedora.nic_model, NetworkModel::Virtio);
+    assert_eq!(fedora.video, VideoModel::Virtio);
+    assert_eq!(fedora.firmware, FirmwareType::Efi);
+    assert!(!fedora.hyperv);
+
+    // No virtio devices listed: fall back to emulated hardware
+    let centos = &db.find("centos4.9").unwrap().defaults;
+    assert_eq!(centos.disk_bus, DiskBus::Ide);
+    assert_eq!(centos.nic_model, NetworkModel::E1000);
+    assert_eq!(centos.video, VideoModel::Vga);
+    assert_eq!(centos.firmware, FirmwareType::Bios);
+    assert_eq!(centos.memory_mib, 512);
+}
+
+// --- Built-in table ---
+
+#[test]
+fn builtin_table_detects_common_installers() {
+    let db = OsDatabase::builtin();
+    let detect = |label: &str| db.detect(label).map(|v| v.short_id.as_str());
+    assert_eq!(detect("CCCOMA_X64FRE_EN-US_DV9"), Some("win11"));
+    assert_eq!(detect("Fedora-WS-Live-40-1-14"), Some("fedora-unknown"));
+    assert_eq!(detect("Ubuntu 24.04 LTS amd64"), Some("ubuntu-lts-latest"));
+    assert_eq!(detect("CentOS-5.11 x86_64"), Some("centos5.11"));
+    assert_eq!(detect("14_1_RELEASE_AMD64_CD"), Some("freebsd-unknown"));
+    assert_eq!(detect("ARCH_202410"), Some("archlinux"));
+}
@@ -0,0 +1,173 @@
+// Snapshot flags, definitions and the parent/child tree.
+
+use grustyvman_core::snapshot::{
+    embedded_domain_xml, parse_snapshot_xml, snapshot_flags, snapshot_tree, snapshot_xml,
+};
+use grustyvman_core::types::{
+    CreateSnapshotParams, DiskSnapshotMode, SnapshotDisk, SnapshotInfo, SnapshotKind,
+    SnapshotState,
+};
+use virt::sys;
+
+const EXTERNAL_SNAPSHOT: &str = "<domainsnapshot>
+  <name>before-upgrade</name>
+  <description>Schema v1</description>
+  <state>running</state>
+  <parent>
+    <name>base</name>
+  </parent>
+  <creationTime>1700000000</creationTime>
+  <memory snapshot='external' file='/var/lib/libvirt/images/db-before-upgrade.mem'/>
+  <disks>
+    <disk name='vda' snapshot='external' type='file'>
+      <driver type='qcow2'/>
+      <source file='/var/lib/libvirt/images/db.before-upgrade'/>
+    </disk>
+    <disk name='vdb' snapshot='no'/>
+  </disks>
+  <domain type='kvm'>
+    <name>db</name>
+    <description>The domain's own description</description>
+  </domain>
+</domainsnapshot>";
+
+fn snap(name: &str, parent: Option<&str>, creation_time: i64) -> SnapshotInfo {
+    SnapshotInfo {
+        name: name.to_string(),
+        description: String::new(),
+        state: SnapshotState::Shutoff,
+        creation_time,
+        is_current: false,
+        parent: parent.map(str::to_string),
+        disks: Vec::new(),
+        memory_file: None,
+    }
+}
+
+#[test]
+fn snapshot_flags_follow_the_kind() {
+    let mut params = CreateSnapshotParams::new("s", "");
+    assert_eq!(
+        snapshot_flags(&params, true).unwrap(),
+        sys::VIR_DOMAIN_SNAPSHOT_CREATE_ATOMIC
+    );
+
+    params.kind = SnapshotKind::External;
+    params.atomic = false;
+    assert_eq!(snapshot_flags(&params, true).unwrap(), 0);
+    // A shut-off VM has no memory, so only its disks are snapshotted
+    assert_eq!(
+        snapshot_flags(&params, false).unwrap(),
+        sys::VIR_DOMAIN_SNAPSHOT_CREATE_DISK_ONLY
+    );
+
+    params.kind = SnapshotKind::DiskOnly;
+    params.quiesce = true;
+    assert_eq!(
+        snapshot_flags(&params, true).unwrap(),
+        sys::VIR_DOMAIN_SNAPSHOT_CREATE_DISK_ONLY | sys::VIR_DOMAIN_SNAPSHOT_CREATE_QUIESCE
+    );
+}
+
+#[test]
+fn quiescing_needs_a_disk_only_snapshot_of_a_running_vm() {
+    let mut params = CreateSnapshotParams::new("s", "");
+    params.quiesce = true;
+    assert!(snapshot_flags(&params, true).is_err());
+
+    params.kind = SnapshotKind::DiskOnly;
+    assert!(snapshot_flags(&params, false).is_err());
+}
+
+#[test]
+fn external_snapshot_xml_names_memory_file_and_disks() {
+    let mut params = CreateSnapshotParams::new("before-upgrade", "Schema v1 & data");
+    params.kind = SnapshotKind::External;
+    params.disks = vec![
+        SnapshotDisk {
+            target_dev: "vda".to_string(),
+            mode: DiskSnapshotMode::External,
+            file: Some("/images/db.overlay".to_string()),
+        },
+        SnapshotDisk {
+            target_dev: "vdb".to_string(),
+            mode: DiskSnapshotMode::Excluded,
+            file: None,
+        },
+    ];
+    let xml = snapshot_xml(&params, true, Some("/images/db.mem"));
+    assert!(xml.contains("<description>Schema v1 &amp; data</description>"), "{xml}");
+    assert!(xml.contains("<memory snapshot=\"external\" file=\"/images/db.mem\"/>"), "{xml}");
+    assert!(xml.contains("<source file=\"/images/db.overlay\"/>"), "{xml}");
+    assert!(xml.contains("<disk name=\"vdb\" snapshot=\"no\"/>"), "{xml}");
+
+    // Read back, it describes the same snapshot
+    let info = parse_snapshot_xml(&xml).unwrap();
+    assert_eq!(info.memory_file.as_deref(), Some("/images/db.mem"));
+    assert_eq!(info.disks, params.disks);
+
+    params.kind = SnapshotKind::DiskOnly;
+    params.disks.clear();
+    let xml = snapshot_xml(&params, true, None);
+    assert!(xml.contains("<memory snapshot=\"no\"/>"), "{xml}");
+    assert!(!xml.contains("<disks"), "{xml}");
+
+    params.kind = SnapshotKind::Internal;
+    assert!(!snapshot_xml(&params, true, None).contains("<memory"));
+}
+
+#[test]
+fn parsing_ignores_the_embedded_domain() {
+    let info = parse_snapshot_xml(EXTERNAL_SNAPSHOT).unwrap();
+    assert_eq!(info.name, "before-upgrade");
+    assert_eq!(info.description, "Schema v1");
+    assert_eq!(info.state, SnapshotState::Running);
+    assert_eq!(info.creation_time, 1_700_000_000);
+    assert_eq!(info.parent.as_deref(), Some("base"));
+    assert_eq!(
+        info.memory_file.as_deref(),
+        Some("/var/lib/libvirt/images/db-before-upgrade.mem")
+    );
+    assert_eq!(info.disks.len(), 2);
+    assert_eq!(
+        info.disks[0].file.as_deref(),
+        Some("/var/lib/libvirt/images/db.before-upgrade")
+    );
+    assert_eq!(info.disks[1].mode, DiskSnapshotMode::Excluded);
+    assert!(info.is_external());
+}
+
+#[test]
+fn embedded_domain_is_the_recorded_config() {
+    let xml = embedded_domain_xml(EXTERNAL_SNAPSHOT).unwrap();
+    assert!(xml.starts_with("<domain"));
+    assert!(xml.contains("<name>db</name>"));
+    assert!(!xml.contains("before-upgrade"));
+    assert_eq!(embedded_domain_xml("<domainsnapshot><name>x</name></domainsnapshot>"), None);
+}
+
+#[test]
+fn tree_puts_children_below_their_parent() {
+    // Listed newest first, as list_snapshots returns them
+    let snapshots = vec![
+        snap("branch-b", Some("base"), 50),
+        snap("orphan", Some("deleted"), 40),
+        snap("tip", Some("branch-a"), 30),
+        snap("branch-a", Some("base"), 20),
+        snap("base", None, 10),
+    ];
+    let tree: Vec<(usize, &str)> = snapshot_tree(&snapshots)
+        .into_iter()
+        .map(|(depth, s)| (depth, s.name.as_str()))
+        .collect();
+    assert_eq!(
+        tree,
+        vec![
+            (0, "base"),
+            (1, "branch-a"),
+            (2, "tip"),
+            (1, "branch-b"),
+            (0, "orphan"),
+        ]
+    );
+}
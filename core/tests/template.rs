@@ -0,0 +1,152 @@
+// Template records in domain metadata, instance naming and seed attachment.
+
+use grustyvman_core::domain_model::DomainXml;
+use grustyvman_core::template::{self, TEMPLATE_NS};
+use grustyvman_core::types::TemplateInfo;
+use grustyvman_core::xml_tree::Document;
+
+/// A template as libvirt reports it, with a libosinfo record next to ours.
+fn template_domain() -> String {
+    format!(
+        r#"<domain type="kvm">
+  <name>golden</name>
+  <uuid>6695eb01-f6a4-8304-79aa-97f2502e193f</uuid>
+  <metadata>
+    <libosinfo:libosinfo xmlns:libosinfo="http://libosinfo.org/xmlns/libvirt/domain/1.0">
+      <libosinfo:os id="http://fedoraproject.org/fedora/40"/>
+    </libosinfo:libosinfo>
+    <grustyvman:template xmlns:grustyvman="{TEMPLATE_NS}">
+      <description>Fedora 40 base</description>
+      <created>1760000000</created>
+    </grustyvman:template>
+  </metadata>
+  <devices>
+    <disk type="file" device="disk">
+      <driver name="qemu" type="qcow2"/>
+      <source file="/var/lib/libvirt/images/golden.qcow2"/>
+      <target dev="vda" bus="virtio"/>
+    </disk>
+    <disk type="file" device="cdrom">
+      <driver name="qemu" type="raw"/>
+      <source file="/var/lib/libvirt/images/golden-cidata.iso"/>
+      <target dev="sda" bus="sata"/>
+      <readonly/>
+    </disk>
+    <interface type="network">
+      <mac address="52:54:00:12:34:56"/>
+      <source network="default"/>
+    </interface>
+  </devices>
+</domain>
+"#
+    )
+}
+
+fn names(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+// --- Metadata record ---
+
+#[test]
+fn template_record_is_read_from_metadata() {
+    let domain = DomainXml::parse(&template_domain()).unwrap();
+    assert_eq!(
+        domain.template(),
+        Some(TemplateInfo {
+            description: "Fedora 40 base".to_string(),
+            created: 1_760_000_000,
+        })
+    );
+}
+
+#[test]
+fn records_in_other_namespaces_are_not_templates() {
+    let xml = template_domain().replace(TEMPLATE_NS, "http://example.com/other");
+    assert_eq!(DomainXml::parse(&xml).unwrap().template(), None);
+}
+
+#[test]
+fn metadata_xml_round_trips() {
+    let info = TemplateInfo {
+        description: "web <tier> & friends".to_string(),
+        created: 42,
+    };
+    // libvirt hands the record back in our namespace
+    let xml = template::template_metadata_xml(&info).replacen(
+        "<template>",
+        &format!(r#"<grustyvman:template xmlns:grustyvman="{TEMPLATE_NS}">"#),
+        1,
+    );
+    let xml = xml.replace("</template>", "</grustyvman:template>");
+    let doc = Document::parse(&xml).unwrap();
+    assert!(template::is_template_element(doc.root()));
+    assert_eq!(template::template_info(doc.root()), info);
+}
+
+#[test]
+fn clones_of_a_template_are_ordinary_vms() {
+    let mut domain = DomainXml::parse(&template_domain()).unwrap();
+    domain.prepare_clone("web-1", &[]);
+    assert_eq!(domain.template(), None);
+    let xml = domain.to_xml();
+    assert!(xml.contains("libosinfo:os"));
+    assert!(!xml.contains("52:54:00:12:34:56"));
+}
+
+// --- Instance names ---
+
+#[test]
+fn instance_names_fill_in_the_pattern() {
+    let got = template::instance_names("{template}-{n}", "web", 3, &[]).unwrap();
+    assert_eq!(got, names(&["web-1", "web-2", "web-3"]));
+}
+
+#[test]
+fn instance_names_skip_taken_numbers() {
+    let taken = names(&["web-1", "web-3"]);
+    let got = template::instance_names("web-{n}", "golden", 3, &taken).unwrap();
+    assert_eq!(got, names(&["web-2", "web-4", "web-5"]));
+}
+
+#[test]
+fn pattern_without_number() {
+    assert_eq!(
+        template::instance_names("db", "golden", 1, &[]).unwrap(),
+        names(&["db"])
+    );
+    assert_eq!(
+        template::instance_names("db", "golden", 2, &[]).unwrap(),
+        names(&["db-1", "db-2"])
+    );
+    assert!(template::instance_names("db", "golden", 1, &names(&["db"])).is_err());
+    assert!(template::instance_names("  ", "golden", 1, &[]).is_err());
+}
+
+// --- Seed ---
+
+#[test]
+fn seed_replaces_the_template_seed() {
+    let xml = template::attach_seed_xml(&template_domain(), "/pool/web-1-cidata.iso").unwrap();
+    let disks = DomainXml::parse(&xml).unwrap().disks();
+    let cdroms: Vec<_> = disks.iter().filter(|d| d.device_type == "cdrom").collect();
+    assert_eq!(cdroms.len(), 1);
+    assert_eq!(cdroms[0].target_dev, "sda");
+    assert_eq!(cdroms[0].source_file.as_deref(), Some("/pool/web-1-cidata.iso"));
+}
+
+#[test]
+fn seed_goes_on_a_free_sata_slot() {
+    let xml = template_domain().replace("golden-cidata.iso", "installer.iso");
+    let xml = template::attach_seed_xml(&xml, "/pool/web-1-cidata.iso").unwrap();
+    let disks = DomainXml::parse(&xml).unwrap().disks();
+    let seed = disks
+        .iter()
+        .find(|d| d.source_file.as_deref() == Some("/pool/web-1-cidata.iso"))
+        .unwrap();
+    assert_eq!((seed.target_dev.as_str(), seed.bus.as_str()), ("sdb", "sata"));
+    assert_eq!(seed.device_type, "cdrom");
+    assert!(disks
+        .iter()
+        .any(|d| d.source_file.as_deref().is_some_and(|f| f.ends_with("/installer.iso"))));
+}
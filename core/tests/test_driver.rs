@@ -0,0 +1,561 @@
+// Integration tests for the backend API against libvirt's test driver.
+//
+// The test driver runs inside the libvirt client library, so these need
+// libvirt installed but no daemon, KVM or root. Each test copies
+// tests/fixtures/testdriver/host.xml to its own file and connects to
+// test://<that file>: every URI gets a private in-memory host, so tests can
+// run in parallel without seeing each other's changes.
+//
+// Run with `cargo test -p grustyvman-core --test test_driver`.
+
+use std::fs;
+use std::path::Path;
+
+use grustyvman_core::types::{
+    CreateSnapshotParams, ForwardMode, NetworkCreateParams, NetworkState, PoolCreateParams,
+    PoolState, SnapshotState, VmState,
+};
+use grustyvman_core::jobs::Job;
+use grustyvman_core::{
+    connection, domain, domain_xml, network, snapshot, storage, template, AppError,
+};
+
+const WEB_UUID: &str = "6695eb01-f6a4-8304-79aa-97f2502e193f";
+const DB_UUID: &str = "0f1e2d3c-4b5a-6978-8796-a5b4c3d2e1f0";
+const IMAGES_POOL_UUID: &str = "35bb2ad9-388a-cdfe-461a-b8907f6e53fe";
+const MISSING_UUID: &str = "00000000-0000-0000-0000-00000000dead";
+
+/// Connection URI for a fresh copy of the test host, private to `test`.
+fn test_host(test: &str) -> String {
+    let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/testdriver/host.xml");
+    let dst = std::env::temp_dir().join(format!(
+        "grustyvman-test-driver-{}-{test}.xml",
+        std::process::id()
+    ));
+    fs::copy(&src, &dst).unwrap();
+    let uri = format!("test://{}", dst.display());
+    // The file is only read when the connection opens; the registry then
+    // keeps that connection (and its state) for the rest of the test.
+    connection::open_conn(&uri).unwrap();
+    let _ = fs::remove_file(&dst);
+    uri
+}
+
+fn vm_state(uri: &str, uuid: &str) -> VmState {
+    connection::list_all_vms(uri)
+        .unwrap()
+        .into_iter()
+        .find(|vm| vm.uuid == uuid)
+        .unwrap_or_else(|| panic!("no VM with UUID {uuid}"))
+        .state
+}
+
+fn pool_uuid(uri: &str, name: &str) -> Option<String> {
+    storage::list_all_pools(uri)
+        .unwrap()
+        .into_iter()
+        .find(|p| p.name == name)
+        .map(|p| p.uuid)
+}
+
+fn network_uuid(uri: &str, name: &str) -> Option<String> {
+    network::list_all_networks(uri)
+        .unwrap()
+        .into_iter()
+        .find(|n| n.name == name)
+        .map(|n| n.uuid)
+}
+
+fn snapshot_names(uri: &str, uuid: &str) -> Vec<String> {
+    let mut names: Vec<String> = snapshot::list_snapshots(uri, uuid)
+        .unwrap()
+        .into_iter()
+        .map(|s| s.name)
+        .collect();
+    names.sort();
+    names
+}
+
+// --- Host ---
+
+#[test]
+fn host_info_comes_from_the_node_definition() {
+    let uri = test_host("host-info");
+    let info = connection::get_host_info(&uri).unwrap();
+    assert_eq!(info.uri, uri);
+    assert_eq!(info.cpu_cores, 4);
+    assert_eq!(info.cpu_threads, 2);
+    assert_eq!(info.cpu_mhz, 2600);
+    assert_eq!(info.memory_kib, 16_777_216);
+}
+
+#[test]
+fn bad_test_host_file_fails_to_connect() {
+    let uri = "test:///nonexistent/grustyvman/host.xml";
+    assert!(connection::open_conn(uri).is_err());
+}
+
+// --- VM lifecycle ---
+
+#[test]
+fn list_all_vms_reports_defined_domains() {
+    let uri = test_host("list-vms");
+    let vms = connection::list_all_vms(&uri).unwrap();
+    let names: Vec<&str> = vms.iter().map(|vm| vm.name.as_str()).collect();
+    assert_eq!(names, vec!["db", "web"]);
+
+    let db = &vms[0];
+    assert_eq!(db.uuid, DB_UUID);
+    assert_eq!(db.state, VmState::Running);
+    assert_eq!(db.vcpus, 4);
+    assert_eq!(db.memory_kib, 4_194_304);
+    assert!(db.id.is_some());
+
+    let web = &vms[1];
+    assert_eq!(web.state, VmState::Shutoff);
+    assert_eq!(web.id, None);
+}
+
+#[test]
+fn vm_lifecycle() {
+    let uri = test_host("lifecycle");
+
+    domain::start_vm(&uri, WEB_UUID).unwrap();
+    assert_eq!(vm_state(&uri, WEB_UUID), VmState::Running);
+
+    domain::pause_vm(&uri, WEB_UUID).unwrap();
+    assert_eq!(vm_state(&uri, WEB_UUID), VmState::Paused);
+
+    domain::resume_vm(&uri, WEB_UUID).unwrap();
+    assert_eq!(vm_state(&uri, WEB_UUID), VmState::Running);
+
+    // on_reboot is "restart"
+    domain::reboot_vm(&uri, WEB_UUID).unwrap();
+    assert_eq!(vm_state(&uri, WEB_UUID), VmState::Running);
+
+    domain::shutdown_vm(&uri, WEB_UUID).unwrap();
+    assert_eq!(vm_state(&uri, WEB_UUID), VmState::Shutoff);
+
+    domain::start_vm(&uri, WEB_UUID).unwrap();
+    domain::force_stop_vm(&uri, WEB_UUID).unwrap();
+    assert_eq!(vm_state(&uri, WEB_UUID), VmState::Shutoff);
+}
+
+#[test]
+fn saved_vms_resume_on_start() {
+    let uri = test_host("managed-save");
+
+    domain::start_vm(&uri, WEB_UUID).unwrap();
+    domain::managed_save_vm(&uri, WEB_UUID).unwrap();
+    assert_eq!(vm_state(&uri, WEB_UUID), VmState::Saved);
+    domain::start_vm(&uri, WEB_UUID).unwrap();
+    assert_eq!(vm_state(&uri, WEB_UUID), VmState::Running);
+
+    domain::managed_save_vm(&uri, WEB_UUID).unwrap();
+    domain::discard_saved_state(&uri, WEB_UUID).unwrap();
+    assert_eq!(vm_state(&uri, WEB_UUID), VmState::Shutoff);
+
+    let path = std::env::temp_dir().join(format!("grustyvman-save-{}", std::process::id()));
+    let path = path.to_string_lossy();
+    domain::start_vm(&uri, WEB_UUID).unwrap();
+    domain::save_vm_to_file(&uri, WEB_UUID, &path).unwrap();
+    assert_eq!(vm_state(&uri, WEB_UUID), VmState::Shutoff);
+    domain::restore_vm_from_file(&uri, &path).unwrap();
+    assert_eq!(vm_state(&uri, WEB_UUID), VmState::Running);
+    let _ = fs::remove_file(&*path);
+}
+
+#[test]
+fn lifecycle_errors_are_classified() {
+    let uri = test_host("lifecycle-errors");
+
+    let err = domain::start_vm(&uri, DB_UUID).unwrap_err();
+    assert!(matches!(err, AppError::InvalidState(_)), "{err:?}");
+
+    let err = domain::pause_vm(&uri, WEB_UUID).unwrap_err();
+    assert!(matches!(err, AppError::InvalidState(_)), "{err:?}");
+
+    let err = domain::start_vm(&uri, MISSING_UUID).unwrap_err();
+    assert!(matches!(err, AppError::NotFound(_)), "{err:?}");
+
+    // Neither error is a connection problem, so the host stays usable
+    assert_eq!(connection::list_all_vms(&uri).unwrap().len(), 2);
+}
+
+#[test]
+fn domain_xml_edits_are_applied() {
+    let uri = test_host("edit-xml");
+    let xml = domain::get_domain_xml(&uri, WEB_UUID).unwrap();
+    let details = domain_xml::parse_domain_xml(&xml).unwrap();
+    assert_eq!(details.name, "web");
+    assert_eq!(details.vcpus, 2);
+
+    let edited = domain_xml::modify_domain_xml(&xml, 3, 1024).unwrap();
+    domain::update_domain_xml(&uri, &edited).unwrap();
+
+    let vm = connection::list_all_vms(&uri)
+        .unwrap()
+        .into_iter()
+        .find(|vm| vm.uuid == WEB_UUID)
+        .unwrap();
+    assert_eq!(vm.vcpus, 3);
+    assert_eq!(vm.memory_kib, 1024 * 1024);
+
+    assert_eq!(
+        domain::get_vm_disk_paths(&uri, WEB_UUID).unwrap(),
+        vec!["/images/web.img"]
+    );
+}
+
+#[test]
+fn autostart_round_trips() {
+    let uri = test_host("autostart");
+    assert!(!domain::get_autostart(&uri, WEB_UUID).unwrap());
+    domain::set_autostart(&uri, WEB_UUID, true).unwrap();
+    assert!(domain::get_autostart(&uri, WEB_UUID).unwrap());
+    domain::set_autostart(&uri, WEB_UUID, false).unwrap();
+    assert!(!domain::get_autostart(&uri, WEB_UUID).unwrap());
+}
+
+#[test]
+fn templates_cannot_be_started() {
+    let uri = test_host("template");
+    domain::set_autostart(&uri, WEB_UUID, true).unwrap();
+    template::mark_template(&uri, WEB_UUID, "web base").unwrap();
+
+    let info = template::get_template(&uri, WEB_UUID).unwrap().unwrap();
+    assert_eq!(info.description, "web base");
+    assert!(!domain::get_autostart(&uri, WEB_UUID).unwrap());
+    let vms = connection::list_all_vms(&uri).unwrap();
+    assert!(vms.iter().find(|vm| vm.uuid == WEB_UUID).unwrap().is_template);
+    assert!(!vms.iter().find(|vm| vm.uuid == DB_UUID).unwrap().is_template);
+
+    assert!(matches!(domain::start_vm(&uri, WEB_UUID), Err(AppError::Backend(_))));
+    assert!(domain::set_autostart(&uri, WEB_UUID, true).is_err());
+    assert_eq!(vm_state(&uri, WEB_UUID), VmState::Shutoff);
+
+    template::unmark_template(&uri, WEB_UUID).unwrap();
+    assert_eq!(template::get_template(&uri, WEB_UUID).unwrap(), None);
+    domain::start_vm(&uri, WEB_UUID).unwrap();
+    assert_eq!(vm_state(&uri, WEB_UUID), VmState::Running);
+}
+
+#[test]
+fn running_vms_cannot_become_templates() {
+    let uri = test_host("template-running");
+    assert!(template::mark_template(&uri, DB_UUID, "").is_err());
+    assert_eq!(template::get_template(&uri, DB_UUID).unwrap(), None);
+}
+
+#[test]
+fn clone_copies_disks_within_their_pool() {
+    let uri = test_host("clone");
+    domain::clone_domain(&uri, WEB_UUID, "web-copy", true).unwrap();
+
+    let vms = connection::list_all_vms(&uri).unwrap();
+    let copy = vms.iter().find(|vm| vm.name == "web-copy").unwrap();
+    assert_ne!(copy.uuid, WEB_UUID);
+    let xml = domain::get_domain_xml(&uri, &copy.uuid).unwrap();
+    assert_eq!(domain_xml::extract_disk_paths(&xml), vec!["/images/web-copy.img"]);
+    let volumes = storage::list_pool_volumes(&uri, IMAGES_POOL_UUID).unwrap();
+    assert!(volumes.iter().any(|v| v.name == "web-copy.img"));
+
+    // Running VMs are refused before anything is created
+    assert!(domain::clone_domain(&uri, DB_UUID, "db-copy", true).is_err());
+    let volumes = storage::list_pool_volumes(&uri, IMAGES_POOL_UUID).unwrap();
+    assert!(!volumes.iter().any(|v| v.name.starts_with("db-copy")));
+}
+
+#[test]
+fn rename_keeps_the_uuid() {
+    let uri = test_host("rename");
+    domain::rename_domain(&uri, WEB_UUID, "frontend").unwrap();
+    assert_eq!(domain::get_domain_name(&uri, WEB_UUID).unwrap(), "frontend");
+    assert_eq!(vm_state(&uri, WEB_UUID), VmState::Shutoff);
+}
+
+#[test]
+fn delete_vm_undefines_running_domain() {
+    let uri = test_host("delete-vm");
+    domain::delete_vm(&uri, DB_UUID).unwrap();
+    let vms = connection::list_all_vms(&uri).unwrap();
+    assert!(vms.iter().all(|vm| vm.uuid != DB_UUID));
+
+    let err = domain::get_domain_xml(&uri, DB_UUID).unwrap_err();
+    assert!(matches!(err, AppError::NotFound(_)), "{err:?}");
+}
+
+#[test]
+fn delete_vm_with_storage_removes_volumes() {
+    let uri = test_host("delete-vm-storage");
+    let paths = domain::get_vm_disk_paths(&uri, WEB_UUID).unwrap();
+    domain::delete_vm_with_storage(&uri, WEB_UUID, paths).unwrap();
+
+    let names: Vec<String> = storage::list_pool_volumes(&uri, IMAGES_POOL_UUID)
+        .unwrap()
+        .into_iter()
+        .map(|v| v.name)
+        .collect();
+    assert_eq!(names, vec!["db.img"]);
+}
+
+// --- Storage ---
+
+#[test]
+fn list_pools_and_volumes() {
+    let uri = test_host("list-pools");
+    let pools = storage::list_all_pools(&uri).unwrap();
+    let images = pools.iter().find(|p| p.name == "images").unwrap();
+    assert_eq!(images.uuid, IMAGES_POOL_UUID);
+    assert_eq!(images.state, PoolState::Running);
+    assert!(images.active);
+    assert!(images.persistent);
+
+    let volumes = storage::list_pool_volumes(&uri, IMAGES_POOL_UUID).unwrap();
+    let paths: Vec<&str> = volumes.iter().map(|v| v.path.as_str()).collect();
+    assert_eq!(paths, vec!["/images/db.img", "/images/web.img"]);
+    assert_eq!(volumes[0].capacity, 10 * 1024 * 1024 * 1024);
+
+    let all = storage::list_all_pool_volumes(&uri).unwrap();
+    assert!(all
+        .iter()
+        .any(|(name, vols)| name == "images" && vols.len() == 2));
+
+    let xml = storage::get_pool_xml(&uri, IMAGES_POOL_UUID).unwrap();
+    assert_eq!(
+        storage::extract_pool_type_and_path(&xml),
+        ("dir".to_string(), "/images".to_string())
+    );
+}
+
+#[test]
+fn volume_crud() {
+    let uri = test_host("volume-crud");
+    storage::create_volume(&uri, IMAGES_POOL_UUID, "scratch.qcow2", 1 << 30, "qcow2").unwrap();
+    let volumes = storage::list_pool_volumes(&uri, IMAGES_POOL_UUID).unwrap();
+    let scratch = volumes.iter().find(|v| v.name == "scratch.qcow2").unwrap();
+    assert_eq!(scratch.path, "/images/scratch.qcow2");
+    assert_eq!(scratch.capacity, 1 << 30);
+
+    // Creating the same name twice is refused
+    assert!(
+        storage::create_volume(&uri, IMAGES_POOL_UUID, "scratch.qcow2", 1 << 30, "qcow2").is_err()
+    );
+
+    storage::delete_volume(&uri, IMAGES_POOL_UUID, "scratch.qcow2").unwrap();
+    assert_eq!(
+        storage::list_pool_volumes(&uri, IMAGES_POOL_UUID)
+            .unwrap()
+            .len(),
+        2
+    );
+
+    let err = storage::delete_volume(&uri, IMAGES_POOL_UUID, "scratch.qcow2").unwrap_err();
+    assert!(matches!(err, AppError::NotFound(_)), "{err:?}");
+
+    storage::delete_volume_by_path(&uri, "/images/db.img").unwrap();
+    assert_eq!(
+        storage::list_pool_volumes(&uri, IMAGES_POOL_UUID)
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn volume_details_resize_and_clone() {
+    let uri = test_host("volume-ops");
+    let web = storage::volume_details(&uri, IMAGES_POOL_UUID, "web.img").unwrap();
+    assert_eq!(web.path, "/images/web.img");
+    assert_eq!(web.capacity, 10 << 30);
+    assert_eq!(web.used_by, vec!["web"]);
+    assert!(web.backing_chain.is_empty());
+
+    // Shrinking needs consent; volumes of running VMs are left alone
+    let err = storage::resize_volume(&uri, IMAGES_POOL_UUID, "web.img", 5 << 30, false, false)
+        .unwrap_err();
+    assert!(err.to_string().contains("shrinking"), "{err}");
+    let grow = storage::resize_volume(&uri, IMAGES_POOL_UUID, "db.img", 20 << 30, false, false);
+    assert!(grow.is_err());
+
+    let job = Job::detached("Clone");
+    let path = storage::clone_pool_volume(
+        &uri,
+        IMAGES_POOL_UUID,
+        "web.img",
+        IMAGES_POOL_UUID,
+        "web-2.img",
+        &job,
+    )
+    .unwrap();
+    assert_eq!(path, "/images/web-2.img");
+    let copy = storage::volume_details(&uri, IMAGES_POOL_UUID, "web-2.img").unwrap();
+    assert_eq!(copy.capacity, web.capacity);
+    assert!(copy.used_by.is_empty());
+}
+
+#[test]
+fn create_vm_disk_falls_back_to_first_active_pool() {
+    let uri = test_host("vm-disk");
+    // There is no pool called "default" in the test host
+    let path = storage::create_vm_disk(&uri, "new-vm", 20, "qcow2", "qcow2", None).unwrap();
+    assert_eq!(path, "/images/new-vm.qcow2");
+    assert!(storage::list_pool_volumes(&uri, IMAGES_POOL_UUID)
+        .unwrap()
+        .iter()
+        .any(|v| v.path == path));
+}
+
+#[test]
+fn pool_crud() {
+    let uri = test_host("pool-crud");
+    let params = PoolCreateParams {
+        target_path: "/scratch".to_string(),
+        ..Default::default()
+    };
+    storage::create_pool(&uri, "scratch", "dir", &params).unwrap();
+    let uuid = pool_uuid(&uri, "scratch").unwrap();
+
+    let pool = |uri: &str| {
+        storage::list_all_pools(uri)
+            .unwrap()
+            .into_iter()
+            .find(|p| p.uuid == uuid)
+            .unwrap()
+    };
+    assert!(pool(&uri).active);
+
+    storage::stop_pool(&uri, &uuid).unwrap();
+    assert_eq!(pool(&uri).state, PoolState::Inactive);
+    storage::start_pool(&uri, &uuid).unwrap();
+    storage::refresh_pool(&uri, &uuid).unwrap();
+    assert_eq!(pool(&uri).state, PoolState::Running);
+
+    storage::set_pool_autostart(&uri, &uuid, true).unwrap();
+    assert!(pool(&uri).autostart);
+
+    storage::delete_pool(&uri, &uuid).unwrap();
+    assert!(pool_uuid(&uri, "scratch").is_none());
+
+    let err = storage::start_pool(&uri, &uuid).unwrap_err();
+    assert!(matches!(err, AppError::NotFound(_)), "{err:?}");
+}
+
+// --- Networks ---
+
+#[test]
+fn list_networks_parses_definition() {
+    let uri = test_host("list-networks");
+    let networks = network::list_all_networks(&uri).unwrap();
+    let default = networks.iter().find(|n| n.name == "default").unwrap();
+    assert_eq!(default.state, NetworkState::Active);
+    assert_eq!(default.forward_mode, ForwardMode::Nat);
+    assert_eq!(default.bridge_name.as_deref(), Some("virbr0"));
+    assert_eq!(default.ip_address.as_deref(), Some("192.168.122.1"));
+    assert_eq!(default.dhcp_start.as_deref(), Some("192.168.122.2"));
+    assert_eq!(default.dhcp_end.as_deref(), Some("192.168.122.254"));
+
+    assert!(domain::list_networks(&uri)
+        .unwrap()
+        .contains(&"default".to_string()));
+}
+
+#[test]
+fn network_crud() {
+    let uri = test_host("network-crud");
+    let params = NetworkCreateParams {
+        name: "lab".to_string(),
+        forward_mode: ForwardMode::Isolated,
+        bridge_name: String::new(),
+        ip_address: "10.10.0.1".to_string(),
+        ip_netmask: "255.255.255.0".to_string(),
+        dhcp_enabled: true,
+        dhcp_start: "10.10.0.100".to_string(),
+        dhcp_end: "10.10.0.200".to_string(),
+    };
+    network::create_network(&uri, &params).unwrap();
+    let uuid = network_uuid(&uri, "lab").unwrap();
+
+    let lab = |uri: &str| {
+        network::list_all_networks(uri)
+            .unwrap()
+            .into_iter()
+            .find(|n| n.uuid == uuid)
+            .unwrap()
+    };
+    let created = lab(&uri);
+    assert!(created.active);
+    assert!(created.persistent);
+    assert_eq!(created.forward_mode, ForwardMode::Isolated);
+    assert_eq!(created.dhcp_start.as_deref(), Some("10.10.0.100"));
+
+    network::stop_network(&uri, &uuid).unwrap();
+    assert_eq!(lab(&uri).state, NetworkState::Inactive);
+    network::start_network(&uri, &uuid).unwrap();
+    assert_eq!(lab(&uri).state, NetworkState::Active);
+
+    network::set_network_autostart(&uri, &uuid, true).unwrap();
+    assert!(lab(&uri).autostart);
+
+    network::delete_network(&uri, &uuid).unwrap();
+    assert!(network_uuid(&uri, "lab").is_none());
+}
+
+// --- Snapshots ---
+
+#[test]
+fn snapshot_create_revert_delete() {
+    let uri = test_host("snapshots");
+    assert!(snapshot::list_snapshots(&uri, DB_UUID).unwrap().is_empty());
+
+    let create = |name: &str, description: &str| {
+        let params = CreateSnapshotParams::new(name, description);
+        snapshot::create_snapshot(&uri, DB_UUID, &params).unwrap();
+    };
+    create("before-upgrade", "Schema v1 & data <ok>");
+    create("after-upgrade", "");
+    assert_eq!(
+        snapshot_names(&uri, DB_UUID),
+        vec!["after-upgrade", "before-upgrade"]
+    );
+
+    let snapshots = snapshot::list_snapshots(&uri, DB_UUID).unwrap();
+    let before = snapshots
+        .iter()
+        .find(|s| s.name == "before-upgrade")
+        .unwrap();
+    assert_eq!(before.description, "Schema v1 & data <ok>");
+    assert!(!before.is_current);
+    assert!(snapshots
+        .iter()
+        .any(|s| s.name == "after-upgrade" && s.is_current));
+    assert!(snapshots
+        .iter()
+        .any(|s| s.name == "after-upgrade" && s.parent.as_deref() == Some("before-upgrade")));
+
+    snapshot::revert_snapshot(&uri, DB_UUID, "before-upgrade").unwrap();
+    let current: Vec<String> = snapshot::list_snapshots(&uri, DB_UUID)
+        .unwrap()
+        .into_iter()
+        .filter(|s| s.is_current)
+        .map(|s| s.name)
+        .collect();
+    assert_eq!(current, vec!["before-upgrade"]);
+
+    snapshot::delete_snapshot(&uri, DB_UUID, "after-upgrade").unwrap();
+    assert_eq!(snapshot_names(&uri, DB_UUID), vec!["before-upgrade"]);
+
+    let err = snapshot::delete_snapshot(&uri, DB_UUID, "after-upgrade").unwrap_err();
+    assert!(matches!(err, AppError::NotFound(_)), "{err:?}");
+}
+
+#[test]
+fn snapshot_of_stopped_vm_records_state() {
+    let uri = test_host("snapshot-state");
+    let params = CreateSnapshotParams::new("cold", "");
+    snapshot::create_snapshot(&uri, WEB_UUID, &params).unwrap();
+    let snapshots = snapshot::list_snapshots(&uri, WEB_UUID).unwrap();
+    assert_eq!(snapshots.len(), 1);
+    assert_eq!(snapshots[0].state, SnapshotState::Shutoff);
+    assert!(snapshots[0].creation_time > 0);
+}
@@ -0,0 +1,135 @@
+// autounattend.xml generation for unattended Windows installs.
+
+use grustyvman_core::osinfo;
+use grustyvman_core::types::FirmwareType;
+use grustyvman_core::unattend::{self, WindowsUnattendConfig};
+use grustyvman_core::xml_tree::{Document, Element};
+
+fn config() -> WindowsUnattendConfig {
+    WindowsUnattendConfig {
+        locale: "de-DE".to_string(),
+        timezone: "W. Europe Standard Time".to_string(),
+        product_key: Some("VK7JG-NPHTM-C97JM-9MPGT-3V66T".to_string()),
+        computer_name: "WIN11-LAB".to_string(),
+        admin_user: "lab".to_string(),
+        admin_password: "p<&>ss".to_string(),
+        virtio_driver_dir: Some("w11".to_string()),
+    }
+}
+
+fn parse(firmware: FirmwareType, cfg: &WindowsUnattendConfig) -> Document {
+    Document::parse(&cfg.autounattend_xml(firmware)).expect("well-formed answer file")
+}
+
+fn pass<'a>(doc: &'a Document, name: &str) -> &'a Element {
+    doc.root()
+        .children_named("settings")
+        .find(|s| s.attr_is("pass", name))
+        .unwrap_or_else(|| panic!("no {name} pass"))
+}
+
+fn component<'a>(settings: &'a Element, name: &str) -> &'a Element {
+    settings
+        .children_named("component")
+        .find(|c| c.attr_is("name", name))
+        .unwrap_or_else(|| panic!("no {name} component"))
+}
+
+fn descendant_texts(e: &Element, name: &str, out: &mut Vec<String>) {
+    for child in e.children() {
+        if child.name() == name {
+            out.push(child.text());
+        }
+        descendant_texts(child, name, out);
+    }
+}
+
+fn texts(e: &Element, name: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    descendant_texts(e, name, &mut out);
+    out
+}
+
+#[test]
+fn efi_install_uses_gpt_layout() {
+    let doc = parse(FirmwareType::Efi, &config());
+    let setup = component(pass(&doc, "windowsPE"), "Microsoft-Windows-Setup");
+    assert_eq!(texts(setup, "Type"), ["EFI", "MSR", "Primary"]);
+    assert_eq!(texts(setup, "Format"), ["FAT32", "NTFS"]);
+    let install_to = setup.child("ImageInstall").unwrap().child("OSImage").unwrap();
+    assert_eq!(texts(install_to, "PartitionID"), ["3"]);
+}
+
+#[test]
+fn bios_install_uses_mbr_layout() {
+    let doc = parse(FirmwareType::Bios, &config());
+    let setup = component(pass(&doc, "windowsPE"), "Microsoft-Windows-Setup");
+    assert_eq!(texts(setup, "Type"), ["Primary", "Primary"]);
+    assert_eq!(texts(setup, "Active"), ["true"]);
+    let install_to = setup.child("ImageInstall").unwrap().child("OSImage").unwrap();
+    assert_eq!(texts(install_to, "PartitionID"), ["2"]);
+}
+
+#[test]
+fn answers_are_escaped_and_placed() {
+    let doc = parse(FirmwareType::Efi, &config());
+    let setup = component(pass(&doc, "windowsPE"), "Microsoft-Windows-Setup");
+    assert_eq!(texts(setup, "Key"), ["VK7JG-NPHTM-C97JM-9MPGT-3V66T"]);
+
+    let winpe = component(pass(&doc, "windowsPE"), "Microsoft-Windows-International-Core-WinPE");
+    assert_eq!(winpe.child_text("SystemLocale").as_deref(), Some("de-DE"));
+
+    let specialize = component(pass(&doc, "specialize"), "Microsoft-Windows-Shell-Setup");
+    assert_eq!(specialize.child_text("ComputerName").as_deref(), Some("WIN11-LAB"));
+    assert_eq!(specialize.child_text("TimeZone").as_deref(), Some("W. Europe Standard Time"));
+
+    let oobe = component(pass(&doc, "oobeSystem"), "Microsoft-Windows-Shell-Setup");
+    let accounts = oobe.child("UserAccounts").unwrap();
+    assert_eq!(texts(accounts, "Name"), ["lab"]);
+    assert_eq!(texts(accounts, "Value"), ["p<&>ss"]);
+}
+
+#[test]
+fn missing_key_and_name_are_left_to_setup() {
+    let cfg = WindowsUnattendConfig {
+        product_key: Some("  ".to_string()),
+        computer_name: String::new(),
+        ..config()
+    };
+    let doc = parse(FirmwareType::Efi, &cfg);
+    let setup = component(pass(&doc, "windowsPE"), "Microsoft-Windows-Setup");
+    assert!(texts(setup, "Key").is_empty());
+    let specialize = component(pass(&doc, "specialize"), "Microsoft-Windows-Shell-Setup");
+    assert_eq!(specialize.child_text("ComputerName").as_deref(), Some("*"));
+}
+
+#[test]
+fn virtio_drivers_are_injected_for_the_release() {
+    let doc = parse(FirmwareType::Efi, &config());
+    let pnp = component(pass(&doc, "windowsPE"), "Microsoft-Windows-PnpCustomizationsWinPE");
+    let paths = texts(pnp, "Path");
+    assert!(paths.contains(&"E:\\viostor\\w11\\amd64".to_string()));
+    assert!(paths.contains(&"D:\\NetKVM\\w11\\amd64".to_string()));
+    assert!(paths.iter().all(|p| p.ends_with("\\w11\\amd64")));
+
+    let without = WindowsUnattendConfig { virtio_driver_dir: None, ..config() };
+    let xml = without.autounattend_xml(FirmwareType::Efi);
+    assert!(!xml.contains("PnpCustomizationsWinPE"));
+    assert!(!xml.contains("virtio-win-guest-tools"));
+}
+
+#[test]
+fn virtio_driver_dir_follows_osinfo_ids() {
+    assert_eq!(unattend::virtio_driver_dir("win11"), Some("w11"));
+    assert_eq!(unattend::virtio_driver_dir("win2k22"), Some("2k22"));
+    assert_eq!(unattend::virtio_driver_dir("fedora40"), None);
+}
+
+#[test]
+fn media_iso_carries_the_answer_file() {
+    let iso = config().media_iso(FirmwareType::Efi);
+    let label = osinfo::iso_volume_label(&iso[16 * 2048..17 * 2048]);
+    assert_eq!(label.as_deref(), Some(unattend::UNATTEND_VOLUME_ID));
+    let ucs2: Vec<u8> = "autounattend.xml;1".encode_utf16().flat_map(u16::to_be_bytes).collect();
+    assert!(iso.windows(ucs2.len()).any(|w| w == ucs2.as_slice()));
+}
@@ -0,0 +1,43 @@
+// Backing stores read from storage volume XML.
+
+use grustyvman_core::storage::volume_backing_store;
+use grustyvman_core::types::{BackingStore, WipeAlgorithm};
+
+#[test]
+fn backing_store_is_read_from_volume_xml() {
+    let xml = r#"<volume type="file">
+  <name>web-1.qcow2</name>
+  <target>
+    <path>/var/lib/libvirt/images/web-1.qcow2</path>
+    <format type="qcow2"/>
+  </target>
+  <backingStore>
+    <path>/var/lib/libvirt/images/golden.qcow2</path>
+    <format type="qcow2"/>
+  </backingStore>
+</volume>"#;
+    assert_eq!(
+        volume_backing_store(xml),
+        Some(BackingStore {
+            path: "/var/lib/libvirt/images/golden.qcow2".to_string(),
+            format: "qcow2".to_string(),
+        })
+    );
+}
+
+#[test]
+fn volumes_without_backing_store() {
+    assert_eq!(volume_backing_store("<volume><name>a.img</name></volume>"), None);
+    // Some backends emit an empty element
+    let xml = "<volume><backingStore><path></path></backingStore></volume>";
+    assert_eq!(volume_backing_store(xml), None);
+}
+
+#[test]
+fn wipe_algorithms_round_trip_their_names() {
+    for algorithm in WipeAlgorithm::ALL {
+        assert_eq!(WipeAlgorithm::parse(algorithm.as_str()), Some(*algorithm));
+    }
+    assert_eq!(WipeAlgorithm::parse("shred"), None);
+    assert_eq!(WipeAlgorithm::Trim.to_libvirt(), 9);
+}
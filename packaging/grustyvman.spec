@@ -43,6 +43,8 @@ install -Dm755 target/release/grustyvman \
     %{buildroot}%{_bindir}/grustyvman
 install -Dm755 target/release/grustyvman-viewer \
     %{buildroot}%{_bindir}/grustyvman-viewer
+install -Dm755 target/release/grustyvman-cli \
+    %{buildroot}%{_bindir}/grustyvman-cli
 
 install -Dm644 packaging/com.github.grustyvman.desktop \
     %{buildroot}%{_datadir}/applications/com.github.grustyvman.desktop
@@ -73,6 +75,7 @@ fi
 %files
 %{_bindir}/grustyvman
 %{_bindir}/grustyvman-viewer
+%{_bindir}/grustyvman-cli
 %{_datadir}/applications/com.github.grustyvman.desktop
 %{_datadir}/applications/grustyvman-viewer.desktop
 %{_datadir}/icons/hicolor/256x256/apps/grustyvman.png
@@ -4,6 +4,7 @@ use gtk::prelude::*;
 use libadwaita as adw;
 
 use crate::config;
+use crate::settings::Settings;
 use crate::ui::window::Window;
 
 mod imp {
@@ -27,7 +28,7 @@ mod imp {
     impl ApplicationImpl for GrustyvmanApplication {
         fn activate(&self) {
             let app = self.obj();
-            let window = Window::new(app.upcast_ref());
+            let window = Window::new(app.upcast_ref(), Settings::load());
             window.present();
         }
     }
@@ -1,9 +0,0 @@
-pub mod connection;
-pub mod domain;
-pub mod domain_xml;
-pub mod network;
-pub mod nodedev;
-pub mod performance;
-pub mod snapshot;
-pub mod storage;
-pub mod types;
@@ -1,3 +1,4 @@
 pub const APP_ID: &str = "com.github.grustyvman";
 pub const APP_NAME: &str = "Grustyvman";
 pub const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const DEFAULT_CONNECTION_URI: &str = "qemu:///system";
@@ -1,8 +1,7 @@
 mod application;
-mod backend;
 mod config;
-mod error;
 mod models;
+mod settings;
 mod ui;
 
 use gtk4::prelude::*;
@@ -10,6 +9,9 @@ use gtk4::prelude::*;
 fn main() {
     env_logger::init();
 
+    // Must happen before the first libvirt connection is opened
+    grustyvman_core::events::init_event_loop();
+
     let app = application::GrustyvmanApplication::new();
     app.run();
 }
@@ -9,6 +9,8 @@ mod imp {
     #[derive(Debug, Default, Properties)]
     #[properties(wrapper_type = super::NetworkObject)]
     pub struct NetworkObject {
+        #[property(get, set)]
+        uri: RefCell<String>,
         #[property(get, set)]
         name: RefCell<String>,
         #[property(get, set)]
@@ -41,8 +43,9 @@ glib::wrapper! {
 }
 
 impl NetworkObject {
-    pub fn new(info: &crate::backend::types::VirtNetworkInfo) -> Self {
+    pub fn new(uri: &str, info: &grustyvman_core::types::VirtNetworkInfo) -> Self {
         glib::Object::builder()
+            .property("uri", uri)
             .property("name", &info.name)
             .property("uuid", &info.uuid)
             .property("state", info.state.label())
@@ -53,7 +56,7 @@ impl NetworkObject {
             .build()
     }
 
-    pub fn update_from(&self, info: &crate::backend::types::VirtNetworkInfo) {
+    pub fn update_from(&self, info: &grustyvman_core::types::VirtNetworkInfo) {
         self.set_name(info.name.clone());
         self.set_state(info.state.label().to_string());
         self.set_state_css(info.state.css_class().to_string());
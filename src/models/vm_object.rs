@@ -9,6 +9,8 @@ mod imp {
     #[derive(Debug, Default, Properties)]
     #[properties(wrapper_type = super::VmObject)]
     pub struct VmObject {
+        #[property(get, set)]
+        uri: RefCell<String>,
         #[property(get, set)]
         name: RefCell<String>,
         #[property(get, set)]
@@ -23,6 +25,8 @@ mod imp {
         vcpus: RefCell<u32>,
         #[property(get, set)]
         memory_kib: RefCell<u64>,
+        #[property(get, set)]
+        is_template: RefCell<bool>,
     }
 
     #[glib::object_subclass]
@@ -41,8 +45,9 @@ glib::wrapper! {
 }
 
 impl VmObject {
-    pub fn new(info: &crate::backend::types::VmInfo) -> Self {
+    pub fn new(uri: &str, info: &grustyvman_core::types::VmInfo) -> Self {
         glib::Object::builder()
+            .property("uri", uri)
             .property("name", &info.name)
             .property("uuid", &info.uuid)
             .property("state", info.state.as_str())
@@ -50,15 +55,17 @@ impl VmObject {
             .property("subtitle", &info.subtitle())
             .property("vcpus", info.vcpus)
             .property("memory-kib", info.memory_kib)
+            .property("is-template", info.is_template)
             .build()
     }
 
-    pub fn update_from(&self, info: &crate::backend::types::VmInfo) {
+    pub fn update_from(&self, info: &grustyvman_core::types::VmInfo) {
         self.set_name(info.name.clone());
         self.set_state(info.state.as_str().to_string());
         self.set_state_css(info.state.css_class().to_string());
         self.set_subtitle(info.subtitle());
         self.set_vcpus(info.vcpus);
         self.set_memory_kib(info.memory_kib);
+        self.set_is_template(info.is_template);
     }
 }
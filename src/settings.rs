@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use crate::config;
+use grustyvman_core::error::AppError;
+
+// ---------------------------------------------------------------------------
+// Persistent settings
+//
+// Stored as TOML in $XDG_CONFIG_HOME/grustyvman/settings.toml. Every field has
+// a default, so a missing file, a missing key or a file written by an older
+// version all load cleanly. Unknown keys are ignored.
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Saved connection URIs, in sidebar order.
+    pub connections: Vec<String>,
+    /// Connection that was active when the window was last closed.
+    pub last_connection: Option<String>,
+    /// Seconds between VM/pool/network list refreshes.
+    pub poll_interval_secs: u32,
+    /// Seconds between performance samples of the selected VM.
+    pub perf_interval_secs: u32,
+    /// Pool new VM disks are created in. `None` prefers "default".
+    pub default_pool: Option<String>,
+    /// Virtual network preselected for new VMs. `None` prefers "default".
+    pub default_network: Option<String>,
+    pub new_vm: NewVmDefaults,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NewVmDefaults {
+    pub vcpus: u32,
+    pub memory_mib: u64,
+    pub disk_size_gib: u64,
+    /// "qcow2" or "raw"
+    pub disk_format: String,
+    /// "bios" or "efi"
+    pub firmware: String,
+    pub tpm: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            connections: vec![config::DEFAULT_CONNECTION_URI.to_string()],
+            last_connection: None,
+            poll_interval_secs: 2,
+            perf_interval_secs: 2,
+            default_pool: None,
+            default_network: None,
+            new_vm: NewVmDefaults::default(),
+        }
+    }
+}
+
+impl Default for NewVmDefaults {
+    fn default() -> Self {
+        Self {
+            vcpus: 2,
+            memory_mib: 2048,
+            disk_size_gib: 20,
+            disk_format: "qcow2".to_string(),
+            firmware: "bios".to_string(),
+            tpm: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn path() -> PathBuf {
+        glib::user_config_dir().join("grustyvman").join("settings.toml")
+    }
+
+    /// Load settings from disk, falling back to defaults if the file is
+    /// missing or unreadable.
+    pub fn load() -> Self {
+        let path = Self::path();
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Self::default(),
+            Err(e) => {
+                log::warn!("Failed to read {}: {e}", path.display());
+                return Self::default();
+            }
+        };
+
+        match toml::from_str::<Settings>(&text) {
+            Ok(settings) => settings.sanitized(),
+            Err(e) => {
+                log::warn!("Ignoring invalid settings file {}: {e}", path.display());
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<(), AppError> {
+        let path = Self::path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let text = toml::to_string_pretty(self)
+            .map_err(|e| AppError::Io(std::io::Error::other(e)))?;
+
+        // Write to a temp file first so a crash never leaves a truncated file
+        let tmp = path.with_extension("toml.tmp");
+        std::fs::write(&tmp, text)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// Clamp values that would break the UI (zero-second timers, empty or
+    /// repeated connections) back into range.
+    fn sanitized(mut self) -> Self {
+        self.poll_interval_secs = self.poll_interval_secs.clamp(1, 3600);
+        self.perf_interval_secs = self.perf_interval_secs.clamp(1, 60);
+        let mut seen = HashSet::new();
+        self.connections
+            .retain(|uri| !uri.trim().is_empty() && seen.insert(uri.clone()));
+        if self.connections.is_empty() {
+            self.connections.push(config::DEFAULT_CONNECTION_URI.to_string());
+        }
+        self.default_pool = self.default_pool.filter(|s| !s.is_empty());
+        self.default_network = self.default_network.filter(|s| !s.is_empty());
+        self
+    }
+
+    /// The connection to activate on startup.
+    pub fn initial_connection(&self) -> String {
+        self.last_connection
+            .clone()
+            .filter(|uri| self.connections.contains(uri))
+            .or_else(|| self.connections.first().cloned())
+            .unwrap_or_else(|| config::DEFAULT_CONNECTION_URI.to_string())
+    }
+}
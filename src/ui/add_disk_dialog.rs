@@ -5,7 +5,7 @@ use adw::prelude::*;
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use crate::backend::types::{DomainDetails, NewDiskParams};
+use grustyvman_core::types::{DomainDetails, NewDiskParams};
 
 pub fn show_add_disk_dialog(
     parent: &adw::ApplicationWindow,
@@ -159,13 +159,14 @@ pub fn show_add_disk_dialog(
         let create_new = create_new_row.is_active();
         let device_type = if type_row.selected() == 0 { "disk" } else { "cdrom" };
 
+        // A new image is only named here; it becomes a volume in the
+        // default storage pool when the disk is added
         let source_file = if create_new {
-            let img_name = name_row.text().to_string();
+            let img_name = name_row.text().trim().to_string();
             if img_name.is_empty() {
                 return;
             }
-            let home = std::env::var("HOME").unwrap_or_else(|_| "/root".to_string());
-            format!("{home}/.local/share/libvirt/images/{img_name}")
+            img_name
         } else {
             match file_path.borrow().clone() {
                 Some(p) => p,
@@ -3,7 +3,7 @@ use gtk::prelude::*;
 use libadwaita as adw;
 use adw::prelude::*;
 
-use crate::backend::types::HostdevInfo;
+use grustyvman_core::types::HostdevInfo;
 
 pub fn show_add_hostdev_dialog(
     parent: &adw::ApplicationWindow,
@@ -44,7 +44,7 @@ pub fn show_add_hostdev_dialog(
     let pci_group = adw::PreferencesGroup::new();
     pci_group.set_title("PCI Devices");
 
-    let pci_devices = crate::backend::nodedev::list_pci_devices();
+    let pci_devices = grustyvman_core::nodedev::list_pci_devices();
     let pci_labels: Vec<&str> = pci_devices.iter().map(|d| d.display_name.as_str()).collect();
     let pci_empty = pci_labels.is_empty();
     let pci_list = gtk::StringList::new(if pci_empty { &["No PCI devices found"] } else { &pci_labels });
@@ -57,7 +57,7 @@ pub fn show_add_hostdev_dialog(
     let usb_group = adw::PreferencesGroup::new();
     usb_group.set_title("USB Devices");
 
-    let usb_devices = crate::backend::nodedev::list_usb_devices();
+    let usb_devices = grustyvman_core::nodedev::list_usb_devices();
     let usb_labels: Vec<&str> = usb_devices.iter().map(|d| d.display_name.as_str()).collect();
     let usb_empty = usb_labels.is_empty();
     let usb_list = gtk::StringList::new(if usb_empty { &["No USB devices found"] } else { &usb_labels });
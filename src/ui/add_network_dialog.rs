@@ -3,7 +3,7 @@ use gtk::prelude::*;
 use libadwaita as adw;
 use adw::prelude::*;
 
-use crate::backend::types::NewNetworkParams;
+use grustyvman_core::types::NewNetworkParams;
 
 pub fn show_add_network_dialog(
     parent: &adw::ApplicationWindow,
@@ -3,7 +3,7 @@ use gtk::prelude::*;
 use libadwaita as adw;
 use adw::prelude::*;
 
-use crate::backend::types::{ChangeNetworkSourceParams, NetworkSourceType};
+use grustyvman_core::types::{ChangeNetworkSourceParams, NetworkSourceType};
 
 pub fn show_change_network_source_dialog(
     parent: &adw::ApplicationWindow,
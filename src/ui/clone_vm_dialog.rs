@@ -2,20 +2,19 @@ use gtk4 as gtk;
 use gtk::prelude::*;
 use libadwaita as adw;
 use adw::prelude::*;
+use grustyvman_core::types::{CloneParams, NvramClone};
 
-pub struct CloneParams {
-    pub new_name: String,
-    pub full_clone: bool,
-}
-
+/// `has_nvram` says whether the source VM has UEFI variables, whose row is
+/// only shown then.
 pub fn show_clone_vm_dialog(
     parent: &adw::ApplicationWindow,
     source_name: &str,
+    has_nvram: bool,
     on_clone: impl Fn(CloneParams) + 'static,
 ) {
     let dialog = gtk::Window::new();
     dialog.set_title(Some("Clone VM"));
-    dialog.set_default_size(400, 280);
+    dialog.set_default_size(400, 420);
     dialog.set_decorated(false);
     dialog.set_modal(true);
     dialog.set_transient_for(Some(parent));
@@ -50,6 +49,19 @@ pub fn show_clone_vm_dialog(
 
     content.append(&group);
 
+    let firmware_group = adw::PreferencesGroup::new();
+    firmware_group.set_title("Firmware State");
+    firmware_group.set_visible(has_nvram);
+
+    let nvram_list = gtk::StringList::new(&["Reset to defaults", "Copy (keeps boot entries)"]);
+    let nvram_row = adw::ComboRow::new();
+    nvram_row.set_title("UEFI Variables");
+    nvram_row.set_subtitle("A copy is made on the clone's first start");
+    nvram_row.set_model(Some(&nvram_list));
+    firmware_group.add(&nvram_row);
+
+    content.append(&firmware_group);
+
     let clone_btn = gtk::Button::with_label("Clone VM");
     clone_btn.add_css_class("suggested-action");
     clone_btn.add_css_class("pill");
@@ -68,7 +80,12 @@ pub fn show_clone_vm_dialog(
             return;
         }
         let full_clone = clone_type_row.selected() == 0;
-        on_clone(CloneParams { new_name, full_clone });
+        let nvram = if nvram_row.selected() == 0 { NvramClone::Reset } else { NvramClone::Copy };
+        on_clone(CloneParams {
+            new_name,
+            full_clone,
+            nvram,
+        });
         dialog_ref.close();
     });
 
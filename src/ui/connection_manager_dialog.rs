@@ -0,0 +1,133 @@
+use gtk4 as gtk;
+use gtk::prelude::*;
+use libadwaita as adw;
+use adw::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// `on_add` is handed a new URI and a callback to report whether connecting
+/// to it worked; only then is the host listed.
+pub fn show_connection_manager_dialog(
+    parent: &adw::ApplicationWindow,
+    connections: &[String],
+    on_add: impl Fn(String, Box<dyn FnOnce(bool)>) + 'static,
+    on_remove: impl Fn(String) + 'static,
+) {
+    let dialog = gtk::Window::new();
+    dialog.set_title(Some("Connections"));
+    dialog.set_default_size(460, 420);
+    dialog.set_decorated(false);
+    dialog.set_modal(true);
+    dialog.set_transient_for(Some(parent));
+
+    let toolbar_view = adw::ToolbarView::new();
+    let header = adw::HeaderBar::new();
+    toolbar_view.add_top_bar(&header);
+
+    let clamp = adw::Clamp::new();
+    clamp.set_maximum_size(420);
+    clamp.set_margin_top(24);
+    clamp.set_margin_bottom(24);
+    clamp.set_margin_start(12);
+    clamp.set_margin_end(12);
+
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 20);
+
+    let hosts_group = adw::PreferencesGroup::new();
+    hosts_group.set_title("Hosts");
+    hosts_group.set_description(Some("Every host listed here stays connected at the same time"));
+
+    let add_group = adw::PreferencesGroup::new();
+    add_group.set_title("Add Connection");
+
+    let uri_row = adw::EntryRow::new();
+    uri_row.set_title("URI (e.g. qemu+ssh://user@host/system)");
+    uri_row.set_show_apply_button(false);
+    add_group.add(&uri_row);
+
+    content.append(&hosts_group);
+    content.append(&add_group);
+
+    let add_btn = gtk::Button::with_label("Connect");
+    add_btn.add_css_class("suggested-action");
+    add_btn.add_css_class("pill");
+    add_btn.set_halign(gtk::Align::Center);
+    add_btn.set_margin_top(12);
+    content.append(&add_btn);
+
+    let scrolled = gtk::ScrolledWindow::new();
+    scrolled.set_vexpand(true);
+    clamp.set_child(Some(&content));
+    scrolled.set_child(Some(&clamp));
+    toolbar_view.set_content(Some(&scrolled));
+    dialog.set_child(Some(&toolbar_view));
+
+    let on_remove: Rc<dyn Fn(String)> = Rc::new(on_remove);
+    let known: Rc<RefCell<Vec<String>>> = Rc::new(RefCell::new(connections.to_vec()));
+
+    for uri in connections {
+        append_host_row(&hosts_group, uri, known.clone(), on_remove.clone());
+    }
+
+    let hosts_group_ref = hosts_group.clone();
+    let uri_row_ref = uri_row.clone();
+    add_btn.connect_clicked(move |_| {
+        let uri = uri_row_ref.text().trim().to_string();
+        if uri.is_empty() || known.borrow().contains(&uri) {
+            return;
+        }
+        // Taken while connecting, so it cannot be added twice
+        known.borrow_mut().push(uri.clone());
+        uri_row_ref.set_text("");
+
+        let hosts_group = hosts_group_ref.clone();
+        let uri_row = uri_row_ref.clone();
+        let known = known.clone();
+        let on_remove = on_remove.clone();
+        let added = uri.clone();
+        on_add(
+            uri,
+            Box::new(move |connected| {
+                if connected {
+                    append_host_row(&hosts_group, &added, known, on_remove);
+                } else {
+                    known.borrow_mut().retain(|u| *u != added);
+                    // Leave it for the user to correct
+                    if uri_row.text().is_empty() {
+                        uri_row.set_text(&added);
+                    }
+                }
+            }),
+        );
+    });
+
+    dialog.present();
+}
+
+fn append_host_row(
+    group: &adw::PreferencesGroup,
+    uri: &str,
+    known: Rc<RefCell<Vec<String>>>,
+    on_remove: Rc<dyn Fn(String)>,
+) {
+    let row = adw::ActionRow::new();
+    row.set_title(uri);
+    row.add_prefix(&gtk::Image::from_icon_name("network-server-symbolic"));
+
+    let remove_btn = gtk::Button::from_icon_name("user-trash-symbolic");
+    remove_btn.set_tooltip_text(Some("Disconnect and Remove"));
+    remove_btn.set_valign(gtk::Align::Center);
+    remove_btn.add_css_class("flat");
+    row.add_suffix(&remove_btn);
+
+    group.add(&row);
+
+    let group_ref = group.clone();
+    let row_ref = row.clone();
+    let uri = uri.to_string();
+    remove_btn.connect_clicked(move |_| {
+        group_ref.remove(&row_ref);
+        known.borrow_mut().retain(|u| *u != uri);
+        on_remove(uri.clone());
+    });
+}
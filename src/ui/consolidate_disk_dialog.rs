@@ -0,0 +1,129 @@
+use gtk4 as gtk;
+use gtk::prelude::*;
+use libadwaita as adw;
+use adw::prelude::*;
+use grustyvman_core::types::ChainMerge;
+
+/// Show the backing chain of disk `target_dev` (the image the VM writes to
+/// first) and offer to flatten it. Block jobs need the VM running.
+pub fn show_consolidate_disk_dialog(
+    parent: &adw::ApplicationWindow,
+    target_dev: &str,
+    chain: &[String],
+    is_running: bool,
+    on_consolidate: impl Fn(ChainMerge) + 'static,
+) {
+    let dialog = adw::Window::builder()
+        .title(format!("Disk Chain of /dev/{target_dev}"))
+        .modal(true)
+        .transient_for(parent)
+        .default_width(480)
+        .default_height(460)
+        .build();
+
+    let toolbar = adw::ToolbarView::new();
+    let header = adw::HeaderBar::new();
+    toolbar.add_top_bar(&header);
+
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 24);
+    content.set_margin_top(24);
+    content.set_margin_bottom(24);
+    content.set_margin_start(24);
+    content.set_margin_end(24);
+
+    let chain_group = adw::PreferencesGroup::new();
+    chain_group.set_title("Backing Chain");
+    if !is_running {
+        chain_group.set_description(Some("Start the VM to see and consolidate its chain"));
+    }
+    for (i, image) in chain.iter().enumerate() {
+        let row = adw::ActionRow::new();
+        row.set_title(image);
+        let role = if i == 0 {
+            "Written by the VM"
+        } else if i == chain.len() - 1 {
+            "Base image"
+        } else {
+            "Overlay"
+        };
+        row.set_subtitle(role);
+        row.set_title_lines(2);
+        chain_group.add(&row);
+    }
+    content.append(&chain_group);
+
+    let can_merge = is_running && chain.len() > 1;
+    let merge_group = adw::PreferencesGroup::new();
+    merge_group.set_title("Consolidate");
+    merge_group.set_sensitive(can_merge);
+
+    let merge_labels: Vec<&str> = ChainMerge::ALL.iter().map(|m| m.label()).collect();
+    let merge_row = adw::ComboRow::new();
+    merge_row.set_title("Method");
+    merge_row.set_model(Some(&gtk::StringList::new(&merge_labels)));
+    merge_group.add(&merge_row);
+
+    let hint = gtk::Label::new(None);
+    hint.set_wrap(true);
+    hint.set_xalign(0.0);
+    hint.add_css_class("dim-label");
+    hint.set_margin_top(6);
+    merge_group.add(&hint);
+    let update_hint = {
+        let hint = hint.clone();
+        move |merge_row: &adw::ComboRow| {
+            hint.set_label(match ChainMerge::ALL[merge_row.selected() as usize] {
+                ChainMerge::Pull => {
+                    "The top image gets a full copy of the data and no longer needs the \
+                     others, which are kept."
+                }
+                ChainMerge::Commit => {
+                    "The overlays are merged into the base image and deleted. Not \
+                     possible while other VMs, such as linked clones, use the base image \
+                     or snapshots refer to the overlays."
+                }
+            });
+        }
+    };
+    update_hint(&merge_row);
+    merge_row.connect_selected_notify(update_hint);
+
+    content.append(&merge_group);
+
+    // Buttons
+    let button_box = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+    button_box.set_halign(gtk::Align::End);
+    button_box.set_margin_top(12);
+
+    let cancel_btn = gtk::Button::with_label("Cancel");
+    let consolidate_btn = gtk::Button::with_label("Consolidate");
+    consolidate_btn.add_css_class("suggested-action");
+    consolidate_btn.set_sensitive(can_merge);
+
+    button_box.append(&cancel_btn);
+    button_box.append(&consolidate_btn);
+    content.append(&button_box);
+
+    let scrolled = gtk::ScrolledWindow::new();
+    scrolled.set_hscrollbar_policy(gtk::PolicyType::Never);
+    scrolled.set_child(Some(&content));
+    toolbar.set_content(Some(&scrolled));
+    dialog.set_content(Some(&toolbar));
+
+    let dialog_weak = dialog.downgrade();
+    cancel_btn.connect_clicked(move |_| {
+        if let Some(d) = dialog_weak.upgrade() {
+            d.close();
+        }
+    });
+
+    let dialog_weak = dialog.downgrade();
+    consolidate_btn.connect_clicked(move |_| {
+        on_consolidate(ChainMerge::ALL[merge_row.selected() as usize]);
+        if let Some(d) = dialog_weak.upgrade() {
+            d.close();
+        }
+    });
+
+    dialog.present();
+}
@@ -3,7 +3,7 @@ use gtk::prelude::*;
 use libadwaita as adw;
 use adw::prelude::*;
 
-use crate::backend::types::{ForwardMode, NetworkCreateParams};
+use grustyvman_core::types::{ForwardMode, NetworkCreateParams};
 
 pub fn show_create_network_dialog(
     parent: &adw::ApplicationWindow,
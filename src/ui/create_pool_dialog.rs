@@ -3,7 +3,7 @@ use gtk::prelude::*;
 use libadwaita as adw;
 use adw::prelude::*;
 
-use crate::backend::types::PoolCreateParams;
+use grustyvman_core::types::PoolCreateParams;
 
 const POOL_TYPES: &[(&str, &str)] = &[
     ("dir", "Filesystem Directory"),
@@ -2,17 +2,23 @@ use gtk4 as gtk;
 use gtk::prelude::*;
 use libadwaita as adw;
 use adw::prelude::*;
+use grustyvman_core::types::{CreateSnapshotParams, DiskSnapshotMode, SnapshotDisk, SnapshotKind};
 
+/// `disks` are the target devices of the VM's disks, each of which can be
+/// left out. A memory file and quiescing are only offered while the VM
+/// (`is_running`) has memory to save or a guest agent to flush.
 pub fn show_create_snapshot_dialog(
     parent: &adw::ApplicationWindow,
-    on_create: impl Fn(String, String) + 'static,
+    disks: &[String],
+    is_running: bool,
+    on_create: impl Fn(CreateSnapshotParams) + 'static,
 ) {
     let dialog = adw::Window::builder()
         .title("Create Snapshot")
         .modal(true)
         .transient_for(parent)
         .default_width(400)
-        .default_height(350)
+        .default_height(640)
         .build();
 
     let toolbar = adw::ToolbarView::new();
@@ -35,8 +41,63 @@ pub fn show_create_snapshot_dialog(
     name_row.set_text(&default_name);
     group.add(&name_row);
 
+    let kind_labels: Vec<&str> = SnapshotKind::ALL.iter().map(|k| k.label()).collect();
+    let kind_row = adw::ComboRow::new();
+    kind_row.set_title("Type");
+    kind_row.set_model(Some(&gtk::StringList::new(&kind_labels)));
+    group.add(&kind_row);
+
+    let memory_row = adw::EntryRow::new();
+    memory_row.set_title("Memory File (next to the first disk if empty)");
+    group.add(&memory_row);
+
+    let quiesce_row = adw::SwitchRow::new();
+    quiesce_row.set_title("Quiesce");
+    quiesce_row.set_subtitle("Flush the guest's filesystems through the guest agent");
+    group.add(&quiesce_row);
+
+    let atomic_row = adw::SwitchRow::new();
+    atomic_row.set_title("Atomic");
+    atomic_row.set_subtitle("Snapshot every disk or none");
+    atomic_row.set_active(true);
+    group.add(&atomic_row);
+
     content.append(&group);
 
+    let disks_group = adw::PreferencesGroup::new();
+    disks_group.set_title("Disks");
+    let disk_rows: Vec<(String, adw::SwitchRow)> = disks
+        .iter()
+        .map(|dev| {
+            let row = adw::SwitchRow::new();
+            row.set_title(&format!("/dev/{dev}"));
+            row.set_active(true);
+            disks_group.add(&row);
+            (dev.clone(), row)
+        })
+        .collect();
+    if !disks.is_empty() {
+        content.append(&disks_group);
+    }
+
+    // Only external snapshots of a running VM save memory to a file, and
+    // only disk-only ones of a running VM can be quiesced
+    let update_kind = {
+        let memory_row = memory_row.clone();
+        let quiesce_row = quiesce_row.clone();
+        move |kind_row: &adw::ComboRow| {
+            let kind = SnapshotKind::ALL[kind_row.selected() as usize];
+            memory_row.set_visible(is_running && kind == SnapshotKind::External);
+            let can_quiesce = is_running && kind == SnapshotKind::DiskOnly;
+            quiesce_row.set_sensitive(can_quiesce);
+            if !can_quiesce {
+                quiesce_row.set_active(false);
+            }
+        }
+    };
+    update_kind(&kind_row);
+    kind_row.connect_selected_notify(update_kind);
+
     // Description field
     let desc_group = adw::PreferencesGroup::new();
     desc_group.set_title("Description");
@@ -91,7 +152,24 @@ pub fn show_create_snapshot_dialog(
             .text(&buffer.start_iter(), &buffer.end_iter(), false)
             .to_string();
 
-        on_create(name, description);
+        let memory_file = memory_row.text().trim().to_string();
+        let excluded = disk_rows
+            .iter()
+            .filter(|(_, row)| !row.is_active())
+            .map(|(dev, _)| SnapshotDisk {
+                target_dev: dev.clone(),
+                mode: DiskSnapshotMode::Excluded,
+                file: None,
+            });
+        on_create(CreateSnapshotParams {
+            kind: SnapshotKind::ALL[kind_row.selected() as usize],
+            disks: excluded.collect(),
+            memory_file: (memory_row.is_visible() && !memory_file.is_empty())
+                .then_some(memory_file),
+            quiesce: quiesce_row.is_active(),
+            atomic: atomic_row.is_active(),
+            ..CreateSnapshotParams::new(&name, &description)
+        });
         if let Some(d) = dialog_weak.upgrade() {
             d.close();
         }
@@ -0,0 +1,89 @@
+use gtk4 as gtk;
+use gtk::prelude::*;
+use libadwaita as adw;
+use adw::prelude::*;
+
+const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// Ask how much to grow disk `target_dev` by. `on_grow` gets the bytes to
+/// add and whether the guest agent should grow the filesystems, which is
+/// only offered while the VM runs.
+pub fn show_grow_disk_dialog(
+    parent: &adw::ApplicationWindow,
+    target_dev: &str,
+    is_running: bool,
+    on_grow: impl Fn(u64, bool) + 'static,
+) {
+    let dialog = adw::Window::builder()
+        .title(format!("Grow /dev/{target_dev}"))
+        .modal(true)
+        .transient_for(parent)
+        .default_width(420)
+        .default_height(280)
+        .build();
+
+    let toolbar = adw::ToolbarView::new();
+    let header = adw::HeaderBar::new();
+    toolbar.add_top_bar(&header);
+
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 24);
+    content.set_margin_top(24);
+    content.set_margin_bottom(24);
+    content.set_margin_start(24);
+    content.set_margin_end(24);
+
+    let group = adw::PreferencesGroup::new();
+    group.set_title("Grow Disk");
+    if is_running {
+        group.set_description(Some("The guest sees the new size right away"));
+    } else {
+        group.set_description(Some("The guest sees the new size on its next boot"));
+    }
+
+    let size_adj = gtk::Adjustment::new(10.0, 1.0, 65536.0, 1.0, 10.0, 0.0);
+    let size_row = adw::SpinRow::new(Some(&size_adj), 1.0, 0);
+    size_row.set_title("Add (GiB)");
+    group.add(&size_row);
+
+    let fs_row = adw::SwitchRow::new();
+    fs_row.set_title("Grow Filesystems");
+    fs_row.set_subtitle("Grow the partition and filesystems through the guest agent");
+    fs_row.set_active(is_running);
+    fs_row.set_sensitive(is_running);
+    group.add(&fs_row);
+
+    content.append(&group);
+
+    // Buttons
+    let button_box = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+    button_box.set_halign(gtk::Align::End);
+    button_box.set_margin_top(12);
+
+    let cancel_btn = gtk::Button::with_label("Cancel");
+    let grow_btn = gtk::Button::with_label("Grow");
+    grow_btn.add_css_class("suggested-action");
+
+    button_box.append(&cancel_btn);
+    button_box.append(&grow_btn);
+    content.append(&button_box);
+
+    toolbar.set_content(Some(&content));
+    dialog.set_content(Some(&toolbar));
+
+    let dialog_weak = dialog.downgrade();
+    cancel_btn.connect_clicked(move |_| {
+        if let Some(d) = dialog_weak.upgrade() {
+            d.close();
+        }
+    });
+
+    let dialog_weak = dialog.downgrade();
+    grow_btn.connect_clicked(move |_| {
+        on_grow((size_row.value() * GIB) as u64, is_running && fs_row.is_active());
+        if let Some(d) = dialog_weak.upgrade() {
+            d.close();
+        }
+    });
+
+    dialog.present();
+}
@@ -2,7 +2,7 @@ use gtk4 as gtk;
 use gtk::prelude::*;
 use libadwaita as adw;
 use adw::prelude::*;
-use crate::backend::types::HostInfo;
+use grustyvman_core::types::HostInfo;
 
 pub struct HostDetailsView {
     pub container: gtk::Box,
@@ -0,0 +1,133 @@
+use gtk4 as gtk;
+use gtk::prelude::*;
+use libadwaita as adw;
+use adw::prelude::*;
+use grustyvman_core::cloudinit::{CloudInitConfig, CloudInitUser};
+use grustyvman_core::template::InstantiateParams;
+use crate::ui::vm_creation_dialog::{multiline_entry, text_lines};
+
+pub fn show_instantiate_template_dialog(
+    parent: &adw::ApplicationWindow,
+    template_name: &str,
+    on_instantiate: impl Fn(InstantiateParams) + 'static,
+) {
+    let dialog = gtk::Window::new();
+    dialog.set_title(Some("Instantiate Template"));
+    dialog.set_default_size(460, 560);
+    dialog.set_decorated(false);
+    dialog.set_modal(true);
+    dialog.set_transient_for(Some(parent));
+
+    let toolbar_view = adw::ToolbarView::new();
+    let header = adw::HeaderBar::new();
+    toolbar_view.add_top_bar(&header);
+
+    let clamp = adw::Clamp::new();
+    clamp.set_maximum_size(420);
+    clamp.set_margin_top(24);
+    clamp.set_margin_bottom(24);
+    clamp.set_margin_start(12);
+    clamp.set_margin_end(12);
+
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 20);
+
+    let group = adw::PreferencesGroup::new();
+    group.set_title("Instances");
+    group.set_description(Some(&format!(
+        "Linked clones of {template_name} with fresh MAC addresses. {{n}} in the name is replaced by a free number."
+    )));
+
+    let name_row = adw::EntryRow::new();
+    name_row.set_title("Name Pattern");
+    name_row.set_text(&format!("{template_name}-{{n}}"));
+    name_row.set_show_apply_button(false);
+    group.add(&name_row);
+
+    let count_row = adw::SpinRow::with_range(1.0, 50.0, 1.0);
+    count_row.set_title("Count");
+    count_row.set_value(1.0);
+    group.add(&count_row);
+
+    content.append(&group);
+
+    let cloud_init_group = adw::PreferencesGroup::new();
+    cloud_init_group.set_title("Provisioning");
+
+    let cloud_init_row = adw::ExpanderRow::new();
+    cloud_init_row.set_title("cloud-init Seed");
+    cloud_init_row.set_subtitle("Each instance gets its name as hostname");
+    cloud_init_row.set_show_enable_switch(true);
+    cloud_init_row.set_enable_expansion(false);
+    cloud_init_group.add(&cloud_init_row);
+
+    let user_row = adw::EntryRow::new();
+    user_row.set_title("User (blank for the image's default user)");
+    cloud_init_row.add_row(&user_row);
+
+    let packages_row = adw::EntryRow::new();
+    packages_row.set_title("Packages (space separated)");
+    cloud_init_row.add_row(&packages_row);
+
+    let ssh_keys_view = multiline_entry(&cloud_init_group, "SSH Public Keys (one per line)");
+    let runcmd_view = multiline_entry(&cloud_init_group, "First Boot Commands (one per line)");
+    // The text views sit below the expander; only show them while it is on
+    for view in [&ssh_keys_view, &runcmd_view] {
+        if let Some(container) = view.parent() {
+            cloud_init_row
+                .bind_property("enable-expansion", &container, "visible")
+                .sync_create()
+                .build();
+        }
+    }
+    content.append(&cloud_init_group);
+
+    let instantiate_btn = gtk::Button::with_label("Instantiate");
+    instantiate_btn.add_css_class("suggested-action");
+    instantiate_btn.add_css_class("pill");
+    instantiate_btn.set_halign(gtk::Align::Center);
+    instantiate_btn.set_margin_top(12);
+    content.append(&instantiate_btn);
+
+    clamp.set_child(Some(&content));
+    let scrolled = gtk::ScrolledWindow::new();
+    scrolled.set_hscrollbar_policy(gtk::PolicyType::Never);
+    scrolled.set_child(Some(&clamp));
+    toolbar_view.set_content(Some(&scrolled));
+    dialog.set_child(Some(&toolbar_view));
+
+    let dialog_ref = dialog.clone();
+    instantiate_btn.connect_clicked(move |_| {
+        let name_pattern = name_row.text().trim().to_string();
+        if name_pattern.is_empty() {
+            return;
+        }
+
+        let cloud_init = cloud_init_row.enables_expansion().then(|| {
+            let keys = text_lines(&ssh_keys_view);
+            let user = user_row.text().trim().to_string();
+            // Keys go to the named user, else to the image's default user
+            let (users, ssh_authorized_keys) = if user.is_empty() {
+                (Vec::new(), keys)
+            } else {
+                let user = CloudInitUser { name: user, ssh_authorized_keys: keys, sudo: true };
+                (vec![user], Vec::new())
+            };
+            CloudInitConfig {
+                hostname: String::new(),
+                users,
+                ssh_authorized_keys,
+                packages: packages_row.text().split_whitespace().map(str::to_string).collect(),
+                runcmd: text_lines(&runcmd_view),
+            }
+        });
+
+        on_instantiate(InstantiateParams {
+            name_pattern,
+            count: count_row.value() as u32,
+            cloud_init,
+        });
+        dialog_ref.close();
+    });
+
+    dialog.present();
+}
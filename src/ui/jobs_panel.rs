@@ -0,0 +1,139 @@
+use gtk4 as gtk;
+use gtk::prelude::*;
+use grustyvman_core::types::{JobInfo, JobStatus};
+
+/// Header bar button whose popover lists running and finished jobs.
+pub struct JobsPanel {
+    pub button: gtk::MenuButton,
+    pub clear_button: gtk::Button,
+    list: gtk::ListBox,
+}
+
+impl JobsPanel {
+    pub fn new() -> Self {
+        let button = gtk::MenuButton::new();
+        button.set_icon_name("emblem-synchronizing-symbolic");
+        button.set_tooltip_text(Some("Jobs"));
+
+        let list = gtk::ListBox::new();
+        list.set_selection_mode(gtk::SelectionMode::None);
+        list.add_css_class("boxed-list");
+        let placeholder = gtk::Label::new(Some("No jobs"));
+        placeholder.add_css_class("dim-label");
+        placeholder.set_margin_top(12);
+        placeholder.set_margin_bottom(12);
+        list.set_placeholder(Some(&placeholder));
+
+        let scrolled = gtk::ScrolledWindow::new();
+        scrolled.set_hscrollbar_policy(gtk::PolicyType::Never);
+        scrolled.set_propagate_natural_height(true);
+        scrolled.set_max_content_height(400);
+        scrolled.set_min_content_width(340);
+        scrolled.set_child(Some(&list));
+
+        let clear_button = gtk::Button::with_label("Clear Finished");
+        clear_button.add_css_class("flat");
+        clear_button.set_halign(gtk::Align::End);
+
+        let content = gtk::Box::new(gtk::Orientation::Vertical, 6);
+        content.append(&scrolled);
+        content.append(&clear_button);
+
+        let popover = gtk::Popover::new();
+        popover.set_child(Some(&content));
+        button.set_popover(Some(&popover));
+
+        JobsPanel { button, clear_button, list }
+    }
+
+    /// Show `jobs`, newest first. `on_cancel` gets the id of a job whose
+    /// cancel button was clicked.
+    pub fn update(&self, jobs: &[JobInfo], on_cancel: impl Fn(u64) + Clone + 'static) {
+        while let Some(row) = self.list.row_at_index(0) {
+            self.list.remove(&row);
+        }
+        for job in jobs.iter().rev() {
+            self.list.append(&job_row(job, on_cancel.clone()));
+        }
+
+        let running = jobs.iter().filter(|j| j.is_running()).count();
+        if running > 0 {
+            self.button.add_css_class("accent");
+            self.button.set_tooltip_text(Some(&format!("Jobs ({running} running)")));
+        } else {
+            self.button.remove_css_class("accent");
+            self.button.set_tooltip_text(Some("Jobs"));
+        }
+        self.clear_button.set_sensitive(jobs.iter().any(|j| !j.is_running()));
+    }
+}
+
+fn job_row(job: &JobInfo, on_cancel: impl Fn(u64) + 'static) -> gtk::ListBoxRow {
+    let hbox = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+    hbox.set_margin_top(8);
+    hbox.set_margin_bottom(8);
+    hbox.set_margin_start(12);
+    hbox.set_margin_end(12);
+
+    let vbox = gtk::Box::new(gtk::Orientation::Vertical, 4);
+    vbox.set_hexpand(true);
+
+    let title = gtk::Label::new(Some(&job.title));
+    title.set_xalign(0.0);
+    title.set_ellipsize(gtk::pango::EllipsizeMode::End);
+    vbox.append(&title);
+
+    let status = gtk::Label::new(None);
+    status.set_xalign(0.0);
+    status.set_wrap(true);
+    status.add_css_class("caption");
+    match &job.status {
+        JobStatus::Running => {
+            let percent = job.fraction().map(|f| format!("{:.0}%", f * 100.0));
+            let text = match (job.detail.as_str(), percent) {
+                ("", Some(p)) => p,
+                ("", None) => "Running".to_string(),
+                (detail, Some(p)) => format!("{detail} — {p}"),
+                (detail, None) => detail.to_string(),
+            };
+            status.set_text(&text);
+            status.add_css_class("dim-label");
+
+            let bar = gtk::ProgressBar::new();
+            match job.fraction() {
+                Some(f) => bar.set_fraction(f),
+                None => bar.pulse(),
+            }
+            vbox.append(&bar);
+        }
+        JobStatus::Succeeded => {
+            status.set_text("Done");
+            status.add_css_class("dim-label");
+        }
+        JobStatus::Cancelled => {
+            status.set_text("Cancelled");
+            status.add_css_class("dim-label");
+        }
+        JobStatus::Failed(err) => {
+            status.set_text(err);
+            status.add_css_class("error");
+        }
+    }
+    vbox.append(&status);
+    hbox.append(&vbox);
+
+    if job.is_running() && job.cancellable {
+        let cancel = gtk::Button::from_icon_name("process-stop-symbolic");
+        cancel.set_tooltip_text(Some("Cancel"));
+        cancel.add_css_class("flat");
+        cancel.set_valign(gtk::Align::Center);
+        let id = job.id;
+        cancel.connect_clicked(move |_| on_cancel(id));
+        hbox.append(&cancel);
+    }
+
+    let row = gtk::ListBoxRow::new();
+    row.set_activatable(false);
+    row.set_child(Some(&hbox));
+    row
+}
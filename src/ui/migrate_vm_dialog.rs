@@ -0,0 +1,146 @@
+use gtk4 as gtk;
+use gtk::prelude::*;
+use libadwaita as adw;
+use adw::prelude::*;
+use grustyvman_core::types::{MigrateParams, MigrationMode};
+
+/// `destinations` are the URIs of the other open connections. A shut-off
+/// VM (`is_running` false) can only be migrated offline.
+pub fn show_migrate_vm_dialog(
+    parent: &adw::ApplicationWindow,
+    vm_name: &str,
+    destinations: &[String],
+    is_running: bool,
+    on_migrate: impl Fn(MigrateParams) + 'static,
+) {
+    let dialog = gtk::Window::new();
+    dialog.set_title(Some("Migrate VM"));
+    dialog.set_default_size(420, 620);
+    dialog.set_decorated(false);
+    dialog.set_modal(true);
+    dialog.set_transient_for(Some(parent));
+
+    let toolbar_view = adw::ToolbarView::new();
+    let header = adw::HeaderBar::new();
+    toolbar_view.add_top_bar(&header);
+
+    let clamp = adw::Clamp::new();
+    clamp.set_maximum_size(400);
+    clamp.set_margin_top(24);
+    clamp.set_margin_bottom(24);
+    clamp.set_margin_start(12);
+    clamp.set_margin_end(12);
+
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 20);
+
+    let group = adw::PreferencesGroup::new();
+    group.set_title(&format!("Migrate {vm_name}"));
+
+    let dest_list: Vec<&str> = destinations.iter().map(|d| d.as_str()).collect();
+    let dest_row = adw::ComboRow::new();
+    dest_row.set_title("Destination Host");
+    dest_row.set_model(Some(&gtk::StringList::new(&dest_list)));
+    group.add(&dest_row);
+
+    let mode_labels: Vec<&str> = MigrationMode::ALL.iter().map(|m| m.label()).collect();
+    let mode_row = adw::ComboRow::new();
+    mode_row.set_title("Mode");
+    mode_row.set_model(Some(&gtk::StringList::new(&mode_labels)));
+    if !is_running {
+        mode_row.set_selected(1);
+        mode_row.set_sensitive(false);
+    }
+    group.add(&mode_row);
+
+    let storage_row = adw::SwitchRow::new();
+    storage_row.set_title("Copy Storage");
+    storage_row.set_subtitle("For hosts that do not share the VM's disks");
+    group.add(&storage_row);
+
+    content.append(&group);
+
+    let options_group = adw::PreferencesGroup::new();
+    options_group.set_title("Options");
+
+    let p2p_row = adw::SwitchRow::new();
+    p2p_row.set_title("Peer-to-Peer");
+    p2p_row.set_subtitle("The source host connects to the destination itself");
+    options_group.add(&p2p_row);
+
+    let persistent_row = adw::SwitchRow::new();
+    persistent_row.set_title("Define on Destination");
+    persistent_row.set_active(true);
+    options_group.add(&persistent_row);
+
+    let undefine_row = adw::SwitchRow::new();
+    undefine_row.set_title("Remove from This Host");
+    options_group.add(&undefine_row);
+
+    let compress_row = adw::SwitchRow::new();
+    compress_row.set_title("Compress Memory");
+    options_group.add(&compress_row);
+
+    let bandwidth_adj = gtk::Adjustment::new(0.0, 0.0, 100_000.0, 10.0, 100.0, 0.0);
+    let bandwidth_row = adw::SpinRow::new(Some(&bandwidth_adj), 10.0, 0);
+    bandwidth_row.set_title("Bandwidth Limit (MiB/s)");
+    bandwidth_row.set_subtitle("0 for no limit");
+    options_group.add(&bandwidth_row);
+
+    content.append(&options_group);
+
+    // Offline migration moves only the definition
+    let update_mode = {
+        let storage_row = storage_row.clone();
+        let persistent_row = persistent_row.clone();
+        let compress_row = compress_row.clone();
+        let bandwidth_row = bandwidth_row.clone();
+        move |mode_row: &adw::ComboRow| {
+            let live = MigrationMode::ALL[mode_row.selected() as usize] == MigrationMode::Live;
+            for row in [&storage_row, &persistent_row, &compress_row] {
+                row.set_sensitive(live);
+            }
+            bandwidth_row.set_sensitive(live);
+            if !live {
+                storage_row.set_active(false);
+                persistent_row.set_active(true);
+            }
+        }
+    };
+    update_mode(&mode_row);
+    mode_row.connect_selected_notify(update_mode);
+
+    let migrate_btn = gtk::Button::with_label("Migrate VM");
+    migrate_btn.add_css_class("suggested-action");
+    migrate_btn.add_css_class("pill");
+    migrate_btn.set_halign(gtk::Align::Center);
+    migrate_btn.set_margin_top(12);
+    migrate_btn.set_sensitive(!destinations.is_empty());
+    content.append(&migrate_btn);
+
+    clamp.set_child(Some(&content));
+    let scrolled = gtk::ScrolledWindow::new();
+    scrolled.set_hscrollbar_policy(gtk::PolicyType::Never);
+    scrolled.set_child(Some(&clamp));
+    toolbar_view.set_content(Some(&scrolled));
+    dialog.set_child(Some(&toolbar_view));
+
+    let destinations = destinations.to_vec();
+    let dialog_ref = dialog.clone();
+    migrate_btn.connect_clicked(move |_| {
+        let Some(dest_uri) = destinations.get(dest_row.selected() as usize) else { return };
+        let bandwidth = bandwidth_row.value() as u64;
+        on_migrate(MigrateParams {
+            mode: MigrationMode::ALL[mode_row.selected() as usize],
+            copy_storage: storage_row.is_active(),
+            peer_to_peer: p2p_row.is_active(),
+            persistent: persistent_row.is_active(),
+            undefine_source: undefine_row.is_active(),
+            bandwidth_mib: (bandwidth > 0).then_some(bandwidth),
+            compressed: compress_row.is_active(),
+            ..MigrateParams::new(dest_uri)
+        });
+        dialog_ref.close();
+    });
+
+    dialog.present();
+}
@@ -3,18 +3,26 @@ pub mod change_network_source_dialog;
 pub mod add_hostdev_dialog;
 pub mod add_network_dialog;
 pub mod clone_vm_dialog;
+pub mod connection_manager_dialog;
+pub mod consolidate_disk_dialog;
 pub mod rename_vm_dialog;
+pub mod snapshot_diff_dialog;
 pub mod host_details_view;
+pub mod instantiate_template_dialog;
+pub mod jobs_panel;
+pub mod migrate_vm_dialog;
 pub mod create_network_dialog;
 pub mod create_pool_dialog;
 pub mod create_snapshot_dialog;
 pub mod create_volume_dialog;
+pub mod grow_disk_dialog;
 pub mod upload_volume_dialog;
 pub mod network_details_view;
 pub mod network_row;
 pub mod perf_graph;
 pub mod pool_details_view;
 pub mod pool_row;
+pub mod preferences_dialog;
 pub mod storage_volume_picker_dialog;
 pub mod vm_config_dialog;
 pub mod vm_creation_dialog;
@@ -24,4 +32,5 @@ pub mod vm_performance_view;
 pub mod vm_row;
 pub mod vm_snapshot_view;
 pub mod vm_xml_editor;
+pub mod volume_properties_dialog;
 pub mod window;
@@ -3,7 +3,7 @@ use gtk::prelude::*;
 use libadwaita as adw;
 use adw::prelude::*;
 
-use crate::backend::types::VirtNetworkInfo;
+use grustyvman_core::types::VirtNetworkInfo;
 
 pub struct NetworkDetailsView {
     pub container: gtk::Box,
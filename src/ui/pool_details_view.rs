@@ -3,7 +3,7 @@ use gtk::prelude::*;
 use libadwaita as adw;
 use adw::prelude::*;
 
-use crate::backend::types::{format_bytes, PoolInfo, VolumeInfo};
+use grustyvman_core::types::{format_bytes, PoolInfo, VolumeInfo};
 
 pub struct PoolDetailsView {
     pub container: gtk::Box,
@@ -30,6 +30,8 @@ pub struct PoolDetailsView {
     on_add_volume: std::cell::RefCell<Option<std::rc::Rc<dyn Fn()>>>,
     on_upload_volume: std::cell::RefCell<Option<std::rc::Rc<dyn Fn()>>>,
     on_delete_volume: std::cell::RefCell<Option<std::rc::Rc<dyn Fn(String)>>>,
+    on_export_volume: std::cell::RefCell<Option<std::rc::Rc<dyn Fn(String)>>>,
+    on_volume_properties: std::cell::RefCell<Option<std::rc::Rc<dyn Fn(String)>>>,
     on_set_autostart: std::cell::RefCell<Option<std::rc::Rc<dyn Fn(bool)>>>,
 }
 
@@ -164,6 +166,8 @@ impl PoolDetailsView {
             on_add_volume: std::cell::RefCell::new(None),
             on_upload_volume: std::cell::RefCell::new(None),
             on_delete_volume: std::cell::RefCell::new(None),
+            on_export_volume: std::cell::RefCell::new(None),
+            on_volume_properties: std::cell::RefCell::new(None),
             on_set_autostart: std::cell::RefCell::new(None),
         }
     }
@@ -180,6 +184,14 @@ impl PoolDetailsView {
         *self.on_delete_volume.borrow_mut() = Some(std::rc::Rc::new(f));
     }
 
+    pub fn set_on_export_volume(&self, f: impl Fn(String) + 'static) {
+        *self.on_export_volume.borrow_mut() = Some(std::rc::Rc::new(f));
+    }
+
+    pub fn set_on_volume_properties(&self, f: impl Fn(String) + 'static) {
+        *self.on_volume_properties.borrow_mut() = Some(std::rc::Rc::new(f));
+    }
+
     pub fn set_on_autostart(&self, f: impl Fn(bool) + 'static) {
         let cb = std::rc::Rc::new(f);
         *self.on_set_autostart.borrow_mut() = Some(cb.clone());
@@ -279,13 +291,37 @@ impl PoolDetailsView {
                     format_bytes(vol.allocation),
                     format_bytes(vol.capacity)
                 ));
-                row.set_activatable(false);
+                row.set_activatable(info.active);
+
+                if let Some(ref cb) = *self.on_volume_properties.borrow() {
+                    let cb = cb.clone();
+                    let vol_name = vol.name.clone();
+                    row.connect_activated(move |_| {
+                        cb(vol_name.clone());
+                    });
+                }
+
+                let export_btn = gtk::Button::from_icon_name("document-save-symbolic");
+                export_btn.set_tooltip_text(Some("Export…"));
+                export_btn.set_valign(gtk::Align::Center);
+                export_btn.add_css_class("flat");
+                export_btn.set_sensitive(info.active);
+                row.add_suffix(&export_btn);
+
+                if let Some(ref cb) = *self.on_export_volume.borrow() {
+                    let cb = cb.clone();
+                    let vol_name = vol.name.clone();
+                    export_btn.connect_clicked(move |_| {
+                        cb(vol_name.clone());
+                    });
+                }
 
                 let del_btn = gtk::Button::from_icon_name("edit-delete-symbolic");
                 del_btn.set_tooltip_text(Some("Delete Volume"));
                 del_btn.set_valign(gtk::Align::Center);
                 del_btn.add_css_class("flat");
                 row.add_suffix(&del_btn);
+                row.add_suffix(&gtk::Image::from_icon_name("go-next-symbolic"));
 
                 let vol_name = vol.name.clone();
                 if let Some(ref cb) = *self.on_delete_volume.borrow() {
@@ -0,0 +1,150 @@
+use gtk4 as gtk;
+use gtk::prelude::*;
+use libadwaita as adw;
+use adw::prelude::*;
+
+use grustyvman_core::types::{DiskFormat, FirmwareType};
+use crate::settings::Settings;
+
+/// Show the application preferences. `on_apply` receives the edited settings
+/// when the window is closed; connections and the last-used connection are
+/// passed through unchanged (they are managed from the connection manager).
+pub fn show_preferences_dialog(
+    parent: &adw::ApplicationWindow,
+    settings: &Settings,
+    on_apply: impl Fn(Settings) + 'static,
+) {
+    let window = adw::PreferencesWindow::new();
+    window.set_title(Some("Preferences"));
+    window.set_default_size(520, 620);
+    window.set_modal(true);
+    window.set_transient_for(Some(parent));
+    window.set_search_enabled(false);
+
+    // --- General page ---
+    let general_page = adw::PreferencesPage::new();
+    general_page.set_title("General");
+    general_page.set_icon_name(Some("preferences-system-symbolic"));
+
+    let refresh_group = adw::PreferencesGroup::new();
+    refresh_group.set_title("Refresh");
+
+    let poll_row = adw::SpinRow::with_range(1.0, 3600.0, 1.0);
+    poll_row.set_title("List Refresh Interval (s)");
+    poll_row.set_subtitle("How often VM, pool and network lists are polled");
+    poll_row.set_value(settings.poll_interval_secs as f64);
+    refresh_group.add(&poll_row);
+
+    let perf_row = adw::SpinRow::with_range(1.0, 60.0, 1.0);
+    perf_row.set_title("Performance Sampling Interval (s)");
+    perf_row.set_value(settings.perf_interval_secs as f64);
+    refresh_group.add(&perf_row);
+
+    general_page.add(&refresh_group);
+
+    let defaults_group = adw::PreferencesGroup::new();
+    defaults_group.set_title("Defaults");
+    defaults_group.set_description(Some("Leave empty to use the pool or network named \"default\""));
+
+    let pool_row = adw::EntryRow::new();
+    pool_row.set_title("Default Storage Pool");
+    pool_row.set_text(settings.default_pool.as_deref().unwrap_or(""));
+    defaults_group.add(&pool_row);
+
+    let network_row = adw::EntryRow::new();
+    network_row.set_title("Default Network");
+    network_row.set_text(settings.default_network.as_deref().unwrap_or(""));
+    defaults_group.add(&network_row);
+
+    general_page.add(&defaults_group);
+    window.add(&general_page);
+
+    // --- New VM page ---
+    let new_vm_page = adw::PreferencesPage::new();
+    new_vm_page.set_title("New VM");
+    new_vm_page.set_icon_name(Some("computer-symbolic"));
+
+    let resources_group = adw::PreferencesGroup::new();
+    resources_group.set_title("Resources");
+
+    let cpu_row = adw::SpinRow::with_range(1.0, 32.0, 1.0);
+    cpu_row.set_title("vCPUs");
+    cpu_row.set_value(settings.new_vm.vcpus as f64);
+    resources_group.add(&cpu_row);
+
+    let memory_row = adw::SpinRow::with_range(256.0, 65536.0, 256.0);
+    memory_row.set_title("Memory (MiB)");
+    memory_row.set_value(settings.new_vm.memory_mib as f64);
+    resources_group.add(&memory_row);
+
+    let disk_row = adw::SpinRow::with_range(1.0, 1000.0, 1.0);
+    disk_row.set_title("Disk Size (GiB)");
+    disk_row.set_value(settings.new_vm.disk_size_gib as f64);
+    resources_group.add(&disk_row);
+
+    let format_labels: Vec<&str> = DiskFormat::ALL.iter().map(|f| f.label()).collect();
+    let format_row = adw::ComboRow::new();
+    format_row.set_title("Disk Format");
+    format_row.set_model(Some(&gtk::StringList::new(&format_labels)));
+    let fmt = DiskFormat::from_name(&settings.new_vm.disk_format);
+    format_row.set_selected(DiskFormat::ALL.iter().position(|f| *f == fmt).unwrap_or(0) as u32);
+    resources_group.add(&format_row);
+
+    new_vm_page.add(&resources_group);
+
+    let platform_group = adw::PreferencesGroup::new();
+    platform_group.set_title("Platform");
+
+    let firmware_labels: Vec<&str> = FirmwareType::ALL.iter().map(|f| f.label()).collect();
+    let firmware_row = adw::ComboRow::new();
+    firmware_row.set_title("Firmware");
+    firmware_row.set_model(Some(&gtk::StringList::new(&firmware_labels)));
+    let fw = FirmwareType::from_name(&settings.new_vm.firmware);
+    firmware_row.set_selected(FirmwareType::ALL.iter().position(|f| *f == fw).unwrap_or(0) as u32);
+    platform_group.add(&firmware_row);
+
+    let tpm_row = adw::SwitchRow::new();
+    tpm_row.set_title("Enable TPM");
+    tpm_row.set_active(settings.new_vm.tpm);
+    platform_group.add(&tpm_row);
+
+    new_vm_page.add(&platform_group);
+    window.add(&new_vm_page);
+
+    let base = settings.clone();
+    window.connect_close_request(move |_| {
+        let optional = |text: glib::GString| {
+            let text = text.trim().to_string();
+            (!text.is_empty()).then_some(text)
+        };
+
+        let mut updated = base.clone();
+        updated.poll_interval_secs = poll_row.value() as u32;
+        updated.perf_interval_secs = perf_row.value() as u32;
+        updated.default_pool = optional(pool_row.text());
+        updated.default_network = optional(network_row.text());
+        updated.new_vm.vcpus = cpu_row.value() as u32;
+        updated.new_vm.memory_mib = memory_row.value() as u64;
+        updated.new_vm.disk_size_gib = disk_row.value() as u64;
+        updated.new_vm.disk_format = DiskFormat::ALL
+            .get(format_row.selected() as usize)
+            .copied()
+            .unwrap_or(DiskFormat::Qcow2)
+            .as_str()
+            .to_string();
+        updated.new_vm.firmware = FirmwareType::ALL
+            .get(firmware_row.selected() as usize)
+            .copied()
+            .unwrap_or(FirmwareType::Bios)
+            .as_str()
+            .to_string();
+        updated.new_vm.tpm = tpm_row.is_active();
+
+        if updated != base {
+            on_apply(updated);
+        }
+        glib::Propagation::Proceed
+    });
+
+    window.present();
+}
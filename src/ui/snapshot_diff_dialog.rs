@@ -0,0 +1,72 @@
+use gtk4 as gtk;
+use gtk::prelude::*;
+use libadwaita as adw;
+use adw::prelude::*;
+use grustyvman_core::types::ConfigChange;
+
+/// List what changed in the VM's configuration since snapshot `snap_name`,
+/// one group per section. `changes` come in section order.
+pub fn show_snapshot_diff_dialog(
+    parent: &adw::ApplicationWindow,
+    snap_name: &str,
+    changes: &[ConfigChange],
+) {
+    let dialog = adw::Window::builder()
+        .title(format!("Changes Since {snap_name}"))
+        .modal(true)
+        .transient_for(parent)
+        .default_width(560)
+        .default_height(520)
+        .build();
+
+    let toolbar = adw::ToolbarView::new();
+    let header = adw::HeaderBar::new();
+    toolbar.add_top_bar(&header);
+
+    if changes.is_empty() {
+        let empty_page = adw::StatusPage::new();
+        empty_page.set_title("No Changes");
+        empty_page.set_description(Some(
+            "The VM is configured as it was when the snapshot was taken",
+        ));
+        empty_page.set_icon_name(Some("emblem-ok-symbolic"));
+        toolbar.set_content(Some(&empty_page));
+        dialog.set_content(Some(&toolbar));
+        dialog.present();
+        return;
+    }
+
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 24);
+    content.set_margin_top(24);
+    content.set_margin_bottom(24);
+    content.set_margin_start(24);
+    content.set_margin_end(24);
+
+    for section in changes.chunk_by(|a, b| a.section == b.section) {
+        let group = adw::PreferencesGroup::new();
+        group.set_title(section[0].section.label());
+
+        for change in section {
+            let row = adw::ActionRow::new();
+            row.set_title(&change.item);
+            let subtitle = match (&change.before, &change.after) {
+                (Some(before), Some(after)) => format!("{before} \u{2192} {after}"),
+                (None, Some(after)) => format!("Added: {after}"),
+                (Some(before), None) => format!("Removed: {before}"),
+                (None, None) => String::new(),
+            };
+            row.set_subtitle(&subtitle);
+            row.set_subtitle_lines(3);
+            group.add(&row);
+        }
+        content.append(&group);
+    }
+
+    let scrolled = gtk::ScrolledWindow::new();
+    scrolled.set_hscrollbar_policy(gtk::PolicyType::Never);
+    scrolled.set_child(Some(&content));
+    toolbar.set_content(Some(&scrolled));
+    dialog.set_content(Some(&toolbar));
+
+    dialog.present();
+}
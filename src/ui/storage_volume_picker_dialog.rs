@@ -5,7 +5,7 @@ use adw::prelude::*;
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use crate::backend::types::VolumeInfo;
+use grustyvman_core::types::VolumeInfo;
 
 fn format_vol_size(bytes: u64) -> String {
     if bytes == 0 {
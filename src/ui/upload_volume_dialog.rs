@@ -4,10 +4,11 @@ use libadwaita as adw;
 use adw::prelude::*;
 use std::cell::RefCell;
 use std::rc::Rc;
+use grustyvman_core::storage;
 
 pub fn show_upload_volume_dialog(
     parent: &adw::ApplicationWindow,
-    on_upload: impl Fn(String, String) + 'static,
+    on_upload: impl Fn(String, String, bool) + 'static,
 ) {
     let dialog = adw::Window::builder()
         .title("Upload Image")
@@ -47,6 +48,12 @@ pub fn show_upload_volume_dialog(
     name_row.set_title("Volume Name");
     group.add(&name_row);
 
+    // Only offered for images from other hypervisors
+    let convert_row = adw::SwitchRow::new();
+    convert_row.set_title("Convert to qcow2");
+    convert_row.set_visible(false);
+    group.add(&convert_row);
+
     content.append(&group);
 
     // Buttons
@@ -70,7 +77,23 @@ pub fn show_upload_volume_dialog(
     let selected_path: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
 
     // File dialog
+    let file_name: Rc<RefCell<String>> = Rc::new(RefCell::new(String::new()));
+
+    // Follow the switch with the suggested name, unless it was edited
+    let file_name_toggle = file_name.clone();
+    let name_row_toggle = name_row.clone();
+    convert_row.connect_active_notify(move |row| {
+        let original = file_name_toggle.borrow().clone();
+        let converted = storage::qcow2_volume_name(&original);
+        let (from, to) = if row.is_active() { (original, converted) } else { (converted, original) };
+        if name_row_toggle.text() == from {
+            name_row_toggle.set_text(&to);
+        }
+    });
+
     let selected_path_browse = selected_path.clone();
+    let file_name_browse = file_name.clone();
+    let convert_row_ref = convert_row.clone();
     let file_row_ref = file_row.clone();
     let name_row_ref = name_row.clone();
     let upload_btn_ref = upload_btn.clone();
@@ -83,13 +106,19 @@ pub fn show_upload_volume_dialog(
         filter.add_pattern("*.iso");
         filter.add_pattern("*.img");
         filter.add_pattern("*.qcow2");
-        filter.set_name(Some("Disk Images (*.iso, *.img, *.qcow2)"));
+        filter.add_pattern("*.vmdk");
+        filter.add_pattern("*.vdi");
+        filter.add_pattern("*.vhd");
+        filter.add_pattern("*.vhdx");
+        filter.set_name(Some("Disk Images"));
 
         let filters = gio::ListStore::new::<gtk::FileFilter>();
         filters.append(&filter);
         file_dialog.set_filters(Some(&filters));
 
         let sp = selected_path_browse.clone();
+        let fname_ref = file_name_browse.clone();
+        let cr = convert_row_ref.clone();
         let fr = file_row_ref.clone();
         let nr = name_row_ref.clone();
         let ub = upload_btn_ref.clone();
@@ -106,6 +135,18 @@ pub fn show_upload_volume_dialog(
                         // Always update if it was the same as the previous filename
                         nr.set_text(&name);
                     }
+                    *fname_ref.borrow_mut() = nr.text().to_string();
+                    cr.set_active(false);
+                    match storage::image_format(&path_str) {
+                        Ok(format) if format.is_foreign() => {
+                            cr.set_subtitle(&format!("{format} image"));
+                            cr.set_visible(true);
+                            // Pools cannot hold images qemu-img alone knows
+                            cr.set_sensitive(format.libvirt_supported());
+                            cr.set_active(true);
+                        }
+                        _ => cr.set_visible(false),
+                    }
                     fr.set_subtitle(&path_str);
                     *sp.borrow_mut() = Some(path_str);
                     ub.set_sensitive(true);
@@ -133,7 +174,7 @@ pub fn show_upload_volume_dialog(
         if vol_name.is_empty() {
             return;
         }
-        on_upload(src, vol_name);
+        on_upload(src, vol_name, convert_row.is_visible() && convert_row.is_active());
         if let Some(d) = dialog_weak.upgrade() {
             d.close();
         }
@@ -5,7 +5,7 @@ use adw::prelude::*;
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use crate::backend::types::{
+use grustyvman_core::types::{
     BootDevice, ChannelInfo, ConfigAction, ConfigChanges, ControllerInfo, CpuMode, CpuTune,
     DomainDetails, FilesystemInfo, FirmwareType, GraphicsType, InputInfo, MemballoonModel,
     PanicModel, SmartcardMode, SoundModel, TpmModel, VcpuPin, VideoModel, VolumeInfo, CPU_MODELS,
@@ -387,7 +387,13 @@ pub fn show_config_dialog(
         let row = adw::ActionRow::new();
         let type_label = if disk.device_type == "cdrom" { " (CD-ROM)" } else { "" };
         row.set_title(&format!("/dev/{}{}", disk.target_dev, type_label));
-        row.set_subtitle(&disk.source_file.clone().unwrap_or_else(|| "No media".to_string()));
+        let mut subtitle = disk.source_file.clone().unwrap_or_else(|| "No media".to_string());
+        match disk.backing_chain.len() {
+            0 => {}
+            1 => subtitle.push_str(" (on 1 backing image)"),
+            n => subtitle.push_str(&format!(" (on {n} backing images)")),
+        }
+        row.set_subtitle(&subtitle);
 
         let btn_box = gtk::Box::new(gtk::Orientation::Horizontal, 4);
         btn_box.set_valign(gtk::Align::Center);
@@ -456,6 +462,61 @@ pub fn show_config_dialog(
                 );
             });
             btn_box.append(&change_btn);
+
+            // Grow button, live while the VM runs
+            let grow_btn = gtk::Button::from_icon_name("zoom-fit-best-symbolic");
+            grow_btn.add_css_class("flat");
+            grow_btn.set_tooltip_text(Some("Grow Disk"));
+            let on_action_grow = on_action.clone();
+            let target = disk.target_dev.clone();
+            let window_ref = window.clone();
+            let parent_ref = parent.clone();
+            grow_btn.connect_clicked(move |_| {
+                let on_action = on_action_grow.clone();
+                let target_grow = target.clone();
+                let wr = window_ref.clone();
+                crate::ui::grow_disk_dialog::show_grow_disk_dialog(
+                    &parent_ref,
+                    &target,
+                    is_running,
+                    move |grow_by, grow_filesystems| {
+                        on_action(ConfigAction::ResizeDisk(
+                            target_grow.clone(),
+                            grow_by,
+                            grow_filesystems,
+                        ));
+                        wr.close();
+                    },
+                );
+            });
+            btn_box.append(&grow_btn);
+
+            // Backing chain, which block jobs can flatten while the VM runs
+            let chain_btn = gtk::Button::from_icon_name("view-list-symbolic");
+            chain_btn.add_css_class("flat");
+            chain_btn.set_tooltip_text(Some("Consolidate Disk Chain"));
+            let on_action_chain = on_action.clone();
+            let target = disk.target_dev.clone();
+            let mut chain: Vec<String> = disk.source_file.iter().cloned().collect();
+            chain.extend(disk.backing_chain.iter().cloned());
+            let window_ref = window.clone();
+            let parent_ref = parent.clone();
+            chain_btn.connect_clicked(move |_| {
+                let on_action = on_action_chain.clone();
+                let target_chain = target.clone();
+                let wr = window_ref.clone();
+                crate::ui::consolidate_disk_dialog::show_consolidate_disk_dialog(
+                    &parent_ref,
+                    &target,
+                    &chain,
+                    is_running,
+                    move |merge| {
+                        on_action(ConfigAction::ConsolidateDisk(target_chain.clone(), merge));
+                        wr.close();
+                    },
+                );
+            });
+            btn_box.append(&chain_btn);
         }
 
         // Remove button (for all disks)
@@ -968,7 +1029,7 @@ pub fn show_config_dialog(
         } else {
             (false, "isa-serial".to_string())
         };
-        use crate::backend::types::SerialInfo;
+        use grustyvman_core::types::SerialInfo;
         on_action_serial(ConfigAction::AddSerial(SerialInfo {
             is_console,
             target_type,
@@ -992,7 +1053,7 @@ pub fn show_config_dialog(
     let rng_group = adw::PreferencesGroup::new();
     rng_group.set_title("Random Number Generator");
 
-    use crate::backend::types::RngBackend;
+    use grustyvman_core::types::RngBackend;
     let rng_labels: Vec<&str> = {
         let mut v: Vec<&str> = RngBackend::ALL.iter().map(|r| r.label()).collect();
         v.push("None (disabled)");
@@ -1033,7 +1094,7 @@ pub fn show_config_dialog(
     let wd_group = adw::PreferencesGroup::new();
     wd_group.set_title("Watchdog");
 
-    use crate::backend::types::{WatchdogAction, WatchdogModel};
+    use grustyvman_core::types::{WatchdogAction, WatchdogModel};
     let wd_model_labels: Vec<&str> = WatchdogModel::ALL.iter().map(|m| m.label()).collect();
     let wd_model_list = gtk::StringList::new(&wd_model_labels);
     let wd_model_row = adw::ComboRow::new();
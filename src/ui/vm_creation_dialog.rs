@@ -5,110 +5,407 @@ use adw::prelude::*;
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use crate::backend::domain_xml::NewVmParams;
-use crate::backend::types::{DiskFormat, FirmwareType, NetworkModel, NetworkSourceType, NewVmNetworkConfig, TpmModel, VolumeInfo};
+use grustyvman_core::cloudinit::{CloudInitConfig, CloudInitUser};
+use grustyvman_core::domain_xml::NewVmParams;
+use grustyvman_core::osinfo::{OsDatabase, OsDefaults, OsFamily, OsVariant};
+use grustyvman_core::unattend::{self, WindowsUnattendConfig};
+use crate::settings::Settings;
+use grustyvman_core::types::{
+    DiskBus, DiskFormat, FirmwareType, InstallSource, NetworkModel, NetworkSourceType,
+    NewVmNetworkConfig, TpmModel, VideoModel, VolumeInfo,
+};
+
+/// How the wizard's first page gets an operating system onto the VM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InstallMethod {
+    Iso,
+    Import,
+    CloudImage,
+    Empty,
+}
+
+impl InstallMethod {
+    const ALL: &[InstallMethod] = &[
+        InstallMethod::Iso,
+        InstallMethod::Import,
+        InstallMethod::CloudImage,
+        InstallMethod::Empty,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            InstallMethod::Iso => "Install from ISO image",
+            InstallMethod::Import => "Import existing disk image",
+            InstallMethod::CloudImage => "Provision a cloud image",
+            InstallMethod::Empty => "Empty disk (network boot)",
+        }
+    }
+}
+
+/// Widgets on the hardware pages that follow the selected guest OS.
+#[derive(Clone)]
+struct HardwareRows {
+    cpu_row: adw::SpinRow,
+    memory_row: adw::SpinRow,
+    disk_row: adw::SpinRow,
+    bus_row: adw::ComboRow,
+    firmware_row: adw::ComboRow,
+    video_row: adw::ComboRow,
+    model_row: adw::ComboRow,
+    tpm_enable_row: adw::SwitchRow,
+    tpm_model_row: adw::ComboRow,
+    hyperv_row: adw::SwitchRow,
+}
+
+impl HardwareRows {
+    fn apply(&self, defaults: &OsDefaults, tpm_models: &[TpmModel]) {
+        self.cpu_row.set_value(defaults.vcpus as f64);
+        self.memory_row.set_value(defaults.memory_mib as f64);
+        self.disk_row.set_value(defaults.disk_size_gib as f64);
+        select(&self.bus_row, DiskBus::ALL, defaults.disk_bus);
+        select(&self.firmware_row, FirmwareType::ALL, defaults.firmware);
+        select(&self.video_row, VideoModel::ALL, defaults.video);
+        select(&self.model_row, NetworkModel::ALL, defaults.nic_model);
+        self.tpm_enable_row.set_active(defaults.tpm.is_some());
+        if let Some(model) = defaults.tpm {
+            select(&self.tpm_model_row, tpm_models, model);
+        }
+        self.hyperv_row.set_active(defaults.hyperv);
+    }
+}
+
+fn select<T: PartialEq>(row: &adw::ComboRow, all: &[T], value: T) {
+    if let Some(idx) = all.iter().position(|v| *v == value) {
+        row.set_selected(idx as u32);
+    }
+}
+
+fn combo_row(title: &str, labels: &[&str]) -> adw::ComboRow {
+    let row = adw::ComboRow::new();
+    row.set_title(title);
+    row.set_model(Some(&gtk::StringList::new(labels)));
+    row
+}
+
+/// One wizard step: a header bar (with the back button) over a scrolling
+/// column of preference groups.
+fn wizard_page(title: &str, content: &gtk::Box) -> adw::NavigationPage {
+    let toolbar_view = adw::ToolbarView::new();
+    toolbar_view.add_top_bar(&adw::HeaderBar::new());
+
+    let clamp = adw::Clamp::new();
+    clamp.set_maximum_size(480);
+    clamp.set_margin_top(24);
+    clamp.set_margin_bottom(24);
+    clamp.set_margin_start(12);
+    clamp.set_margin_end(12);
+    clamp.set_child(Some(content));
+
+    let scrolled = gtk::ScrolledWindow::new();
+    scrolled.set_hscrollbar_policy(gtk::PolicyType::Never);
+    scrolled.set_child(Some(&clamp));
+    toolbar_view.set_content(Some(&scrolled));
+
+    adw::NavigationPage::new(&toolbar_view, title)
+}
 
+fn pill_button(label: &str) -> gtk::Button {
+    let button = gtk::Button::with_label(label);
+    button.add_css_class("suggested-action");
+    button.add_css_class("pill");
+    button.set_halign(gtk::Align::Center);
+    button.set_margin_top(12);
+    button
+}
+
+pub(crate) fn multiline_entry(group: &adw::PreferencesGroup, title: &str) -> gtk::TextView {
+    let label = gtk::Label::new(Some(title));
+    label.set_halign(gtk::Align::Start);
+    label.add_css_class("caption-heading");
+    label.set_margin_top(12);
+    label.set_margin_bottom(6);
+
+    let text_view = gtk::TextView::new();
+    text_view.set_monospace(true);
+    text_view.set_wrap_mode(gtk::WrapMode::Char);
+    text_view.set_top_margin(6);
+    text_view.set_bottom_margin(6);
+    text_view.set_left_margin(6);
+    text_view.set_right_margin(6);
+    text_view.set_height_request(72);
+    text_view.add_css_class("card");
+
+    let container = gtk::Box::new(gtk::Orientation::Vertical, 0);
+    container.append(&label);
+    container.append(&text_view);
+    group.add(&container);
+    text_view
+}
+
+/// Non-empty, trimmed lines of a text view.
+pub(crate) fn text_lines(text_view: &gtk::TextView) -> Vec<String> {
+    let buffer = text_view.buffer();
+    buffer
+        .text(&buffer.start_iter(), &buffer.end_iter(), false)
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn file_name(path: &str) -> String {
+    std::path::Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+/// Multi-step VM creation wizard. `read_iso_label` reads an ISO's volume
+/// label off the main thread; the label is matched against `os_db` to
+/// preselect hardware for the guest OS.
 pub fn show_creation_dialog(
     parent: &adw::ApplicationWindow,
     pool_volumes: Vec<(String, Vec<VolumeInfo>)>,
     virtual_networks: Vec<String>,
+    os_db: &'static OsDatabase,
+    settings: &Settings,
+    read_iso_label: impl Fn(String) -> async_channel::Receiver<Option<String>> + 'static,
     on_create: impl Fn(NewVmParams) + 'static,
 ) {
     let dialog = gtk::Window::new();
     dialog.set_title(Some("New Virtual Machine"));
-    dialog.set_default_size(480, 520);
+    dialog.set_default_size(480, 640);
     dialog.set_decorated(false); // suppress WM title bar; adw::HeaderBar provides the only bar
     dialog.set_modal(true);
     dialog.set_transient_for(Some(parent));
 
-    let toolbar_view = adw::ToolbarView::new();
+    let nav_view = adw::NavigationView::new();
 
-    let header = adw::HeaderBar::new();
-    toolbar_view.add_top_bar(&header);
+    // Hardware for an unrecognised guest: the user's defaults on virtio
+    let generic_defaults = OsDefaults {
+        vcpus: settings.new_vm.vcpus,
+        memory_mib: settings.new_vm.memory_mib,
+        disk_size_gib: settings.new_vm.disk_size_gib,
+        firmware: FirmwareType::from_name(&settings.new_vm.firmware),
+        tpm: settings.new_vm.tpm.then_some(TpmModel::Crb),
+        ..OsDefaults::virtio()
+    };
 
-    let clamp = adw::Clamp::new();
-    clamp.set_maximum_size(480);
-    clamp.set_margin_top(24);
-    clamp.set_margin_bottom(24);
-    clamp.set_margin_start(12);
-    clamp.set_margin_end(12);
+    // ---- Page 1: installation source and OS ----
+
+    let source_content = gtk::Box::new(gtk::Orientation::Vertical, 24);
 
-    let content = gtk::Box::new(gtk::Orientation::Vertical, 24);
+    let method_group = adw::PreferencesGroup::new();
+    method_group.set_title("Installation");
+    let method_labels: Vec<&str> = InstallMethod::ALL.iter().map(|m| m.label()).collect();
+    let method_row = combo_row("Method", &method_labels);
+    method_group.add(&method_row);
+
+    let iso_row = adw::ActionRow::new();
+    iso_row.set_title("ISO Image");
+    iso_row.set_subtitle("No ISO selected");
+    let iso_browse_btn = gtk::Button::with_label("Browse...");
+    iso_browse_btn.set_valign(gtk::Align::Center);
+    iso_row.add_suffix(&iso_browse_btn);
+    method_group.add(&iso_row);
+
+    let import_row = adw::ActionRow::new();
+    import_row.set_title("Disk Image");
+    import_row.set_subtitle("No disk image selected");
+    import_row.set_visible(false);
+    let import_browse_btn = gtk::Button::with_label("Browse...");
+    import_browse_btn.set_valign(gtk::Align::Center);
+    import_row.add_suffix(&import_browse_btn);
+    method_group.add(&import_row);
+
+    let cloud_image_row = adw::ActionRow::new();
+    cloud_image_row.set_title("Cloud Image");
+    cloud_image_row.set_subtitle("No cloud image selected");
+    cloud_image_row.set_visible(false);
+    let cloud_image_browse_btn = gtk::Button::with_label("Browse...");
+    cloud_image_browse_btn.set_valign(gtk::Align::Center);
+    cloud_image_row.add_suffix(&cloud_image_browse_btn);
+    method_group.add(&cloud_image_row);
+
+    if pool_volumes.is_empty() {
+        for btn in [&iso_browse_btn, &import_browse_btn, &cloud_image_browse_btn] {
+            btn.set_sensitive(false);
+            btn.set_tooltip_text(Some("No storage pools available"));
+        }
+    }
+    source_content.append(&method_group);
+
+    let os_group = adw::PreferencesGroup::new();
+    os_group.set_title("Operating System");
+    os_group.set_description(Some(
+        "Detected from the ISO volume label when possible. Hardware on the next pages follows this choice.",
+    ));
+
+    // Index 0 is "Generic"; the rest follow os_choices
+    let os_choices: Vec<&'static OsVariant> = os_db.installable().collect();
+    let mut os_labels: Vec<&str> = vec!["Generic OS"];
+    os_labels.extend(os_choices.iter().map(|v| v.name.as_str()));
+    let os_row = combo_row("Guest OS", &os_labels);
+    os_row.set_enable_search(true);
+    os_row.set_expression(Some(gtk::PropertyExpression::new(
+        gtk::StringObject::static_type(),
+        None::<gtk::Expression>,
+        "string",
+    )));
+    os_group.add(&os_row);
+
+    let detect_row = adw::ActionRow::new();
+    detect_row.set_title("Detection");
+    detect_row.set_subtitle("Select an ISO image to detect its OS");
+    os_group.add(&detect_row);
+    source_content.append(&os_group);
+
+    // cloud-init user-data, for cloud images only
+    let cloud_init_group = adw::PreferencesGroup::new();
+    cloud_init_group.set_title("Provisioning");
+    cloud_init_group.set_description(Some(
+        "Applied by cloud-init on first boot. The image itself is not modified; the VM gets a linked copy.",
+    ));
+    cloud_init_group.set_visible(false);
+
+    let hostname_row = adw::EntryRow::new();
+    hostname_row.set_title("Hostname (defaults to the VM name)");
+    cloud_init_group.add(&hostname_row);
+
+    let user_row = adw::EntryRow::new();
+    user_row.set_title("User (blank for the image's default user)");
+    cloud_init_group.add(&user_row);
+
+    let packages_row = adw::EntryRow::new();
+    packages_row.set_title("Packages (space separated)");
+    cloud_init_group.add(&packages_row);
+
+    let ssh_keys_view = multiline_entry(&cloud_init_group, "SSH Public Keys (one per line)");
+    let runcmd_view = multiline_entry(&cloud_init_group, "First Boot Commands (one per line)");
+    source_content.append(&cloud_init_group);
+
+    // Windows only: answer file and virtio drivers
+    let windows_group = adw::PreferencesGroup::new();
+    windows_group.set_title("Windows Setup");
+    windows_group.set_visible(false);
+
+    let virtio_win_row = adw::ActionRow::new();
+    virtio_win_row.set_title("virtio-win Drivers");
+    virtio_win_row.set_subtitle("No driver ISO selected");
+    let virtio_win_browse_btn = gtk::Button::with_label("Browse...");
+    virtio_win_browse_btn.set_valign(gtk::Align::Center);
+    virtio_win_browse_btn.set_sensitive(!pool_volumes.is_empty());
+    virtio_win_row.add_suffix(&virtio_win_browse_btn);
+    windows_group.add(&virtio_win_row);
+
+    let unattend_row = adw::ExpanderRow::new();
+    unattend_row.set_title("Unattended Install");
+    unattend_row.set_subtitle("Answer Windows Setup automatically");
+    unattend_row.set_show_enable_switch(true);
+    unattend_row.set_enable_expansion(false);
+    windows_group.add(&unattend_row);
+
+    let admin_user_row = adw::EntryRow::new();
+    admin_user_row.set_title("Administrator");
+    admin_user_row.set_text("Admin");
+    unattend_row.add_row(&admin_user_row);
+
+    let admin_password_row = adw::PasswordEntryRow::new();
+    admin_password_row.set_title("Password");
+    unattend_row.add_row(&admin_password_row);
+
+    let computer_name_row = adw::EntryRow::new();
+    computer_name_row.set_title("Computer Name (blank for random)");
+    unattend_row.add_row(&computer_name_row);
+
+    let product_key_row = adw::EntryRow::new();
+    product_key_row.set_title("Product Key (optional)");
+    unattend_row.add_row(&product_key_row);
+
+    let locale_row = adw::EntryRow::new();
+    locale_row.set_title("Locale");
+    locale_row.set_text("en-US");
+    unattend_row.add_row(&locale_row);
+
+    let timezone_row = adw::EntryRow::new();
+    timezone_row.set_title("Time Zone");
+    timezone_row.set_text("UTC");
+    unattend_row.add_row(&timezone_row);
+    source_content.append(&windows_group);
+
+    let source_next_btn = pill_button("Next");
+    source_next_btn.set_sensitive(false);
+    source_content.append(&source_next_btn);
+
+    let source_page = wizard_page("New Virtual Machine", &source_content);
+
+    // ---- Page 2: resources ----
+
+    let resources_content = gtk::Box::new(gtk::Orientation::Vertical, 24);
 
-    // General group
     let general_group = adw::PreferencesGroup::new();
     general_group.set_title("General");
-
     let name_row = adw::EntryRow::new();
     name_row.set_title("Name");
     name_row.set_text("new-vm");
     general_group.add(&name_row);
+    resources_content.append(&general_group);
 
-    let firmware_labels: Vec<&str> = FirmwareType::ALL.iter().map(|f| f.label()).collect();
-    let firmware_list = gtk::StringList::new(&firmware_labels);
-    let firmware_row = adw::ComboRow::new();
-    firmware_row.set_title("Firmware");
-    firmware_row.set_model(Some(&firmware_list));
-    firmware_row.set_selected(0);
-    general_group.add(&firmware_row);
-
-    content.append(&general_group);
-
-    // Resources group
     let resources_group = adw::PreferencesGroup::new();
     resources_group.set_title("Resources");
 
     let cpu_row = adw::SpinRow::with_range(1.0, 32.0, 1.0);
     cpu_row.set_title("vCPUs");
-    cpu_row.set_value(2.0);
     resources_group.add(&cpu_row);
 
     let memory_row = adw::SpinRow::with_range(256.0, 65536.0, 256.0);
     memory_row.set_title("Memory (MiB)");
-    memory_row.set_value(2048.0);
     resources_group.add(&memory_row);
+    resources_content.append(&resources_group);
+
+    let storage_group = adw::PreferencesGroup::new();
+    storage_group.set_title("Storage");
 
     let disk_row = adw::SpinRow::with_range(1.0, 1000.0, 1.0);
     disk_row.set_title("Disk Size (GiB)");
-    disk_row.set_value(20.0);
-    resources_group.add(&disk_row);
+    storage_group.add(&disk_row);
 
     let format_labels: Vec<&str> = DiskFormat::ALL.iter().map(|f| f.label()).collect();
-    let format_list = gtk::StringList::new(&format_labels);
-    let format_row = adw::ComboRow::new();
-    format_row.set_title("Disk Format");
-    format_row.set_model(Some(&format_list));
-    format_row.set_selected(0); // qcow2 by default
-    resources_group.add(&format_row);
+    let format_row = combo_row("Disk Format", &format_labels);
+    select(&format_row, DiskFormat::ALL, DiskFormat::from_name(&settings.new_vm.disk_format));
+    storage_group.add(&format_row);
 
-    content.append(&resources_group);
+    let bus_labels: Vec<&str> = DiskBus::ALL.iter().map(|b| b.label()).collect();
+    let bus_row = combo_row("Disk Bus", &bus_labels);
+    storage_group.add(&bus_row);
+    resources_content.append(&storage_group);
 
-    // ISO group
-    let iso_group = adw::PreferencesGroup::new();
-    iso_group.set_title("Installation Media");
+    let resources_next_btn = pill_button("Next");
+    resources_content.append(&resources_next_btn);
 
-    let iso_row = adw::ActionRow::new();
-    iso_row.set_title("ISO Image");
-    iso_row.set_subtitle("No ISO selected");
+    let resources_page = wizard_page("Resources", &resources_content);
 
-    let iso_path: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+    // ---- Page 3: devices ----
 
-    let browse_btn = gtk::Button::with_label("Browse...");
-    browse_btn.set_valign(gtk::Align::Center);
-    if pool_volumes.is_empty() {
-        browse_btn.set_sensitive(false);
-        browse_btn.set_tooltip_text(Some("No storage pools available"));
-    }
-    iso_row.add_suffix(&browse_btn);
+    let devices_content = gtk::Box::new(gtk::Orientation::Vertical, 24);
+
+    let system_group = adw::PreferencesGroup::new();
+    system_group.set_title("System");
+
+    let firmware_labels: Vec<&str> = FirmwareType::ALL.iter().map(|f| f.label()).collect();
+    let firmware_row = combo_row("Firmware", &firmware_labels);
+    system_group.add(&firmware_row);
 
-    let clear_btn = gtk::Button::from_icon_name("edit-clear-symbolic");
-    clear_btn.set_valign(gtk::Align::Center);
-    clear_btn.set_tooltip_text(Some("Clear ISO selection"));
-    clear_btn.set_visible(false);
-    iso_row.add_suffix(&clear_btn);
+    let video_labels: Vec<&str> = VideoModel::ALL.iter().map(|v| v.label()).collect();
+    let video_row = combo_row("Video", &video_labels);
+    system_group.add(&video_row);
 
-    iso_group.add(&iso_row);
-    content.append(&iso_group);
+    let hyperv_row = adw::SwitchRow::new();
+    hyperv_row.set_title("Hyper-V Enlightenments");
+    hyperv_row.set_subtitle("Recommended for Windows guests");
+    system_group.add(&hyperv_row);
+    devices_content.append(&system_group);
 
     // Network group
     let network_group = adw::PreferencesGroup::new();
@@ -116,10 +413,7 @@ pub fn show_creation_dialog(
 
     // Source type
     let src_labels: Vec<&str> = NetworkSourceType::ALL.iter().map(|s| s.label()).collect();
-    let src_list = gtk::StringList::new(&src_labels);
-    let src_type_row = adw::ComboRow::new();
-    src_type_row.set_title("Network Source");
-    src_type_row.set_model(Some(&src_list));
+    let src_type_row = combo_row("Network Source", &src_labels);
     src_type_row.set_selected(0); // Virtual Network
     network_group.add(&src_type_row);
 
@@ -134,8 +428,13 @@ pub fn show_creation_dialog(
     } else {
         let virt_net_list = gtk::StringList::new(&virt_net_labels);
         virt_net_row.set_model(Some(&virt_net_list));
-        // Select "default" if present, else index 0
-        let default_idx = virtual_networks.iter().position(|n| n == "default").unwrap_or(0);
+        // Select the configured default network, then "default", else index 0
+        let preferred = settings.default_network.as_deref().unwrap_or("default");
+        let default_idx = virtual_networks
+            .iter()
+            .position(|n| n == preferred)
+            .or_else(|| virtual_networks.iter().position(|n| n == "default"))
+            .unwrap_or(0);
         virt_net_row.set_selected(default_idx as u32);
     }
     network_group.add(&virt_net_row);
@@ -146,16 +445,10 @@ pub fn show_creation_dialog(
     dev_entry_row.set_visible(false);
     network_group.add(&dev_entry_row);
 
-    // Model
     let model_labels: Vec<&str> = NetworkModel::ALL.iter().map(|m| m.label()).collect();
-    let model_list = gtk::StringList::new(&model_labels);
-    let model_row = adw::ComboRow::new();
-    model_row.set_title("Model");
-    model_row.set_model(Some(&model_list));
-    model_row.set_selected(0); // virtio
+    let model_row = combo_row("Model", &model_labels);
     network_group.add(&model_row);
-
-    content.append(&network_group);
+    devices_content.append(&network_group);
 
     // Wire up source type selection to show/hide rows
     let virt_net_row_clone = virt_net_row.clone();
@@ -187,61 +480,21 @@ pub fn show_creation_dialog(
         }
     });
 
-    // Browse button handler — opens the libvirt storage volume picker
-    let iso_path_clone = iso_path.clone();
-    let iso_row_clone = iso_row.clone();
-    let clear_btn_clone = clear_btn.clone();
-    let parent_clone = parent.clone();
-    let pool_volumes_clone = pool_volumes.clone();
-    browse_btn.connect_clicked(move |_| {
-        let iso_path = iso_path_clone.clone();
-        let iso_row = iso_row_clone.clone();
-        let clear_btn = clear_btn_clone.clone();
-        crate::ui::storage_volume_picker_dialog::show_storage_volume_picker(
-            &parent_clone,
-            &pool_volumes_clone,
-            move |path| {
-                let display = std::path::Path::new(&path)
-                    .file_name()
-                    .map(|n| n.to_string_lossy().to_string())
-                    .unwrap_or_else(|| path.clone());
-                iso_row.set_subtitle(&display);
-                *iso_path.borrow_mut() = Some(path);
-                clear_btn.set_visible(true);
-            },
-        );
-    });
-
-    // Clear button handler
-    let iso_path_clone = iso_path.clone();
-    let iso_row_clone = iso_row.clone();
-    clear_btn.connect_clicked(move |btn| {
-        *iso_path_clone.borrow_mut() = None;
-        iso_row_clone.set_subtitle("No ISO selected");
-        btn.set_visible(false);
-    });
-
     // TPM group
     let tpm_group = adw::PreferencesGroup::new();
     tpm_group.set_title("Security");
 
     let tpm_enable_row = adw::SwitchRow::new();
     tpm_enable_row.set_title("Enable TPM");
-    tpm_enable_row.set_active(false);
     tpm_group.add(&tpm_enable_row);
 
     // Only show real models (not None)
     let tpm_models: Vec<TpmModel> = TpmModel::ALL.iter().copied().filter(|m| *m != TpmModel::None).collect();
     let tpm_model_labels: Vec<&str> = tpm_models.iter().map(|m| m.label()).collect();
-    let tpm_model_list = gtk::StringList::new(&tpm_model_labels);
-    let tpm_model_row = adw::ComboRow::new();
-    tpm_model_row.set_title("TPM Model");
-    tpm_model_row.set_model(Some(&tpm_model_list));
+    let tpm_model_row = combo_row("TPM Model", &tpm_model_labels);
     tpm_model_row.set_selected(0); // CRB
-    tpm_model_row.set_sensitive(false);
     tpm_group.add(&tpm_model_row);
-
-    content.append(&tpm_group);
+    devices_content.append(&tpm_group);
 
     // Wire enable switch to model row sensitivity
     let tpm_model_row_clone = tpm_model_row.clone();
@@ -249,24 +502,341 @@ pub fn show_creation_dialog(
         tpm_model_row_clone.set_sensitive(row.is_active());
     });
 
-    // Create button
-    let create_btn = gtk::Button::with_label("Create");
-    create_btn.add_css_class("suggested-action");
-    create_btn.add_css_class("pill");
-    create_btn.set_halign(gtk::Align::Center);
-    create_btn.set_margin_top(12);
-    content.append(&create_btn);
+    let create_btn = pill_button("Create");
+    devices_content.append(&create_btn);
+
+    let devices_page = wizard_page("Devices", &devices_content);
+
+    // ---- Guest OS defaults ----
+
+    let hardware = HardwareRows {
+        cpu_row: cpu_row.clone(),
+        memory_row: memory_row.clone(),
+        disk_row: disk_row.clone(),
+        bus_row: bus_row.clone(),
+        firmware_row: firmware_row.clone(),
+        video_row: video_row.clone(),
+        model_row: model_row.clone(),
+        tpm_enable_row: tpm_enable_row.clone(),
+        tpm_model_row: tpm_model_row.clone(),
+        hyperv_row: hyperv_row.clone(),
+    };
+    hardware.apply(&generic_defaults, &tpm_models);
+    tpm_model_row.set_sensitive(tpm_enable_row.is_active());
+
+    let selected_os: Rc<dyn Fn() -> Option<&'static OsVariant>> = {
+        let os_row = os_row.clone();
+        let os_choices = os_choices.clone();
+        Rc::new(move || {
+            (os_row.selected() as usize)
+                .checked_sub(1)
+                .and_then(|idx| os_choices.get(idx).copied())
+        })
+    };
+
+    {
+        let hardware = hardware.clone();
+        let tpm_models = tpm_models.clone();
+        let selected_os = selected_os.clone();
+        os_row.connect_notify_local(Some("selected"), move |_, _| {
+            let defaults = selected_os().map_or(&generic_defaults, |os| &os.defaults);
+            hardware.apply(defaults, &tpm_models);
+        });
+    }
+
+    // ---- Installation source ----
+
+    let iso_path: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+    let import_path: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+    let cloud_image_path: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+
+    let selected_method = {
+        let method_row = method_row.clone();
+        move || {
+            InstallMethod::ALL
+                .get(method_row.selected() as usize)
+                .copied()
+                .unwrap_or(InstallMethod::Iso)
+        }
+    };
+
+    // Next is available once the chosen method has its media
+    let update_source_next = Rc::new({
+        let source_next_btn = source_next_btn.clone();
+        let iso_path = iso_path.clone();
+        let import_path = import_path.clone();
+        let cloud_image_path = cloud_image_path.clone();
+        let selected_method = selected_method.clone();
+        move || {
+            let ready = match selected_method() {
+                InstallMethod::Iso => iso_path.borrow().is_some(),
+                InstallMethod::Import => import_path.borrow().is_some(),
+                InstallMethod::CloudImage => cloud_image_path.borrow().is_some(),
+                InstallMethod::Empty => true,
+            };
+            source_next_btn.set_sensitive(ready);
+        }
+    });
+
+    {
+        let iso_row = iso_row.clone();
+        let import_row = import_row.clone();
+        let cloud_image_row = cloud_image_row.clone();
+        let cloud_init_group = cloud_init_group.clone();
+        let disk_row = disk_row.clone();
+        let format_row = format_row.clone();
+        let update_source_next = update_source_next.clone();
+        let selected_method = selected_method.clone();
+        method_row.connect_notify_local(Some("selected"), move |_, _| {
+            let method = selected_method();
+            iso_row.set_visible(method == InstallMethod::Iso);
+            import_row.set_visible(method == InstallMethod::Import);
+            cloud_image_row.set_visible(method == InstallMethod::CloudImage);
+            cloud_init_group.set_visible(method == InstallMethod::CloudImage);
+            // An imported image keeps its own size
+            disk_row.set_visible(method != InstallMethod::Import);
+            // A cloud image overlay is always qcow2
+            format_row.set_sensitive(method != InstallMethod::CloudImage);
+            if method == InstallMethod::CloudImage {
+                select(&format_row, DiskFormat::ALL, DiskFormat::Qcow2);
+            }
+            update_source_next();
+        });
+    }
+
+    // Browse for an installer ISO, then detect the OS from its label
+    let read_iso_label = Rc::new(read_iso_label);
+    {
+        let iso_path = iso_path.clone();
+        let iso_row = iso_row.clone();
+        let detect_row = detect_row.clone();
+        let os_row = os_row.clone();
+        let os_choices = os_choices.clone();
+        let update_source_next = update_source_next.clone();
+        let parent = parent.clone();
+        let pool_volumes = pool_volumes.clone();
+        iso_browse_btn.connect_clicked(move |_| {
+            let iso_path = iso_path.clone();
+            let iso_row = iso_row.clone();
+            let detect_row = detect_row.clone();
+            let os_row = os_row.clone();
+            let os_choices = os_choices.clone();
+            let update_source_next = update_source_next.clone();
+            let read_iso_label = read_iso_label.clone();
+            crate::ui::storage_volume_picker_dialog::show_storage_volume_picker(
+                &parent,
+                &pool_volumes,
+                move |path| {
+                    iso_row.set_subtitle(&file_name(&path));
+                    *iso_path.borrow_mut() = Some(path.clone());
+                    update_source_next();
+
+                    detect_row.set_subtitle("Reading volume label...");
+                    let rx = read_iso_label(path);
+                    let detect_row = detect_row.clone();
+                    let os_row = os_row.clone();
+                    let os_choices = os_choices.clone();
+                    glib::spawn_future_local(async move {
+                        let label = rx.recv().await.ok().flatten();
+                        let Some(label) = label else {
+                            detect_row.set_subtitle("No volume label found; choose the OS below");
+                            return;
+                        };
+                        match os_db.detect(&label) {
+                            Some(os) => {
+                                if let Some(idx) = os_choices.iter().position(|v| v.id == os.id) {
+                                    os_row.set_selected(idx as u32 + 1);
+                                }
+                                detect_row.set_subtitle(&format!("{} (label “{label}”)", os.name));
+                            }
+                            None => detect_row.set_subtitle(&format!(
+                                "Label “{label}” not recognised; choose the OS below"
+                            )),
+                        }
+                    });
+                },
+            );
+        });
+    }
+
+    // Browse for an existing disk image to import
+    {
+        let import_path = import_path.clone();
+        let import_row = import_row.clone();
+        let format_row = format_row.clone();
+        let update_source_next = update_source_next.clone();
+        let parent = parent.clone();
+        let pool_volumes = pool_volumes.clone();
+        import_browse_btn.connect_clicked(move |_| {
+            let import_path = import_path.clone();
+            let import_row = import_row.clone();
+            let format_row = format_row.clone();
+            let update_source_next = update_source_next.clone();
+            crate::ui::storage_volume_picker_dialog::show_storage_volume_picker(
+                &parent,
+                &pool_volumes,
+                move |path| {
+                    import_row.set_subtitle(&file_name(&path));
+                    let format = if path.ends_with(".qcow2") {
+                        DiskFormat::Qcow2
+                    } else {
+                        DiskFormat::Raw
+                    };
+                    select(&format_row, DiskFormat::ALL, format);
+                    *import_path.borrow_mut() = Some(path);
+                    update_source_next();
+                },
+            );
+        });
+    }
+
+    // Browse for a cloud image to use as the base of the new disk
+    {
+        let cloud_image_path = cloud_image_path.clone();
+        let cloud_image_row = cloud_image_row.clone();
+        let update_source_next = update_source_next.clone();
+        let parent = parent.clone();
+        let pool_volumes = pool_volumes.clone();
+        cloud_image_browse_btn.connect_clicked(move |_| {
+            let cloud_image_path = cloud_image_path.clone();
+            let cloud_image_row = cloud_image_row.clone();
+            let update_source_next = update_source_next.clone();
+            crate::ui::storage_volume_picker_dialog::show_storage_volume_picker(
+                &parent,
+                &pool_volumes,
+                move |path| {
+                    cloud_image_row.set_subtitle(&file_name(&path));
+                    *cloud_image_path.borrow_mut() = Some(path);
+                    update_source_next();
+                },
+            );
+        });
+    }
+
+    // Windows setup options follow the guest OS and need an installer ISO
+    {
+        let windows_group = windows_group.clone();
+        let selected_os = selected_os.clone();
+        let selected_method = selected_method.clone();
+        let update = Rc::new(move || {
+            let windows = selected_os().is_some_and(|os| os.family == OsFamily::Windows);
+            windows_group.set_visible(windows && selected_method() == InstallMethod::Iso);
+        });
+        let update_clone = update.clone();
+        os_row.connect_notify_local(Some("selected"), move |_, _| update_clone());
+        method_row.connect_notify_local(Some("selected"), move |_, _| update());
+    }
+
+    let virtio_win_path: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+    {
+        let virtio_win_path = virtio_win_path.clone();
+        let virtio_win_row = virtio_win_row.clone();
+        let parent = parent.clone();
+        let pool_volumes = pool_volumes.clone();
+        virtio_win_browse_btn.connect_clicked(move |_| {
+            let virtio_win_path = virtio_win_path.clone();
+            let virtio_win_row = virtio_win_row.clone();
+            crate::ui::storage_volume_picker_dialog::show_storage_volume_picker(
+                &parent,
+                &pool_volumes,
+                move |path| {
+                    virtio_win_row.set_subtitle(&file_name(&path));
+                    *virtio_win_path.borrow_mut() = Some(path);
+                },
+            );
+        });
+    }
+
+    // ---- Navigation ----
+
+    nav_view.add(&source_page);
+
+    let nav_view_clone = nav_view.clone();
+    source_next_btn.connect_clicked(move |_| {
+        nav_view_clone.push(&resources_page);
+    });
+
+    let nav_view_clone = nav_view.clone();
+    resources_next_btn.connect_clicked(move |_| {
+        nav_view_clone.push(&devices_page);
+    });
 
-    clamp.set_child(Some(&content));
-    toolbar_view.set_content(Some(&clamp));
-    dialog.set_child(Some(&toolbar_view));
+    // Name must be set before creating
+    let create_btn_clone = create_btn.clone();
+    name_row.connect_changed(move |row| {
+        create_btn_clone.set_sensitive(!row.text().is_empty());
+    });
 
+    dialog.set_child(Some(&nav_view));
+
+    let storage_pool = settings.default_pool.clone();
     let dialog_ref = dialog.clone();
     create_btn.connect_clicked(move |_| {
+        let install = match selected_method() {
+            InstallMethod::Iso => match iso_path.borrow().clone() {
+                Some(path) => InstallSource::Iso(path),
+                None => return,
+            },
+            InstallMethod::Import => match import_path.borrow().clone() {
+                Some(path) => InstallSource::ImportDisk(path),
+                None => return,
+            },
+            InstallMethod::CloudImage => match cloud_image_path.borrow().clone() {
+                Some(path) => InstallSource::CloudImage(path),
+                None => return,
+            },
+            InstallMethod::Empty => InstallSource::None,
+        };
+
+        // Hidden Windows options must not leak into other guests
+        let windows = windows_group.is_visible();
+        let virtio_win_iso = virtio_win_path.borrow().clone().filter(|_| windows);
+        let unattend = (windows && unattend_row.enables_expansion()).then(|| {
+            let product_key = product_key_row.text().trim().to_string();
+            WindowsUnattendConfig {
+                locale: locale_row.text().trim().to_string(),
+                timezone: timezone_row.text().trim().to_string(),
+                product_key: (!product_key.is_empty()).then_some(product_key),
+                computer_name: computer_name_row.text().trim().to_string(),
+                admin_user: admin_user_row.text().trim().to_string(),
+                admin_password: admin_password_row.text().to_string(),
+                // Drivers only load if the ISO is attached
+                virtio_driver_dir: virtio_win_iso
+                    .as_ref()
+                    .and(selected_os())
+                    .and_then(|os| unattend::virtio_driver_dir(&os.short_id))
+                    .map(str::to_string),
+            }
+        });
+
+        let cloud_init = matches!(install, InstallSource::CloudImage(_)).then(|| {
+            let keys = text_lines(&ssh_keys_view);
+            let user = user_row.text().trim().to_string();
+            // Keys go to the named user, else to the image's default user
+            let (users, ssh_authorized_keys) = if user.is_empty() {
+                (Vec::new(), keys)
+            } else {
+                let user = CloudInitUser { name: user, ssh_authorized_keys: keys, sudo: true };
+                (vec![user], Vec::new())
+            };
+            let hostname = hostname_row.text().trim().to_string();
+            CloudInitConfig {
+                hostname: if hostname.is_empty() { name_row.text().to_string() } else { hostname },
+                users,
+                ssh_authorized_keys,
+                packages: packages_row.text().split_whitespace().map(str::to_string).collect(),
+                runcmd: text_lines(&runcmd_view),
+            }
+        });
+
         let fw_idx = firmware_row.selected() as usize;
         let firmware = FirmwareType::ALL.get(fw_idx).copied().unwrap_or(FirmwareType::Bios);
         let fmt_idx = format_row.selected() as usize;
         let disk_format = DiskFormat::ALL.get(fmt_idx).copied().unwrap_or(DiskFormat::Qcow2);
+        let bus_idx = bus_row.selected() as usize;
+        let disk_bus = DiskBus::ALL.get(bus_idx).copied().unwrap_or(DiskBus::Virtio);
+        let video_idx = video_row.selected() as usize;
+        let video = VideoModel::ALL.get(video_idx).copied().unwrap_or(VideoModel::Virtio);
 
         let src_idx = src_type_row.selected() as usize;
         let source_type = NetworkSourceType::ALL.get(src_idx).copied().unwrap_or(NetworkSourceType::VirtualNetwork);
@@ -293,10 +863,18 @@ pub fn show_creation_dialog(
             memory_mib: memory_row.value() as u64,
             disk_size_gib: disk_row.value() as u64,
             disk_format,
-            iso_path: iso_path.borrow().clone(),
+            install,
             firmware,
             network: NewVmNetworkConfig { source_type, source_value, model },
             tpm_model,
+            storage_pool: storage_pool.clone(),
+            disk_bus,
+            video,
+            hyperv: hyperv_row.is_active(),
+            os_id: selected_os().map(|os| os.id.clone()),
+            cloud_init,
+            unattend,
+            virtio_win_iso,
         };
 
         if params.name.is_empty() {
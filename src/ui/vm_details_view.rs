@@ -2,7 +2,7 @@ use gtk4 as gtk;
 use gtk::prelude::*;
 use libadwaita as adw;
 use adw::prelude::*;
-use crate::backend::types::DomainDetails;
+use grustyvman_core::types::DomainDetails;
 
 pub struct VmDetailsView {
     pub container: gtk::Box,
@@ -158,7 +158,7 @@ impl VmDetailsView {
 
         // CPU mode
         let cpu_subtitle = match details.cpu_mode {
-            crate::backend::types::CpuMode::Custom => {
+            grustyvman_core::types::CpuMode::Custom => {
                 if let Some(ref model) = details.cpu_model {
                     format!("{} ({})", details.cpu_mode.label(), model)
                 } else {
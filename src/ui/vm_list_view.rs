@@ -18,3 +18,71 @@ pub fn create_vm_row_factory(list_box: &gtk::ListBox, model: &gio::ListStore) {
         row.upcast()
     });
 }
+
+/// Show a host header above the first row of every connection in `model`.
+/// Items are expected to carry a `uri` property and be grouped by host.
+pub fn set_host_header_func(list_box: &gtk::ListBox, model: &gio::ListStore) {
+    let model = model.clone();
+    list_box.set_header_func(move |row, before| {
+        let uri_at = |r: &gtk::ListBoxRow| {
+            model
+                .item(r.index() as u32)
+                .map(|obj| obj.property::<String>("uri"))
+        };
+
+        let uri = uri_at(row);
+        if before.and_then(uri_at) == uri {
+            row.set_header(None::<&gtk::Widget>);
+            return;
+        }
+
+        row.set_header(uri.as_deref().map(header_label).as_ref());
+    });
+}
+
+/// Like [`set_host_header_func`], plus a "Templates" heading above each
+/// host's templates, which are kept after its VMs.
+pub fn set_vm_header_func(list_box: &gtk::ListBox, model: &gio::ListStore) {
+    let model = model.clone();
+    list_box.set_header_func(move |row, before| {
+        let vm_at = |r: &gtk::ListBoxRow| {
+            model
+                .item(r.index() as u32)
+                .and_then(|obj| obj.downcast::<VmObject>().ok())
+        };
+
+        let Some(vm) = vm_at(row) else {
+            row.set_header(None::<&gtk::Widget>);
+            return;
+        };
+        let prev = before.and_then(vm_at);
+        let new_host = prev.as_ref().map(|p| p.uri()) != Some(vm.uri());
+        let first_template =
+            vm.is_template() && (new_host || !prev.as_ref().is_some_and(|p| p.is_template()));
+        if !new_host && !first_template {
+            row.set_header(None::<&gtk::Widget>);
+            return;
+        }
+
+        let header = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        if new_host {
+            header.append(&header_label(&vm.uri()));
+        }
+        if first_template {
+            header.append(&header_label("Templates"));
+        }
+        row.set_header(Some(&header));
+    });
+}
+
+fn header_label(text: &str) -> gtk::Label {
+    let label = gtk::Label::new(Some(text));
+    label.set_halign(gtk::Align::Start);
+    label.set_ellipsize(gtk::pango::EllipsizeMode::Middle);
+    label.set_margin_top(12);
+    label.set_margin_bottom(6);
+    label.set_margin_start(12);
+    label.add_css_class("heading");
+    label.add_css_class("dim-label");
+    label
+}
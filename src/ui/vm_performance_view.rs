@@ -3,7 +3,7 @@ use gtk::prelude::*;
 use libadwaita as adw;
 use adw::prelude::*;
 
-use crate::backend::types::PerfDataPoint;
+use grustyvman_core::types::PerfDataPoint;
 use crate::ui::perf_graph::PerfGraph;
 
 pub struct VmPerformanceView {
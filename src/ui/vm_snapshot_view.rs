@@ -5,7 +5,11 @@ use adw::prelude::*;
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use crate::backend::types::SnapshotInfo;
+use grustyvman_core::snapshot::snapshot_tree;
+use grustyvman_core::types::SnapshotInfo;
+
+/// Indentation of each level of the snapshot tree, in pixels.
+const TREE_INDENT: i32 = 24;
 
 pub struct VmSnapshotView {
     pub container: gtk::Box,
@@ -16,6 +20,7 @@ pub struct VmSnapshotView {
     on_create: RefCell<Option<Rc<dyn Fn()>>>,
     on_revert: RefCell<Option<Rc<dyn Fn(String)>>>,
     on_delete: RefCell<Option<Rc<dyn Fn(String)>>>,
+    on_compare: RefCell<Option<Rc<dyn Fn(String)>>>,
 }
 
 impl VmSnapshotView {
@@ -61,6 +66,7 @@ impl VmSnapshotView {
             on_create: RefCell::new(None),
             on_revert: RefCell::new(None),
             on_delete: RefCell::new(None),
+            on_compare: RefCell::new(None),
         }
     }
 
@@ -76,6 +82,10 @@ impl VmSnapshotView {
         *self.on_delete.borrow_mut() = Some(Rc::new(f));
     }
 
+    pub fn set_on_compare(&self, f: impl Fn(String) + 'static) {
+        *self.on_compare.borrow_mut() = Some(Rc::new(f));
+    }
+
     pub fn update(&self, snapshots: &[SnapshotInfo]) {
         // Clear existing rows
         clear_pref_group(&self.snapshots_group);
@@ -103,11 +113,13 @@ impl VmSnapshotView {
 
         self.stack.set_visible_child_name("list");
 
-        for snap in snapshots {
+        // Children below their parent, indented one level deeper
+        for (depth, snap) in snapshot_tree(snapshots) {
             let row = adw::ActionRow::new();
 
             // Status dot prefix
             let dot = gtk::Label::new(Some("\u{25CF}"));
+            dot.set_margin_start(depth as i32 * TREE_INDENT);
             dot.add_css_class("status-dot");
             dot.add_css_class(snap.state.css_class());
             row.add_prefix(&dot);
@@ -123,12 +135,30 @@ impl VmSnapshotView {
             // Subtitle: date, state, description
             let date_str = format_timestamp(snap.creation_time);
             let mut subtitle = format!("{} \u{2022} {}", date_str, snap.state);
+            if snap.is_external() {
+                subtitle.push_str(" \u{2022} External");
+            }
             if !snap.description.is_empty() {
                 subtitle.push_str(&format!(" \u{2022} {}", snap.description));
             }
             row.set_subtitle(&subtitle);
             row.set_activatable(false);
 
+            // Compare button
+            let compare_btn = gtk::Button::from_icon_name("view-dual-symbolic");
+            compare_btn.set_tooltip_text(Some("Compare with Current"));
+            compare_btn.set_valign(gtk::Align::Center);
+            compare_btn.add_css_class("flat");
+
+            let snap_name = snap.name.clone();
+            if let Some(ref cb) = *self.on_compare.borrow() {
+                let cb = cb.clone();
+                compare_btn.connect_clicked(move |_| {
+                    cb(snap_name.clone());
+                });
+            }
+            row.add_suffix(&compare_btn);
+
             // Revert button
             let revert_btn = gtk::Button::from_icon_name("edit-undo-symbolic");
             revert_btn.set_tooltip_text(Some("Revert to Snapshot"));
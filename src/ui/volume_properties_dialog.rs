@@ -0,0 +1,235 @@
+use gtk4 as gtk;
+use gtk::prelude::*;
+use libadwaita as adw;
+use adw::prelude::*;
+
+use grustyvman_core::types::{format_bytes, PoolInfo, VolumeDetails, WipeAlgorithm};
+
+const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// What the user asked to do with the volume.
+pub enum VolumeAction {
+    Resize { capacity: u64, allocate: bool, allow_shrink: bool },
+    Wipe(WipeAlgorithm),
+    Clone { pool_uuid: String, new_name: String },
+}
+
+/// Show `details` with Resize, Clone and Wipe actions. `pools` are the
+/// active pools a clone can go to, `pool_uuid` the volume's own.
+pub fn show_volume_properties_dialog(
+    parent: &adw::ApplicationWindow,
+    details: &VolumeDetails,
+    pools: &[PoolInfo],
+    pool_uuid: &str,
+    on_action: impl Fn(VolumeAction) + 'static,
+) {
+    let dialog = adw::Window::builder()
+        .title(&details.name)
+        .modal(true)
+        .transient_for(parent)
+        .default_width(480)
+        .default_height(640)
+        .build();
+
+    let toolbar = adw::ToolbarView::new();
+    let header = adw::HeaderBar::new();
+    toolbar.add_top_bar(&header);
+
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 24);
+    content.set_margin_top(24);
+    content.set_margin_bottom(24);
+    content.set_margin_start(24);
+    content.set_margin_end(24);
+
+    // --- Properties ---
+    let props_group = adw::PreferencesGroup::new();
+    props_group.set_title("Properties");
+    let format = if details.format.is_empty() { "Unknown" } else { details.format.as_str() };
+    let allocation = format!(
+        "{} of {}",
+        format_bytes(details.allocation),
+        format_bytes(details.capacity)
+    );
+    for (title, value) in [
+        ("Path", details.path.as_str()),
+        ("Type", details.kind.label()),
+        ("Format", format),
+        ("Allocation", allocation.as_str()),
+    ] {
+        let row = adw::ActionRow::new();
+        row.set_title(title);
+        row.set_subtitle(value);
+        row.set_subtitle_selectable(true);
+        props_group.add(&row);
+    }
+    let level_bar = gtk::LevelBar::new();
+    level_bar.set_value(if details.capacity > 0 {
+        (details.allocation as f64 / details.capacity as f64).min(1.0)
+    } else {
+        0.0
+    });
+    level_bar.set_margin_top(6);
+    props_group.add(&level_bar);
+    content.append(&props_group);
+
+    // --- Backing chain ---
+    let backing_group = adw::PreferencesGroup::new();
+    backing_group.set_title("Backing Chain");
+    if details.backing_chain.is_empty() {
+        let row = adw::ActionRow::new();
+        row.set_title("None");
+        backing_group.add(&row);
+    }
+    for backing in &details.backing_chain {
+        let row = adw::ActionRow::new();
+        row.set_title(&backing.path);
+        row.set_subtitle(if backing.format.is_empty() { "Unknown format" } else { &backing.format });
+        row.set_title_selectable(true);
+        backing_group.add(&row);
+    }
+    content.append(&backing_group);
+
+    // --- Users ---
+    let users_group = adw::PreferencesGroup::new();
+    users_group.set_title("Used By");
+    if details.used_by.is_empty() {
+        let row = adw::ActionRow::new();
+        row.set_title("No VMs");
+        users_group.add(&row);
+    }
+    for vm in &details.used_by {
+        let row = adw::ActionRow::new();
+        row.set_title(vm);
+        users_group.add(&row);
+    }
+    content.append(&users_group);
+
+    // --- Actions ---
+    let actions_group = adw::PreferencesGroup::new();
+    actions_group.set_title("Actions");
+
+    let resize_row = adw::ExpanderRow::new();
+    resize_row.set_title("Resize");
+    let current_gib = details.capacity as f64 / GIB;
+    let size_adj = gtk::Adjustment::new(current_gib, 0.1, 65536.0, 1.0, 10.0, 0.0);
+    let size_row = adw::SpinRow::new(Some(&size_adj), 1.0, 1);
+    size_row.set_title("New Size (GiB)");
+    resize_row.add_row(&size_row);
+    let allocate_row = adw::SwitchRow::new();
+    allocate_row.set_title("Allocate Now");
+    allocate_row.set_subtitle("Reserve the added space instead of growing on demand");
+    resize_row.add_row(&allocate_row);
+    let shrink_row = adw::SwitchRow::new();
+    shrink_row.set_title("Allow Shrinking");
+    shrink_row.set_subtitle("Data past the new end is lost");
+    resize_row.add_row(&shrink_row);
+    let resize_btn = action_button("Resize", &resize_row);
+    actions_group.add(&resize_row);
+
+    let clone_row = adw::ExpanderRow::new();
+    clone_row.set_title("Clone");
+    let name_row = adw::EntryRow::new();
+    name_row.set_title("Name");
+    name_row.set_text(&clone_name(&details.name));
+    clone_row.add_row(&name_row);
+    let pool_row = adw::ComboRow::new();
+    pool_row.set_title("Pool");
+    let pool_names: Vec<&str> = pools.iter().map(|p| p.name.as_str()).collect();
+    pool_row.set_model(Some(&gtk::StringList::new(&pool_names)));
+    if let Some(i) = pools.iter().position(|p| p.uuid == pool_uuid) {
+        pool_row.set_selected(i as u32);
+    }
+    clone_row.add_row(&pool_row);
+    let clone_btn = action_button("Clone", &clone_row);
+    actions_group.add(&clone_row);
+
+    let wipe_row = adw::ExpanderRow::new();
+    wipe_row.set_title("Wipe");
+    wipe_row.set_subtitle("Overwrite all data on the volume");
+    let algorithm_row = adw::ComboRow::new();
+    algorithm_row.set_title("Algorithm");
+    let labels: Vec<&str> = WipeAlgorithm::ALL.iter().map(|a| a.label()).collect();
+    algorithm_row.set_model(Some(&gtk::StringList::new(&labels)));
+    wipe_row.add_row(&algorithm_row);
+    let wipe_btn = action_button("Wipe", &wipe_row);
+    wipe_btn.add_css_class("destructive-action");
+    actions_group.add(&wipe_row);
+
+    // Volumes of VMs are only changed through the VM
+    if !details.used_by.is_empty() {
+        for row in [&resize_row, &wipe_row] {
+            row.set_sensitive(false);
+            row.set_subtitle("In use by a VM");
+        }
+    }
+    content.append(&actions_group);
+
+    let scrolled = gtk::ScrolledWindow::new();
+    scrolled.set_hscrollbar_policy(gtk::PolicyType::Never);
+    scrolled.set_child(Some(&content));
+    toolbar.set_content(Some(&scrolled));
+    dialog.set_content(Some(&toolbar));
+
+    let on_action = std::rc::Rc::new(on_action);
+
+    let dialog_weak = dialog.downgrade();
+    let cb = on_action.clone();
+    resize_btn.connect_clicked(move |_| {
+        cb(VolumeAction::Resize {
+            capacity: (size_row.value() * GIB) as u64,
+            allocate: allocate_row.is_active(),
+            allow_shrink: shrink_row.is_active(),
+        });
+        if let Some(d) = dialog_weak.upgrade() {
+            d.close();
+        }
+    });
+
+    let dialog_weak = dialog.downgrade();
+    let cb = on_action.clone();
+    let pools = pools.to_vec();
+    clone_btn.connect_clicked(move |_| {
+        let new_name = name_row.text().trim().to_string();
+        let Some(pool) = pools.get(pool_row.selected() as usize) else { return };
+        if new_name.is_empty() {
+            return;
+        }
+        cb(VolumeAction::Clone { pool_uuid: pool.uuid.clone(), new_name });
+        if let Some(d) = dialog_weak.upgrade() {
+            d.close();
+        }
+    });
+
+    let dialog_weak = dialog.downgrade();
+    wipe_btn.connect_clicked(move |_| {
+        let algorithm = WipeAlgorithm::ALL[algorithm_row.selected() as usize];
+        on_action(VolumeAction::Wipe(algorithm));
+        if let Some(d) = dialog_weak.upgrade() {
+            d.close();
+        }
+    });
+
+    dialog.present();
+}
+
+/// A button at the bottom of an action's expander row.
+fn action_button(label: &str, expander: &adw::ExpanderRow) -> gtk::Button {
+    let button = gtk::Button::with_label(label);
+    button.set_halign(gtk::Align::End);
+    button.set_margin_top(6);
+    button.set_margin_bottom(6);
+    button.set_margin_end(12);
+    let row = gtk::ListBoxRow::new();
+    row.set_activatable(false);
+    row.set_child(Some(&button));
+    expander.add_row(&row);
+    button
+}
+
+/// "disk.qcow2" → "disk-clone.qcow2".
+fn clone_name(name: &str) -> String {
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{stem}-clone.{ext}"),
+        _ => format!("{name}-clone"),
+    }
+}
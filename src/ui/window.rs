@@ -6,8 +6,12 @@ use libadwaita as adw;
 use adw::prelude::*;
 use std::cell::RefCell;
 
-use crate::backend;
-use crate::backend::types::RawPerfSample;
+use grustyvman_core as backend;
+use grustyvman_core::AppError;
+use grustyvman_core::jobs::{Job, JobManager};
+use grustyvman_core::types::{HostEvent, HostEventKind, JobInfo, RawPerfSample};
+use crate::config;
+use crate::settings::Settings;
 use crate::models::network_object::NetworkObject;
 use crate::models::pool_object::PoolObject;
 use crate::models::vm_object::VmObject;
@@ -16,11 +20,14 @@ use crate::ui::network_row::NetworkRow;
 use crate::ui::pool_details_view::PoolDetailsView;
 use crate::ui::pool_row::PoolRow;
 use crate::ui::host_details_view::HostDetailsView;
+use crate::ui::jobs_panel::JobsPanel;
 use crate::ui::vm_details_view::VmDetailsView;
 use crate::ui::vm_performance_view::VmPerformanceView;
 use crate::ui::vm_snapshot_view::VmSnapshotView;
 use crate::ui::vm_xml_editor::VmXmlEditor;
 use crate::ui::vm_list_view;
+use crate::ui::volume_properties_dialog::VolumeAction;
+use crate::ui::connection_manager_dialog;
 
 fn spawn_blocking<F, T>(f: F) -> async_channel::Receiver<T>
 where
@@ -42,8 +49,21 @@ mod imp {
     #[allow(deprecated)]
     pub struct Window {
         pub split_view: adw::NavigationSplitView,
+        pub settings: RefCell<Settings>,
+        pub poll_timer_id: RefCell<Option<glib::SourceId>>,
+        // libvirt event delivery
+        pub event_tx: RefCell<Option<async_channel::Sender<HostEvent>>>,
+        pub event_pending: RefCell<std::collections::HashSet<String>>,
+        pub pending_refresh: RefCell<std::collections::HashSet<(String, &'static str)>>,
+        // Connected hosts, in sidebar order
+        pub connections: RefCell<Vec<String>>,
+        // Long-running operations
+        pub jobs: JobManager,
+        pub jobs_rx: RefCell<Option<async_channel::Receiver<JobInfo>>>,
+        pub jobs_panel: JobsPanel,
         // VM state
         pub list_store: gio::ListStore,
+        pub vm_list_box: gtk::ListBox,
         pub outer_stack: gtk::Stack,
         pub view_stack: adw::ViewStack,
         pub details_view: VmDetailsView,
@@ -58,11 +78,19 @@ mod imp {
         pub btn_stop: gtk::Button,
         pub btn_force_stop: gtk::Button,
         pub btn_reboot: gtk::Button,
+        pub btn_save_state: gtk::MenuButton,
+        pub save_state_item: gtk::Button,
+        pub save_file_item: gtk::Button,
+        pub restore_file_item: gtk::Button,
+        pub discard_save_item: gtk::Button,
         pub btn_console: gtk::Button,
         pub btn_delete: gtk::Button,
         pub btn_settings: gtk::Button,
         pub btn_rename: gtk::Button,
         pub btn_clone: gtk::Button,
+        pub btn_migrate: gtk::Button,
+        pub btn_template: gtk::Button,
+        pub btn_instantiate: gtk::Button,
         // Perf sampling state
         pub perf_timer_id: RefCell<Option<glib::SourceId>>,
         pub last_perf_sample: RefCell<Option<(Instant, RawPerfSample)>>,
@@ -71,11 +99,13 @@ mod imp {
         // Storage state
         pub sidebar_stack: gtk::Stack,
         pub pool_list_store: gio::ListStore,
+        pub pool_list_box: gtk::ListBox,
         pub pool_details_view: PoolDetailsView,
         pub selected_pool_uuid: RefCell<Option<String>>,
         pub active_sidebar: RefCell<String>,
         // Network state
         pub network_list_store: gio::ListStore,
+        pub network_list_box: gtk::ListBox,
         pub network_details_view: NetworkDetailsView,
         pub selected_network_uuid: RefCell<Option<String>>,
         // Host details
@@ -87,16 +117,27 @@ mod imp {
     #[allow(deprecated)]
     impl Default for Window {
         fn default() -> Self {
+            let (jobs, jobs_rx) = JobManager::new();
             Self {
                 split_view: adw::NavigationSplitView::new(),
+                settings: RefCell::new(Settings::default()),
+                poll_timer_id: RefCell::new(None),
+                event_tx: RefCell::new(None),
+                event_pending: RefCell::new(std::collections::HashSet::new()),
+                pending_refresh: RefCell::new(std::collections::HashSet::new()),
+                connections: RefCell::new(vec![config::DEFAULT_CONNECTION_URI.to_string()]),
+                jobs,
+                jobs_rx: RefCell::new(Some(jobs_rx)),
+                jobs_panel: JobsPanel::new(),
                 list_store: gio::ListStore::new::<VmObject>(),
+                vm_list_box: vm_list_view::create_vm_list_box(),
                 outer_stack: gtk::Stack::new(),
                 view_stack: adw::ViewStack::new(),
                 details_view: VmDetailsView::new(),
                 perf_view: VmPerformanceView::new(),
                 snapshot_view: VmSnapshotView::new(),
                 toast_overlay: adw::ToastOverlay::new(),
-                connection_uri: RefCell::new("qemu:///system".to_string()),
+                connection_uri: RefCell::new(config::DEFAULT_CONNECTION_URI.to_string()),
                 selected_uuid: RefCell::new(None),
                 view_switcher_title: adw::ViewSwitcherTitle::new(),
                 btn_start: gtk::Button::new(),
@@ -104,21 +145,31 @@ mod imp {
                 btn_stop: gtk::Button::new(),
                 btn_force_stop: gtk::Button::new(),
                 btn_reboot: gtk::Button::new(),
+                btn_save_state: gtk::MenuButton::new(),
+                save_state_item: gtk::Button::with_label("Save State"),
+                save_file_item: gtk::Button::with_label("Save to File…"),
+                restore_file_item: gtk::Button::with_label("Restore from File…"),
+                discard_save_item: gtk::Button::with_label("Discard Saved State"),
                 btn_console: gtk::Button::new(),
                 btn_delete: gtk::Button::new(),
                 btn_settings: gtk::Button::new(),
                 btn_rename: gtk::Button::new(),
                 btn_clone: gtk::Button::new(),
+                btn_migrate: gtk::Button::new(),
+                btn_template: gtk::Button::new(),
+                btn_instantiate: gtk::Button::new(),
                 perf_timer_id: RefCell::new(None),
                 last_perf_sample: RefCell::new(None),
                 disk_targets: RefCell::new(Vec::new()),
                 iface_targets: RefCell::new(Vec::new()),
                 sidebar_stack: gtk::Stack::new(),
                 pool_list_store: gio::ListStore::new::<PoolObject>(),
+                pool_list_box: gtk::ListBox::new(),
                 pool_details_view: PoolDetailsView::new(),
                 selected_pool_uuid: RefCell::new(None),
                 active_sidebar: RefCell::new("vms".to_string()),
                 network_list_store: gio::ListStore::new::<NetworkObject>(),
+                network_list_box: gtk::ListBox::new(),
                 network_details_view: NetworkDetailsView::new(),
                 selected_network_uuid: RefCell::new(None),
                 host_details_view: HostDetailsView::new(),
@@ -160,8 +211,10 @@ glib::wrapper! {
 
 #[allow(deprecated)]
 impl Window {
-    pub fn new(app: &adw::Application) -> Self {
-        glib::Object::builder().property("application", app).build()
+    pub fn new(app: &adw::Application, settings: Settings) -> Self {
+        let win: Self = glib::Object::builder().property("application", app).build();
+        win.apply_settings(settings);
+        win
     }
 
     fn setup_ui(&self) {
@@ -178,6 +231,8 @@ impl Window {
             .status-paused { color: #f5c211; }
             .status-shutoff .status-dot,
             .status-shutoff { color: @insensitive_fg_color; }
+            .status-saved .status-dot,
+            .status-saved { color: #3584e4; }
             .status-crashed .status-dot,
             .status-crashed { color: #e01b24; }
             "#,
@@ -192,13 +247,18 @@ impl Window {
         let sidebar_toolbar = adw::ToolbarView::new();
         let sidebar_header = adw::HeaderBar::new();
 
-        let conn_dropdown = gtk::DropDown::from_strings(&["qemu:///system", "qemu:///session"]);
-        conn_dropdown.set_selected(0);
-        sidebar_header.set_title_widget(Some(&conn_dropdown));
+        let conn_btn = gtk::Button::from_icon_name("network-server-symbolic");
+        conn_btn.set_tooltip_text(Some("Manage Connections"));
+        sidebar_header.pack_start(&conn_btn);
+
+        let prefs_btn = gtk::Button::from_icon_name("preferences-system-symbolic");
+        prefs_btn.set_tooltip_text(Some("Preferences"));
+        sidebar_header.pack_start(&prefs_btn);
 
         let new_vm_btn = gtk::Button::from_icon_name("list-add-symbolic");
         new_vm_btn.set_tooltip_text(Some("New Virtual Machine"));
         sidebar_header.pack_end(&new_vm_btn);
+        sidebar_header.pack_end(&imp.jobs_panel.button);
 
         sidebar_toolbar.add_top_bar(&sidebar_header);
 
@@ -229,15 +289,16 @@ impl Window {
         toggle_box.append(&btn_host);
 
         // VM list
-        let vm_list_box = vm_list_view::create_vm_list_box();
-        vm_list_view::create_vm_row_factory(&vm_list_box, &imp.list_store);
+        let vm_list_box = &imp.vm_list_box;
+        vm_list_view::create_vm_row_factory(vm_list_box, &imp.list_store);
+        vm_list_view::set_vm_header_func(vm_list_box, &imp.list_store);
 
         let vm_scrolled = gtk::ScrolledWindow::new();
         vm_scrolled.set_vexpand(true);
-        vm_scrolled.set_child(Some(&vm_list_box));
+        vm_scrolled.set_child(Some(vm_list_box));
 
         // Pool list
-        let pool_list_box = gtk::ListBox::new();
+        let pool_list_box = &imp.pool_list_box;
         pool_list_box.set_selection_mode(gtk::SelectionMode::Single);
         pool_list_box.add_css_class("navigation-sidebar");
         pool_list_box.bind_model(Some(&imp.pool_list_store), |obj| {
@@ -246,13 +307,14 @@ impl Window {
             row.bind(pool);
             row.upcast()
         });
+        vm_list_view::set_host_header_func(pool_list_box, &imp.pool_list_store);
 
         let pool_scrolled = gtk::ScrolledWindow::new();
         pool_scrolled.set_vexpand(true);
-        pool_scrolled.set_child(Some(&pool_list_box));
+        pool_scrolled.set_child(Some(pool_list_box));
 
         // Network list
-        let network_list_box = gtk::ListBox::new();
+        let network_list_box = &imp.network_list_box;
         network_list_box.set_selection_mode(gtk::SelectionMode::Single);
         network_list_box.add_css_class("navigation-sidebar");
         network_list_box.bind_model(Some(&imp.network_list_store), |obj| {
@@ -261,10 +323,11 @@ impl Window {
             row.bind(network);
             row.upcast()
         });
+        vm_list_view::set_host_header_func(network_list_box, &imp.network_list_store);
 
         let network_scrolled = gtk::ScrolledWindow::new();
         network_scrolled.set_vexpand(true);
-        network_scrolled.set_child(Some(&network_list_box));
+        network_scrolled.set_child(Some(network_list_box));
 
         // Sidebar stack
         let sidebar_stack = &imp.sidebar_stack;
@@ -316,6 +379,29 @@ impl Window {
         btn_reboot.set_tooltip_text(Some("Reboot"));
         btn_reboot.set_sensitive(false);
 
+        // Saving stops the VM with its memory kept, in libvirt's managed
+        // save image or a file
+        let btn_save_state = &imp.btn_save_state;
+        btn_save_state.set_icon_name("document-save-symbolic");
+        btn_save_state.set_tooltip_text(Some("Save State"));
+        btn_save_state.set_sensitive(false);
+        let save_menu = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        for item in [
+            &imp.save_state_item,
+            &imp.save_file_item,
+            &imp.restore_file_item,
+            &imp.discard_save_item,
+        ] {
+            item.add_css_class("flat");
+            if let Some(label) = item.child().and_downcast::<gtk::Label>() {
+                label.set_xalign(0.0);
+            }
+            save_menu.append(item);
+        }
+        let save_popover = gtk::Popover::new();
+        save_popover.set_child(Some(&save_menu));
+        btn_save_state.set_popover(Some(&save_popover));
+
         let btn_console = &imp.btn_console;
         btn_console.set_icon_name("utilities-terminal-symbolic");
         btn_console.set_tooltip_text(Some("Console"));
@@ -342,15 +428,34 @@ impl Window {
         btn_clone.set_tooltip_text(Some("Clone VM"));
         btn_clone.set_sensitive(false);
 
+        let btn_migrate = &imp.btn_migrate;
+        btn_migrate.set_icon_name("send-to-symbolic");
+        btn_migrate.set_tooltip_text(Some("Migrate VM"));
+        btn_migrate.set_sensitive(false);
+
+        let btn_template = &imp.btn_template;
+        btn_template.set_icon_name("folder-templates-symbolic");
+        btn_template.set_tooltip_text(Some("Convert to Template"));
+        btn_template.set_sensitive(false);
+
+        let btn_instantiate = &imp.btn_instantiate;
+        btn_instantiate.set_icon_name("document-new-symbolic");
+        btn_instantiate.set_tooltip_text(Some("Instantiate Template"));
+        btn_instantiate.set_sensitive(false);
+
         content_header.pack_start(btn_start);
         content_header.pack_start(btn_pause);
         content_header.pack_start(btn_stop);
         content_header.pack_start(btn_force_stop);
         content_header.pack_start(btn_reboot);
+        content_header.pack_start(btn_save_state);
         content_header.pack_end(btn_settings);
         content_header.pack_end(btn_delete);
         content_header.pack_end(btn_rename);
         content_header.pack_end(btn_clone);
+        content_header.pack_end(btn_migrate);
+        content_header.pack_end(btn_template);
+        content_header.pack_end(btn_instantiate);
         content_header.pack_end(btn_console);
 
         content_toolbar.add_top_bar(&content_header);
@@ -461,6 +566,7 @@ impl Window {
                 if let Some(win) = win.upgrade() {
                     *win.imp().active_sidebar.borrow_mut() = "vms".to_string();
                     win.imp().sidebar_stack.set_visible_child_name("vms");
+                    win.sync_connection_from_selection();
                     new_btn_ref.set_tooltip_text(Some("New Virtual Machine"));
                     new_btn_ref.set_icon_name("list-add-symbolic");
                     // Show VM content or empty
@@ -481,6 +587,7 @@ impl Window {
                 if let Some(win) = win.upgrade() {
                     *win.imp().active_sidebar.borrow_mut() = "storage".to_string();
                     win.imp().sidebar_stack.set_visible_child_name("storage");
+                    win.sync_connection_from_selection();
                     new_btn_ref.set_tooltip_text(Some("New Storage Pool"));
                     new_btn_ref.set_icon_name("list-add-symbolic");
                     win.stop_perf_sampling();
@@ -503,6 +610,7 @@ impl Window {
                 if let Some(win) = win.upgrade() {
                     *win.imp().active_sidebar.borrow_mut() = "networks".to_string();
                     win.imp().sidebar_stack.set_visible_child_name("networks");
+                    win.sync_connection_from_selection();
                     new_btn_ref.set_tooltip_text(Some("New Virtual Network"));
                     new_btn_ref.set_icon_name("list-add-symbolic");
                     win.stop_perf_sampling();
@@ -560,29 +668,28 @@ impl Window {
             }
         });
 
-        // Connection dropdown
+        // Connection manager
         let win = self.downgrade();
-        conn_dropdown.connect_selected_notify(move |dropdown| {
+        conn_btn.connect_clicked(move |_| {
             if let Some(win) = win.upgrade() {
-                let uris = ["qemu:///system", "qemu:///session"];
-                let idx = dropdown.selected() as usize;
-                if idx < uris.len() {
-                    *win.imp().connection_uri.borrow_mut() = uris[idx].to_string();
-                    *win.imp().selected_uuid.borrow_mut() = None;
-                    *win.imp().selected_pool_uuid.borrow_mut() = None;
-                    *win.imp().selected_network_uuid.borrow_mut() = None;
-                    win.imp().outer_stack.set_visible_child_name("empty");
-                    win.imp().view_switcher_title.set_title("");
-                    win.imp().view_switcher_title.set_subtitle("");
-                    win.update_button_sensitivity(None);
-                    win.stop_perf_sampling();
-                    win.imp().pool_list_store.remove_all();
-                    win.imp().network_list_store.remove_all();
-                    win.refresh_vm_list();
-                }
+                win.show_connection_manager_dialog();
             }
         });
 
+        // Preferences
+        let win = self.downgrade();
+        prefs_btn.connect_clicked(move |_| {
+            if let Some(win) = win.upgrade() {
+                win.show_preferences_dialog();
+            }
+        });
+
+        // Persist connections and the active host on close
+        self.connect_close_request(|win| {
+            win.save_settings();
+            glib::Propagation::Proceed
+        });
+
         // VM list selection
         let win = self.downgrade();
         vm_list_box.connect_row_selected(move |_, row| {
@@ -592,7 +699,12 @@ impl Window {
                     if let Some(obj) = win.imp().list_store.item(idx) {
                         let vm = obj.downcast_ref::<VmObject>().unwrap();
                         let uuid = vm.uuid();
+                        *win.imp().connection_uri.borrow_mut() = vm.uri();
                         *win.imp().selected_uuid.borrow_mut() = Some(uuid.clone());
+                        if win.imp().active_sidebar.borrow().as_str() == "host" {
+                            win.load_host_info();
+                            return;
+                        }
                         win.imp().view_switcher_title.set_title(&vm.name());
                         win.imp().view_switcher_title.set_subtitle(&vm.state());
                         win.load_vm_details(&uuid);
@@ -617,6 +729,7 @@ impl Window {
                     if let Some(obj) = win.imp().pool_list_store.item(idx) {
                         let pool = obj.downcast_ref::<PoolObject>().unwrap();
                         let uuid = pool.uuid();
+                        *win.imp().connection_uri.borrow_mut() = pool.uri();
                         *win.imp().selected_pool_uuid.borrow_mut() = Some(uuid.clone());
                         win.imp().view_switcher_title.set_title(&pool.name());
                         win.imp().view_switcher_title.set_subtitle(&pool.state());
@@ -640,6 +753,7 @@ impl Window {
                     if let Some(obj) = win.imp().network_list_store.item(idx) {
                         let network = obj.downcast_ref::<NetworkObject>().unwrap();
                         let uuid = network.uuid();
+                        *win.imp().connection_uri.borrow_mut() = network.uri();
                         *win.imp().selected_network_uuid.borrow_mut() = Some(uuid.clone());
                         win.imp().view_switcher_title.set_title(&network.name());
                         win.imp().view_switcher_title.set_subtitle(&network.state());
@@ -672,26 +786,6 @@ impl Window {
         self.connect_network_action_buttons();
         self.connect_snapshot_callbacks();
         self.connect_xml_editor_callback();
-
-        // Auto-refresh timer
-        let win = self.downgrade();
-        glib::timeout_add_seconds_local(2, move || {
-            if let Some(win) = win.upgrade() {
-                win.refresh_vm_list();
-                let sidebar = win.imp().active_sidebar.borrow().clone();
-                match sidebar.as_str() {
-                    "storage" => win.refresh_pool_list(),
-                    "networks" => win.refresh_network_list(),
-                    _ => {}
-                }
-                glib::ControlFlow::Continue
-            } else {
-                glib::ControlFlow::Break
-            }
-        });
-
-        // Initial refresh
-        self.refresh_vm_list();
     }
 
     fn set_vm_buttons_visible(&self, visible: bool) {
@@ -701,11 +795,15 @@ impl Window {
         imp.btn_stop.set_visible(visible);
         imp.btn_force_stop.set_visible(visible);
         imp.btn_reboot.set_visible(visible);
+        imp.btn_save_state.set_visible(visible);
         imp.btn_console.set_visible(visible);
         imp.btn_delete.set_visible(visible);
         imp.btn_settings.set_visible(visible);
         imp.btn_rename.set_visible(visible);
         imp.btn_clone.set_visible(visible);
+        imp.btn_migrate.set_visible(visible);
+        imp.btn_template.set_visible(visible);
+        imp.btn_instantiate.set_visible(visible);
         imp.view_switcher_title.set_visible(visible);
     }
 
@@ -723,6 +821,7 @@ impl Window {
                     "Running" => Some(backend::types::VmState::Running),
                     "Paused" => Some(backend::types::VmState::Paused),
                     "Shutoff" => Some(backend::types::VmState::Shutoff),
+                    "Saved" => Some(backend::types::VmState::Saved),
                     "Crashed" => Some(backend::types::VmState::Crashed),
                     _ => None,
                 });
@@ -784,6 +883,31 @@ impl Window {
             }
         });
 
+        let items = [
+            (&imp.save_state_item, "save_state"),
+            (&imp.save_file_item, "save_file"),
+            (&imp.restore_file_item, "restore_file"),
+            (&imp.discard_save_item, "discard_save"),
+        ];
+        for (item, action) in items {
+            let win = self.downgrade();
+            item.connect_clicked(move |_| {
+                let Some(win) = win.upgrade() else { return };
+                win.imp().btn_save_state.popdown();
+                match action {
+                    "save_state" => win.save_vm_state(),
+                    "save_file" => win.save_vm_to_file(),
+                    "restore_file" => win.restore_vm_from_file(),
+                    _ => win.confirm_and_act(
+                        "Discard Saved State?",
+                        "The VM will boot afresh on its next start. Whatever was running in it is lost.",
+                        "Discard",
+                        "discard_save",
+                    ),
+                }
+            });
+        }
+
         let win = self.downgrade();
         imp.btn_console.connect_clicked(move |_| {
             if let Some(win) = win.upgrade() {
@@ -818,6 +942,27 @@ impl Window {
                 win.show_clone_dialog();
             }
         });
+
+        let win = self.downgrade();
+        imp.btn_migrate.connect_clicked(move |_| {
+            if let Some(win) = win.upgrade() {
+                win.show_migrate_dialog();
+            }
+        });
+
+        let win = self.downgrade();
+        imp.btn_template.connect_clicked(move |_| {
+            if let Some(win) = win.upgrade() {
+                win.show_template_dialog();
+            }
+        });
+
+        let win = self.downgrade();
+        imp.btn_instantiate.connect_clicked(move |_| {
+            if let Some(win) = win.upgrade() {
+                win.show_instantiate_dialog();
+            }
+        });
     }
 
     fn connect_pool_action_buttons(&self) {
@@ -892,6 +1037,20 @@ impl Window {
             }
         });
 
+        let win = self.downgrade();
+        imp.pool_details_view.set_on_volume_properties(move |vol_name| {
+            if let Some(win) = win.upgrade() {
+                win.show_volume_properties(&vol_name);
+            }
+        });
+
+        let win = self.downgrade();
+        imp.pool_details_view.set_on_export_volume(move |vol_name| {
+            if let Some(win) = win.upgrade() {
+                win.export_volume(&vol_name);
+            }
+        });
+
         let win = self.downgrade();
         imp.pool_details_view.set_on_autostart(move |enabled| {
             if let Some(win) = win.upgrade() {
@@ -954,6 +1113,7 @@ impl Window {
                 "pause" => backend::domain::pause_vm(&uri, &uuid),
                 "resume" => backend::domain::resume_vm(&uri, &uuid),
                 "reboot" => backend::domain::reboot_vm(&uri, &uuid),
+                "discard_save" => backend::domain::discard_saved_state(&uri, &uuid),
                 "delete" => backend::domain::delete_vm_with_storage(&uri, &uuid, vec![]),
                 "console" => backend::domain::launch_console(&uri, &uuid),
                 _ => Ok(()),
@@ -973,6 +1133,7 @@ impl Window {
                         "pause" => "VM paused",
                         "resume" => "VM resumed",
                         "reboot" => "Reboot signal sent",
+                        "discard_save" => "Saved state discarded",
                         "delete" => {
                             *win.imp().selected_uuid.borrow_mut() = None;
                             win.imp().outer_stack.set_visible_child_name("empty");
@@ -988,14 +1149,14 @@ impl Window {
                     win.show_toast(msg);
                     win.refresh_vm_list();
 
-                    if matches!(action.as_str(), "start" | "force_stop" | "shutdown") {
+                    if matches!(action.as_str(), "start" | "force_stop" | "shutdown" | "discard_save") {
                         if let Some(uuid) = win.imp().selected_uuid.borrow().clone() {
                             win.load_vm_details(&uuid);
                         }
                     }
                 }
                 Err(e) => {
-                    win.show_toast(&format!("Error: {e}"));
+                    win.show_error("Error", &e);
                 }
             }
         });
@@ -1007,6 +1168,28 @@ impl Window {
         self.imp().toast_overlay.add_toast(toast);
     }
 
+    /// Toast a failed backend call as "context: error", followed by a hint
+    /// when the error kind suggests an obvious fix.
+    fn show_error(&self, context: &str, err: &AppError) {
+        if matches!(err, AppError::Cancelled) {
+            self.show_toast(&format!("{context}: cancelled"));
+            return;
+        }
+        let toast = match err.hint() {
+            Some(hint) => {
+                let toast = adw::Toast::new(&format!("{context}: {err}. {hint}"));
+                toast.set_timeout(6);
+                toast
+            }
+            None => {
+                let toast = adw::Toast::new(&format!("{context}: {err}"));
+                toast.set_timeout(3);
+                toast
+            }
+        };
+        self.imp().toast_overlay.add_toast(toast);
+    }
+
     fn load_host_info(&self) {
         let uri = self.imp().connection_uri.borrow().clone();
         let win = self.downgrade();
@@ -1024,17 +1207,27 @@ impl Window {
                     win.imp().host_details_view.update(&info);
                 }
                 Err(e) => {
-                    win.show_toast(&format!("Failed to load host info: {e}"));
+                    win.show_error("Failed to load host info", &e);
                 }
             }
         });
     }
 
     fn refresh_vm_list(&self) {
-        let uri = self.imp().connection_uri.borrow().clone();
+        let uris = self.imp().connections.borrow().clone();
+        for uri in &uris {
+            self.refresh_vm_list_for(uri);
+        }
+    }
+
+    fn refresh_vm_list_for(&self, uri: &str) {
+        let uri = uri.to_string();
         let win = self.downgrade();
 
-        let rx = spawn_blocking(move || backend::connection::list_all_vms(&uri));
+        let rx = spawn_blocking({
+            let uri = uri.clone();
+            move || backend::connection::list_all_vms(&uri)
+        });
 
         glib::spawn_future_local(async move {
             let Ok(result) = rx.recv().await else { return };
@@ -1042,50 +1235,93 @@ impl Window {
 
             match result {
                 Ok(vms) => {
-                    win.update_vm_list(&vms);
+                    win.update_vm_list(&uri, &vms);
                 }
                 Err(e) => {
-                    log::error!("Failed to list VMs: {e}");
+                    log::error!("Failed to list VMs on {uri}: {e}");
                 }
             }
         });
     }
 
-    fn update_vm_list(&self, vms: &[backend::types::VmInfo]) {
+    fn update_vm_list(&self, uri: &str, vms: &[backend::types::VmInfo]) {
+        // A late result for a host that was removed in the meantime
+        if !self.imp().connections.borrow().iter().any(|c| c == uri) {
+            return;
+        }
+
         let store = &self.imp().list_store;
-        let selected_uuid = self.imp().selected_uuid.borrow().clone();
 
         let mut existing: std::collections::HashMap<String, (u32, VmObject)> =
             std::collections::HashMap::new();
         for i in 0..store.n_items() {
             if let Some(obj) = store.item(i) {
                 let vm = obj.downcast_ref::<VmObject>().unwrap();
-                existing.insert(vm.uuid(), (i, vm.clone()));
+                if vm.uri() == uri {
+                    existing.insert(vm.uuid(), (i, vm.clone()));
+                }
             }
         }
 
         let new_uuids: std::collections::HashSet<String> =
             vms.iter().map(|v| v.uuid.clone()).collect();
 
+        // A VM that became a template (or back) moves to the other section
+        let moved: Vec<String> = vms
+            .iter()
+            .filter(|v| {
+                existing
+                    .get(&v.uuid)
+                    .is_some_and(|(_, obj)| obj.is_template() != v.is_template)
+            })
+            .map(|v| v.uuid.clone())
+            .collect();
+        let reselect = self
+            .selected_object::<VmObject>(&self.imp().vm_list_box, store)
+            .map(|vm| vm.uuid())
+            .filter(|uuid| moved.contains(uuid));
+
         let mut to_remove: Vec<u32> = existing
             .iter()
-            .filter(|(uuid, _)| !new_uuids.contains(*uuid))
+            .filter(|(uuid, _)| !new_uuids.contains(*uuid) || moved.contains(*uuid))
             .map(|(_, (idx, _))| *idx)
             .collect();
         to_remove.sort_unstable_by(|a, b| b.cmp(a));
         for idx in to_remove {
             store.remove(idx);
         }
+        for uuid in &moved {
+            existing.remove(uuid);
+        }
 
         for vm_info in vms {
             if let Some((_, obj)) = existing.get(&vm_info.uuid) {
                 obj.update_from(vm_info);
             } else {
-                store.append(&VmObject::new(vm_info));
+                let pos = self.vm_insert_position(uri, vm_info.is_template);
+                store.insert(pos, &VmObject::new(uri, vm_info));
+            }
+        }
+
+        if let Some(uuid) = reselect {
+            let idx = (0..store.n_items()).find(|&i| {
+                store
+                    .item(i)
+                    .and_then(|obj| obj.downcast::<VmObject>().ok())
+                    .is_some_and(|vm| vm.uuid() == uuid)
+            });
+            if let Some(idx) = idx {
+                let list_box = &self.imp().vm_list_box;
+                list_box.select_row(list_box.row_at_index(idx as i32).as_ref());
             }
         }
 
-        if let Some(ref uuid) = selected_uuid {
+        let selected = self
+            .selected_object::<VmObject>(&self.imp().vm_list_box, store)
+            .filter(|vm| vm.uri() == uri)
+            .map(|vm| vm.uuid());
+
+        if let Some(ref uuid) = selected {
             let state = vms.iter().find(|v| v.uuid == *uuid).map(|v| v.state);
 
             let sidebar = self.imp().active_sidebar.borrow().clone();
@@ -1104,122 +1340,563 @@ impl Window {
         }
     }
 
-    fn update_button_sensitivity(&self, state: Option<backend::types::VmState>) {
-        use crate::backend::types::VmState;
+    // --- Settings methods ---
+
+    fn apply_settings(&self, settings: Settings) {
         let imp = self.imp();
+        *imp.connections.borrow_mut() = settings.connections.clone();
+        *imp.connection_uri.borrow_mut() = settings.initial_connection();
+        *imp.settings.borrow_mut() = settings;
+
+        self.start_event_listener();
+        self.start_jobs_listener();
+        let uris = imp.connections.borrow().clone();
+        for uri in &uris {
+            self.subscribe_events(uri);
+        }
 
-        let (start, pause, stop, force, reboot, console, delete, settings, rename, clone) = match state {
-            Some(VmState::Running) => (false, true, true, true, true, true, false, true, false, false),
-            Some(VmState::Paused)  => (false, true, false, true, false, false, false, true, false, false),
-            Some(VmState::Shutoff) => (true, false, false, false, false, false, true, true, true, true),
-            Some(VmState::Crashed) => (false, false, false, true, false, false, true, true, false, false),
-            Some(_)                => (false, false, false, true, false, false, false, true, false, false),
-            None                   => (false, false, false, false, false, false, false, false, false, false),
+        self.start_polling();
+        self.refresh_vm_list();
+    }
+
+    /// (Re)start the list refresh timer at the configured interval. Hosts
+    /// with live event subscriptions are skipped; the timer only polls hosts
+    /// where event registration failed or the subscription was lost.
+    fn start_polling(&self) {
+        if let Some(id) = self.imp().poll_timer_id.borrow_mut().take() {
+            id.remove();
+        }
+
+        let interval = self.imp().settings.borrow().poll_interval_secs;
+        let win = self.downgrade();
+        let source_id = glib::timeout_add_seconds_local(interval, move || {
+            if let Some(win) = win.upgrade() {
+                let uris = win.imp().connections.borrow().clone();
+                let sidebar = win.imp().active_sidebar.borrow().clone();
+                for uri in &uris {
+                    if backend::events::is_subscribed(uri) {
+                        continue;
+                    }
+                    win.refresh_vm_list_for(uri);
+                    match sidebar.as_str() {
+                        "storage" => win.refresh_pool_list_for(uri),
+                        "networks" => win.refresh_network_list_for(uri),
+                        _ => {}
+                    }
+                    // Subscription lost with its connection: try again
+                    win.subscribe_events(uri);
+                }
+                glib::ControlFlow::Continue
+            } else {
+                glib::ControlFlow::Break
+            }
+        });
+        *self.imp().poll_timer_id.borrow_mut() = Some(source_id);
+    }
+
+    fn save_settings(&self) {
+        let imp = self.imp();
+        let settings = {
+            let mut settings = imp.settings.borrow_mut();
+            settings.connections = imp.connections.borrow().clone();
+            settings.last_connection = Some(imp.connection_uri.borrow().clone());
+            settings.clone()
         };
 
-        imp.btn_start.set_sensitive(start);
-        imp.btn_pause.set_sensitive(pause);
-        imp.btn_stop.set_sensitive(stop);
-        imp.btn_force_stop.set_sensitive(force);
-        imp.btn_reboot.set_sensitive(reboot);
-        imp.btn_console.set_sensitive(console);
-        imp.btn_delete.set_sensitive(delete);
-        imp.btn_settings.set_sensitive(settings);
-        imp.btn_rename.set_sensitive(rename);
-        imp.btn_clone.set_sensitive(clone);
+        if let Err(e) = settings.save() {
+            log::error!("Failed to save settings: {e}");
+        }
     }
 
-    fn load_vm_details(&self, uuid: &str) {
-        let uri = self.imp().connection_uri.borrow().clone();
-        let uuid = uuid.to_string();
+    fn show_preferences_dialog(&self) {
+        let settings = self.imp().settings.borrow().clone();
         let win = self.downgrade();
 
-        let rx = spawn_blocking({
-            let uri = uri.clone();
-            let uuid = uuid.clone();
-            move || {
-                let xml = backend::domain::get_domain_xml(&uri, &uuid)?;
-                let details = backend::domain_xml::parse_domain_xml(&xml)?;
-                let iface_targets = backend::domain_xml::extract_interface_targets(&xml);
-                let disk_targets: Vec<String> = details.disks.iter().map(|d| d.target_dev.clone()).collect();
-                let autostart = backend::domain::get_autostart(&uri, &uuid)?;
-                let vms = backend::connection::list_all_vms(&uri)?;
-                let vm_info = vms.into_iter().find(|v| v.uuid == uuid);
-                Ok::<_, crate::error::AppError>((details, vm_info, autostart, disk_targets, iface_targets, xml))
+        crate::ui::preferences_dialog::show_preferences_dialog(self.upcast_ref(), &settings, move |updated| {
+            let Some(win) = win.upgrade() else { return };
+            let old = win.imp().settings.replace(updated);
+            let new_poll = win.imp().settings.borrow().poll_interval_secs;
+            let new_perf = win.imp().settings.borrow().perf_interval_secs;
+
+            if old.poll_interval_secs != new_poll {
+                win.start_polling();
+            }
+            if old.perf_interval_secs != new_perf && win.imp().perf_timer_id.borrow().is_some() {
+                win.stop_perf_sampling();
+                win.start_perf_sampling();
             }
+            win.save_settings();
         });
+    }
+
+    // --- Event methods ---
 
+    fn start_event_listener(&self) {
+        let (tx, rx) = async_channel::unbounded::<HostEvent>();
+        *self.imp().event_tx.borrow_mut() = Some(tx);
+
+        let win = self.downgrade();
         glib::spawn_future_local(async move {
-            let Ok(result) = rx.recv().await else { return };
+            while let Ok(event) = rx.recv().await {
+                let Some(win) = win.upgrade() else { return };
+                win.handle_host_event(event);
+            }
+        });
+    }
+
+    fn start_jobs_listener(&self) {
+        let Some(rx) = self.imp().jobs_rx.take() else { return };
+
+        let win = self.downgrade();
+        self.imp().jobs_panel.clear_button.connect_clicked(move |_| {
             let Some(win) = win.upgrade() else { return };
+            win.imp().jobs.clear_finished();
+            win.update_jobs_panel();
+        });
 
-            match result {
-                Ok((details, vm_info, autostart, disk_targets, iface_targets, raw_xml)) => {
-                    let state_label = vm_info
-                        .as_ref()
-                        .map(|v| v.state.label())
-                        .unwrap_or("Unknown");
-                    let domain_id = vm_info.as_ref().and_then(|v| v.id);
+        let win = self.downgrade();
+        glib::spawn_future_local(async move {
+            while rx.recv().await.is_ok() {
+                let Some(win) = win.upgrade() else { return };
+                // Each update only says something changed; show them all
+                while rx.try_recv().is_ok() {}
+                win.update_jobs_panel();
+            }
+        });
+        self.update_jobs_panel();
+    }
 
-                    win.imp().details_view.update(&details, state_label, domain_id, autostart);
-                    win.imp().xml_editor.set_xml(&raw_xml);
-                    win.imp().outer_stack.set_visible_child_name("vm-content");
+    fn update_jobs_panel(&self) {
+        let win = self.downgrade();
+        self.imp().jobs_panel.update(&self.imp().jobs.jobs(), move |id| {
+            if let Some(win) = win.upgrade() {
+                win.imp().jobs.cancel(id);
+            }
+        });
+    }
 
-                    let uuid_for_snap = win.imp().selected_uuid.borrow().clone();
-                    if let Some(uuid) = uuid_for_snap {
-                        win.load_snapshots(&uuid);
-                    }
+    /// Run `f` on a worker thread as a job listed in the jobs panel, where
+    /// its progress shows and it can be cancelled.
+    fn spawn_job<F, T>(&self, title: &str, f: F) -> async_channel::Receiver<Result<T, AppError>>
+    where
+        F: FnOnce(&Job) -> Result<T, AppError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let job = self.imp().jobs.start(title);
+        spawn_blocking(move || {
+            let result = f(&job);
+            job.finish(&result);
+            result
+        })
+    }
 
-                    *win.imp().disk_targets.borrow_mut() = disk_targets;
-                    *win.imp().iface_targets.borrow_mut() = iface_targets;
+    /// Register libvirt event callbacks for `uri` in the background, unless
+    /// an attempt is already under way (`event_pending`). Hosts that refuse
+    /// registration are polled, and the poll timer tries again.
+    fn subscribe_events(&self, uri: &str) {
+        let Some(tx) = self.imp().event_tx.borrow().clone() else { return };
+        if !self.imp().event_pending.borrow_mut().insert(uri.to_string()) {
+            return;
+        }
 
-                    let state = vm_info.map(|v| v.state);
-                    win.update_button_sensitivity(state);
+        let uri = uri.to_string();
+        let win = self.downgrade();
 
-                    if state == Some(backend::types::VmState::Running) {
-                        win.imp().perf_view.clear();
-                        win.start_perf_sampling();
-                    } else {
-                        win.stop_perf_sampling();
-                        win.imp().perf_view.clear();
-                    }
+        let rx = spawn_blocking({
+            let uri = uri.clone();
+            move || backend::events::subscribe(&uri, tx)
+        });
+
+        glib::spawn_future_local(async move {
+            let Ok(result) = rx.recv().await else { return };
+            let Some(win) = win.upgrade() else { return };
+            win.imp().event_pending.borrow_mut().remove(&uri);
+
+            match result {
+                Ok(()) => {
+                    // Catch anything that changed before the callbacks existed
+                    win.schedule_refresh(&uri, "vms");
                 }
                 Err(e) => {
-                    win.show_toast(&format!("Failed to load details: {e}"));
+                    log::warn!("Event registration failed on {uri}, polling instead: {e}");
                 }
             }
         });
     }
 
-    fn start_perf_sampling(&self) {
-        if self.imp().perf_timer_id.borrow().is_some() {
+    fn handle_host_event(&self, event: HostEvent) {
+        let imp = self.imp();
+        if !imp.connections.borrow().iter().any(|c| *c == event.uri) {
             return;
         }
 
-        *self.imp().last_perf_sample.borrow_mut() = None;
+        if event.kind.is_domain() {
+            self.schedule_refresh(&event.uri, "vms");
 
-        let win = self.downgrade();
-        let source_id = glib::timeout_add_local(std::time::Duration::from_secs(2), move || {
-            let Some(win) = win.upgrade() else {
-                return glib::ControlFlow::Break;
-            };
+            // Hot-plugged devices change the details page of the open VM
+            if matches!(event.kind, HostEventKind::DomainDeviceAdded | HostEventKind::DomainDeviceRemoved) {
+                let selected = self
+                    .selected_object::<VmObject>(&imp.vm_list_box, &imp.list_store)
+                    .filter(|vm| vm.uri() == event.uri && vm.uuid() == event.uuid);
+                if selected.is_some() && imp.active_sidebar.borrow().as_str() == "vms" {
+                    self.load_vm_details(&event.uuid);
+                }
+            }
+            return;
+        }
 
-            let uri = win.imp().connection_uri.borrow().clone();
-            let uuid = win.imp().selected_uuid.borrow().clone();
-            let Some(uuid) = uuid else {
-                return glib::ControlFlow::Continue;
-            };
+        match event.kind {
+            HostEventKind::PoolLifecycle | HostEventKind::PoolRefresh => {
+                self.schedule_refresh(&event.uri, "storage");
+                let selected = self
+                    .selected_object::<PoolObject>(&imp.pool_list_box, &imp.pool_list_store)
+                    .filter(|pool| pool.uri() == event.uri && pool.uuid() == event.uuid);
+                if selected.is_some() && imp.active_sidebar.borrow().as_str() == "storage" {
+                    self.load_pool_details(&event.uuid);
+                }
+            }
+            HostEventKind::NetworkLifecycle => {
+                self.schedule_refresh(&event.uri, "networks");
+            }
+            _ => {}
+        }
+    }
 
-            let disk_targets = win.imp().disk_targets.borrow().clone();
-            let iface_targets = win.imp().iface_targets.borrow().clone();
+    /// Coalesce bursts of events (a VM start emits several) into a single
+    /// list refresh per host and list.
+    fn schedule_refresh(&self, uri: &str, list: &'static str) {
+        let was_empty = {
+            let mut pending = self.imp().pending_refresh.borrow_mut();
+            let was_empty = pending.is_empty();
+            pending.insert((uri.to_string(), list));
+            was_empty
+        };
+        if !was_empty {
+            return;
+        }
 
-            let rx = spawn_blocking(move || {
-                backend::performance::collect_perf_sample(&uri, &uuid, &disk_targets, &iface_targets)
-            });
+        let win = self.downgrade();
+        glib::timeout_add_local_once(std::time::Duration::from_millis(200), move || {
+            let Some(win) = win.upgrade() else { return };
+            let pending: Vec<_> = win.imp().pending_refresh.borrow_mut().drain().collect();
+            for (uri, list) in pending {
+                match list {
+                    "storage" => win.refresh_pool_list_for(&uri),
+                    "networks" => win.refresh_network_list_for(&uri),
+                    _ => win.refresh_vm_list_for(&uri),
+                }
+            }
+        });
+    }
 
-            let win2 = win.downgrade();
-            glib::spawn_future_local(async move {
-                let Ok(result) = rx.recv().await else { return };
+    // --- Connection methods ---
+
+    /// Item currently selected in `list_box`, looked up in its backing `store`.
+    fn selected_object<T: IsA<glib::Object>>(
+        &self,
+        list_box: &gtk::ListBox,
+        store: &gio::ListStore,
+    ) -> Option<T> {
+        let row = list_box.selected_row()?;
+        store.item(row.index() as u32)?.downcast::<T>().ok()
+    }
+
+    /// Make `connection_uri` follow the row selected in the visible sidebar
+    /// list, so actions go to the host the selected object lives on.
+    fn sync_connection_from_selection(&self) {
+        let imp = self.imp();
+        let sidebar = imp.active_sidebar.borrow().clone();
+        let uri = match sidebar.as_str() {
+            "storage" => self
+                .selected_object::<PoolObject>(&imp.pool_list_box, &imp.pool_list_store)
+                .map(|p| p.uri()),
+            "networks" => self
+                .selected_object::<NetworkObject>(&imp.network_list_box, &imp.network_list_store)
+                .map(|n| n.uri()),
+            _ => self
+                .selected_object::<VmObject>(&imp.vm_list_box, &imp.list_store)
+                .map(|v| v.uri()),
+        };
+        if let Some(uri) = uri {
+            *imp.connection_uri.borrow_mut() = uri;
+        }
+    }
+
+    /// Position at which a new item for `uri` goes so that every host's
+    /// items stay grouped together, in the order hosts were added.
+    fn host_insert_position(&self, store: &gio::ListStore, uri: &str) -> u32 {
+        let connections = self.imp().connections.borrow();
+        let rank = |u: &str| connections.iter().position(|c| c == u).unwrap_or(usize::MAX);
+        let target = rank(uri);
+
+        let mut pos = 0;
+        for i in 0..store.n_items() {
+            if let Some(obj) = store.item(i) {
+                if rank(&obj.property::<String>("uri")) <= target {
+                    pos = i + 1;
+                }
+            }
+        }
+        pos
+    }
+
+    /// Like [`Self::host_insert_position`] for the VM list, where each host's
+    /// templates follow its VMs.
+    fn vm_insert_position(&self, uri: &str, is_template: bool) -> u32 {
+        let store = &self.imp().list_store;
+        let connections = self.imp().connections.borrow();
+        let rank = |u: &str| connections.iter().position(|c| c == u).unwrap_or(usize::MAX);
+        let target = (rank(uri), is_template);
+
+        let mut pos = 0;
+        for i in 0..store.n_items() {
+            if let Some(vm) = store.item(i).and_then(|obj| obj.downcast::<VmObject>().ok()) {
+                if (rank(&vm.uri()), vm.is_template()) <= target {
+                    pos = i + 1;
+                }
+            }
+        }
+        pos
+    }
+
+    fn show_connection_manager_dialog(&self) {
+        let connections = self.imp().connections.borrow().clone();
+        let win_add = self.downgrade();
+        let win_remove = self.downgrade();
+
+        connection_manager_dialog::show_connection_manager_dialog(
+            self.upcast_ref(),
+            &connections,
+            move |uri, on_done| {
+                if let Some(win) = win_add.upgrade() {
+                    win.add_connection(&uri, on_done);
+                }
+            },
+            move |uri| {
+                if let Some(win) = win_remove.upgrade() {
+                    win.remove_connection(&uri);
+                }
+            },
+        );
+    }
+
+    /// Connect to `uri` and, once that works, list and remember the host.
+    /// `on_done` learns whether it did.
+    fn add_connection(&self, uri: &str, on_done: impl FnOnce(bool) + 'static) {
+        let uri = uri.to_string();
+        let win = self.downgrade();
+
+        let rx = spawn_blocking({
+            let uri = uri.clone();
+            move || backend::connection::open_conn(&uri)
+        });
+
+        glib::spawn_future_local(async move {
+            let Ok(result) = rx.recv().await else { return };
+            let Some(win) = win.upgrade() else { return };
+
+            match result {
+                Ok(()) => {
+                    {
+                        let mut connections = win.imp().connections.borrow_mut();
+                        if !connections.contains(&uri) {
+                            connections.push(uri.clone());
+                        }
+                    }
+                    win.save_settings();
+                    on_done(true);
+                    win.show_toast(&format!("Connected to {uri}"));
+                    win.subscribe_events(&uri);
+                    win.refresh_vm_list();
+                    match win.imp().active_sidebar.borrow().as_str() {
+                        "storage" => win.refresh_pool_list(),
+                        "networks" => win.refresh_network_list(),
+                        _ => {}
+                    }
+                }
+                Err(e) => {
+                    on_done(false);
+                    win.show_error(&format!("Failed to connect to {uri}"), &e);
+                }
+            }
+        });
+    }
+
+    fn remove_connection(&self, uri: &str) {
+        let imp = self.imp();
+        imp.connections.borrow_mut().retain(|c| c != uri);
+
+        for store in [&imp.list_store, &imp.pool_list_store, &imp.network_list_store] {
+            for i in (0..store.n_items()).rev() {
+                if let Some(obj) = store.item(i) {
+                    if obj.property::<String>("uri") == uri {
+                        store.remove(i);
+                    }
+                }
+            }
+        }
+
+        if *imp.connection_uri.borrow() == uri {
+            if let Some(first) = imp.connections.borrow().first() {
+                *imp.connection_uri.borrow_mut() = first.clone();
+            }
+        }
+
+        self.save_settings();
+
+        imp.event_pending.borrow_mut().remove(uri);
+
+        // Closing a remote connection can block on the socket
+        let uri = uri.to_string();
+        std::thread::spawn(move || {
+            backend::events::unsubscribe(&uri);
+            backend::connection::invalidate_conn(&uri);
+        });
+    }
+
+    fn update_button_sensitivity(&self, state: Option<backend::types::VmState>) {
+        use grustyvman_core::types::VmState;
+        let imp = self.imp();
+
+        let (start, pause, stop, force, reboot, console, delete, settings, rename, clone) = match state {
+            Some(VmState::Running) => (false, true, true, true, true, true, false, true, false, false),
+            Some(VmState::Paused)  => (false, true, false, true, false, false, false, true, false, false),
+            Some(VmState::Shutoff) => (true, false, false, false, false, false, true, true, true, true),
+            Some(VmState::Saved)   => (true, false, false, false, false, false, true, true, false, false),
+            Some(VmState::Crashed) => (false, false, false, true, false, false, true, true, false, false),
+            Some(_)                => (false, false, false, true, false, false, false, true, false, false),
+            None                   => (false, false, false, false, false, false, false, false, false, false),
+        };
+
+        imp.btn_start.set_sensitive(start);
+        imp.btn_pause.set_sensitive(pause);
+        imp.btn_stop.set_sensitive(stop);
+        imp.btn_force_stop.set_sensitive(force);
+        imp.btn_reboot.set_sensitive(reboot);
+        imp.btn_console.set_sensitive(console);
+        imp.btn_delete.set_sensitive(delete);
+        imp.btn_settings.set_sensitive(settings);
+        imp.btn_rename.set_sensitive(rename);
+        imp.btn_clone.set_sensitive(clone);
+
+        let running = matches!(state, Some(VmState::Running | VmState::Paused));
+        imp.btn_save_state.set_sensitive(state.is_some());
+        imp.save_state_item.set_sensitive(running);
+        imp.save_file_item.set_sensitive(running);
+        imp.discard_save_item.set_sensitive(state == Some(VmState::Saved));
+
+        // Templates never run; converting either way needs the VM shut off
+        let is_template = self
+            .selected_object::<VmObject>(&imp.vm_list_box, &imp.list_store)
+            .is_some_and(|vm| vm.is_template());
+        let shut_off = state == Some(VmState::Shutoff);
+        if is_template {
+            imp.btn_start.set_sensitive(false);
+        }
+        imp.btn_template.set_sensitive(shut_off);
+        imp.btn_template.set_tooltip_text(Some(if is_template {
+            "Convert to VM"
+        } else {
+            "Convert to Template"
+        }));
+        imp.btn_instantiate.set_sensitive(shut_off && is_template);
+        // Migrating needs another host to go to
+        let other_hosts = imp.connections.borrow().len() > 1;
+        imp.btn_migrate.set_sensitive(state.is_some() && other_hosts && !is_template);
+    }
+
+    fn load_vm_details(&self, uuid: &str) {
+        let uri = self.imp().connection_uri.borrow().clone();
+        let uuid = uuid.to_string();
+        let win = self.downgrade();
+
+        let rx = spawn_blocking({
+            let uri = uri.clone();
+            let uuid = uuid.clone();
+            move || {
+                let xml = backend::domain::get_domain_xml(&uri, &uuid)?;
+                let details = backend::domain_xml::parse_domain_xml(&xml)?;
+                let iface_targets = backend::domain_xml::extract_interface_targets(&xml);
+                let disk_targets: Vec<String> = details.disks.iter().map(|d| d.target_dev.clone()).collect();
+                let autostart = backend::domain::get_autostart(&uri, &uuid)?;
+                let vms = backend::connection::list_all_vms(&uri)?;
+                let vm_info = vms.into_iter().find(|v| v.uuid == uuid);
+                Ok::<_, grustyvman_core::error::AppError>((details, vm_info, autostart, disk_targets, iface_targets, xml))
+            }
+        });
+
+        glib::spawn_future_local(async move {
+            let Ok(result) = rx.recv().await else { return };
+            let Some(win) = win.upgrade() else { return };
+
+            match result {
+                Ok((details, vm_info, autostart, disk_targets, iface_targets, raw_xml)) => {
+                    let state_label = vm_info
+                        .as_ref()
+                        .map(|v| v.state.label())
+                        .unwrap_or("Unknown");
+                    let domain_id = vm_info.as_ref().and_then(|v| v.id);
+
+                    win.imp().details_view.update(&details, state_label, domain_id, autostart);
+                    win.imp().xml_editor.set_xml(&raw_xml);
+                    win.imp().outer_stack.set_visible_child_name("vm-content");
+
+                    let uuid_for_snap = win.imp().selected_uuid.borrow().clone();
+                    if let Some(uuid) = uuid_for_snap {
+                        win.load_snapshots(&uuid);
+                    }
+
+                    *win.imp().disk_targets.borrow_mut() = disk_targets;
+                    *win.imp().iface_targets.borrow_mut() = iface_targets;
+
+                    let state = vm_info.map(|v| v.state);
+                    win.update_button_sensitivity(state);
+
+                    if state == Some(backend::types::VmState::Running) {
+                        win.imp().perf_view.clear();
+                        win.start_perf_sampling();
+                    } else {
+                        win.stop_perf_sampling();
+                        win.imp().perf_view.clear();
+                    }
+                }
+                Err(e) => {
+                    win.show_error("Failed to load details", &e);
+                }
+            }
+        });
+    }
+
+    fn start_perf_sampling(&self) {
+        if self.imp().perf_timer_id.borrow().is_some() {
+            return;
+        }
+
+        *self.imp().last_perf_sample.borrow_mut() = None;
+
+        let win = self.downgrade();
+        let interval = self.imp().settings.borrow().perf_interval_secs;
+        let source_id = glib::timeout_add_local(std::time::Duration::from_secs(interval.into()), move || {
+            let Some(win) = win.upgrade() else {
+                return glib::ControlFlow::Break;
+            };
+
+            let uri = win.imp().connection_uri.borrow().clone();
+            let uuid = win.imp().selected_uuid.borrow().clone();
+            let Some(uuid) = uuid else {
+                return glib::ControlFlow::Continue;
+            };
+
+            let disk_targets = win.imp().disk_targets.borrow().clone();
+            let iface_targets = win.imp().iface_targets.borrow().clone();
+
+            let rx = spawn_blocking(move || {
+                backend::performance::collect_perf_sample(&uri, &uuid, &disk_targets, &iface_targets)
+            });
+
+            let win2 = win.downgrade();
+            glib::spawn_future_local(async move {
+                let Ok(result) = rx.recv().await else { return };
                 let Some(win) = win2.upgrade() else { return };
 
                 match result {
@@ -1299,10 +1976,20 @@ impl Window {
     // --- Storage Pool methods ---
 
     fn refresh_pool_list(&self) {
-        let uri = self.imp().connection_uri.borrow().clone();
+        let uris = self.imp().connections.borrow().clone();
+        for uri in &uris {
+            self.refresh_pool_list_for(uri);
+        }
+    }
+
+    fn refresh_pool_list_for(&self, uri: &str) {
+        let uri = uri.to_string();
         let win = self.downgrade();
 
-        let rx = spawn_blocking(move || backend::storage::list_all_pools(&uri));
+        let rx = spawn_blocking({
+            let uri = uri.clone();
+            move || backend::storage::list_all_pools(&uri)
+        });
 
         glib::spawn_future_local(async move {
             let Ok(result) = rx.recv().await else { return };
@@ -1310,16 +1997,20 @@ impl Window {
 
             match result {
                 Ok(pools) => {
-                    win.update_pool_list(&pools);
+                    win.update_pool_list(&uri, &pools);
                 }
                 Err(e) => {
-                    log::error!("Failed to list pools: {e}");
+                    log::error!("Failed to list pools on {uri}: {e}");
                 }
             }
         });
     }
 
-    fn update_pool_list(&self, pools: &[backend::types::PoolInfo]) {
+    fn update_pool_list(&self, uri: &str, pools: &[backend::types::PoolInfo]) {
+        if !self.imp().connections.borrow().iter().any(|c| c == uri) {
+            return;
+        }
+
         let store = &self.imp().pool_list_store;
 
         let mut existing: std::collections::HashMap<String, (u32, PoolObject)> =
@@ -1327,7 +2018,9 @@ impl Window {
         for i in 0..store.n_items() {
             if let Some(obj) = store.item(i) {
                 let pool = obj.downcast_ref::<PoolObject>().unwrap();
-                existing.insert(pool.uuid(), (i, pool.clone()));
+                if pool.uri() == uri {
+                    existing.insert(pool.uuid(), (i, pool.clone()));
+                }
             }
         }
 
@@ -1348,7 +2041,8 @@ impl Window {
             if let Some((_, obj)) = existing.get(&pool_info.uuid) {
                 obj.update_from(pool_info);
             } else {
-                store.append(&PoolObject::new(pool_info));
+                let pos = self.host_insert_position(store, uri);
+                store.insert(pos, &PoolObject::new(uri, pool_info));
             }
         }
     }
@@ -1371,7 +2065,7 @@ impl Window {
                 } else {
                     Vec::new()
                 };
-                Ok::<_, crate::error::AppError>((pool_info, volumes, pool_type, pool_path))
+                Ok::<_, grustyvman_core::error::AppError>((pool_info, volumes, pool_type, pool_path))
             }
         });
 
@@ -1390,7 +2084,7 @@ impl Window {
                     win.show_toast("Pool not found");
                 }
                 Err(e) => {
-                    win.show_toast(&format!("Failed to load pool: {e}"));
+                    win.show_error("Failed to load pool", &e);
                 }
             }
         });
@@ -1447,7 +2141,7 @@ impl Window {
                     }
                 }
                 Err(e) => {
-                    win.show_toast(&format!("Error: {e}"));
+                    win.show_error("Error", &e);
                 }
             }
         });
@@ -1474,7 +2168,7 @@ impl Window {
                         win.refresh_pool_list();
                     }
                     Err(e) => {
-                        win.show_toast(&format!("Failed to create pool: {e}"));
+                        win.show_error("Failed to create pool", &e);
                     }
                 }
             });
@@ -1507,7 +2201,7 @@ impl Window {
                         }
                     }
                     Err(e) => {
-                        win.show_toast(&format!("Failed to create volume: {e}"));
+                        win.show_error("Failed to create volume", &e);
                     }
                 }
             });
@@ -1516,16 +2210,17 @@ impl Window {
 
     fn show_upload_volume_dialog(&self) {
         let win = self.downgrade();
-        crate::ui::upload_volume_dialog::show_upload_volume_dialog(self.upcast_ref(), move |src_path, vol_name| {
+        crate::ui::upload_volume_dialog::show_upload_volume_dialog(self.upcast_ref(), move |src_path, vol_name, convert| {
             let Some(win) = win.upgrade() else { return };
             let uri = win.imp().connection_uri.borrow().clone();
             let pool_uuid = win.imp().selected_pool_uuid.borrow().clone();
             let Some(pool_uuid) = pool_uuid else { return };
 
-            win.show_toast("Uploading…");
-
-            let rx = spawn_blocking(move || {
-                crate::backend::storage::upload_volume(&uri, &pool_uuid, &src_path, &vol_name)
+            let title = format!("Upload {vol_name}");
+            let rx = win.spawn_job(&title, move |job| {
+                grustyvman_core::storage::upload_volume_with_progress(
+                    &uri, &pool_uuid, &src_path, &vol_name, convert, job,
+                )
             });
 
             let win2 = win.downgrade();
@@ -1535,15 +2230,181 @@ impl Window {
                 let Some(win) = win2.upgrade() else { return };
 
                 match result {
-                    Ok(()) => {
+                    Ok(_) => {
                         win.show_toast("Image uploaded successfully");
                         if let Some(uuid) = pool_uuid2 {
                             win.load_pool_details(&uuid);
                         }
                     }
                     Err(e) => {
-                        win.show_toast(&format!("Upload failed: {e}"));
+                        win.show_error("Upload failed", &e);
+                    }
+                }
+            });
+        });
+    }
+
+    fn show_volume_properties(&self, vol_name: &str) {
+        let uri = self.imp().connection_uri.borrow().clone();
+        let Some(pool_uuid) = self.imp().selected_pool_uuid.borrow().clone() else { return };
+        let vol_name = vol_name.to_string();
+        let win = self.downgrade();
+
+        let rx = spawn_blocking({
+            let pool_uuid = pool_uuid.clone();
+            move || {
+                let details = backend::storage::volume_details(&uri, &pool_uuid, &vol_name)?;
+                let pools: Vec<_> = backend::storage::list_all_pools(&uri)?
+                    .into_iter()
+                    .filter(|p| p.active)
+                    .collect();
+                Ok::<_, AppError>((details, pools))
+            }
+        });
+
+        glib::spawn_future_local(async move {
+            let Ok(result) = rx.recv().await else { return };
+            let Some(win) = win.upgrade() else { return };
+            let (details, pools) = match result {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    win.show_error("Failed to load volume", &e);
+                    return;
+                }
+            };
+
+            let win_weak = win.downgrade();
+            let vol_name = details.name.clone();
+            crate::ui::volume_properties_dialog::show_volume_properties_dialog(
+                win.upcast_ref(),
+                &details,
+                &pools,
+                &pool_uuid,
+                move |action| {
+                    if let Some(win) = win_weak.upgrade() {
+                        win.run_volume_action(&vol_name, action);
+                    }
+                },
+            );
+        });
+    }
+
+    fn run_volume_action(&self, vol_name: &str, action: VolumeAction) {
+        let uri = self.imp().connection_uri.borrow().clone();
+        let Some(pool_uuid) = self.imp().selected_pool_uuid.borrow().clone() else { return };
+        let vol_name = vol_name.to_string();
+
+        let (title, done, failed) = match &action {
+            VolumeAction::Resize { .. } => {
+                (format!("Resize {vol_name}"), "Volume resized", "Resize failed")
+            }
+            VolumeAction::Wipe(_) => (format!("Wipe {vol_name}"), "Volume wiped", "Wipe failed"),
+            VolumeAction::Clone { new_name, .. } => {
+                (format!("Clone {vol_name} to {new_name}"), "Volume cloned", "Clone failed")
+            }
+        };
+
+        let confirm = matches!(action, VolumeAction::Wipe(_));
+        let start = {
+            let win = self.downgrade();
+            let vol_name = vol_name.clone();
+            move || {
+                let Some(win) = win.upgrade() else { return };
+                let rx = win.spawn_job(&title, move |job| match action {
+                    VolumeAction::Resize { capacity, allocate, allow_shrink } => {
+                        backend::storage::resize_volume(
+                            &uri, &pool_uuid, &vol_name, capacity, allocate, allow_shrink,
+                        )
+                    }
+                    VolumeAction::Wipe(algorithm) => {
+                        backend::storage::wipe_volume(&uri, &pool_uuid, &vol_name, algorithm)
+                    }
+                    VolumeAction::Clone { pool_uuid: dst_pool_uuid, new_name } => {
+                        backend::storage::clone_pool_volume(
+                            &uri, &pool_uuid, &vol_name, &dst_pool_uuid, &new_name, job,
+                        )
+                        .map(|_| ())
                     }
+                });
+
+                let win2 = win.downgrade();
+                glib::spawn_future_local(async move {
+                    let Ok(result) = rx.recv().await else { return };
+                    let Some(win) = win2.upgrade() else { return };
+                    match result {
+                        Ok(()) => {
+                            win.show_toast(done);
+                            let selected = win.imp().selected_pool_uuid.borrow().clone();
+                            if let Some(uuid) = selected {
+                                win.load_pool_details(&uuid);
+                            }
+                        }
+                        Err(e) => win.show_error(failed, &e),
+                    }
+                });
+            }
+        };
+
+        if !confirm {
+            start();
+            return;
+        }
+
+        let dialog = adw::MessageDialog::new(
+            Some(self),
+            Some("Wipe Volume?"),
+            Some(&format!(
+                "This will overwrite all data on the volume \"{vol_name}\". This cannot be undone."
+            )),
+        );
+        dialog.add_response("cancel", "Cancel");
+        dialog.add_response("confirm", "Wipe");
+        dialog.set_response_appearance("confirm", adw::ResponseAppearance::Destructive);
+        dialog.set_default_response(Some("cancel"));
+        dialog.set_close_response("cancel");
+        let start = std::cell::Cell::new(Some(start));
+        dialog.connect_response(None, move |_, response| {
+            if response == "confirm" {
+                if let Some(start) = start.take() {
+                    start();
+                }
+            }
+        });
+        dialog.present();
+    }
+
+    fn export_volume(&self, vol_name: &str) {
+        let vol_name = vol_name.to_string();
+        let file_dialog = gtk::FileDialog::new();
+        file_dialog.set_title("Export Volume");
+        file_dialog.set_initial_name(Some(&vol_name));
+
+        let win = self.downgrade();
+        file_dialog.save(Some(self), gio::Cancellable::NONE, move |result| {
+            let Ok(file) = result else { return };
+            let Some(path) = file.path() else { return };
+            let Some(win) = win.upgrade() else { return };
+            let uri = win.imp().connection_uri.borrow().clone();
+            let pool_uuid = win.imp().selected_pool_uuid.borrow().clone();
+            let Some(pool_uuid) = pool_uuid else { return };
+            let dst_path = path.to_string_lossy().to_string();
+
+            let title = format!("Export {vol_name}");
+            let vol_name = vol_name.clone();
+            let rx = win.spawn_job(&title, move |job| {
+                backend::storage::download_volume_with_progress(
+                    &uri, &pool_uuid, &vol_name, &dst_path, job,
+                )
+            });
+
+            let win2 = win.downgrade();
+            glib::spawn_future_local(async move {
+                let Ok(result) = rx.recv().await else { return };
+                let Some(win) = win2.upgrade() else { return };
+
+                match result {
+                    Ok(()) => win.show_toast("Volume exported"),
+                    Err(e) => win.show_error("Export failed", &e),
                 }
             });
         });
@@ -1570,7 +2431,7 @@ impl Window {
                     win.show_toast(msg);
                 }
                 Err(e) => {
-                    win.show_toast(&format!("Failed to set autostart: {e}"));
+                    win.show_error("Failed to set autostart", &e);
                 }
             }
         });
@@ -1619,7 +2480,7 @@ impl Window {
                         win.load_pool_details(&pool_uuid2);
                     }
                     Err(e) => {
-                        win.show_toast(&format!("Failed to delete volume: {e}"));
+                        win.show_error("Failed to delete volume", &e);
                     }
                 }
             });
@@ -1678,10 +2539,20 @@ impl Window {
     }
 
     fn refresh_network_list(&self) {
-        let uri = self.imp().connection_uri.borrow().clone();
+        let uris = self.imp().connections.borrow().clone();
+        for uri in &uris {
+            self.refresh_network_list_for(uri);
+        }
+    }
+
+    fn refresh_network_list_for(&self, uri: &str) {
+        let uri = uri.to_string();
         let win = self.downgrade();
 
-        let rx = spawn_blocking(move || backend::network::list_all_networks(&uri));
+        let rx = spawn_blocking({
+            let uri = uri.clone();
+            move || backend::network::list_all_networks(&uri)
+        });
 
         glib::spawn_future_local(async move {
             let Ok(result) = rx.recv().await else { return };
@@ -1689,16 +2560,20 @@ impl Window {
 
             match result {
                 Ok(networks) => {
-                    win.update_network_list(&networks);
+                    win.update_network_list(&uri, &networks);
                 }
                 Err(e) => {
-                    log::error!("Failed to list networks: {e}");
+                    log::error!("Failed to list networks on {uri}: {e}");
                 }
             }
         });
     }
 
-    fn update_network_list(&self, networks: &[backend::types::VirtNetworkInfo]) {
+    fn update_network_list(&self, uri: &str, networks: &[backend::types::VirtNetworkInfo]) {
+        if !self.imp().connections.borrow().iter().any(|c| c == uri) {
+            return;
+        }
+
         let store = &self.imp().network_list_store;
 
         let mut existing: std::collections::HashMap<String, (u32, NetworkObject)> =
@@ -1706,7 +2581,9 @@ impl Window {
         for i in 0..store.n_items() {
             if let Some(obj) = store.item(i) {
                 let network = obj.downcast_ref::<NetworkObject>().unwrap();
-                existing.insert(network.uuid(), (i, network.clone()));
+                if network.uri() == uri {
+                    existing.insert(network.uuid(), (i, network.clone()));
+                }
             }
         }
 
@@ -1727,7 +2604,8 @@ impl Window {
             if let Some((_, obj)) = existing.get(&net_info.uuid) {
                 obj.update_from(net_info);
             } else {
-                store.append(&NetworkObject::new(net_info));
+                let pos = self.host_insert_position(store, uri);
+                store.insert(pos, &NetworkObject::new(uri, net_info));
             }
         }
     }
@@ -1743,7 +2621,7 @@ impl Window {
             move || {
                 let networks = backend::network::list_all_networks(&uri)?;
                 let net_info = networks.into_iter().find(|n| n.uuid == uuid);
-                Ok::<_, crate::error::AppError>(net_info)
+                Ok::<_, grustyvman_core::error::AppError>(net_info)
             }
         });
 
@@ -1762,7 +2640,7 @@ impl Window {
                     win.show_toast("Network not found");
                 }
                 Err(e) => {
-                    win.show_toast(&format!("Failed to load network: {e}"));
+                    win.show_error("Failed to load network", &e);
                 }
             }
         });
@@ -1817,7 +2695,7 @@ impl Window {
                     }
                 }
                 Err(e) => {
-                    win.show_toast(&format!("Error: {e}"));
+                    win.show_error("Error", &e);
                 }
             }
         });
@@ -1844,7 +2722,7 @@ impl Window {
                     win.show_toast(msg);
                 }
                 Err(e) => {
-                    win.show_toast(&format!("Failed to set autostart: {e}"));
+                    win.show_error("Failed to set autostart", &e);
                 }
             }
         });
@@ -1871,7 +2749,7 @@ impl Window {
                         win.refresh_network_list();
                     }
                     Err(e) => {
-                        win.show_toast(&format!("Failed to create network: {e}"));
+                        win.show_error("Failed to create network", &e);
                     }
                 }
             });
@@ -1894,19 +2772,34 @@ impl Window {
             let uri = uri.clone();
             move || backend::domain::list_networks(&uri)
         });
+        // Loading osinfo-db reads many files; do it before the wizard opens
+        let rx_os = spawn_blocking(backend::osinfo::OsDatabase::system);
 
         glib::spawn_future_local(async move {
             let Ok(pool_volumes) = rx_vols.recv().await else { return };
             let Ok(networks) = rx_nets.recv().await else { return };
+            let Ok(os_db) = rx_os.recv().await else { return };
             let Some(win) = win.upgrade() else { return };
             let pool_volumes = pool_volumes.unwrap_or_default();
             let virtual_networks = networks.unwrap_or_default();
 
             let win_weak = win.downgrade();
+            let settings = win.imp().settings.borrow().clone();
             crate::ui::vm_creation_dialog::show_creation_dialog(
                 win.upcast_ref(),
                 pool_volumes,
                 virtual_networks,
+                os_db,
+                &settings,
+                move |path| {
+                    let uri = uri.clone();
+                    spawn_blocking(move || {
+                        backend::osinfo::read_iso_label(&uri, &path).unwrap_or_else(|e| {
+                            log::warn!("Cannot read volume label of {path}: {e}");
+                            None
+                        })
+                    })
+                },
                 move |params| {
                     let Some(win) = win_weak.upgrade() else { return };
                     let uri = win.imp().connection_uri.borrow().clone();
@@ -1924,7 +2817,7 @@ impl Window {
                                 win.refresh_vm_list();
                             }
                             Err(e) => {
-                                win.show_toast(&format!("Failed to create VM: {e}"));
+                                win.show_error("Failed to create VM", &e);
                             }
                         }
                     });
@@ -2026,7 +2919,7 @@ impl Window {
                             win.refresh_vm_list();
                         }
                         Err(e) => {
-                            win.show_toast(&format!("Delete failed: {e}"));
+                            win.show_error("Delete failed", &e);
                         }
                     }
                 });
@@ -2060,7 +2953,7 @@ impl Window {
                     .unwrap_or(0);
                 let pool_volumes = backend::storage::list_all_pool_volumes(&uri)
                     .unwrap_or_default();
-                Ok::<_, crate::error::AppError>((details, autostart, networks, is_running, host_cpu_count, pool_volumes))
+                Ok::<_, grustyvman_core::error::AppError>((details, autostart, networks, is_running, host_cpu_count, pool_volumes))
             }
         });
 
@@ -2087,6 +2980,14 @@ impl Window {
                             let uri = uri.clone();
                             let uuid = uuid_clone.clone();
 
+                            // Block jobs take a while; run them with progress
+                            if let backend::types::ConfigAction::ConsolidateDisk(target_dev, merge) =
+                                action
+                            {
+                                win.consolidate_disk(&uuid, target_dev, merge);
+                                return;
+                            }
+
                             let rx = spawn_blocking({
                                 let uri = uri.clone();
                                 let uuid = uuid.clone();
@@ -2108,7 +3009,7 @@ impl Window {
                                         win.load_vm_details(&uuid2);
                                     }
                                     Err(e) => {
-                                        win.show_toast(&format!("Failed to update config: {e}"));
+                                        win.show_error("Failed to update config", &e);
                                     }
                                 }
                             });
@@ -2116,7 +3017,7 @@ impl Window {
                     );
                 }
                 Err(e) => {
-                    win.show_toast(&format!("Failed to load config: {e}"));
+                    win.show_error("Failed to load config", &e);
                 }
             }
         });
@@ -2158,7 +3059,7 @@ impl Window {
                             }
                         }
                         Err(e) => {
-                            win.show_toast(&format!("Rename failed: {e}"));
+                            win.show_error("Rename failed", &e);
                         }
                     }
                 });
@@ -2173,20 +3074,25 @@ impl Window {
         };
         let uri = self.imp().connection_uri.borrow().clone();
         let source_name = backend::domain::get_domain_name(&uri, &uuid).unwrap_or_default();
+        let source = backend::domain::get_domain_xml(&uri, &uuid)
+            .and_then(|xml| backend::domain_model::DomainXml::parse(&xml));
+        let has_nvram = source
+            .is_ok_and(|domain| domain.firmware() == backend::types::FirmwareType::Efi);
         let win = self.downgrade();
+        let title_name = source_name.clone();
 
         crate::ui::clone_vm_dialog::show_clone_vm_dialog(
             self.upcast_ref(),
             &source_name,
+            has_nvram,
             move |params| {
                 let Some(win) = win.upgrade() else { return };
                 let uri2 = win.imp().connection_uri.borrow().clone();
                 let uuid2 = uuid.clone();
 
-                win.show_toast("Cloning VM… this may take a while");
-
-                let rx = spawn_blocking(move || {
-                    backend::domain::clone_domain(&uri2, &uuid2, &params.new_name, params.full_clone)
+                let title = format!("Clone {title_name} to {}", params.new_name);
+                let rx = win.spawn_job(&title, move |job| {
+                    backend::domain::clone_domain_with_progress(&uri2, &uuid2, &params, job)
                 });
 
                 let win2 = win.downgrade();
@@ -2199,7 +3105,284 @@ impl Window {
                             win.refresh_vm_list();
                         }
                         Err(e) => {
-                            win.show_toast(&format!("Clone failed: {e}"));
+                            win.show_error("Clone failed", &e);
+                        }
+                    }
+                });
+            },
+        );
+    }
+
+    fn save_vm_state(&self) {
+        let imp = self.imp();
+        let Some(vm) = self.selected_object::<VmObject>(&imp.vm_list_box, &imp.list_store) else {
+            return;
+        };
+        let uri = vm.uri();
+        let uuid = vm.uuid();
+        let rx = self.spawn_job(&format!("Save {}", vm.name()), move |job| {
+            backend::domain::managed_save_vm_with_progress(&uri, &uuid, job)
+        });
+        self.finish_save_job(rx, "VM state saved", "Save failed");
+    }
+
+    fn save_vm_to_file(&self) {
+        let imp = self.imp();
+        let Some(vm) = self.selected_object::<VmObject>(&imp.vm_list_box, &imp.list_store) else {
+            return;
+        };
+        // The file is written by libvirtd, on the VM's host
+        let file_dialog = gtk::FileDialog::new();
+        file_dialog.set_title("Save VM to File");
+        file_dialog.set_initial_name(Some(&format!("{}.save", vm.name())));
+
+        let win = self.downgrade();
+        file_dialog.save(Some(self), gio::Cancellable::NONE, move |result| {
+            let Ok(file) = result else { return };
+            let Some(path) = file.path() else { return };
+            let Some(win) = win.upgrade() else { return };
+            let path = path.to_string_lossy().to_string();
+            let uri = vm.uri();
+            let uuid = vm.uuid();
+            let rx = win.spawn_job(&format!("Save {}", vm.name()), move |job| {
+                backend::domain::save_vm_to_file_with_progress(&uri, &uuid, &path, job)
+            });
+            win.finish_save_job(rx, "VM saved to file", "Save failed");
+        });
+    }
+
+    fn restore_vm_from_file(&self) {
+        let file_dialog = gtk::FileDialog::new();
+        file_dialog.set_title("Restore VM from File");
+
+        let win = self.downgrade();
+        file_dialog.open(Some(self), gio::Cancellable::NONE, move |result| {
+            let Ok(file) = result else { return };
+            let Some(path) = file.path() else { return };
+            let Some(win) = win.upgrade() else { return };
+            let path = path.to_string_lossy().to_string();
+            let uri = win.imp().connection_uri.borrow().clone();
+            let title = format!("Restore {}", file.basename().unwrap_or_default().display());
+            let rx = win.spawn_job(&title, move |_job| {
+                backend::domain::restore_vm_from_file(&uri, &path)
+            });
+            win.finish_save_job(rx, "VM restored", "Restore failed");
+        });
+    }
+
+    /// Report the outcome of a save or restore job and show the new state.
+    fn finish_save_job(
+        &self,
+        rx: async_channel::Receiver<Result<(), AppError>>,
+        done: &'static str,
+        failed: &'static str,
+    ) {
+        let win = self.downgrade();
+        glib::spawn_future_local(async move {
+            let Ok(result) = rx.recv().await else { return };
+            let Some(win) = win.upgrade() else { return };
+            match result {
+                Ok(()) => {
+                    win.show_toast(done);
+                    win.refresh_vm_list();
+                    if let Some(uuid) = win.imp().selected_uuid.borrow().clone() {
+                        win.load_vm_details(&uuid);
+                    }
+                }
+                Err(e) => {
+                    win.show_error(failed, &e);
+                }
+            }
+        });
+    }
+
+    fn show_migrate_dialog(&self) {
+        let imp = self.imp();
+        let Some(vm) = self.selected_object::<VmObject>(&imp.vm_list_box, &imp.list_store) else {
+            return;
+        };
+        let uri = vm.uri();
+        let destinations: Vec<String> = imp
+            .connections
+            .borrow()
+            .iter()
+            .filter(|c| **c != uri)
+            .cloned()
+            .collect();
+        let uuid = vm.uuid();
+        let vm_name = vm.name();
+        let state = vm.state();
+        let is_running = state == backend::types::VmState::Running.as_str()
+            || state == backend::types::VmState::Paused.as_str();
+        let win = self.downgrade();
+
+        crate::ui::migrate_vm_dialog::show_migrate_vm_dialog(
+            self.upcast_ref(),
+            &vm_name,
+            &destinations,
+            is_running,
+            move |params| {
+                let Some(win) = win.upgrade() else { return };
+                let uri2 = uri.clone();
+                let uuid2 = uuid.clone();
+                let dest_uri = params.dest_uri.clone();
+
+                let title = format!("Migrate {vm_name}");
+                let rx = win.spawn_job(&title, move |job| {
+                    backend::migration::migrate_vm_with_progress(&uri2, &uuid2, &params, job)
+                });
+
+                let win2 = win.downgrade();
+                glib::spawn_future_local(async move {
+                    let Ok(result) = rx.recv().await else { return };
+                    let Some(win) = win2.upgrade() else { return };
+                    match result {
+                        Ok(()) => {
+                            win.show_toast(&format!("VM migrated to {dest_uri}"));
+                            win.refresh_vm_list();
+                        }
+                        Err(e) => {
+                            win.show_error("Migration failed", &e);
+                        }
+                    }
+                });
+            },
+        );
+    }
+
+    // --- Template methods ---
+
+    fn show_template_dialog(&self) {
+        let imp = self.imp();
+        let Some(vm) = self.selected_object::<VmObject>(&imp.vm_list_box, &imp.list_store) else {
+            return;
+        };
+        let to_template = !vm.is_template();
+
+        let dialog = if to_template {
+            adw::MessageDialog::new(
+                Some(self),
+                Some("Convert to Template?"),
+                Some(&format!(
+                    "{} will be kept shut off and can only be instantiated as linked clones.",
+                    vm.name()
+                )),
+            )
+        } else {
+            adw::MessageDialog::new(
+                Some(self),
+                Some("Convert to VM?"),
+                Some(&format!(
+                    "{} can be started again. Its instances use its disks as backing files, so starting it will corrupt them.",
+                    vm.name()
+                )),
+            )
+        };
+
+        let description_row = adw::EntryRow::new();
+        description_row.set_title("Description");
+        if to_template {
+            let list = gtk::ListBox::new();
+            list.set_selection_mode(gtk::SelectionMode::None);
+            list.add_css_class("boxed-list");
+            list.append(&description_row);
+            dialog.set_extra_child(Some(&list));
+        }
+
+        dialog.add_response("cancel", "Cancel");
+        dialog.add_response("confirm", "Convert");
+        dialog.set_response_appearance(
+            "confirm",
+            if to_template {
+                adw::ResponseAppearance::Suggested
+            } else {
+                adw::ResponseAppearance::Destructive
+            },
+        );
+        dialog.set_default_response(Some("cancel"));
+        dialog.set_close_response("cancel");
+
+        let win = self.downgrade();
+        dialog.connect_response(None, move |_, response| {
+            if response != "confirm" {
+                return;
+            }
+            let Some(win) = win.upgrade() else { return };
+            let uri = vm.uri();
+            let uuid = vm.uuid();
+            let description = description_row.text().trim().to_string();
+
+            let rx = spawn_blocking({
+                let uri = uri.clone();
+                move || {
+                    if to_template {
+                        backend::template::mark_template(&uri, &uuid, &description)
+                    } else {
+                        backend::template::unmark_template(&uri, &uuid)
+                    }
+                }
+            });
+
+            let win2 = win.downgrade();
+            glib::spawn_future_local(async move {
+                let Ok(result) = rx.recv().await else { return };
+                let Some(win) = win2.upgrade() else { return };
+                match result {
+                    Ok(()) => {
+                        win.show_toast(if to_template {
+                            "Converted to template"
+                        } else {
+                            "Converted to VM"
+                        });
+                        win.refresh_vm_list_for(&uri);
+                    }
+                    Err(e) => {
+                        win.show_error("Conversion failed", &e);
+                    }
+                }
+            });
+        });
+
+        dialog.present();
+    }
+
+    fn show_instantiate_dialog(&self) {
+        let imp = self.imp();
+        let Some(vm) = self.selected_object::<VmObject>(&imp.vm_list_box, &imp.list_store) else {
+            return;
+        };
+        if !vm.is_template() {
+            return;
+        }
+        let win = self.downgrade();
+
+        crate::ui::instantiate_template_dialog::show_instantiate_template_dialog(
+            self.upcast_ref(),
+            &vm.name(),
+            move |params| {
+                let Some(win) = win.upgrade() else { return };
+                let uri = vm.uri();
+                let uuid = vm.uuid();
+
+                win.show_toast("Creating instances…");
+
+                let rx = spawn_blocking({
+                    let uri = uri.clone();
+                    move || backend::template::instantiate_template(&uri, &uuid, &params)
+                });
+
+                let win2 = win.downgrade();
+                glib::spawn_future_local(async move {
+                    let Ok(result) = rx.recv().await else { return };
+                    let Some(win) = win2.upgrade() else { return };
+                    match result {
+                        Ok(names) => {
+                            win.show_toast(&format!("Created {}", names.join(", ")));
+                            win.refresh_vm_list_for(&uri);
+                        }
+                        Err(e) => {
+                            win.show_error("Instantiation failed", &e);
+                            win.refresh_vm_list_for(&uri);
                         }
                     }
                 });
@@ -2235,7 +3418,7 @@ impl Window {
                         win.load_vm_details(&uuid2);
                     }
                     Err(e) => {
-                        win.show_toast(&format!("Failed to update XML: {e}"));
+                        win.show_error("Failed to update XML", &e);
                     }
                 }
             });
@@ -2263,6 +3446,13 @@ impl Window {
                 win.confirm_and_delete_snapshot(&snap_name);
             }
         });
+
+        let win = self.downgrade();
+        self.imp().snapshot_view.set_on_compare(move |snap_name| {
+            if let Some(win) = win.upgrade() {
+                win.show_snapshot_diff(&snap_name);
+            }
+        });
     }
 
     fn load_snapshots(&self, uuid: &str) {
@@ -2289,39 +3479,112 @@ impl Window {
     }
 
     fn show_create_snapshot_dialog(&self) {
+        let imp = self.imp();
+        let Some(vm) = self.selected_object::<VmObject>(&imp.vm_list_box, &imp.list_store) else {
+            return;
+        };
+        let uri = imp.connection_uri.borrow().clone();
+        let uuid = vm.uuid();
+        let state = vm.state();
+        let is_running = state == backend::types::VmState::Running.as_str()
+            || state == backend::types::VmState::Paused.as_str();
         let win = self.downgrade();
-        crate::ui::create_snapshot_dialog::show_create_snapshot_dialog(
-            self.upcast_ref(),
-            move |name, description| {
-                let Some(win) = win.upgrade() else { return };
-                let uri = win.imp().connection_uri.borrow().clone();
-                let uuid = win.imp().selected_uuid.borrow().clone();
-                let Some(uuid) = uuid else { return };
 
-                let params = backend::types::CreateSnapshotParams { name, description };
-
-                let rx = spawn_blocking({
-                    let uuid = uuid.clone();
-                    move || backend::snapshot::create_snapshot(&uri, &uuid, &params)
-                });
+        let rx = spawn_blocking({
+            let uri = uri.clone();
+            let uuid = uuid.clone();
+            move || {
+                let xml = backend::domain::get_domain_xml(&uri, &uuid)?;
+                let details = backend::domain_xml::parse_domain_xml(&xml)?;
+                let disks: Vec<String> = details
+                    .disks
+                    .into_iter()
+                    .filter(|d| d.device_type != "cdrom")
+                    .map(|d| d.target_dev)
+                    .collect();
+                Ok::<_, grustyvman_core::error::AppError>(disks)
+            }
+        });
 
-                let win2 = win.downgrade();
-                glib::spawn_future_local(async move {
-                    let Ok(result) = rx.recv().await else { return };
-                    let Some(win) = win2.upgrade() else { return };
+        glib::spawn_future_local(async move {
+            let Ok(result) = rx.recv().await else { return };
+            let Some(win) = win.upgrade() else { return };
+            let disks = match result {
+                Ok(disks) => disks,
+                Err(e) => {
+                    win.show_error("Failed to read VM disks", &e);
+                    return;
+                }
+            };
 
-                    match result {
-                        Ok(()) => {
-                            win.show_toast("Snapshot created");
-                            win.load_snapshots(&uuid);
+            let win_ref = win.downgrade();
+            crate::ui::create_snapshot_dialog::show_create_snapshot_dialog(
+                win.upcast_ref(),
+                &disks,
+                is_running,
+                move |params| {
+                    let Some(win) = win_ref.upgrade() else { return };
+                    let title = format!("Snapshot {}", params.name);
+                    let rx = win.spawn_job(&title, {
+                        let uri = uri.clone();
+                        let uuid = uuid.clone();
+                        move |job| {
+                            backend::snapshot::create_snapshot_with_progress(
+                                &uri, &uuid, &params, job,
+                            )
                         }
-                        Err(e) => {
-                            win.show_toast(&format!("Failed to create snapshot: {e}"));
+                    });
+
+                    let win2 = win.downgrade();
+                    let uuid = uuid.clone();
+                    glib::spawn_future_local(async move {
+                        let Ok(result) = rx.recv().await else { return };
+                        let Some(win) = win2.upgrade() else { return };
+
+                        match result {
+                            Ok(()) => {
+                                win.show_toast("Snapshot created");
+                                win.load_snapshots(&uuid);
+                            }
+                            Err(e) => {
+                                win.show_error("Failed to create snapshot", &e);
+                            }
                         }
-                    }
-                });
-            },
-        );
+                    });
+                },
+            );
+        });
+    }
+
+    fn show_snapshot_diff(&self, snap_name: &str) {
+        let uri = self.imp().connection_uri.borrow().clone();
+        let uuid = self.imp().selected_uuid.borrow().clone();
+        let Some(uuid) = uuid else { return };
+        let snap_name = snap_name.to_string();
+        let win = self.downgrade();
+
+        let rx = spawn_blocking({
+            let snap_name = snap_name.clone();
+            move || backend::snapshot::compare_with_current(&uri, &uuid, &snap_name)
+        });
+
+        glib::spawn_future_local(async move {
+            let Ok(result) = rx.recv().await else { return };
+            let Some(win) = win.upgrade() else { return };
+
+            match result {
+                Ok(changes) => {
+                    crate::ui::snapshot_diff_dialog::show_snapshot_diff_dialog(
+                        win.upcast_ref(),
+                        &snap_name,
+                        &changes,
+                    );
+                }
+                Err(e) => {
+                    win.show_error("Failed to compare snapshot", &e);
+                }
+            }
+        });
     }
 
     fn confirm_and_revert_snapshot(&self, snap_name: &str) {
@@ -2369,7 +3632,7 @@ impl Window {
                         win.load_vm_details(&uuid2);
                     }
                     Err(e) => {
-                        win.show_toast(&format!("Failed to revert: {e}"));
+                        win.show_error("Failed to revert", &e);
                     }
                 }
             });
@@ -2408,8 +3671,9 @@ impl Window {
             let win2 = win.downgrade();
             let uuid2 = uuid.clone();
 
-            let rx = spawn_blocking(move || {
-                backend::snapshot::delete_snapshot(&uri, &uuid, &snap_name)
+            let title = format!("Delete snapshot {snap_name}");
+            let rx = win.spawn_job(&title, move |job| {
+                backend::snapshot::delete_snapshot_with_progress(&uri, &uuid, &snap_name, job)
             });
 
             glib::spawn_future_local(async move {
@@ -2422,7 +3686,7 @@ impl Window {
                         win.load_snapshots(&uuid2);
                     }
                     Err(e) => {
-                        win.show_toast(&format!("Failed to delete snapshot: {e}"));
+                        win.show_error("Failed to delete snapshot", &e);
                     }
                 }
             });
@@ -2431,11 +3695,47 @@ impl Window {
         dialog.present();
     }
 
+    /// Flatten the backing chain of disk `target_dev` as a job.
+    fn consolidate_disk(&self, uuid: &str, target_dev: String, merge: backend::types::ChainMerge) {
+        let uri = self.imp().connection_uri.borrow().clone();
+        let uuid = uuid.to_string();
+        let title = format!("Consolidate {target_dev}");
+        let rx = self.spawn_job(&title, {
+            let uuid = uuid.clone();
+            move |job| {
+                backend::blockjob::consolidate_disk_with_progress(
+                    &uri,
+                    &uuid,
+                    &target_dev,
+                    merge,
+                    false,
+                    job,
+                )
+            }
+        });
+
+        let win = self.downgrade();
+        glib::spawn_future_local(async move {
+            let Ok(result) = rx.recv().await else { return };
+            let Some(win) = win.upgrade() else { return };
+
+            match result {
+                Ok(()) => {
+                    win.show_toast("Disk chain consolidated");
+                    win.load_vm_details(&uuid);
+                }
+                Err(e) => {
+                    win.show_error("Failed to consolidate disk", &e);
+                }
+            }
+        });
+    }
+
     fn handle_config_action(
         uri: &str,
         uuid: &str,
         action: backend::types::ConfigAction,
-    ) -> Result<(), crate::error::AppError> {
+    ) -> Result<(), grustyvman_core::error::AppError> {
         use backend::types::ConfigAction;
 
         match action {
@@ -2463,9 +3763,17 @@ impl Window {
                 backend::domain::update_domain_xml(uri, &xml)?;
                 Ok(())
             }
-            ConfigAction::AddDisk(params) => {
+            ConfigAction::AddDisk(mut params) => {
+                // New images are created by libvirt in the default pool, so
+                // this also works remotely and with root-owned pool dirs
                 if params.create_new {
-                    backend::domain::create_disk_image(&params.source_file, params.size_gib)?;
+                    params.source_file = backend::storage::create_vm_volume(
+                        uri,
+                        &params.source_file,
+                        params.size_gib,
+                        &params.driver_type,
+                        None,
+                    )?;
                 }
                 let xml = backend::domain::get_domain_xml(uri, uuid)?;
                 let xml = backend::domain_xml::add_disk_device(&xml, &params)?;
@@ -2530,6 +3838,29 @@ impl Window {
                 backend::domain::update_domain_xml(uri, &xml)?;
                 Ok(())
             }
+            ConfigAction::ResizeDisk(target_dev, grow_by, grow_filesystems) => {
+                use backend::types::GuestResize;
+                let outcome = backend::domain::grow_disk(
+                    uri,
+                    uuid,
+                    &target_dev,
+                    grow_by,
+                    grow_filesystems,
+                )?;
+                // The disk grew either way; say so when the guest did not follow
+                match outcome {
+                    GuestResize::NotRequested | GuestResize::Grown(_) => Ok(()),
+                    GuestResize::NoAgent => Err(grustyvman_core::error::AppError::Backend(
+                        format!("{target_dev} grew, but no guest agent answered to grow its filesystems"),
+                    )),
+                    GuestResize::Failed(msg) => Err(grustyvman_core::error::AppError::Backend(
+                        format!("{target_dev} grew, but its filesystems did not: {msg}"),
+                    )),
+                }
+            }
+            ConfigAction::ConsolidateDisk(target_dev, merge) => {
+                backend::blockjob::consolidate_disk(uri, uuid, &target_dev, merge, false)
+            }
             ConfigAction::ApplyCpuTune(cpu_tune) => {
                 let xml = backend::domain::get_domain_xml(uri, uuid)?;
                 let xml = backend::domain_xml::modify_cputune(&xml, &cpu_tune)?;
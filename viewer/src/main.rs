@@ -7,13 +7,15 @@
 ///     [--password PASS] [--title TITLE]
 ///
 /// All GTK3 / spice-client-gtk work is done in spice_helpers.c; this file
-/// handles argument parsing, VM control via the virt crate, and wiring the
+/// handles argument parsing, VM control via grustyvman-core, and wiring the
 /// Rust action callback into the C toolbar.
 
 use std::ffi::CString;
 use std::os::raw::{c_char, c_int, c_void};
 use std::sync::atomic::{AtomicUsize, Ordering};
 
+use grustyvman_core::domain;
+use grustyvman_core::AppError;
 use virt::connect::Connect;
 use virt::domain::Domain;
 
@@ -124,29 +126,18 @@ struct SpiceEndpoint {
 }
 
 impl VmControl {
-    fn with_domain<T, F>(&self, f: F) -> Result<(), String>
-    where
-        F: FnOnce(&Domain) -> Result<T, virt::error::Error>,
-    {
-        let conn = Connect::open(Some(&self.uri))
-            .map_err(|e| format!("libvirt connect: {e}"))?;
-        let domain = Domain::lookup_by_uuid_string(&conn, &self.uuid)
-            .map_err(|e| format!("domain lookup: {e}"))?;
-        f(&domain).map(|_| ()).map_err(|e| format!("{e}"))
-    }
-
-    fn start(&self)        -> Result<(), String> { self.with_domain(|d| d.create()) }
-    fn pause(&self)        -> Result<(), String> { self.with_domain(|d| d.suspend()) }
-    fn resume(&self)       -> Result<(), String> { self.with_domain(|d| d.resume()) }
-    fn shutdown(&self)     -> Result<(), String> { self.with_domain(|d| d.shutdown()) }
-    fn reboot(&self)       -> Result<(), String> { self.with_domain(|d| d.reboot(0)) }
-    fn force_stop(&self)   -> Result<(), String> { self.with_domain(|d| d.destroy()) }
+    fn start(&self)      -> Result<(), String> { lifecycle(domain::start_vm(&self.uri, &self.uuid)) }
+    fn pause(&self)      -> Result<(), String> { lifecycle(domain::pause_vm(&self.uri, &self.uuid)) }
+    fn resume(&self)     -> Result<(), String> { lifecycle(domain::resume_vm(&self.uri, &self.uuid)) }
+    fn shutdown(&self)   -> Result<(), String> { lifecycle(domain::shutdown_vm(&self.uri, &self.uuid)) }
+    fn reboot(&self)     -> Result<(), String> { lifecycle(domain::reboot_vm(&self.uri, &self.uuid)) }
+    fn force_stop(&self) -> Result<(), String> { lifecycle(domain::force_stop_vm(&self.uri, &self.uuid)) }
 
     fn force_reboot(&self) -> Result<(), String> {
         // virDomainReset would be ideal here, but destroy+create is universally safe.
-        self.with_domain(|d| d.destroy())?;
+        self.force_stop()?;
         std::thread::sleep(std::time::Duration::from_millis(400));
-        self.with_domain(|d| d.create())
+        self.start()
     }
 
     fn spice_endpoint(&self) -> Result<Option<SpiceEndpoint>, String> {
@@ -189,6 +180,10 @@ impl VmControl {
     }
 }
 
+fn lifecycle(result: Result<(), AppError>) -> Result<(), String> {
+    result.map_err(|e| e.to_string())
+}
+
 fn extract_attr(tag: &str, key: &str) -> Option<String> {
     for quote in ['"', '\''] {
         let needle = format!("{key}={quote}");